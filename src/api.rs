@@ -14,18 +14,25 @@ use tokio::sync::Mutex;
 use tower_http::cors::{Any, CorsLayer};
 use tower_http::services::ServeDir;
 
-use crate::db::Database;
+use crate::calendar::parse_date;
+use crate::config::Paths;
+use crate::db::{AnalyticsFilter, CalendarSettings, Database, HealthSort, StudentStatus};
+use crate::gradebook::ExportFormat;
 #[allow(unused_imports)]
 use crate::models::{
-    Assignment, AssignmentDifficulty, AssignmentTypeStats, BlockerAssignment, Class,
-    CompletionMetrics, DayOfWeekStats, EngagementGap, GradeDistribution, Mentor, NightSummary,
-    ProgressSummary, ProgressionRecord, SectionProgress, Student, StudentActivity,
-    StudentAssignmentStatus, StudentDetail, StudentHealth, StudentProgressPoint, VelocityStats,
-    WeeklyProgress,
+    Assignment, AssignmentDifficulty, AssignmentFunnel, AssignmentTypeStats, BlockerAssignment,
+    Class, ClassCalendarInfo, ClassTimezoneInfo, CompletionMetrics, CompletionProjection,
+    DayOfWeekStats, EngagementGap, ExpectedPacePoint, GradeDistribution, ItemAnalysis, Mentor,
+    NightSummary, Pathway, PathwayStudent, PendingReview, ProgramRollup, ProgressSummary,
+    ProgressionRecord, ReviewTurnaroundReport, RiskHistoryPoint, ScheduleStatus, SectionProgress,
+    SectionTimeOnTask, Student, StudentActivity, StudentAssignmentStatus, StudentDetail,
+    StudentHealth, StudentProgressPoint, StudentSnapshot, SyncDigest, TimeOnTask, TimeOutlier,
+    VelocityStats, WeeklyProgress,
 };
 
 pub struct AppState {
     pub db: Mutex<Database>,
+    pub paths: Paths,
 }
 
 // Response types
@@ -44,14 +51,20 @@ pub struct ErrorResponse {
 }
 
 // Error handling for API
-pub struct ApiError(anyhow::Error);
+pub struct ApiError(StatusCode, anyhow::Error);
+
+impl ApiError {
+    fn bad_request(err: anyhow::Error) -> Self {
+        Self(StatusCode::BAD_REQUEST, err)
+    }
+}
 
 impl IntoResponse for ApiError {
     fn into_response(self) -> axum::response::Response {
         let body = Json(ErrorResponse {
-            error: self.0.to_string(),
+            error: self.1.to_string(),
         });
-        (StatusCode::INTERNAL_SERVER_ERROR, body).into_response()
+        (self.0, body).into_response()
     }
 }
 
@@ -60,14 +73,86 @@ where
     E: Into<anyhow::Error>,
 {
     fn from(err: E) -> Self {
-        Self(err.into())
+        Self(StatusCode::INTERNAL_SERVER_ERROR, err.into())
+    }
+}
+
+#[derive(Debug, Deserialize)]
+pub struct MentorStudentRequest {
+    pub student_id: String,
+    pub class_id: String,
+}
+
+/// Filter parameters accepted by every metrics endpoint: `night`, `region`,
+/// `mentor` (id, name or email), `section`, `type`, `status`, a `from`/`to`
+/// date window (YYYY-MM-DD, inclusive) and `as_of` to compute metrics as they
+/// stood at the end of a past day.
+fn analytics_filter(
+    params: &std::collections::HashMap<String, String>,
+) -> Result<AnalyticsFilter, ApiError> {
+    let text = |name: &str| {
+        params
+            .get(name)
+            .map(|v| v.trim())
+            .filter(|v| !v.is_empty())
+            .map(|v| v.to_string())
+    };
+    let date = |name: &str| text(name).map(|v| parse_date(&v)).transpose();
+
+    let filter = AnalyticsFilter {
+        night: text("night"),
+        region: text("region"),
+        mentor: text("mentor"),
+        section: text("section"),
+        assignment_type: text("type"),
+        status: text("status")
+            .map(|v| StudentStatus::parse(&v))
+            .transpose()
+            .map_err(ApiError::bad_request)?,
+        from: date("from").map_err(ApiError::bad_request)?,
+        to: date("to").map_err(ApiError::bad_request)?,
+        as_of: date("as_of").map_err(ApiError::bad_request)?,
+    };
+    if let (Some(from), Some(to)) = (filter.from, filter.to) {
+        if from > to {
+            return Err(ApiError::bad_request(anyhow::anyhow!(
+                "'from' must not be after 'to'"
+            )));
+        }
     }
+    Ok(filter)
+}
+
+fn mentor_not_found() -> axum::response::Response {
+    (
+        StatusCode::NOT_FOUND,
+        Json(ErrorResponse {
+            error: "Mentor not found".to_string(),
+        }),
+    )
+        .into_response()
+}
+
+/// Full replacement of a class calendar; omitted fields are cleared.
+#[derive(Debug, Deserialize)]
+pub struct CalendarRequest {
+    pub start_date: Option<String>,
+    pub end_date: Option<String>,
+    #[serde(default)]
+    pub break_weeks: Vec<String>,
+}
+
+/// IANA timezone name; `null` clears it.
+#[derive(Debug, Deserialize)]
+pub struct TimezoneRequest {
+    pub timezone: Option<String>,
 }
 
-// Query parameters
+/// Course the class is a run of; `null` goes back to the one from its
+/// friendly id.
 #[derive(Debug, Deserialize)]
-pub struct StudentActivityQuery {
-    pub night: Option<String>,
+pub struct CourseRequest {
+    pub course: Option<String>,
 }
 
 #[derive(Debug, Deserialize)]
@@ -75,6 +160,11 @@ pub struct ClassListQuery {
     pub all: Option<bool>,
 }
 
+#[derive(Debug, Deserialize)]
+pub struct DigestListQuery {
+    pub limit: Option<usize>,
+}
+
 // Handler functions
 async fn health(State(state): State<Arc<AppState>>) -> Result<Json<HealthResponse>, ApiError> {
     let db = state.db.lock().await;
@@ -139,18 +229,20 @@ async fn progress_summary(
     State(state): State<Arc<AppState>>,
 ) -> Result<Json<ProgressSummary>, ApiError> {
     let db = state.db.lock().await;
-    let night = params.get("night").map(|s| s.as_str());
-    let summary = db.get_progress_summary(&class_id, night)?;
+    let filter = analytics_filter(&params)?;
+    let summary = db.get_progress_summary_filtered(&class_id, &filter)?;
     Ok(Json(summary))
 }
 
 // Analytics handlers
 async fn metrics_completion(
-    Path(_class_id): Path<String>,
+    Path(class_id): Path<String>,
+    Query(params): Query<std::collections::HashMap<String, String>>,
     State(state): State<Arc<AppState>>,
 ) -> Result<Json<CompletionMetrics>, ApiError> {
     let db = state.db.lock().await;
-    let metrics = db.get_completion_metrics()?;
+    let filter = analytics_filter(&params)?;
+    let metrics = db.get_completion_metrics(&class_id, &filter)?;
     Ok(Json(metrics))
 }
 
@@ -160,19 +252,76 @@ async fn metrics_blockers(
     State(state): State<Arc<AppState>>,
 ) -> Result<Json<Vec<BlockerAssignment>>, ApiError> {
     let db = state.db.lock().await;
-    let night = params.get("night").map(|s| s.as_str());
-    let blockers = db.get_blockers(&class_id, 10, night)?; // Top 10 blockers
+    let filter = analytics_filter(&params)?;
+    let blockers = db.get_blockers_filtered(&class_id, 10, &filter)?; // Top 10 blockers
     Ok(Json(blockers))
 }
 
+async fn class_gradebook(
+    Path(class_id): Path<String>,
+    Query(params): Query<std::collections::HashMap<String, String>>,
+    State(state): State<Arc<AppState>>,
+) -> Result<impl IntoResponse, ApiError> {
+    let filter = analytics_filter(&params)?;
+    let format = params
+        .get("format")
+        .filter(|v| !v.trim().is_empty() && v.trim() != "json")
+        .map(|v| ExportFormat::parse(v))
+        .transpose()
+        .map_err(ApiError::bad_request)?;
+
+    let db = state.db.lock().await;
+    let gradebook = db.get_gradebook(&class_id, &filter)?;
+    let Some(format) = format else {
+        return Ok(Json(gradebook).into_response());
+    };
+
+    let body = format.export(&gradebook)?;
+    let disposition = format!(
+        "attachment; filename=\"{}-gradebook.{}\"",
+        class_id,
+        format.extension()
+    );
+    Ok((
+        [
+            (
+                axum::http::header::CONTENT_TYPE,
+                format.content_type().to_string(),
+            ),
+            (axum::http::header::CONTENT_DISPOSITION, disposition),
+        ],
+        body,
+    )
+        .into_response())
+}
+
+async fn metrics_funnel(
+    Path(class_id): Path<String>,
+    Query(params): Query<std::collections::HashMap<String, String>>,
+    State(state): State<Arc<AppState>>,
+) -> Result<Json<AssignmentFunnel>, ApiError> {
+    let db = state.db.lock().await;
+    let filter = analytics_filter(&params)?;
+    let funnel = db.get_assignment_funnel(&class_id, &filter)?;
+    Ok(Json(funnel))
+}
+
 async fn metrics_student_health(
     Path(class_id): Path<String>,
     Query(params): Query<std::collections::HashMap<String, String>>,
     State(state): State<Arc<AppState>>,
 ) -> Result<Json<Vec<StudentHealth>>, ApiError> {
     let db = state.db.lock().await;
-    let night = params.get("night").map(|s| s.as_str());
-    let health = db.get_student_health(&class_id, night)?;
+    let filter = analytics_filter(&params)?;
+    let sort = params
+        .get("sort")
+        .map(|v| HealthSort::parse(v))
+        .transpose()
+        .map_err(ApiError::bad_request)?;
+    let mut health = db.get_student_health_filtered(&class_id, &filter)?;
+    if let Some(sort) = sort {
+        sort.apply(&mut health);
+    }
     Ok(Json(health))
 }
 
@@ -182,18 +331,19 @@ async fn metrics_progress_over_time(
     State(state): State<Arc<AppState>>,
 ) -> Result<Json<Vec<WeeklyProgress>>, ApiError> {
     let db = state.db.lock().await;
-    let night = params.get("night").map(|s| s.as_str());
-    let progress = db.get_progress_over_time(&class_id, night)?;
+    let filter = analytics_filter(&params)?;
+    let progress = db.get_progress_over_time_filtered(&class_id, &filter)?;
     Ok(Json(progress))
 }
 
 async fn metrics_student_activity(
     Path(class_id): Path<String>,
-    Query(query): Query<StudentActivityQuery>,
+    Query(params): Query<std::collections::HashMap<String, String>>,
     State(state): State<Arc<AppState>>,
 ) -> Result<Json<Vec<StudentActivity>>, ApiError> {
     let db = state.db.lock().await;
-    let activity = db.get_student_activity_filtered(&class_id, query.night.as_deref())?;
+    let filter = analytics_filter(&params)?;
+    let activity = db.get_student_activity_filtered(&class_id, &filter)?;
     Ok(Json(activity))
 }
 
@@ -203,12 +353,247 @@ async fn list_mentors(State(state): State<Arc<AppState>>) -> Result<Json<Vec<Men
     Ok(Json(mentors))
 }
 
+async fn mentor_students(
+    Path(mentor): Path<String>,
+    State(state): State<Arc<AppState>>,
+) -> Result<impl IntoResponse, ApiError> {
+    let db = state.db.lock().await;
+    match db.find_mentor(&mentor).map_err(ApiError::bad_request)? {
+        Some(mentor) => {
+            let students = db.get_mentor_students(mentor.id)?;
+            Ok((StatusCode::OK, Json(students)).into_response())
+        }
+        None => Ok(mentor_not_found()),
+    }
+}
+
+async fn assign_mentor_student(
+    Path(mentor): Path<String>,
+    State(state): State<Arc<AppState>>,
+    Json(request): Json<MentorStudentRequest>,
+) -> Result<impl IntoResponse, ApiError> {
+    let db = state.db.lock().await;
+    let mentor = match db.find_mentor(&mentor).map_err(ApiError::bad_request)? {
+        Some(mentor) => mentor,
+        None => return Ok(mentor_not_found()),
+    };
+
+    let known = db
+        .get_students_by_class(&request.class_id)?
+        .iter()
+        .any(|s| s.id == request.student_id);
+    if !known {
+        return Ok((
+            StatusCode::NOT_FOUND,
+            Json(ErrorResponse {
+                error: "Student not found".to_string(),
+            }),
+        )
+            .into_response());
+    }
+
+    let created = db.assign_mentor_student(mentor.id, &request.student_id, &request.class_id)?;
+    Ok(Json(serde_json::json!({"success": true, "created": created})).into_response())
+}
+
+async fn unassign_mentor_student(
+    Path((mentor, class_id, student_id)): Path<(String, String, String)>,
+    State(state): State<Arc<AppState>>,
+) -> Result<impl IntoResponse, ApiError> {
+    let db = state.db.lock().await;
+    let mentor = match db.find_mentor(&mentor).map_err(ApiError::bad_request)? {
+        Some(mentor) => mentor,
+        None => return Ok(mentor_not_found()),
+    };
+
+    let removed = db.unassign_mentor_student(mentor.id, &student_id, &class_id)?;
+    Ok(Json(serde_json::json!({"success": true, "removed": removed})).into_response())
+}
+
+async fn class_calendar(
+    Path(class_id): Path<String>,
+    State(state): State<Arc<AppState>>,
+) -> Result<Json<ClassCalendarInfo>, ApiError> {
+    let db = state.db.lock().await;
+    Ok(Json(db.get_class_calendar_info(&class_id)?))
+}
+
+async fn set_class_calendar(
+    Path(class_id): Path<String>,
+    State(state): State<Arc<AppState>>,
+    Json(request): Json<CalendarRequest>,
+) -> Result<impl IntoResponse, ApiError> {
+    let parse = || -> Result<CalendarSettings> {
+        Ok(CalendarSettings {
+            start_date: request.start_date.as_deref().map(parse_date).transpose()?,
+            end_date: request.end_date.as_deref().map(parse_date).transpose()?,
+            break_weeks: request
+                .break_weeks
+                .iter()
+                .map(|d| parse_date(d))
+                .collect::<Result<_>>()?,
+        })
+    };
+
+    let db = state.db.lock().await;
+    if let Err(e) = parse().and_then(|settings| db.set_calendar_settings(&class_id, &settings)) {
+        return Ok((
+            StatusCode::BAD_REQUEST,
+            Json(ErrorResponse {
+                error: e.to_string(),
+            }),
+        )
+            .into_response());
+    }
+
+    Ok(Json(db.get_class_calendar_info(&class_id)?).into_response())
+}
+
+async fn class_timezone(
+    Path(class_id): Path<String>,
+    State(state): State<Arc<AppState>>,
+) -> Result<Json<ClassTimezoneInfo>, ApiError> {
+    let db = state.db.lock().await;
+    Ok(Json(db.get_class_timezone_info(&class_id)?))
+}
+
+async fn set_class_timezone(
+    Path(class_id): Path<String>,
+    State(state): State<Arc<AppState>>,
+    Json(request): Json<TimezoneRequest>,
+) -> Result<impl IntoResponse, ApiError> {
+    let db = state.db.lock().await;
+    if let Err(e) = db.set_class_timezone(&class_id, request.timezone.as_deref()) {
+        return Ok((
+            StatusCode::BAD_REQUEST,
+            Json(ErrorResponse {
+                error: e.to_string(),
+            }),
+        )
+            .into_response());
+    }
+
+    Ok(Json(db.get_class_timezone_info(&class_id)?).into_response())
+}
+
+async fn set_student_timezone(
+    Path((class_id, student_id)): Path<(String, String)>,
+    State(state): State<Arc<AppState>>,
+    Json(request): Json<TimezoneRequest>,
+) -> Result<impl IntoResponse, ApiError> {
+    let db = state.db.lock().await;
+    match db.set_student_timezone(&class_id, &student_id, request.timezone.as_deref()) {
+        Ok(true) => Ok(Json(db.get_class_timezone_info(&class_id)?).into_response()),
+        Ok(false) => Ok((
+            StatusCode::NOT_FOUND,
+            Json(ErrorResponse {
+                error: "Student not found".to_string(),
+            }),
+        )
+            .into_response()),
+        Err(e) => Ok((
+            StatusCode::BAD_REQUEST,
+            Json(ErrorResponse {
+                error: e.to_string(),
+            }),
+        )
+            .into_response()),
+    }
+}
+
+fn class_not_found() -> axum::response::Response {
+    (
+        StatusCode::NOT_FOUND,
+        Json(ErrorResponse {
+            error: "Class not found".to_string(),
+        }),
+    )
+        .into_response()
+}
+
+async fn class_course(
+    Path(class_id): Path<String>,
+    State(state): State<Arc<AppState>>,
+) -> Result<impl IntoResponse, ApiError> {
+    let db = state.db.lock().await;
+    match db.get_class_course_info(&class_id)? {
+        Some(info) => Ok(Json(info).into_response()),
+        None => Ok(class_not_found()),
+    }
+}
+
+async fn set_class_course(
+    Path(class_id): Path<String>,
+    State(state): State<Arc<AppState>>,
+    Json(request): Json<CourseRequest>,
+) -> Result<impl IntoResponse, ApiError> {
+    let db = state.db.lock().await;
+    if db.get_class_course_info(&class_id)?.is_none() {
+        return Ok(class_not_found());
+    }
+    db.set_class_course(&class_id, request.course.as_deref())
+        .map_err(ApiError::bad_request)?;
+    Ok(Json(db.get_class_course_info(&class_id)?).into_response())
+}
+
+async fn cohort_benchmark(
+    Path(class_id): Path<String>,
+    State(state): State<Arc<AppState>>,
+) -> Result<impl IntoResponse, ApiError> {
+    let db = state.db.lock().await;
+    match db.get_cohort_benchmark(&class_id)? {
+        Some(benchmark) => Ok(Json(benchmark).into_response()),
+        None => Ok(class_not_found()),
+    }
+}
+
+async fn latest_digest(
+    Path(class_id): Path<String>,
+    State(state): State<Arc<AppState>>,
+) -> Result<impl IntoResponse, ApiError> {
+    let db = state.db.lock().await;
+    match db.get_sync_digests(&class_id, 1)?.pop() {
+        Some(digest) => Ok(Json(digest).into_response()),
+        None => Ok((
+            StatusCode::NOT_FOUND,
+            Json(ErrorResponse {
+                error: "No sync digest for this class yet".to_string(),
+            }),
+        )
+            .into_response()),
+    }
+}
+
+async fn list_digests(
+    Path(class_id): Path<String>,
+    Query(query): Query<DigestListQuery>,
+    State(state): State<Arc<AppState>>,
+) -> Result<Json<Vec<SyncDigest>>, ApiError> {
+    let db = state.db.lock().await;
+    Ok(Json(
+        db.get_sync_digests(&class_id, query.limit.unwrap_or(10))?,
+    ))
+}
+
+async fn cohort_difficulty(
+    Path(class_id): Path<String>,
+    State(state): State<Arc<AppState>>,
+) -> Result<impl IntoResponse, ApiError> {
+    let db = state.db.lock().await;
+    match db.get_cohort_difficulty(&class_id)? {
+        Some(assignments) => Ok(Json(assignments).into_response()),
+        None => Ok(class_not_found()),
+    }
+}
+
 async fn metrics_night_summary(
     Path(class_id): Path<String>,
+    Query(params): Query<std::collections::HashMap<String, String>>,
     State(state): State<Arc<AppState>>,
 ) -> Result<Json<Vec<NightSummary>>, ApiError> {
     let db = state.db.lock().await;
-    let summary = db.get_night_summary(&class_id)?;
+    let filter = analytics_filter(&params)?;
+    let summary = db.get_night_summary(&class_id, &filter)?;
     Ok(Json(summary))
 }
 
@@ -218,8 +603,8 @@ async fn metrics_assignment_types(
     State(state): State<Arc<AppState>>,
 ) -> Result<Json<Vec<AssignmentTypeStats>>, ApiError> {
     let db = state.db.lock().await;
-    let night = params.get("night").map(|s| s.as_str());
-    let stats = db.get_assignment_type_stats(&class_id, night)?;
+    let filter = analytics_filter(&params)?;
+    let stats = db.get_assignment_type_stats_filtered(&class_id, &filter)?;
     Ok(Json(stats))
 }
 
@@ -229,8 +614,8 @@ async fn metrics_grade_distribution(
     State(state): State<Arc<AppState>>,
 ) -> Result<Json<Vec<GradeDistribution>>, ApiError> {
     let db = state.db.lock().await;
-    let night = params.get("night").map(|s| s.as_str());
-    let distribution = db.get_grade_distribution(&class_id, night)?;
+    let filter = analytics_filter(&params)?;
+    let distribution = db.get_grade_distribution_filtered(&class_id, &filter)?;
     Ok(Json(distribution))
 }
 
@@ -240,8 +625,8 @@ async fn metrics_velocity(
     State(state): State<Arc<AppState>>,
 ) -> Result<Json<Vec<VelocityStats>>, ApiError> {
     let db = state.db.lock().await;
-    let night = params.get("night").map(|s| s.as_str());
-    let stats = db.get_velocity_stats(&class_id, night)?;
+    let filter = analytics_filter(&params)?;
+    let stats = db.get_velocity_stats_filtered(&class_id, &filter)?;
     Ok(Json(stats))
 }
 
@@ -251,8 +636,8 @@ async fn metrics_engagement_gaps(
     State(state): State<Arc<AppState>>,
 ) -> Result<Json<Vec<EngagementGap>>, ApiError> {
     let db = state.db.lock().await;
-    let night = params.get("night").map(|s| s.as_str());
-    let gaps = db.get_engagement_gaps(&class_id, night)?;
+    let filter = analytics_filter(&params)?;
+    let gaps = db.get_engagement_gaps_filtered(&class_id, &filter)?;
     Ok(Json(gaps))
 }
 
@@ -262,19 +647,29 @@ async fn metrics_assignment_difficulty(
     State(state): State<Arc<AppState>>,
 ) -> Result<Json<Vec<AssignmentDifficulty>>, ApiError> {
     let db = state.db.lock().await;
-    let night = params.get("night").map(|s| s.as_str());
-    let difficulty = db.get_assignment_difficulty(&class_id, night)?;
+    let filter = analytics_filter(&params)?;
+    let difficulty = db.get_assignment_difficulty_filtered(&class_id, &filter)?;
     Ok(Json(difficulty))
 }
 
+async fn metrics_item_analysis(
+    Path(class_id): Path<String>,
+    Query(params): Query<std::collections::HashMap<String, String>>,
+    State(state): State<Arc<AppState>>,
+) -> Result<Json<Vec<ItemAnalysis>>, ApiError> {
+    let db = state.db.lock().await;
+    let filter = analytics_filter(&params)?;
+    Ok(Json(db.get_item_analysis(&class_id, &filter)?))
+}
+
 async fn metrics_day_of_week(
     Path(class_id): Path<String>,
     Query(params): Query<std::collections::HashMap<String, String>>,
     State(state): State<Arc<AppState>>,
 ) -> Result<Json<Vec<DayOfWeekStats>>, ApiError> {
     let db = state.db.lock().await;
-    let night = params.get("night").map(|s| s.as_str());
-    let stats = db.get_completions_by_day_of_week(&class_id, night)?;
+    let filter = analytics_filter(&params)?;
+    let stats = db.get_completions_by_day_of_week_filtered(&class_id, &filter)?;
     Ok(Json(stats))
 }
 
@@ -293,8 +688,8 @@ async fn metrics_time_of_day(
     State(state): State<Arc<AppState>>,
 ) -> Result<Json<Vec<DayOfWeekStats>>, ApiError> {
     let db = state.db.lock().await;
-    let night = params.get("night").map(|s| s.as_str());
-    let stats = db.get_completions_by_time_of_day(&class_id, night)?;
+    let filter = analytics_filter(&params)?;
+    let stats = db.get_completions_by_time_of_day_filtered(&class_id, &filter)?;
     Ok(Json(stats))
 }
 
@@ -313,11 +708,110 @@ async fn metrics_section_progress(
     State(state): State<Arc<AppState>>,
 ) -> Result<Json<Vec<SectionProgress>>, ApiError> {
     let db = state.db.lock().await;
-    let night = params.get("night").map(|s| s.as_str());
-    let progress = db.get_section_progress(&class_id, night)?;
+    let filter = analytics_filter(&params)?;
+    let progress = db.get_section_progress_filtered(&class_id, &filter)?;
     Ok(Json(progress))
 }
 
+async fn metrics_risk_history(
+    Path(class_id): Path<String>,
+    Query(params): Query<std::collections::HashMap<String, String>>,
+    State(state): State<Arc<AppState>>,
+) -> Result<Json<Vec<RiskHistoryPoint>>, ApiError> {
+    let db = state.db.lock().await;
+    let filter = analytics_filter(&params)?;
+    let history = db.get_class_risk_history_filtered(&class_id, &filter)?;
+    Ok(Json(history))
+}
+
+async fn metrics_expected_pace(
+    Path(class_id): Path<String>,
+    Query(params): Query<std::collections::HashMap<String, String>>,
+    State(state): State<Arc<AppState>>,
+) -> Result<Json<Vec<ExpectedPacePoint>>, ApiError> {
+    let db = state.db.lock().await;
+    let filter = analytics_filter(&params)?;
+    let pace = db.get_expected_pace(&class_id, &filter)?;
+    Ok(Json(pace))
+}
+
+async fn metrics_behind_schedule(
+    Path(class_id): Path<String>,
+    Query(params): Query<std::collections::HashMap<String, String>>,
+    State(state): State<Arc<AppState>>,
+) -> Result<Json<Vec<ScheduleStatus>>, ApiError> {
+    let db = state.db.lock().await;
+    let filter = analytics_filter(&params)?;
+    let statuses = db.get_schedule_status_filtered(&class_id, &filter)?;
+    Ok(Json(statuses))
+}
+
+async fn metrics_projected_late(
+    Path(class_id): Path<String>,
+    Query(params): Query<std::collections::HashMap<String, String>>,
+    State(state): State<Arc<AppState>>,
+) -> Result<Json<Vec<CompletionProjection>>, ApiError> {
+    let db = state.db.lock().await;
+    let filter = analytics_filter(&params)?;
+    let late = db.get_projected_late_filtered(&class_id, &filter)?;
+    Ok(Json(late))
+}
+
+async fn metrics_time_on_task(
+    Path(class_id): Path<String>,
+    Query(params): Query<std::collections::HashMap<String, String>>,
+    State(state): State<Arc<AppState>>,
+) -> Result<Json<Vec<TimeOnTask>>, ApiError> {
+    let db = state.db.lock().await;
+    let filter = analytics_filter(&params)?;
+    let times = db.get_time_on_task_filtered(&class_id, &filter)?;
+    Ok(Json(times))
+}
+
+async fn metrics_section_time_on_task(
+    Path(class_id): Path<String>,
+    Query(params): Query<std::collections::HashMap<String, String>>,
+    State(state): State<Arc<AppState>>,
+) -> Result<Json<Vec<SectionTimeOnTask>>, ApiError> {
+    let db = state.db.lock().await;
+    let filter = analytics_filter(&params)?;
+    let times = db.get_section_time_on_task_filtered(&class_id, &filter)?;
+    Ok(Json(times))
+}
+
+async fn metrics_time_outliers(
+    Path(class_id): Path<String>,
+    Query(params): Query<std::collections::HashMap<String, String>>,
+    State(state): State<Arc<AppState>>,
+) -> Result<Json<Vec<TimeOutlier>>, ApiError> {
+    let db = state.db.lock().await;
+    let filter = analytics_filter(&params)?;
+    let outliers = db.get_time_outliers_filtered(&class_id, &filter)?;
+    Ok(Json(outliers))
+}
+
+async fn metrics_review_turnaround(
+    Path(class_id): Path<String>,
+    Query(params): Query<std::collections::HashMap<String, String>>,
+    State(state): State<Arc<AppState>>,
+) -> Result<Json<ReviewTurnaroundReport>, ApiError> {
+    let db = state.db.lock().await;
+    let filter = analytics_filter(&params)?;
+    let report = db.get_review_turnaround_filtered(&class_id, &filter)?;
+    Ok(Json(report))
+}
+
+async fn pending_reviews(
+    Path(class_id): Path<String>,
+    Query(params): Query<std::collections::HashMap<String, String>>,
+    State(state): State<Arc<AppState>>,
+) -> Result<Json<Vec<PendingReview>>, ApiError> {
+    let db = state.db.lock().await;
+    let filter = analytics_filter(&params)?;
+    let pending = db.get_pending_reviews_filtered(&class_id, &filter)?;
+    Ok(Json(pending))
+}
+
 async fn students_by_night(
     Path((class_id, night)): Path<(String, String)>,
     State(state): State<Arc<AppState>>,
@@ -362,6 +856,63 @@ async fn student_progress_timeline(
     Ok(Json(timeline))
 }
 
+async fn student_risk_history(
+    Path((class_id, student_id)): Path<(String, String)>,
+    State(state): State<Arc<AppState>>,
+) -> Result<Json<Vec<StudentSnapshot>>, ApiError> {
+    let db = state.db.lock().await;
+    let history = db.get_student_risk_history(&class_id, &student_id)?;
+    Ok(Json(history))
+}
+
+async fn student_pathway(
+    Path((class_id, student_id)): Path<(String, String)>,
+    State(state): State<Arc<AppState>>,
+) -> Result<impl IntoResponse, ApiError> {
+    let db = state.db.lock().await;
+    match db.get_student_pathway(&class_id, &student_id)? {
+        Some(pathway) => Ok((StatusCode::OK, Json(pathway)).into_response()),
+        None => Ok((
+            StatusCode::NOT_FOUND,
+            Json(ErrorResponse {
+                error: "Student not found".to_string(),
+            }),
+        )
+            .into_response()),
+    }
+}
+
+async fn list_pathways(State(state): State<Arc<AppState>>) -> Result<Json<Vec<Pathway>>, ApiError> {
+    let db = state.db.lock().await;
+    let pathways = db.get_pathways()?;
+    Ok(Json(pathways))
+}
+
+async fn program_rollup(
+    State(state): State<Arc<AppState>>,
+) -> Result<Json<ProgramRollup>, ApiError> {
+    let db = state.db.lock().await;
+    let rollup = db.get_program_rollup()?;
+    Ok(Json(rollup))
+}
+
+async fn pathway_rollups(
+    State(state): State<Arc<AppState>>,
+) -> Result<Json<Vec<ProgramRollup>>, ApiError> {
+    let db = state.db.lock().await;
+    let rollups = db.get_pathway_rollups()?;
+    Ok(Json(rollups))
+}
+
+async fn pathway_students(
+    Path(pathway): Path<String>,
+    State(state): State<Arc<AppState>>,
+) -> Result<Json<Vec<PathwayStudent>>, ApiError> {
+    let db = state.db.lock().await;
+    let students = db.get_pathway_students(&pathway)?;
+    Ok(Json(students))
+}
+
 async fn activate_class(
     Path(class_id): Path<String>,
     State(state): State<Arc<AppState>>,
@@ -382,18 +933,23 @@ async fn deactivate_class(
 
 async fn sync_class(
     Path(class_id): Path<String>,
+    State(state): State<Arc<AppState>>,
 ) -> Sse<impl Stream<Item = Result<axum::response::sse::Event, Infallible>>> {
     use tokio::io::{AsyncBufReadExt, BufReader};
     use tokio::process::Command;
     use tokio::time::{timeout, Duration};
 
+    // The child process must use the same config and database as this server
+    let config_path = state.paths.config_str().to_string();
+    let db_path = state.paths.db_str().to_string();
+
     let stream = async_stream::stream! {
         let current_exe = std::env::current_exe().unwrap_or_else(|_| "cohort-tracker".into());
 
         yield Ok(axum::response::sse::Event::default().data(format!("Starting sync with: {:?}", current_exe)));
 
         let mut child = match Command::new(&current_exe)
-            .args(["sync", "--class", &class_id])
+            .args(["sync", "--class", &class_id, "--config", &config_path, "--db", &db_path])
             .stdout(std::process::Stdio::piped())
             .stderr(std::process::Stdio::piped())
             .spawn() {
@@ -461,96 +1017,188 @@ fn create_router(state: Arc<AppState>) -> Router {
         .route("/health", get(health))
         .route("/classes", get(list_classes))
         .route(
-            "/classes/:class_id/activate",
+            "/classes/{class_id}/activate",
             axum::routing::post(activate_class),
         )
         .route(
-            "/classes/:class_id/deactivate",
+            "/classes/{class_id}/deactivate",
             axum::routing::post(deactivate_class),
         )
-        .route("/classes/:class_id/sync", get(sync_class))
-        .route("/classes/:class_id/students", get(list_students))
-        .route("/classes/:class_id/assignments", get(list_assignments))
-        .route("/classes/:class_id/progressions", get(list_progressions))
-        .route("/classes/:class_id/progress-summary", get(progress_summary))
+        .route("/classes/{class_id}/sync", get(sync_class))
+        .route(
+            "/classes/{class_id}/calendar",
+            get(class_calendar).put(set_class_calendar),
+        )
+        .route(
+            "/classes/{class_id}/timezone",
+            get(class_timezone).put(set_class_timezone),
+        )
+        .route(
+            "/classes/{class_id}/course",
+            get(class_course).put(set_class_course),
+        )
+        .route("/classes/{class_id}/benchmark", get(cohort_benchmark))
+        .route(
+            "/classes/{class_id}/benchmark/difficulty",
+            get(cohort_difficulty),
+        )
+        .route(
+            "/classes/{class_id}/students/{student_id}/timezone",
+            axum::routing::put(set_student_timezone),
+        )
+        .route("/classes/{class_id}/digest", get(latest_digest))
+        .route("/classes/{class_id}/digests", get(list_digests))
+        .route("/classes/{class_id}/students", get(list_students))
+        .route("/classes/{class_id}/assignments", get(list_assignments))
+        .route("/classes/{class_id}/progressions", get(list_progressions))
+        .route(
+            "/classes/{class_id}/progress-summary",
+            get(progress_summary),
+        )
         // Analytics endpoints
         .route(
-            "/classes/:class_id/metrics/completion",
+            "/classes/{class_id}/metrics/completion",
             get(metrics_completion),
         )
-        .route("/classes/:class_id/metrics/blockers", get(metrics_blockers))
         .route(
-            "/classes/:class_id/metrics/student-health",
+            "/classes/{class_id}/metrics/blockers",
+            get(metrics_blockers),
+        )
+        .route("/classes/{class_id}/metrics/funnel", get(metrics_funnel))
+        .route("/classes/{class_id}/gradebook", get(class_gradebook))
+        .route(
+            "/classes/{class_id}/metrics/student-health",
             get(metrics_student_health),
         )
         .route(
-            "/classes/:class_id/metrics/progress-over-time",
+            "/classes/{class_id}/metrics/progress-over-time",
             get(metrics_progress_over_time),
         )
         .route(
-            "/classes/:class_id/metrics/student-activity",
+            "/classes/{class_id}/metrics/student-activity",
             get(metrics_student_activity),
         )
         .route(
-            "/classes/:class_id/metrics/night-summary",
+            "/classes/{class_id}/metrics/night-summary",
             get(metrics_night_summary),
         )
         .route(
-            "/classes/:class_id/metrics/assignment-types",
+            "/classes/{class_id}/metrics/assignment-types",
             get(metrics_assignment_types),
         )
         .route(
-            "/classes/:class_id/metrics/grade-distribution",
+            "/classes/{class_id}/metrics/grade-distribution",
             get(metrics_grade_distribution),
         )
-        .route("/classes/:class_id/metrics/velocity", get(metrics_velocity))
         .route(
-            "/classes/:class_id/metrics/engagement-gaps",
+            "/classes/{class_id}/metrics/velocity",
+            get(metrics_velocity),
+        )
+        .route(
+            "/classes/{class_id}/metrics/engagement-gaps",
             get(metrics_engagement_gaps),
         )
         .route(
-            "/classes/:class_id/metrics/assignment-difficulty",
+            "/classes/{class_id}/metrics/assignment-difficulty",
             get(metrics_assignment_difficulty),
         )
         .route(
-            "/classes/:class_id/metrics/day-of-week",
+            "/classes/{class_id}/metrics/item-analysis",
+            get(metrics_item_analysis),
+        )
+        .route(
+            "/classes/{class_id}/metrics/day-of-week",
             get(metrics_day_of_week),
         )
         .route(
-            "/classes/:class_id/metrics/time-of-day",
+            "/classes/{class_id}/metrics/time-of-day",
             get(metrics_time_of_day),
         )
         .route(
-            "/classes/:class_id/metrics/section-progress",
+            "/classes/{class_id}/metrics/section-progress",
             get(metrics_section_progress),
         )
         .route(
-            "/classes/:class_id/students/night/:night",
+            "/classes/{class_id}/metrics/risk-history",
+            get(metrics_risk_history),
+        )
+        .route(
+            "/classes/{class_id}/metrics/expected-pace",
+            get(metrics_expected_pace),
+        )
+        .route(
+            "/classes/{class_id}/metrics/behind-schedule",
+            get(metrics_behind_schedule),
+        )
+        .route(
+            "/classes/{class_id}/metrics/projected-late",
+            get(metrics_projected_late),
+        )
+        .route(
+            "/classes/{class_id}/metrics/time-on-task",
+            get(metrics_time_on_task),
+        )
+        .route(
+            "/classes/{class_id}/metrics/time-on-task/sections",
+            get(metrics_section_time_on_task),
+        )
+        .route(
+            "/classes/{class_id}/metrics/time-outliers",
+            get(metrics_time_outliers),
+        )
+        .route(
+            "/classes/{class_id}/metrics/review-turnaround",
+            get(metrics_review_turnaround),
+        )
+        .route("/classes/{class_id}/reviews/pending", get(pending_reviews))
+        .route(
+            "/classes/{class_id}/students/night/{night}",
             get(students_by_night),
         )
         // Student detail endpoints
         .route(
-            "/classes/:class_id/students/:student_id/detail",
+            "/classes/{class_id}/students/{student_id}/detail",
             get(student_detail),
         )
         .route(
-            "/classes/:class_id/students/:student_id/assignments",
+            "/classes/{class_id}/students/{student_id}/assignments",
             get(student_assignments),
         )
         .route(
-            "/classes/:class_id/students/:student_id/progress-timeline",
+            "/classes/{class_id}/students/{student_id}/progress-timeline",
             get(student_progress_timeline),
         )
         .route(
-            "/classes/:class_id/students/:student_id/day-of-week",
+            "/classes/{class_id}/students/{student_id}/day-of-week",
             get(student_day_of_week),
         )
         .route(
-            "/classes/:class_id/students/:student_id/time-of-day",
+            "/classes/{class_id}/students/{student_id}/time-of-day",
             get(student_time_of_day),
         )
+        .route(
+            "/classes/{class_id}/students/{student_id}/risk-history",
+            get(student_risk_history),
+        )
+        .route(
+            "/classes/{class_id}/students/{student_id}/pathway",
+            get(student_pathway),
+        )
+        // Pathways (students across Module 1, Module 2, ...)
+        .route("/pathways", get(list_pathways))
+        .route("/pathways/{pathway}/students", get(pathway_students))
+        .route("/program/rollup", get(program_rollup))
+        .route("/program/rollup/pathways", get(pathway_rollups))
         // Mentors
         .route("/mentors", get(list_mentors))
+        .route(
+            "/mentors/{mentor}/students",
+            get(mentor_students).post(assign_mentor_student),
+        )
+        .route(
+            "/mentors/{mentor}/students/{class_id}/{student_id}",
+            axum::routing::delete(unassign_mentor_student),
+        )
         // Dashboard (serve index.html at root)
         .nest_service("/dashboard", ServeDir::new("static"))
         .fallback_service(static_service)
@@ -558,15 +1206,33 @@ fn create_router(state: Arc<AppState>) -> Router {
         .with_state(state)
 }
 
-pub async fn start_server(db_path: &str, port: u16) -> Result<()> {
-    let db = Database::new(db_path)?;
+pub async fn start_server(paths: Paths, port: u16) -> Result<()> {
+    let config = crate::config::Config::from_file(paths.config_str()).ok();
+    let backup = config
+        .as_ref()
+        .map(|c| c.backup.clone())
+        .unwrap_or_default();
+    let (mut db, migration_backup) = Database::open(paths.db_str(), &backup)?;
+    if let Some(path) = migration_backup {
+        println!("✓ Backed up database before migration: {}", path.display());
+    }
+    if let Some(config) = config {
+        db.set_risk_config(config.risk);
+        db.set_pathways(config.pathways);
+        db.set_reviews_config(config.reviews);
+    }
+    let state_db_path = paths.db.display().to_string();
 
-    let state = Arc::new(AppState { db: Mutex::new(db) });
+    let state = Arc::new(AppState {
+        db: Mutex::new(db),
+        paths,
+    });
 
     let app = create_router(state);
 
     let addr = format!("0.0.0.0:{}", port);
     println!("Starting server on http://{}", addr);
+    println!("Database: {}", state_db_path);
     println!();
     println!("Available endpoints:");
     println!("  GET  /health");
@@ -575,21 +1241,55 @@ pub async fn start_server(db_path: &str, port: u16) -> Result<()> {
     println!("  GET  /classes/{{class_id}}/assignments");
     println!("  GET  /classes/{{class_id}}/progressions");
     println!("  GET  /classes/{{class_id}}/progress-summary");
+    println!("  GET  /classes/{{class_id}}/gradebook[?format=csv|xlsx]");
+    println!("  GET  /classes/{{class_id}}/calendar");
+    println!("  PUT  /classes/{{class_id}}/calendar");
+    println!("  GET  /classes/{{class_id}}/timezone");
+    println!("  PUT  /classes/{{class_id}}/timezone");
+    println!("  PUT  /classes/{{class_id}}/students/{{student_id}}/timezone");
+    println!("  GET  /classes/{{class_id}}/course");
+    println!("  PUT  /classes/{{class_id}}/course");
+    println!("  GET  /classes/{{class_id}}/benchmark");
+    println!("  GET  /classes/{{class_id}}/benchmark/difficulty");
+    println!("  GET  /classes/{{class_id}}/digest");
+    println!("  GET  /classes/{{class_id}}/digests[?limit=10]");
     println!();
-    println!("Analytics endpoints:");
+    println!("Analytics endpoints (filter with ?night=Tues and/or ?mentor=<id, name or email>):");
     println!("  GET  /classes/{{class_id}}/metrics/completion");
     println!("  GET  /classes/{{class_id}}/metrics/blockers");
-    println!("  GET  /classes/{{class_id}}/metrics/student-health");
+    println!("  GET  /classes/{{class_id}}/metrics/funnel");
+    println!("  GET  /classes/{{class_id}}/metrics/item-analysis[?type=quiz]");
+    println!("  GET  /classes/{{class_id}}/metrics/student-health[?sort=grade_slope]");
     println!("  GET  /classes/{{class_id}}/metrics/progress-over-time");
     println!("  GET  /classes/{{class_id}}/metrics/student-activity[?night=Tues]");
     println!("  GET  /classes/{{class_id}}/metrics/night-summary");
+    println!("  GET  /classes/{{class_id}}/metrics/risk-history");
+    println!("  GET  /classes/{{class_id}}/metrics/expected-pace");
+    println!("  GET  /classes/{{class_id}}/metrics/behind-schedule");
+    println!("  GET  /classes/{{class_id}}/metrics/projected-late");
+    println!("  GET  /classes/{{class_id}}/metrics/time-on-task");
+    println!("  GET  /classes/{{class_id}}/metrics/time-on-task/sections");
+    println!("  GET  /classes/{{class_id}}/metrics/time-outliers");
+    println!("  GET  /classes/{{class_id}}/metrics/review-turnaround");
+    println!("  GET  /classes/{{class_id}}/reviews/pending");
     println!("  GET  /classes/{{class_id}}/students/night/{{night}}");
     println!("  GET  /mentors");
+    println!("  GET  /mentors/{{mentor}}/students");
+    println!("  POST /mentors/{{mentor}}/students");
+    println!("  DELETE /mentors/{{mentor}}/students/{{class_id}}/{{student_id}}");
     println!();
     println!("Student detail endpoints:");
     println!("  GET  /classes/{{class_id}}/students/{{student_id}}/detail");
     println!("  GET  /classes/{{class_id}}/students/{{student_id}}/assignments");
     println!("  GET  /classes/{{class_id}}/students/{{student_id}}/progress-timeline");
+    println!("  GET  /classes/{{class_id}}/students/{{student_id}}/risk-history");
+    println!("  GET  /classes/{{class_id}}/students/{{student_id}}/pathway");
+    println!();
+    println!("Pathway endpoints:");
+    println!("  GET  /pathways");
+    println!("  GET  /pathways/{{pathway}}/students");
+    println!("  GET  /program/rollup");
+    println!("  GET  /program/rollup/pathways");
     println!();
     println!("Dashboard:");
     println!("  http://localhost:{}/dashboard/", port);
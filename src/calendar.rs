@@ -0,0 +1,139 @@
+use anyhow::{anyhow, Result};
+use chrono::{Datelike, Duration, NaiveDate};
+
+use crate::models::CalendarWeek;
+
+/// Teaching calendar for a class. Weeks run from the start date in 7-day
+/// steps; break weeks are labelled separately and don't advance the week
+/// number, so "Week 5" means the fifth week of teaching in every cohort.
+#[derive(Debug, Clone)]
+pub struct ClassCalendar {
+    pub start: NaiveDate,
+    pub end: Option<NaiveDate>,
+    /// Index (0-based, from `start`) of each break week
+    break_indexes: Vec<i64>,
+}
+
+pub fn parse_date(value: &str) -> Result<NaiveDate> {
+    NaiveDate::parse_from_str(value.trim(), "%Y-%m-%d")
+        .map_err(|_| anyhow!("Invalid date '{}': use YYYY-MM-DD", value))
+}
+
+/// Monday of the week containing `date`.
+pub fn monday_of(date: NaiveDate) -> NaiveDate {
+    date - Duration::days(date.weekday().num_days_from_monday() as i64)
+}
+
+/// First day of the class week (counted in 7-day steps from `start`)
+/// containing `date`.
+pub fn week_start(start: NaiveDate, date: NaiveDate) -> NaiveDate {
+    start + Duration::days((date - start).num_days().div_euclid(7) * 7)
+}
+
+impl ClassCalendar {
+    /// `breaks` can be any day inside each break week.
+    pub fn new(start: NaiveDate, end: Option<NaiveDate>, breaks: &[NaiveDate]) -> Self {
+        let mut calendar = Self {
+            start,
+            end,
+            break_indexes: Vec::new(),
+        };
+        let mut indexes: Vec<i64> = breaks
+            .iter()
+            .map(|d| calendar.index_of(*d))
+            .filter(|i| *i >= 0)
+            .collect();
+        indexes.sort();
+        indexes.dedup();
+        calendar.break_indexes = indexes;
+        calendar
+    }
+
+    fn index_of(&self, date: NaiveDate) -> i64 {
+        (date - self.start).num_days().div_euclid(7)
+    }
+
+    fn week_at(&self, index: i64) -> CalendarWeek {
+        let start = self.start + Duration::days(index * 7);
+        let end = start + Duration::days(6);
+        let breaks_before = self.break_indexes.iter().filter(|b| **b < index).count() as i64;
+        let teaching_week = index + 1 - breaks_before;
+
+        let (number, label, is_break) = if index < 0 {
+            (0, "Before start".to_string(), false)
+        } else if self.break_indexes.contains(&index) {
+            let after = teaching_week - 1;
+            (after, format!("Break (after Week {})", after), true)
+        } else {
+            (teaching_week, format!("Week {}", teaching_week), false)
+        };
+
+        CalendarWeek {
+            number,
+            label,
+            start_date: start.format("%Y-%m-%d").to_string(),
+            end_date: end.format("%Y-%m-%d").to_string(),
+            is_break,
+        }
+    }
+
+    /// The calendar week a date falls in. Dates before the start all map to
+    /// a single "Before start" bucket.
+    pub fn week_of(&self, date: NaiveDate) -> CalendarWeek {
+        self.week_at(self.index_of(date).max(-1))
+    }
+
+    /// Sort key for grouping: calendar weeks in order, with everything before
+    /// the start collapsed into one bucket.
+    pub fn week_index(&self, date: NaiveDate) -> i64 {
+        self.index_of(date).max(-1)
+    }
+
+    fn is_break_day(&self, date: NaiveDate) -> bool {
+        self.break_indexes.contains(&self.index_of(date))
+    }
+
+    /// Teaching days (days outside break weeks) from the start through `date`.
+    fn teaching_days_through(&self, date: NaiveDate) -> i64 {
+        self.teaching_days_between(self.start, date)
+    }
+
+    /// Teaching days from `from` through `to`, both inclusive.
+    pub fn teaching_days_between(&self, from: NaiveDate, to: NaiveDate) -> i64 {
+        from.iter_days()
+            .take_while(|d| *d <= to)
+            .filter(|d| !self.is_break_day(*d))
+            .count() as i64
+    }
+
+    /// The date `days` teaching days after `from`, skipping break weeks.
+    pub fn add_teaching_days(&self, from: NaiveDate, days: i64) -> NaiveDate {
+        if days <= 0 {
+            return from;
+        }
+        from.iter_days()
+            .skip(1)
+            .filter(|d| !self.is_break_day(*d))
+            .nth(days as usize - 1)
+            .unwrap_or(NaiveDate::MAX)
+    }
+
+    /// Share of the class's teaching time that has passed by the end of
+    /// `date`, from 0.0 to 1.0. `None` without an end date.
+    pub fn teaching_fraction(&self, date: NaiveDate) -> Option<f64> {
+        let end = self.end?;
+        let total = self.teaching_days_through(end);
+        if total == 0 {
+            return Some(1.0);
+        }
+        let elapsed = self.teaching_days_through(date.min(end));
+        Some(elapsed as f64 / total as f64)
+    }
+
+    /// Every week from the start through the end date (or through `until`
+    /// when no end date is set).
+    pub fn weeks(&self, until: NaiveDate) -> Vec<CalendarWeek> {
+        let last = self.index_of(self.end.unwrap_or(until)).max(0);
+        (0..=last).map(|i| self.week_at(i)).collect()
+    }
+}
@@ -1,5 +1,6 @@
-use crate::config::Config;
+use crate::config::{Config, Paths};
 use crate::db::Database;
+use crate::gradebook::ExportFormat;
 use anyhow::{anyhow, Result};
 use clap::{Parser, Subcommand};
 use std::io::{self, Write};
@@ -13,6 +14,14 @@ pub struct Cli {
 
     #[arg(short, long, global = true)]
     pub config: Option<String>,
+
+    /// Path to the tracker database (overrides `database_path` in the config)
+    #[arg(long, global = true)]
+    pub db: Option<String>,
+
+    /// Named workspace with its own config and database (e.g. spring-2026)
+    #[arg(short, long, global = true)]
+    pub workspace: Option<String>,
 }
 
 #[derive(Subcommand)]
@@ -57,31 +66,172 @@ pub enum Commands {
 
     /// Import student nights and mentor data from CSV files
     Import {
-        /// Path to students CSV (columns: First Name, Last Name, Region, Night)
+        /// Path to students CSV (columns: First Name, Last Name, Region, Night, optional Email)
         #[arg(long)]
         students: Option<String>,
-        /// Path to mentors CSV (columns: Mentor Name, Night)
+        /// Path to mentors CSV (columns: Mentor Name, Night, optional Email)
         #[arg(long)]
         mentors: Option<String>,
+        /// Path to mentor assignments CSV (columns: Mentor, First Name, Last Name, optional Email)
+        #[arg(long)]
+        mentor_students: Option<String>,
+        /// Class (friendly id) to match students in; required with --students,
+        /// optional scope for --mentor-students
+        #[arg(long)]
+        class: Option<String>,
+        /// Write fuzzy, ambiguous and unmatched rows to this CSV for review
+        #[arg(long)]
+        review: Option<String>,
+        /// Show what would change without writing anything
+        #[arg(long)]
+        dry_run: bool,
+    },
+
+    /// Per-class settings
+    Class {
+        #[command(subcommand)]
+        command: ClassCommands,
+    },
+
+    /// Dropout prediction model
+    Model {
+        #[command(subcommand)]
+        command: ModelCommands,
+    },
+
+    /// Program-wide rollup across all active classes
+    Report {
+        /// One section per pathway instead of a single program total
+        #[arg(long)]
+        by_pathway: bool,
+    },
+
+    /// List named workspaces
+    Workspaces,
+
+    /// Database maintenance (backup, restore)
+    Db {
+        #[command(subcommand)]
+        command: DbCommands,
     },
 
     /// Update to the latest version
     Update,
 }
 
-pub async fn handle_init(email: String, password: String, api_base: String) -> Result<()> {
+#[derive(Subcommand)]
+pub enum ClassCommands {
+    /// Show or set the teaching calendar weekly analytics are grouped by
+    Calendar {
+        /// Class friendly id
+        class: String,
+        /// First day of week 1 (YYYY-MM-DD)
+        #[arg(long)]
+        start: Option<String>,
+        /// Last day of the class (YYYY-MM-DD)
+        #[arg(long)]
+        end: Option<String>,
+        /// Any day in a break week (YYYY-MM-DD); repeat for several weeks
+        #[arg(long = "break")]
+        breaks: Vec<String>,
+        /// Remove existing break weeks before adding any given with --break
+        #[arg(long)]
+        clear_breaks: bool,
+    },
+    /// Show or set the timezone hour and weekday analytics use
+    Timezone {
+        /// Class friendly id
+        class: String,
+        /// IANA timezone, e.g. America/New_York
+        timezone: Option<String>,
+        /// Set (or with --clear, remove) an override for one student
+        #[arg(long)]
+        student: Option<String>,
+        /// Go back to the class timezone (or UTC for the class itself)
+        #[arg(long, conflicts_with = "timezone")]
+        clear: bool,
+    },
+    /// Show or set the course a class is a run of
+    Course {
+        /// Class friendly id
+        class: String,
+        /// Course name shared by every run, e.g. data-analysis-module-2
+        course: Option<String>,
+        /// Go back to the course from the friendly id
+        #[arg(long, conflicts_with = "course")]
+        clear: bool,
+    },
+    /// Compare a class's completion curve with earlier runs of its course
+    Benchmark {
+        /// Class friendly id
+        class: String,
+    },
+    /// Export the student x assignment gradebook with section subtotals
+    Gradebook {
+        /// Class friendly id
+        class: String,
+        /// File to write; .csv or .xlsx picks the format
+        output: String,
+        /// csv or xlsx, overriding the file extension
+        #[arg(long)]
+        format: Option<String>,
+    },
+}
+
+#[derive(Subcommand)]
+pub enum ModelCommands {
+    /// Fit a dropout model on finished classes and report holdout accuracy
+    Train {
+        /// Number of weeks from the class start the features are taken from
+        #[arg(long, default_value_t = crate::db::DEFAULT_EARLY_WEEKS)]
+        weeks: i64,
+    },
+}
+
+#[derive(Subcommand)]
+pub enum DbCommands {
+    /// Copy the database to a file (safe while the server is running)
+    Backup {
+        /// Destination file (default: timestamped file in the backup directory)
+        path: Option<String>,
+    },
+
+    /// Replace the database with a backup (the current database is backed up first)
+    Restore { path: String },
+
+    /// Report orphaned rows, duplicate emails and malformed timestamps
+    Check {
+        /// Apply safe repairs (removes rows no analytics query can reach)
+        #[arg(long)]
+        fix: bool,
+    },
+}
+
+pub async fn handle_init(
+    paths: &Paths,
+    db_override: Option<String>,
+    email: String,
+    password: String,
+    api_base: String,
+) -> Result<()> {
     // Save credentials
     let config = Config {
         email: email.clone(),
         password: password.clone(),
         api_base: api_base.clone(),
         check_for_updates: true,
+        database_path: db_override,
+        backup: Default::default(),
+        import: Default::default(),
+        risk: Default::default(),
+        pathways: Default::default(),
+        notifications: Default::default(),
+        reviews: Default::default(),
     };
 
-    let config_path = Config::default_path();
-    config.save(config_path.to_str().unwrap())?;
+    config.save(paths.config_str())?;
 
-    println!("✓ Configuration saved to {}", config_path.display());
+    println!("✓ Configuration saved to {}", paths.config.display());
 
     // Authenticate and fetch classes
     println!("\nAuthenticating...");
@@ -120,10 +270,7 @@ pub async fn handle_init(email: String, password: String, api_base: String) -> R
     };
 
     // Store classes in database
-    let db_path = crate::config::home_dir()
-        .unwrap_or_else(|| std::path::PathBuf::from("."))
-        .join(".cohort-tracker.db");
-    let db = Database::new(db_path.to_str().unwrap())?;
+    let db = open_database(paths)?;
 
     println!();
     for class in &classes {
@@ -140,27 +287,33 @@ pub async fn handle_init(email: String, password: String, api_base: String) -> R
         );
     }
 
-    println!("\n✓ Setup complete! Run 'cargo run -- sync' to fetch data.");
+    match &paths.workspace {
+        Some(workspace) => println!(
+            "\n✓ Setup complete! Run 'cargo run -- sync --workspace {}' to fetch data.",
+            workspace
+        ),
+        None => println!("\n✓ Setup complete! Run 'cargo run -- sync' to fetch data."),
+    }
 
     Ok(())
 }
 
 pub async fn handle_sync(
-    config_path: Option<String>,
+    paths: &Paths,
     class_friendly_id: Option<String>,
     full: bool,
 ) -> Result<()> {
-    let path = config_path.unwrap_or_else(|| Config::default_path().to_str().unwrap().to_string());
-
-    let config = Config::from_file(&path)?;
-    println!("Loading config from: {}", path);
+    let config = Config::from_file(paths.config_str())?;
+    println!("Loading config from: {}", paths.config.display());
 
     // Create database
-    let db_path = crate::config::home_dir()
-        .unwrap_or_else(|| std::path::PathBuf::from("."))
-        .join(".cohort-tracker.db");
-    let db = Database::new(db_path.to_str().unwrap())?;
-    println!("✓ Database initialized: {}", db_path.display());
+    let db = open_database(paths)?;
+    println!("✓ Database initialized: {}", paths.db.display());
+
+    if full && config.backup.auto {
+        let backup = crate::db::rotate_backup(&db, &paths.db, "pre-full-sync", config.backup.keep)?;
+        println!("✓ Backed up database: {}", backup.display());
+    }
 
     // Create provider and sync engine
     let mut provider = Box::new(crate::lms::openclass::OpenClassProvider::new(
@@ -197,20 +350,37 @@ pub async fn handle_sync(
     println!("Progressions: {}", stats.progressions_inserted);
     println!("Time elapsed: {:.2}s", duration.as_secs_f64());
 
+    // A class's first digest lists everything it has, so it's only printed
+    // and sent once there's an earlier sync to compare with
+    for digest in stats
+        .digests
+        .iter()
+        .filter(|d| d.previous_sync_at.is_some())
+    {
+        println!();
+        for line in crate::notify::summary_lines(digest) {
+            println!("{}", line);
+        }
+        if let (Some(url), false) = (&config.notifications.webhook_url, digest.is_empty()) {
+            if let Err(e) = crate::notify::send_digest(url, digest).await {
+                println!("Warning: Could not send digest notification: {}", e);
+            }
+        }
+    }
+
     Ok(())
 }
 
-pub async fn handle_status(config_path: Option<String>) -> Result<()> {
-    let path = config_path.unwrap_or_else(|| Config::default_path().to_str().unwrap().to_string());
-
-    let config = Config::from_file(&path)?;
-    println!("Config: {}", path);
+pub async fn handle_status(paths: &Paths) -> Result<()> {
+    let config = Config::from_file(paths.config_str())?;
+    if let Some(workspace) = &paths.workspace {
+        println!("Workspace: {}", workspace);
+    }
+    println!("Config: {}", paths.config.display());
+    println!("Database: {}", paths.db.display());
     println!("Email: {}", config.email);
 
-    let db_path = crate::config::home_dir()
-        .unwrap_or_else(|| std::path::PathBuf::from("."))
-        .join(".cohort-tracker.db");
-    let db = Database::new(db_path.to_str().unwrap())?;
+    let db = open_database(paths)?;
 
     // Show per-class stats
     let classes = db.get_active_classes()?;
@@ -239,118 +409,259 @@ pub async fn handle_status(config_path: Option<String>) -> Result<()> {
     Ok(())
 }
 
-pub async fn handle_server(config_path: Option<String>, port: u16) -> Result<()> {
-    let _path = config_path.unwrap_or_else(|| Config::default_path().to_str().unwrap().to_string());
-
-    let db_path = crate::config::home_dir()
-        .unwrap_or_else(|| std::path::PathBuf::from("."))
-        .join(".cohort-tracker.db");
-
-    crate::api::start_server(db_path.to_str().unwrap(), port).await
+pub async fn handle_server(paths: &Paths, port: u16) -> Result<()> {
+    paths.ensure_db_dir()?;
+    crate::api::start_server(paths.clone(), port).await
 }
 
 pub async fn handle_import(
+    paths: &Paths,
     students_path: Option<String>,
     mentors_path: Option<String>,
+    mentor_students_path: Option<String>,
+    class_friendly_id: Option<String>,
+    review_path: Option<String>,
+    dry_run: bool,
 ) -> Result<()> {
-    use std::fs::File;
-    use std::io::{BufRead, BufReader};
+    use crate::import::{self, MatchMethod, StudentMatch};
 
-    let db_path = crate::config::home_dir()
-        .unwrap_or_else(|| std::path::PathBuf::from("."))
-        .join(".cohort-tracker.db");
-    let db = Database::new(db_path.to_str().unwrap())?;
-    println!("Database: {}", db_path.display());
+    let import_config = Config::from_file(paths.config_str())
+        .map(|c| c.import)
+        .unwrap_or_default();
+
+    // Nights are set per enrollment, so the class must be explicit
+    if students_path.is_some() && class_friendly_id.is_none() {
+        return Err(anyhow!(
+            "--students needs --class: nights are imported for one class at a time"
+        ));
+    }
+
+    let db = open_database(paths)?;
+    println!("Database: {}", paths.db.display());
+    if dry_run {
+        println!("Dry run: nothing will be written");
+    }
 
     // Import students CSV
-    if let Some(path) = students_path {
+    if let (Some(path), Some(friendly_id)) = (students_path, &class_friendly_id) {
+        let class = db.get_class_by_friendly_id(friendly_id)?;
+
         println!("\nImporting students from: {}", path);
-        let file = File::open(&path)?;
-        let reader = BufReader::new(file);
-        let mut updated = 0;
-        let mut not_found = 0;
-        let mut skipped = 0;
-
-        for (i, line) in reader.lines().enumerate() {
-            let line = line?;
-            if i == 0 {
-                // Skip header row
-                continue;
+        println!(
+            "Matching only students in {} ({})",
+            class.name, class.friendly_id
+        );
+        let (rows, skipped) = import::read_students_csv(&path, &import_config)?;
+        let plan = import::plan_student_import(&db, rows, &class.id)?;
+
+        let mut updates = Vec::new();
+        let mut not_found = Vec::new();
+        let mut ambiguous = Vec::new();
+        for planned in &plan {
+            match &planned.outcome {
+                StudentMatch::Update { student, method } => {
+                    updates.push((&planned.row, student, *method))
+                }
+                StudentMatch::NotFound => not_found.push(&planned.row),
+                StudentMatch::Ambiguous { candidates, .. } => {
+                    ambiguous.push((&planned.row, candidates))
+                }
             }
+        }
 
-            // TODO: use a proper CSV parser instead of split(',')
-            let fields: Vec<&str> = line.split(',').collect();
-            if fields.len() >= 4 {
-                let first_name = fields[0].trim();
-                let last_name = fields[1].trim();
-                let region = fields[2].trim();
-                let night = fields[3].trim();
-
-                if first_name.is_empty() || last_name.is_empty() {
-                    skipped += 1;
-                    continue;
+        let verb = if dry_run { "Would update" } else { "Updated" };
+        let mut fuzzy = 0;
+        for (row, student, method) in &updates {
+            let how = match method {
+                MatchMethod::Email => "by email".to_string(),
+                MatchMethod::Name => "by name".to_string(),
+                MatchMethod::Fuzzy => {
+                    fuzzy += 1;
+                    format!("FUZZY match for '{} {}'", row.first_name, row.last_name)
                 }
-
-                match db.update_student_night(first_name, last_name, region, night)? {
-                    true => {
-                        updated += 1;
-                        println!(
-                            "  Updated: {} {} -> {} ({})",
-                            first_name, last_name, night, region
-                        );
-                    }
-                    false => {
-                        not_found += 1;
-                        println!("  Not found: {} {}", first_name, last_name);
-                    }
+                MatchMethod::EmailMismatch => {
+                    fuzzy += 1;
+                    format!(
+                        "EMAIL MISMATCH: {} on file, {} in file",
+                        student.email, row.email
+                    )
                 }
-            } else {
-                skipped += 1;
+            };
+            println!(
+                "  {}: {} {} in {} -> {} ({}) [{}]",
+                verb,
+                student.first_name,
+                student.last_name,
+                student.class_id,
+                row.night,
+                row.region,
+                how
+            );
+        }
+        for row in &not_found {
+            println!(
+                "  Not found (line {}): {} {}",
+                row.line, row.first_name, row.last_name
+            );
+        }
+        for (row, candidates) in &ambiguous {
+            println!(
+                "  Ambiguous (line {}): {} {} matches {} enrollments:",
+                row.line,
+                row.first_name,
+                row.last_name,
+                candidates.len()
+            );
+            for student in candidates.iter() {
+                println!(
+                    "      {} {} {} <{}> in {}",
+                    student.id,
+                    student.first_name,
+                    student.last_name,
+                    student.email,
+                    student.class_id
+                );
             }
         }
+        for row in &skipped {
+            println!("  Skipped (line {}): {}", row.line, row.reason);
+        }
 
-        println!("\nStudent import complete:");
-        println!("  Updated: {}", updated);
-        println!("  Not found: {}", not_found);
-        println!("  Skipped: {}", skipped);
+        if let Some(review_path) = &review_path {
+            let count = import::write_review_report(review_path, &plan)?;
+            println!("  Wrote {} row(s) needing review to {}", count, review_path);
+        }
+
+        if !dry_run {
+            import::apply_student_import(&db, &plan)?;
+            db.link_people()?;
+        }
+
+        println!(
+            "\nStudent import {}:",
+            if dry_run { "preview" } else { "complete" }
+        );
+        println!("  {}: {}", verb, updates.len());
+        println!("  Not found: {}", not_found.len());
+        println!("  Fuzzy or email mismatch matches (check these): {}", fuzzy);
+        println!("  Ambiguous (not changed): {}", ambiguous.len());
+        println!("  Skipped: {}", skipped.len());
     }
 
-    // Import mentors CSV
+    // Import mentors CSV (upserted by email or name per night so ids stay stable)
     if let Some(path) = mentors_path {
         println!("\nImporting mentors from: {}", path);
+        let (rows, skipped) = import::read_mentors_csv(&path, &import_config)?;
 
-        // Clear existing mentors
-        db.clear_mentors()?;
-        println!("  Cleared existing mentors");
-
-        let file = File::open(&path)?;
-        let reader = BufReader::new(file);
-        let mut imported = 0;
-
-        for (i, line) in reader.lines().enumerate() {
-            let line = line?;
-            if i == 0 {
-                // Skip header row
-                continue;
+        let mut added = 0;
+        let mut updated = 0;
+        for row in &rows {
+            let email = Some(row.email.as_str()).filter(|e| !e.is_empty());
+            let created = if dry_run {
+                db.find_mentor_for_night(&row.name, email, &row.night)?
+                    .is_none()
+            } else {
+                db.upsert_mentor(&row.name, email, &row.night)?.1
+            };
+
+            let verb = match (dry_run, created) {
+                (true, true) => "Would add",
+                (true, false) => "Would update",
+                (false, true) => "Added",
+                (false, false) => "Updated",
+            };
+            if created {
+                added += 1;
+            } else {
+                updated += 1;
             }
+            println!("  {}: {} ({})", verb, row.name, row.night);
+        }
+        for row in &skipped {
+            println!("  Skipped (line {}): {}", row.line, row.reason);
+        }
 
-            let fields: Vec<&str> = line.split(',').collect();
-            if fields.len() >= 2 {
-                let name = fields[0].trim();
-                let night = fields[1].trim();
+        println!(
+            "\nMentor import {}:",
+            if dry_run { "preview" } else { "complete" }
+        );
+        println!("  Added: {}", added);
+        println!("  Updated: {}", updated);
+        println!("  Skipped: {}", skipped.len());
+    }
 
-                if name.is_empty() || night.is_empty() {
-                    continue;
+    // Import mentor-to-student assignments
+    if let Some(path) = mentor_students_path {
+        let class = match &class_friendly_id {
+            Some(friendly_id) => Some(db.get_class_by_friendly_id(friendly_id)?),
+            None => None,
+        };
+
+        println!("\nImporting mentor assignments from: {}", path);
+        let (rows, skipped) = import::read_mentor_students_csv(&path, &import_config)?;
+        let plan = import::plan_mentor_students(&db, rows, class.as_ref().map(|c| c.id.as_str()))?;
+
+        let verb = if dry_run { "Would assign" } else { "Assigned" };
+        let mut assignable = 0;
+        let mut problems = 0;
+        for planned in &plan {
+            let row = &planned.row;
+            let student_label = format!("{} {}", row.student.first_name, row.student.last_name);
+            match (&planned.mentor, &planned.outcome) {
+                (None, _) => {
+                    problems += 1;
+                    println!(
+                        "  Mentor not found (line {}): {}",
+                        row.student.line, row.mentor
+                    );
+                }
+                (Some(mentor), StudentMatch::Update { student, .. }) => {
+                    assignable += 1;
+                    println!(
+                        "  {}: {} {} in {} -> {}",
+                        verb, student.first_name, student.last_name, student.class_id, mentor.name
+                    );
+                }
+                (Some(_), StudentMatch::NotFound) => {
+                    problems += 1;
+                    println!(
+                        "  Student not found (line {}): {}",
+                        row.student.line,
+                        student_label.trim()
+                    );
+                }
+                (Some(_), StudentMatch::Ambiguous { candidates, .. }) => {
+                    problems += 1;
+                    println!(
+                        "  Ambiguous (line {}): {} matches {} enrollments",
+                        row.student.line,
+                        student_label.trim(),
+                        candidates.len()
+                    );
                 }
-
-                db.import_mentor(name, night)?;
-                imported += 1;
-                println!("  Imported: {} ({})", name, night);
             }
         }
+        for row in &skipped {
+            println!("  Skipped (line {}): {}", row.line, row.reason);
+        }
+
+        if !dry_run {
+            import::apply_mentor_students(&db, &plan)?;
+        }
 
-        println!("\nMentor import complete:");
-        println!("  Imported: {}", imported);
+        println!(
+            "\nMentor assignment {}:",
+            if dry_run { "preview" } else { "complete" }
+        );
+        println!("  {}: {}", verb, assignable);
+        println!("  Not applied: {}", problems);
+        println!("  Skipped: {}", skipped.len());
+    }
+
+    if dry_run {
+        println!("\n=== Dry Run Complete ===");
+        println!("Run again without --dry-run to apply these changes.");
+        return Ok(());
     }
 
     // Show summary
@@ -362,11 +673,8 @@ pub async fn handle_import(
     Ok(())
 }
 
-pub async fn handle_list(all: bool) -> Result<()> {
-    let db_path = crate::config::home_dir()
-        .unwrap_or_else(|| std::path::PathBuf::from("."))
-        .join(".cohort-tracker.db");
-    let db = Database::new(db_path.to_str().unwrap())?;
+pub async fn handle_list(paths: &Paths, all: bool) -> Result<()> {
+    let db = open_database(paths)?;
 
     let classes = if all {
         db.get_classes()?
@@ -392,11 +700,8 @@ pub async fn handle_list(all: bool) -> Result<()> {
     Ok(())
 }
 
-pub async fn handle_activate(friendly_ids: Vec<String>) -> Result<()> {
-    let db_path = crate::config::home_dir()
-        .unwrap_or_else(|| std::path::PathBuf::from("."))
-        .join(".cohort-tracker.db");
-    let db = Database::new(db_path.to_str().unwrap())?;
+pub async fn handle_activate(paths: &Paths, friendly_ids: Vec<String>) -> Result<()> {
+    let db = open_database(paths)?;
 
     for friendly_id in friendly_ids {
         match db.get_class_by_friendly_id(&friendly_id) {
@@ -424,11 +729,8 @@ pub async fn handle_activate(friendly_ids: Vec<String>) -> Result<()> {
     Ok(())
 }
 
-pub async fn handle_deactivate(friendly_ids: Vec<String>) -> Result<()> {
-    let db_path = crate::config::home_dir()
-        .unwrap_or_else(|| std::path::PathBuf::from("."))
-        .join(".cohort-tracker.db");
-    let db = Database::new(db_path.to_str().unwrap())?;
+pub async fn handle_deactivate(paths: &Paths, friendly_ids: Vec<String>) -> Result<()> {
+    let db = open_database(paths)?;
 
     for friendly_id in friendly_ids {
         match db.get_class_by_friendly_id(&friendly_id) {
@@ -455,3 +757,458 @@ pub async fn handle_deactivate(friendly_ids: Vec<String>) -> Result<()> {
 
     Ok(())
 }
+
+pub async fn handle_db(paths: &Paths, command: DbCommands) -> Result<()> {
+    let keep = Config::from_file(paths.config_str())
+        .map(|c| c.backup.keep)
+        .unwrap_or(crate::db::DEFAULT_BACKUP_KEEP);
+
+    match command {
+        DbCommands::Backup { path } => {
+            let db = open_database(paths)?;
+            let dest = match path {
+                Some(path) => {
+                    db.backup_to(&path)?;
+                    std::path::PathBuf::from(path)
+                }
+                None => crate::db::rotate_backup(&db, &paths.db, "manual", keep)?,
+            };
+            println!("✓ Backed up {} to {}", paths.db.display(), dest.display());
+        }
+        DbCommands::Restore { path } => {
+            let version = crate::db::validate_backup(&path)?;
+            println!("✓ Backup is valid (schema version {})", version);
+
+            if let Some(safety) = crate::db::restore_database(&path, &paths.db, keep)? {
+                println!("✓ Saved current database to {}", safety.display());
+            }
+
+            // Opening runs any migrations the restored file needs
+            open_database(paths)?;
+            println!("✓ Restored {} from {}", paths.db.display(), path);
+            println!("Restart the server if it is running so it picks up the restored data.");
+        }
+        DbCommands::Check { fix } => {
+            let db = open_database(paths)?;
+            let report = if fix {
+                let backup = crate::db::rotate_backup(&db, &paths.db, "pre-fix", keep)?;
+                println!("✓ Backed up database: {}", backup.display());
+                db.repair_integrity()?
+            } else {
+                db.check_integrity()?
+            };
+            print_integrity_report(&report, fix);
+        }
+    }
+
+    Ok(())
+}
+
+pub async fn handle_class(paths: &Paths, command: ClassCommands) -> Result<()> {
+    match command {
+        ClassCommands::Calendar {
+            class,
+            start,
+            end,
+            breaks,
+            clear_breaks,
+        } => {
+            let db = open_database(paths)?;
+            let class = db.get_class_by_friendly_id(&class)?;
+
+            if start.is_some() || end.is_some() || !breaks.is_empty() || clear_breaks {
+                let mut settings = db.get_calendar_settings(&class.id)?;
+                if let Some(start) = start {
+                    settings.start_date = Some(crate::calendar::parse_date(&start)?);
+                }
+                if let Some(end) = end {
+                    settings.end_date = Some(crate::calendar::parse_date(&end)?);
+                }
+                if clear_breaks {
+                    settings.break_weeks.clear();
+                }
+                for date in &breaks {
+                    settings
+                        .break_weeks
+                        .push(crate::calendar::parse_date(date)?);
+                }
+                db.set_calendar_settings(&class.id, &settings)?;
+                println!("✓ Updated calendar for {}", class.name);
+            }
+
+            let info = db.get_class_calendar_info(&class.id)?;
+            println!("\nCalendar for {}:", class.name);
+            match (&info.start_date, info.weeks.first()) {
+                (Some(start), _) => println!("  Start: {}", start),
+                (None, Some(first)) => println!(
+                    "  Start: {} (not set; counting from the first completion)",
+                    first.start_date
+                ),
+                (None, None) => println!("  Start: not set"),
+            }
+            println!("  End:   {}", info.end_date.as_deref().unwrap_or("not set"));
+
+            if !info.weeks.is_empty() {
+                println!();
+                for week in &info.weeks {
+                    println!(
+                        "  {:<24} {} – {}",
+                        week.label, week.start_date, week.end_date
+                    );
+                }
+            }
+        }
+        ClassCommands::Timezone {
+            class,
+            timezone,
+            student,
+            clear,
+        } => {
+            let db = open_database(paths)?;
+            let class = db.get_class_by_friendly_id(&class)?;
+
+            if timezone.is_some() || clear {
+                match &student {
+                    Some(student_id) => {
+                        if !db.set_student_timezone(&class.id, student_id, timezone.as_deref())? {
+                            return Err(anyhow!(
+                                "Student '{}' not found in {}",
+                                student_id,
+                                class.name
+                            ));
+                        }
+                        println!("✓ Updated timezone for student {}", student_id);
+                    }
+                    None => {
+                        db.set_class_timezone(&class.id, timezone.as_deref())?;
+                        println!("✓ Updated timezone for {}", class.name);
+                    }
+                }
+            }
+
+            let info = db.get_class_timezone_info(&class.id)?;
+            println!("\nTimezone for {}:", class.name);
+            println!(
+                "  Class: {}",
+                info.timezone.as_deref().unwrap_or("UTC (not set)")
+            );
+            for o in &info.student_overrides {
+                println!("  Student {}: {}", o.student_id, o.timezone);
+            }
+        }
+        ClassCommands::Course {
+            class,
+            course,
+            clear,
+        } => {
+            let db = open_database(paths)?;
+            let class = db.get_class_by_friendly_id(&class)?;
+
+            if course.is_some() || clear {
+                db.set_class_course(&class.id, course.as_deref())?;
+                println!("✓ Updated course for {}", class.name);
+            }
+
+            let info = db
+                .get_class_course_info(&class.id)?
+                .ok_or_else(|| anyhow!("Class '{}' not found", class.friendly_id))?;
+            let source = if info.explicit {
+                "set"
+            } else {
+                "from friendly id"
+            };
+            println!("\nCourse for {}: {} ({})", class.name, info.course, source);
+            println!("\nRuns:");
+            for run in &info.runs {
+                println!(
+                    "  {:<40} {}",
+                    run.friendly_id,
+                    run.start_date.as_deref().unwrap_or("not started")
+                );
+            }
+        }
+        ClassCommands::Benchmark { class } => {
+            let db = open_database(paths)?;
+            let class = db.get_class_by_friendly_id(&class)?;
+            let benchmark = db
+                .get_cohort_benchmark(&class.id)?
+                .ok_or_else(|| anyhow!("Class '{}' not found", class.friendly_id))?;
+            print_benchmark(&class, &benchmark);
+        }
+        ClassCommands::Gradebook {
+            class,
+            output,
+            format,
+        } => {
+            let format = match format {
+                Some(format) => ExportFormat::parse(&format)?,
+                None => ExportFormat::from_path(&output)?,
+            };
+            let db = open_database(paths)?;
+            let class = db.get_class_by_friendly_id(&class)?;
+            let gradebook = db.get_gradebook(&class.id, &crate::db::AnalyticsFilter::default())?;
+            std::fs::write(&output, format.export(&gradebook)?)
+                .map_err(|e| anyhow!("Failed to write {}: {}", output, e))?;
+            println!(
+                "✓ Wrote gradebook for {} ({} students, {} assignments) to {}",
+                class.name,
+                gradebook.students.len(),
+                gradebook.assignments.len(),
+                output
+            );
+        }
+    }
+
+    Ok(())
+}
+
+fn print_benchmark(class: &crate::models::Class, benchmark: &crate::models::CohortBenchmark) {
+    let pct = |value: Option<f64>| match value {
+        Some(v) => format!("{:.0}%", v * 100.0),
+        None => "-".to_string(),
+    };
+
+    println!("\nBenchmark for {} ({}):", class.name, benchmark.course);
+    let earlier = benchmark
+        .weeks
+        .iter()
+        .map(|w| w.historical_runs)
+        .max()
+        .unwrap_or(0);
+    match &benchmark.previous_class_id {
+        Some(previous) => {
+            let previous = benchmark
+                .runs
+                .iter()
+                .find(|r| &r.class_id == previous)
+                .map_or(previous.as_str(), |r| r.friendly_id.as_str());
+            println!("  Previous run: {} ({} earlier run(s))", previous, earlier);
+        }
+        None => {
+            println!("  No earlier runs of this course to compare with.");
+            return;
+        }
+    }
+
+    println!(
+        "\n  {:<6} {:>8} {:>9} {:>8} {:>13}",
+        "Week", "Current", "Previous", "Median", "IQR"
+    );
+    for week in &benchmark.weeks {
+        let iqr = match (week.p25, week.p75) {
+            (Some(p25), Some(p75)) => format!("{:.0}–{:.0}%", p25 * 100.0, p75 * 100.0),
+            _ => "-".to_string(),
+        };
+        println!(
+            "  {:<6} {:>8} {:>9} {:>8} {:>13}",
+            week.week,
+            pct(week.current),
+            pct(week.previous),
+            pct(week.median),
+            iqr
+        );
+    }
+}
+
+pub async fn handle_model(paths: &Paths, command: ModelCommands) -> Result<()> {
+    match command {
+        ModelCommands::Train { weeks } => {
+            if weeks < 1 {
+                return Err(anyhow!("--weeks must be at least 1"));
+            }
+            let db = open_database(paths)?;
+            println!(
+                "Training dropout model on the first {} week(s) of finished classes...",
+                weeks
+            );
+            let report = db.train_dropout_model(weeks)?;
+
+            println!("\n✓ Trained on {} class(es)", report.classes);
+            println!(
+                "  Students: {} training, {} holdout ({:.0}% dropped out)",
+                report.train_size,
+                report.holdout_size,
+                report.dropout_rate * 100.0
+            );
+            println!(
+                "  Holdout accuracy: {:.1}% (always guessing the most common outcome: {:.1}%)",
+                report.holdout_accuracy * 100.0,
+                report.baseline_accuracy * 100.0
+            );
+            println!("\n  Coefficients (standardized; positive = more likely to drop out):");
+            for coefficient in &report.coefficients {
+                println!("    {:<22} {:+.3}", coefficient.feature, coefficient.weight);
+            }
+            println!("\nStudent health and detail now include dropout_probability.");
+        }
+    }
+
+    Ok(())
+}
+
+pub async fn handle_report(paths: &Paths, by_pathway: bool) -> Result<()> {
+    let db = open_database(paths)?;
+    let rollups = if by_pathway {
+        db.get_pathway_rollups()?
+    } else {
+        vec![db.get_program_rollup()?]
+    };
+
+    if rollups.iter().all(|r| r.modules.is_empty()) {
+        println!("No active classes. Run 'init' or 'activate' first.");
+        return Ok(());
+    }
+
+    for rollup in &rollups {
+        print_rollup(rollup);
+    }
+    Ok(())
+}
+
+fn print_rollup(rollup: &crate::models::ProgramRollup) {
+    const MAX_ROWS: usize = 5;
+
+    match &rollup.pathway {
+        Some(pathway) => println!("\n=== Pathway: {} ===", pathway),
+        None => println!("\n=== Program ==="),
+    }
+    println!(
+        "Students: {} across {} class(es)",
+        rollup.total_students,
+        rollup.modules.len()
+    );
+    println!(
+        "Risk: {} critical, {} high, {} medium, {} low",
+        rollup.risk.critical, rollup.risk.high, rollup.risk.medium, rollup.risk.low
+    );
+
+    println!("\n  Modules:");
+    for module in &rollup.modules {
+        println!(
+            "    {:<40} {:>4} students  {:>5.1}% complete  {} at risk",
+            module.friendly_id,
+            module.students,
+            module.completion_rate * 100.0,
+            module.risk.critical + module.risk.high
+        );
+    }
+
+    if !rollup.blockers.is_empty() {
+        println!("\n  Blockers:");
+        for blocker in rollup.blockers.iter().take(MAX_ROWS) {
+            println!(
+                "    {:>5.1}%  {} ({})",
+                blocker.completion_rate * 100.0,
+                blocker.name,
+                blocker.friendly_id
+            );
+        }
+    }
+
+    if !rollup.worst_sections.is_empty() {
+        println!("\n  Weakest sections:");
+        for section in rollup.worst_sections.iter().take(MAX_ROWS) {
+            println!(
+                "    {:>5.1}%  {} ({})",
+                section.completion_rate * 100.0,
+                section.section,
+                section.friendly_id
+            );
+        }
+    }
+}
+
+fn print_integrity_report(report: &crate::models::IntegrityReport, fixed: bool) {
+    use crate::db::integrity::*;
+
+    const MAX_EXAMPLES: usize = 10;
+
+    if fixed {
+        println!("✓ Repaired {} row(s)", report.fixed);
+    }
+
+    if report.issues.is_empty() {
+        println!("✓ No integrity problems found");
+        return;
+    }
+
+    let kinds = [
+        (ORPHANED_PROGRESSION_STUDENT, "Progressions with no student"),
+        (
+            ORPHANED_PROGRESSION_ASSIGNMENT,
+            "Progressions with no assignment",
+        ),
+        (STUDENT_WITHOUT_CLASS, "Students with no class"),
+        (ASSIGNMENT_WITHOUT_CLASS, "Assignments with no class"),
+        (DUPLICATE_EMAIL, "Duplicate emails"),
+        (EMPTY_COMPLETED_AT, "Progressions with empty completed_at"),
+        (UNPARSEABLE_TIMESTAMP, "Unparseable timestamps"),
+        (ASSIGNMENT_WITHOUT_SECTION, "Assignments without a section"),
+    ];
+
+    for (kind, label) in kinds {
+        let matching: Vec<_> = report.issues.iter().filter(|i| i.kind == kind).collect();
+        if matching.is_empty() {
+            continue;
+        }
+
+        println!("\n{} ({})", label, matching.len());
+        for issue in matching.iter().take(MAX_EXAMPLES) {
+            match &issue.class_id {
+                Some(class_id) => {
+                    println!("  {} [{}]: {}", issue.record_id, class_id, issue.detail)
+                }
+                None => println!("  {}: {}", issue.record_id, issue.detail),
+            }
+        }
+        if matching.len() > MAX_EXAMPLES {
+            println!("  ... and {} more", matching.len() - MAX_EXAMPLES);
+        }
+    }
+
+    let fixable = report.issues.iter().filter(|i| i.fixable).count();
+    println!("\n{} issue(s) found", report.issues.len());
+    if fixable > 0 {
+        println!(
+            "Run 'db check --fix' to repair {} of them, then 'sync --full' to re-fetch removed progressions.",
+            fixable
+        );
+    }
+}
+
+pub async fn handle_workspaces() -> Result<()> {
+    let workspaces = crate::config::list_workspaces()?;
+
+    if workspaces.is_empty() {
+        println!("No workspaces yet. Create one with 'init --workspace <name>'.");
+        return Ok(());
+    }
+
+    println!("Workspaces:");
+    for name in workspaces {
+        let dir = crate::config::workspace_dir(&name)?;
+        println!("  {} ({})", name, dir.display());
+    }
+
+    Ok(())
+}
+
+fn open_database(paths: &Paths) -> Result<Database> {
+    paths.ensure_db_dir()?;
+    // Commands that don't need credentials still work without a config
+    let config = Config::from_file(paths.config_str()).ok();
+    let backup = config
+        .as_ref()
+        .map(|c| c.backup.clone())
+        .unwrap_or_default();
+    let (mut db, migration_backup) = Database::open(paths.db_str(), &backup)?;
+    if let Some(path) = migration_backup {
+        println!("✓ Backed up database before migration: {}", path.display());
+    }
+    if let Some(config) = config {
+        db.set_risk_config(config.risk);
+        db.set_pathways(config.pathways);
+        db.set_reviews_config(config.reviews);
+    }
+    Ok(db)
+}
@@ -1,7 +1,8 @@
 use anyhow::{anyhow, Result};
 use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
 use std::fs;
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
 
 #[derive(Debug, Serialize, Deserialize, Clone)]
 pub struct Config {
@@ -10,12 +11,233 @@ pub struct Config {
     pub api_base: String,
     #[serde(default = "default_check_for_updates")]
     pub check_for_updates: bool,
+    /// Overrides where the tracker database lives (default: ~/.cohort-tracker.db)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub database_path: Option<String>,
+    #[serde(default)]
+    pub backup: BackupConfig,
+    #[serde(default, skip_serializing_if = "ImportConfig::is_empty")]
+    pub import: ImportConfig,
+    #[serde(default, skip_serializing_if = "RiskConfig::is_default")]
+    pub risk: RiskConfig,
+    /// Pathway for classes whose friendly id doesn't carry it, keyed by
+    /// friendly id
+    ///
+    /// ```toml
+    /// [pathways]
+    /// "intro-web-jan" = "web-development"
+    /// ```
+    #[serde(default, skip_serializing_if = "HashMap::is_empty")]
+    pub pathways: HashMap<String, String>,
+    #[serde(default, skip_serializing_if = "NotificationsConfig::is_empty")]
+    pub notifications: NotificationsConfig,
+    #[serde(default, skip_serializing_if = "ReviewsConfig::is_default")]
+    pub reviews: ReviewsConfig,
+}
+
+/// Automatic backups taken before `sync --full` and before migrations.
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct BackupConfig {
+    #[serde(default = "default_backup_auto")]
+    pub auto: bool,
+    /// Number of automatic backups to keep; older ones are deleted
+    #[serde(default = "default_backup_keep")]
+    pub keep: usize,
+}
+
+impl Default for BackupConfig {
+    fn default() -> Self {
+        Self {
+            auto: default_backup_auto(),
+            keep: default_backup_keep(),
+        }
+    }
+}
+
+/// Extra CSV header names for `import`, checked before the built-in ones.
+///
+/// ```toml
+/// [import.columns]
+/// night = ["Cohort Day"]
+/// ```
+#[derive(Debug, Serialize, Deserialize, Clone, Default)]
+pub struct ImportConfig {
+    #[serde(default)]
+    pub columns: HashMap<String, Vec<String>>,
+}
+
+impl ImportConfig {
+    pub fn aliases(&self, field: &str) -> &[String] {
+        self.columns.get(field).map(|v| v.as_slice()).unwrap_or(&[])
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.columns.is_empty()
+    }
+}
+
+/// Where sync digests are sent after each sync.
+///
+/// ```toml
+/// [notifications]
+/// webhook_url = "https://hooks.slack.com/services/..."
+/// ```
+#[derive(Debug, Serialize, Deserialize, Clone, Default)]
+pub struct NotificationsConfig {
+    /// Receives a JSON POST with `text` and `digest` for each class that
+    /// changed
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub webhook_url: Option<String>,
+}
+
+impl NotificationsConfig {
+    pub fn is_empty(&self) -> bool {
+        self.webhook_url.is_none()
+    }
+}
+
+/// Assignment types that complete without a mentor review. Submissions of
+/// every other type count toward review turnaround and the pending queue.
+///
+/// ```toml
+/// [reviews]
+/// auto_complete_types = ["lesson", "video"]
+/// ```
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+#[serde(default)]
+pub struct ReviewsConfig {
+    pub auto_complete_types: Vec<String>,
+}
+
+impl Default for ReviewsConfig {
+    fn default() -> Self {
+        Self {
+            auto_complete_types: vec!["lesson".to_string()],
+        }
+    }
+}
+
+impl ReviewsConfig {
+    pub fn is_default(&self) -> bool {
+        *self == Self::default()
+    }
+
+    /// Whether submissions of `assignment_type` wait for a review.
+    pub fn is_reviewed(&self, assignment_type: &str) -> bool {
+        !self
+            .auto_complete_types
+            .iter()
+            .any(|t| t.eq_ignore_ascii_case(assignment_type))
+    }
+}
+
+/// Risk scoring. Each factor adds points to a student's score and the
+/// thresholds turn the score into a level. The defaults score completion
+/// like the original 25/50/75% bands, with the other signals on top.
+///
+/// ```toml
+/// [risk.weights]
+/// inactivity = 3.0
+///
+/// [risk.thresholds]
+/// critical = 50.0
+/// ```
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+#[serde(default)]
+pub struct RiskConfig {
+    pub weights: RiskWeights,
+    pub thresholds: RiskThresholds,
+    /// Average grade below which the grade factor applies (0.0 to 1.0)
+    pub passing_grade: f64,
+    /// Days without a completion before inactivity counts
+    pub inactive_grace_days: i64,
+    /// Most recent grades the grade slope and volatility cover
+    pub grade_window: usize,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+#[serde(default)]
+pub struct RiskWeights {
+    /// Points at 0% complete, scaled down to 0 at 100%
+    pub completion: f64,
+    /// Points per assignment behind the expected pace
+    pub behind_schedule: f64,
+    pub behind_schedule_max: f64,
+    /// Points at a zero average grade, scaled down to 0 at the passing grade
+    pub grade: f64,
+    /// Points for recent grades 20 percentage points below earlier ones
+    pub grade_trend: f64,
+    /// Points per day inactive beyond the grace period
+    pub inactivity: f64,
+    pub inactivity_max: f64,
+    /// Points when completions in the last two weeks drop to zero from the two before
+    pub velocity_drop: f64,
+}
+
+/// Score a student must exceed for each level; anything lower is "low".
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+#[serde(default)]
+pub struct RiskThresholds {
+    pub critical: f64,
+    pub high: f64,
+    pub medium: f64,
+}
+
+impl RiskConfig {
+    pub fn is_default(&self) -> bool {
+        *self == Self::default()
+    }
+}
+
+impl Default for RiskConfig {
+    fn default() -> Self {
+        Self {
+            weights: RiskWeights::default(),
+            thresholds: RiskThresholds::default(),
+            passing_grade: 0.7,
+            inactive_grace_days: 7,
+            grade_window: 5,
+        }
+    }
+}
+
+impl Default for RiskWeights {
+    fn default() -> Self {
+        Self {
+            completion: 60.0,
+            behind_schedule: 5.0,
+            behind_schedule_max: 25.0,
+            grade: 20.0,
+            grade_trend: 10.0,
+            inactivity: 2.0,
+            inactivity_max: 30.0,
+            velocity_drop: 15.0,
+        }
+    }
+}
+
+impl Default for RiskThresholds {
+    fn default() -> Self {
+        Self {
+            critical: 45.0,
+            high: 30.0,
+            medium: 15.0,
+        }
+    }
 }
 
 fn default_check_for_updates() -> bool {
     true
 }
 
+fn default_backup_auto() -> bool {
+    true
+}
+
+fn default_backup_keep() -> usize {
+    crate::db::DEFAULT_BACKUP_KEEP
+}
+
 impl Config {
     pub fn from_file(path: &str) -> Result<Self> {
         let content =
@@ -24,6 +246,11 @@ impl Config {
     }
 
     pub fn save(&self, path: &str) -> Result<()> {
+        if let Some(parent) = Path::new(path).parent() {
+            if !parent.as_os_str().is_empty() {
+                fs::create_dir_all(parent)?;
+            }
+        }
         let content = toml::to_string_pretty(self)?;
         fs::write(path, content)?;
         Ok(())
@@ -34,6 +261,126 @@ impl Config {
             .unwrap_or_else(|| PathBuf::from("."))
             .join(".cohort-tracker.toml")
     }
+
+    pub fn default_db_path() -> PathBuf {
+        home_dir()
+            .unwrap_or_else(|| PathBuf::from("."))
+            .join(".cohort-tracker.db")
+    }
+}
+
+/// Config and database locations for a single invocation.
+///
+/// Resolution order for the database is `--db`, then `database_path` from the
+/// config file, then the workspace database, then `~/.cohort-tracker.db`.
+#[derive(Debug, Clone)]
+pub struct Paths {
+    pub config: PathBuf,
+    pub db: PathBuf,
+    pub workspace: Option<String>,
+}
+
+impl Paths {
+    pub fn resolve(
+        config: Option<&str>,
+        db: Option<&str>,
+        workspace: Option<&str>,
+    ) -> Result<Self> {
+        let workspace_dir = match workspace {
+            Some(name) => Some(workspace_dir(name)?),
+            None => None,
+        };
+
+        let config_path = match (config, &workspace_dir) {
+            (Some(path), _) => expand_home(path),
+            (None, Some(dir)) => dir.join("config.toml"),
+            (None, None) => Config::default_path(),
+        };
+
+        let db_path = if let Some(path) = db {
+            expand_home(path)
+        } else if let Some(path) = config_path
+            .to_str()
+            .and_then(|p| Config::from_file(p).ok())
+            .and_then(|c| c.database_path)
+        {
+            expand_home(&path)
+        } else if let Some(dir) = &workspace_dir {
+            dir.join("cohort-tracker.db")
+        } else {
+            Config::default_db_path()
+        };
+
+        Ok(Self {
+            config: config_path,
+            db: db_path,
+            workspace: workspace.map(|s| s.to_string()),
+        })
+    }
+
+    pub fn config_str(&self) -> &str {
+        self.config.to_str().unwrap_or(".cohort-tracker.toml")
+    }
+
+    pub fn db_str(&self) -> &str {
+        self.db.to_str().unwrap_or(".cohort-tracker.db")
+    }
+
+    /// Make sure the directory holding the database exists (workspaces start empty).
+    pub fn ensure_db_dir(&self) -> Result<()> {
+        if let Some(parent) = self.db.parent() {
+            if !parent.as_os_str().is_empty() {
+                fs::create_dir_all(parent)?;
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Directory holding a named workspace's config and database.
+pub fn workspace_dir(name: &str) -> Result<PathBuf> {
+    let valid = !name.is_empty()
+        && name
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_');
+    if !valid {
+        return Err(anyhow!(
+            "Invalid workspace name '{}': use letters, numbers, '-' or '_'",
+            name
+        ));
+    }
+
+    Ok(home_dir()
+        .unwrap_or_else(|| PathBuf::from("."))
+        .join(".cohort-tracker")
+        .join("workspaces")
+        .join(name))
+}
+
+/// Names of all workspaces that have been created so far.
+pub fn list_workspaces() -> Result<Vec<String>> {
+    let root = home_dir()
+        .unwrap_or_else(|| PathBuf::from("."))
+        .join(".cohort-tracker")
+        .join("workspaces");
+
+    let mut names = Vec::new();
+    if let Ok(entries) = fs::read_dir(root) {
+        for entry in entries.flatten() {
+            if entry.path().is_dir() {
+                names.push(entry.file_name().to_string_lossy().to_string());
+            }
+        }
+    }
+    names.sort();
+    Ok(names)
+}
+
+fn expand_home(path: &str) -> PathBuf {
+    match path.strip_prefix("~/") {
+        Some(rest) => home_dir().unwrap_or_else(|| PathBuf::from(".")).join(rest),
+        None => PathBuf::from(path),
+    }
 }
 
 pub fn home_dir() -> Option<PathBuf> {
@@ -1,5 +1,14 @@
+use super::filter::{bind_values, AnalyticsFilter};
+use super::pace::schedule_delta;
+use super::time_on_task::percentile;
+use super::timestamps::parse_timestamp;
+use super::timezone::LocalClock;
 use super::Database;
+use crate::calendar::ClassCalendar;
+use crate::risk::RiskInputs;
 use anyhow::Result;
+use chrono::{Datelike, NaiveDateTime, Timelike};
+use std::collections::{BTreeMap, HashSet};
 
 // Import models from parent crate
 use crate::models::*;
@@ -10,56 +19,28 @@ impl Database {
         class_id: &str,
         night: Option<&str>,
     ) -> Result<ProgressSummary> {
-        let total_students = if let Some(night_val) = night {
-            let stmt = self
-                .conn
-                .prepare("SELECT COUNT(*) FROM students WHERE class_id = ? AND night = ?")?;
-            let mut stmt = stmt.bind(1, class_id)?.bind(2, night_val)?;
-            match stmt.next()? {
-                sqlite::State::Row => stmt.read::<i64>(0)?,
-                sqlite::State::Done => 0,
-            }
-        } else {
-            self.get_student_count_by_class(class_id)?
-        };
-
-        let total_assignments = self.get_assignment_count_by_class(class_id)?;
-
-        let total_progressions = if let Some(night_val) = night {
-            let stmt = self.conn.prepare(
-                "SELECT COUNT(*) FROM progressions p 
-                 JOIN students s ON p.student_id = s.id AND p.class_id = s.class_id
-                 WHERE p.class_id = ? AND s.night = ?",
-            )?;
-            let mut stmt = stmt.bind(1, class_id)?.bind(2, night_val)?;
-            match stmt.next()? {
-                sqlite::State::Row => stmt.read::<i64>(0)?,
-                sqlite::State::Done => 0,
-            }
-        } else {
-            self.get_progression_count_by_class(class_id)?
-        };
+        self.get_progress_summary_filtered(class_id, &AnalyticsFilter::night(night))
+    }
 
-        let avg_grade = if let Some(night_val) = night {
-            let stmt = self.conn.prepare(
-                "SELECT AVG(p.grade) FROM progressions p 
-                 JOIN students s ON p.student_id = s.id AND p.class_id = s.class_id
-                 WHERE p.grade IS NOT NULL AND p.class_id = ? AND s.night = ?",
-            )?;
-            let mut stmt = stmt.bind(1, class_id)?.bind(2, night_val)?;
-            match stmt.next()? {
-                sqlite::State::Row => stmt.read::<Option<f64>>(0)?,
-                sqlite::State::Done => None,
-            }
-        } else {
-            let stmt = self.conn.prepare(
-                "SELECT AVG(grade) FROM progressions WHERE grade IS NOT NULL AND class_id = ?",
-            )?;
-            let mut stmt = stmt.bind(1, class_id)?;
-            match stmt.next()? {
-                sqlite::State::Row => stmt.read::<Option<f64>>(0)?,
-                sqlite::State::Done => None,
-            }
+    pub fn get_progress_summary_filtered(
+        &self,
+        class_id: &str,
+        filter: &AnalyticsFilter,
+    ) -> Result<ProgressSummary> {
+        let total_students = self.count_students_filtered(class_id, filter)?;
+        let total_assignments = self.count_assignments_filtered(class_id, filter)?;
+
+        let (clause, values) = filter.clause();
+        let stmt = self.conn.prepare(format!(
+            "SELECT COUNT(*), AVG(p.grade) FROM progressions p
+             LEFT JOIN students s ON p.student_id = s.id AND p.class_id = s.class_id
+             WHERE p.class_id = ?{}",
+            clause
+        ))?;
+        let (mut stmt, _) = bind_values(stmt.bind(1, class_id)?, 2, &values)?;
+        let (total_progressions, avg_grade) = match stmt.next()? {
+            sqlite::State::Row => (stmt.read::<i64>(0)?, stmt.read::<Option<f64>>(1)?),
+            sqlite::State::Done => (0, None),
         };
 
         let expected_total = total_students * total_assignments;
@@ -78,19 +59,35 @@ impl Database {
         })
     }
 
-    pub fn get_completion_metrics(&self) -> Result<CompletionMetrics> {
-        let total_students = self.get_student_count()?;
-        let total_assignments = self.get_assignment_count()?;
-
-        let mut stmt = self.conn.prepare(
+    /// Completions per assignment in one class, counting the filter's
+    /// students and completions. Program-wide numbers come from
+    /// `get_program_rollup`.
+    pub fn get_completion_metrics(
+        &self,
+        class_id: &str,
+        filter: &AnalyticsFilter,
+    ) -> Result<CompletionMetrics> {
+        let total_students = self.count_students_filtered(class_id, filter)?;
+        let total_assignments = self.count_assignments_filtered(class_id, filter)?;
+
+        let (clause, values) = filter.clause();
+        let (assignment_clause, assignment_values) = filter.assignment_clause();
+        let stmt = self.conn.prepare(format!(
             "SELECT a.id, a.name, a.type,
                     COUNT(p.id) as completions,
                     AVG(p.grade) as avg_grade
              FROM assignments a
-             LEFT JOIN progressions p ON a.id = p.assignment_id
+             LEFT JOIN (
+                 SELECT p.id, p.assignment_id, p.grade FROM progressions p
+                 LEFT JOIN students s ON p.student_id = s.id AND p.class_id = s.class_id
+                 WHERE p.class_id = ?{clause}
+             ) p ON a.id = p.assignment_id
+             WHERE a.class_id = ?{assignment_clause}
              GROUP BY a.id, a.name, a.type
-             ORDER BY completions DESC",
-        )?;
+             ORDER BY completions DESC"
+        ))?;
+        let (stmt, next) = bind_values(stmt.bind(1, class_id)?, 2, &values)?;
+        let (mut stmt, _) = bind_values(stmt.bind(next, class_id)?, next + 1, &assignment_values)?;
 
         let mut assignments = Vec::new();
         let mut zero_completions = 0i64;
@@ -140,51 +137,33 @@ impl Database {
         limit: usize,
         night: Option<&str>,
     ) -> Result<Vec<BlockerAssignment>> {
-        let total_students = if let Some(night_val) = night {
-            let stmt = self
-                .conn
-                .prepare("SELECT COUNT(*) FROM students WHERE class_id = ? AND night = ?")?;
-            let mut stmt = stmt.bind(1, class_id)?.bind(2, night_val)?;
-            match stmt.next()? {
-                sqlite::State::Row => stmt.read::<i64>(0)?,
-                sqlite::State::Done => 0,
-            }
-        } else {
-            self.get_student_count_by_class(class_id)?
-        };
+        self.get_blockers_filtered(class_id, limit, &AnalyticsFilter::night(night))
+    }
 
-        let query = if night.is_some() {
-            "SELECT a.id, a.name, a.section,
-                    COUNT(CASE WHEN s.night = ? AND s.class_id = a.class_id THEN p.id END) as completions,
-                    AVG(CASE WHEN s.night = ? AND s.class_id = a.class_id THEN p.grade END) as avg_grade
-             FROM assignments a
-             LEFT JOIN progressions p ON a.id = p.assignment_id AND a.class_id = p.class_id
-             LEFT JOIN students s ON p.student_id = s.id
-             WHERE a.class_id = ?
-             GROUP BY a.id, a.name, a.section
-             ORDER BY completions ASC, avg_grade ASC
-             LIMIT ?"
-        } else {
+    pub fn get_blockers_filtered(
+        &self,
+        class_id: &str,
+        limit: usize,
+        filter: &AnalyticsFilter,
+    ) -> Result<Vec<BlockerAssignment>> {
+        let total_students = self.count_students_filtered(class_id, filter)?;
+
+        let (progressions, values) = filtered_progressions(class_id, filter);
+        let (assignment_clause, assignment_values) = filter.assignment_clause();
+        let stmt = self.conn.prepare(format!(
             "SELECT a.id, a.name, a.section,
                     COUNT(p.id) as completions,
                     AVG(p.grade) as avg_grade
              FROM assignments a
-             LEFT JOIN progressions p ON a.id = p.assignment_id AND a.class_id = p.class_id
-             WHERE a.class_id = ?
+             LEFT JOIN ({progressions}) p ON a.id = p.assignment_id
+             WHERE a.class_id = ?{assignment_clause}
              GROUP BY a.id, a.name, a.section
              ORDER BY completions ASC, avg_grade ASC
              LIMIT ?"
-        };
-
-        let stmt = self.conn.prepare(query)?;
-        let mut stmt = if let Some(night_val) = night {
-            stmt.bind(1, night_val)?
-                .bind(2, night_val)?
-                .bind(3, class_id)?
-                .bind(4, limit as i64)?
-        } else {
-            stmt.bind(1, class_id)?.bind(2, limit as i64)?
-        };
+        ))?;
+        let (stmt, next) = bind_values(stmt, 1, &values)?;
+        let (stmt, next) = bind_values(stmt.bind(next, class_id)?, next + 1, &assignment_values)?;
+        let mut stmt = stmt.bind(next, limit as i64)?;
 
         let mut blockers = Vec::new();
 
@@ -216,34 +195,33 @@ impl Database {
         class_id: &str,
         night: Option<&str>,
     ) -> Result<Vec<StudentHealth>> {
-        let total_assignments = self.get_assignment_count_by_class(class_id)?;
+        self.get_student_health_filtered(class_id, &AnalyticsFilter::night(night))
+    }
 
-        let query = if night.is_some() {
-            "SELECT s.id, s.first_name, s.last_name, s.email,
-                    COUNT(p.id) as completed,
-                    AVG(p.grade) as avg_grade
-             FROM students s
-             LEFT JOIN progressions p ON s.id = p.student_id AND s.class_id = p.class_id
-             WHERE s.class_id = ? AND s.night = ?
-             GROUP BY s.id, s.first_name, s.last_name, s.email
-             ORDER BY completed ASC, avg_grade ASC"
-        } else {
+    pub fn get_student_health_filtered(
+        &self,
+        class_id: &str,
+        filter: &AnalyticsFilter,
+    ) -> Result<Vec<StudentHealth>> {
+        let total_assignments = self.count_assignments_filtered(class_id, filter)?;
+        let expected_completed = self.expected_completed_filtered(class_id, filter)?;
+        let mut signals = self.activity_signals(class_id, None, filter)?;
+        let dropout = self.dropout_probabilities(class_id, filter.today())?;
+
+        let (student_clause, student_values) = filter.student_clause();
+        let (progression_clause, progression_values) = filter.progression_clause();
+        let stmt = self.conn.prepare(format!(
             "SELECT s.id, s.first_name, s.last_name, s.email,
                     COUNT(p.id) as completed,
                     AVG(p.grade) as avg_grade
              FROM students s
-             LEFT JOIN progressions p ON s.id = p.student_id AND s.class_id = p.class_id
-             WHERE s.class_id = ?
+             LEFT JOIN progressions p ON s.id = p.student_id AND s.class_id = p.class_id{progression_clause}
+             WHERE s.class_id = ?{student_clause}
              GROUP BY s.id, s.first_name, s.last_name, s.email
              ORDER BY completed ASC, avg_grade ASC"
-        };
-
-        let stmt = self.conn.prepare(query)?;
-        let mut stmt = if let Some(night_val) = night {
-            stmt.bind(1, class_id)?.bind(2, night_val)?
-        } else {
-            stmt.bind(1, class_id)?
-        };
+        ))?;
+        let (stmt, next) = bind_values(stmt, 1, &progression_values)?;
+        let (mut stmt, _) = bind_values(stmt.bind(next, class_id)?, next + 1, &student_values)?;
 
         let mut students = Vec::new();
 
@@ -256,18 +234,19 @@ impl Database {
                 0.0
             };
 
-            let risk = if completion_pct < 0.25 {
-                "critical"
-            } else if completion_pct < 0.50 {
-                "high"
-            } else if completion_pct < 0.75 {
-                "medium"
-            } else {
-                "low"
-            };
+            let student_id = stmt.read::<String>(0)?;
+            let schedule_delta = expected_completed.map(|e| schedule_delta(completed, e));
+            let activity = signals.remove(&student_id).unwrap_or_default();
+            let risk = self.assess_risk(&RiskInputs {
+                completion_pct,
+                schedule_delta,
+                avg_grade,
+                grade_trend: activity.grade_trend,
+                days_inactive: activity.days_inactive,
+                velocity_drop: activity.velocity_drop,
+            });
 
             students.push(StudentHealth {
-                student_id: stmt.read::<String>(0)?,
                 first_name: stmt.read::<String>(1)?,
                 last_name: stmt.read::<String>(2)?,
                 email: stmt.read::<String>(3)?,
@@ -275,7 +254,17 @@ impl Database {
                 total_assignments,
                 completion_pct,
                 avg_grade,
-                risk: risk.to_string(),
+                risk: risk.level,
+                risk_score: risk.score,
+                risk_factors: risk.factors,
+                schedule_delta,
+                dropout_probability: dropout.get(&student_id).copied(),
+                grade_slope: activity.grade_slope,
+                grade_volatility: activity.grade_volatility,
+                active_week_streak: activity.active_week_streak,
+                longest_gap_days: activity.longest_gap_days,
+                declining_grades: activity.declining_grades(),
+                student_id,
             });
         }
 
@@ -287,40 +276,46 @@ impl Database {
         class_id: &str,
         night: Option<&str>,
     ) -> Result<Vec<WeeklyProgress>> {
-        let query = if night.is_some() {
-            "SELECT strftime('%Y-%W', p.completed_at) as week,
-                    COUNT(*) as completed
-             FROM progressions p
-             JOIN students s ON p.student_id = s.id
-             WHERE p.completed_at IS NOT NULL AND p.completed_at != '' AND p.class_id = ? AND s.night = ?
-             GROUP BY week
-             ORDER BY week ASC"
-        } else {
-            "SELECT strftime('%Y-%W', completed_at) as week,
-                    COUNT(*) as completed
-             FROM progressions
-             WHERE completed_at IS NOT NULL AND completed_at != '' AND class_id = ?
-             GROUP BY week
-             ORDER BY week ASC"
-        };
+        self.get_progress_over_time_filtered(class_id, &AnalyticsFilter::night(night))
+    }
 
-        let stmt = self.conn.prepare(query)?;
-        let mut stmt = if let Some(night_val) = night {
-            stmt.bind(1, class_id)?.bind(2, night_val)?
-        } else {
-            stmt.bind(1, class_id)?
+    pub fn get_progress_over_time_filtered(
+        &self,
+        class_id: &str,
+        filter: &AnalyticsFilter,
+    ) -> Result<Vec<WeeklyProgress>> {
+        let Some(calendar) = self.get_class_calendar(class_id)? else {
+            return Ok(Vec::new());
         };
 
+        let (clause, values) = filter.clause();
+        let stmt = self.conn.prepare(format!(
+            "SELECT p.completed_at, p.student_id
+             FROM progressions p
+             LEFT JOIN students s ON p.student_id = s.id AND p.class_id = s.class_id
+             WHERE p.completed_at IS NOT NULL AND p.completed_at != '' AND p.class_id = ?{}",
+            clause
+        ))?;
+        let (mut stmt, _) = bind_values(stmt.bind(1, class_id)?, 2, &values)?;
+
+        let mut weeks = WeekBuckets::new(&calendar, self.local_clock(class_id)?);
+        while let sqlite::State::Row = stmt.next()? {
+            let student_id = stmt.read::<String>(1)?;
+            weeks.add(&student_id, &stmt.read::<String>(0)?, student_id.clone());
+        }
+
         let mut weekly = Vec::new();
         let mut cumulative = 0i64;
 
-        while let sqlite::State::Row = stmt.next()? {
-            let week = stmt.read::<String>(0)?;
-            let completed = stmt.read::<i64>(1)?;
+        for (week, students) in weeks.into_weeks() {
+            let completed = students.len() as i64;
             cumulative += completed;
 
             weekly.push(WeeklyProgress {
-                week,
+                week: week.label,
+                week_number: week.number,
+                week_start: week.start_date,
+                is_break: week.is_break,
                 completed,
                 cumulative,
             });
@@ -330,55 +325,41 @@ impl Database {
     }
 
     pub fn get_student_activity(&self, class_id: &str) -> Result<Vec<StudentActivity>> {
-        self.get_student_activity_filtered(class_id, None)
+        self.get_student_activity_filtered(class_id, &AnalyticsFilter::default())
     }
 
     pub fn get_student_activity_filtered(
         &self,
         class_id: &str,
-        night: Option<&str>,
+        filter: &AnalyticsFilter,
     ) -> Result<Vec<StudentActivity>> {
         // First get total assignments for the class
-        let total_assignments = self.get_assignment_count_by_class(class_id)?;
-
-        let query = match night {
-            Some(_) => {
-                "SELECT s.id, s.first_name, s.last_name, s.email, s.night,
-                        MAX(p.completed_at) as last_activity,
-                        COUNT(p.id) as total_completions
-                 FROM students s
-                 LEFT JOIN progressions p ON s.id = p.student_id AND s.class_id = p.class_id
-                 WHERE s.class_id = ? AND LOWER(s.night) = LOWER(?)
-                 GROUP BY s.id, s.first_name, s.last_name, s.email, s.night
-                 ORDER BY last_activity ASC NULLS FIRST"
-            }
-            None => {
-                "SELECT s.id, s.first_name, s.last_name, s.email, s.night,
-                        MAX(p.completed_at) as last_activity,
-                        COUNT(p.id) as total_completions
-                 FROM students s
-                 LEFT JOIN progressions p ON s.id = p.student_id AND s.class_id = p.class_id
-                 WHERE s.class_id = ?
-                 GROUP BY s.id, s.first_name, s.last_name, s.email, s.night
-                 ORDER BY last_activity ASC NULLS FIRST"
-            }
-        };
-
-        let mut stmt = if let Some(n) = night {
-            let stmt = self.conn.prepare(query)?;
-            stmt.bind(1, class_id)?.bind(2, n)?
-        } else {
-            let stmt = self.conn.prepare(query)?;
-            stmt.bind(1, class_id)?
-        };
+        let total_assignments = self.count_assignments_filtered(class_id, filter)?;
 
+        let (student_clause, student_values) = filter.student_clause();
+        let (progression_clause, progression_values) = filter.progression_clause();
+        let stmt = self.conn.prepare(format!(
+            "SELECT s.id, s.first_name, s.last_name, s.email, s.night,
+                    MAX(p.completed_at) as last_activity,
+                    COUNT(p.id) as total_completions
+             FROM students s
+             LEFT JOIN progressions p ON s.id = p.student_id AND s.class_id = p.class_id{progression_clause}
+             WHERE s.class_id = ?{student_clause}
+             GROUP BY s.id, s.first_name, s.last_name, s.email, s.night
+             ORDER BY last_activity ASC NULLS FIRST"
+        ))?;
+        let (stmt, next) = bind_values(stmt, 1, &progression_values)?;
+        let (mut stmt, _) = bind_values(stmt.bind(next, class_id)?, next + 1, &student_values)?;
+
+        let clock = self.local_clock(class_id)?.frozen_at(filter.as_of);
         let mut activities = Vec::new();
 
         while let sqlite::State::Row = stmt.next()? {
             let last_activity: Option<String> = stmt.read::<Option<String>>(5)?;
 
             let days_inactive = if let Some(ref date_str) = last_activity {
-                self.calculate_days_since(date_str).ok()
+                self.calculate_days_since(&clock, &stmt.read::<String>(0)?, date_str)
+                    .ok()
             } else {
                 None
             };
@@ -399,17 +380,23 @@ impl Database {
         Ok(activities)
     }
 
-    pub fn get_night_summary(&self, class_id: &str) -> Result<Vec<NightSummary>> {
-        let total_assignments = self.get_assignment_count_by_class(class_id)?;
+    pub fn get_night_summary(
+        &self,
+        class_id: &str,
+        filter: &AnalyticsFilter,
+    ) -> Result<Vec<NightSummary>> {
+        let total_assignments = self.count_assignments_filtered(class_id, filter)?;
 
-        let stmt = self.conn.prepare(
+        let (student_clause, student_values) = filter.student_clause();
+        let (progression_clause, progression_values) = filter.progression_clause();
+        let stmt = self.conn.prepare(format!(
             "SELECT s.night,
                     COUNT(DISTINCT s.id) as student_count,
                     COUNT(p.id) as total_completions,
                     AVG(p.grade) as avg_grade
              FROM students s
-             LEFT JOIN progressions p ON s.id = p.student_id AND s.class_id = p.class_id
-             WHERE s.night IS NOT NULL AND s.class_id = ?
+             LEFT JOIN progressions p ON s.id = p.student_id AND s.class_id = p.class_id{progression_clause}
+             WHERE s.night IS NOT NULL AND s.class_id = ?{student_clause}
              GROUP BY s.night
              ORDER BY CASE s.night
                  WHEN 'Mon' THEN 1
@@ -420,9 +407,10 @@ impl Database {
                  WHEN 'Sat' THEN 6
                  WHEN 'Sun' THEN 7
                  ELSE 8
-             END",
-        )?;
-        let mut stmt = stmt.bind(1, class_id)?;
+             END"
+        ))?;
+        let (stmt, next) = bind_values(stmt, 1, &progression_values)?;
+        let (mut stmt, _) = bind_values(stmt.bind(next, class_id)?, next + 1, &student_values)?;
 
         let mut summaries = Vec::new();
 
@@ -467,6 +455,7 @@ impl Database {
         student_id: &str,
     ) -> Result<Option<StudentDetail>> {
         let total_assignments = self.get_assignment_count_by_class(class_id)?;
+        let expected_completed = self.expected_completed_today(class_id)?;
 
         let stmt = self.conn.prepare(
             "SELECT s.id, s.first_name, s.last_name, s.email, s.region, s.night,
@@ -492,21 +481,24 @@ impl Database {
                     0.0
                 };
 
-                let risk = if completion_pct < 0.25 {
-                    "critical"
-                } else if completion_pct < 0.50 {
-                    "high"
-                } else if completion_pct < 0.75 {
-                    "medium"
-                } else {
-                    "low"
-                };
-
-                let days_inactive = if let Some(ref date_str) = last_activity {
-                    self.calculate_days_since(date_str).ok()
-                } else {
-                    None
-                };
+                // Same signals and day as the health table, so both agree on
+                // inactivity, risk and dropout
+                let filter = AnalyticsFilter::default();
+                let schedule_delta = expected_completed.map(|e| schedule_delta(completed, e));
+                let projection = self.project_student(class_id, student_id, completed)?;
+                let activity = self
+                    .activity_signals(class_id, Some(student_id), &filter)?
+                    .remove(student_id)
+                    .unwrap_or_default();
+                let days_inactive = activity.days_inactive;
+                let risk = self.assess_risk(&RiskInputs {
+                    completion_pct,
+                    schedule_delta,
+                    avg_grade,
+                    grade_trend: activity.grade_trend,
+                    days_inactive,
+                    velocity_drop: activity.velocity_drop,
+                });
 
                 Ok(Some(StudentDetail {
                     id: stmt.read::<String>(0)?,
@@ -519,9 +511,25 @@ impl Database {
                     completed,
                     completion_pct,
                     avg_grade,
-                    risk: risk.to_string(),
+                    risk: risk.level,
+                    risk_score: risk.score,
+                    risk_factors: risk.factors,
                     last_activity,
                     days_inactive,
+                    schedule_delta,
+                    dropout_probability: self
+                        .dropout_probabilities(class_id, filter.today())?
+                        .get(student_id)
+                        .copied(),
+                    assignments_remaining: projection.remaining,
+                    recent_velocity: projection.recent_velocity,
+                    projected_finish: projection.finish.map(|d| d.format("%Y-%m-%d").to_string()),
+                    projected_days_past_end: projection.days_past_end,
+                    grade_slope: activity.grade_slope,
+                    grade_volatility: activity.grade_volatility,
+                    active_week_streak: activity.active_week_streak,
+                    longest_gap_days: activity.longest_gap_days,
+                    declining_grades: activity.declining_grades(),
                 }))
             }
             sqlite::State::Done => Ok(None),
@@ -567,28 +575,44 @@ impl Database {
         class_id: &str,
         student_id: &str,
     ) -> Result<Vec<StudentProgressPoint>> {
+        let Some(calendar) = self.get_class_calendar(class_id)? else {
+            return Ok(Vec::new());
+        };
+
         let stmt = self.conn.prepare(
-            "SELECT strftime('%Y-%W', completed_at) as week,
-                    COUNT(*) as completed,
-                    AVG(grade) as avg_grade
+            "SELECT completed_at, grade
              FROM progressions
-             WHERE student_id = ? AND class_id = ? AND completed_at IS NOT NULL AND completed_at != ''
-             GROUP BY week
-             ORDER BY week ASC"
+             WHERE student_id = ? AND class_id = ? AND completed_at IS NOT NULL AND completed_at != ''",
         )?;
         let mut stmt = stmt.bind(1, student_id)?.bind(2, class_id)?;
 
+        let mut weeks = WeekBuckets::new(&calendar, self.local_clock(class_id)?);
+        while let sqlite::State::Row = stmt.next()? {
+            weeks.add(
+                student_id,
+                &stmt.read::<String>(0)?,
+                stmt.read::<Option<f64>>(1)?,
+            );
+        }
+
         let mut timeline = Vec::new();
         let mut cumulative = 0i64;
 
-        while let sqlite::State::Row = stmt.next()? {
-            let week = stmt.read::<String>(0)?;
-            let completed = stmt.read::<i64>(1)?;
-            let avg_grade = stmt.read::<Option<f64>>(2)?;
+        for (week, grades) in weeks.into_weeks() {
+            let completed = grades.len() as i64;
+            let graded: Vec<f64> = grades.into_iter().flatten().collect();
+            let avg_grade = if graded.is_empty() {
+                None
+            } else {
+                Some(graded.iter().sum::<f64>() / graded.len() as f64)
+            };
             cumulative += completed;
 
             timeline.push(StudentProgressPoint {
-                week,
+                week: week.label,
+                week_number: week.number,
+                week_start: week.start_date,
+                is_break: week.is_break,
                 completed,
                 cumulative,
                 avg_grade,
@@ -598,22 +622,16 @@ impl Database {
         Ok(timeline)
     }
 
-    fn calculate_days_since(&self, date_str: &str) -> Result<i64> {
-        use chrono::{NaiveDateTime, Utc};
-
-        // Parse ISO 8601 datetime string
-        let parsed = NaiveDateTime::parse_from_str(date_str, "%Y-%m-%dT%H:%M:%S")
-            .or_else(|_| NaiveDateTime::parse_from_str(date_str, "%Y-%m-%d %H:%M:%S"))
-            .or_else(|_| {
-                // Try parsing just the date part
-                let date_part = date_str.split('T').next().unwrap_or(date_str);
-                chrono::NaiveDate::parse_from_str(date_part, "%Y-%m-%d")
-                    .map(|d| d.and_hms_opt(0, 0, 0).unwrap())
-            })?;
-
-        let now = Utc::now().naive_utc();
-        let duration = now.signed_duration_since(parsed);
-        Ok(duration.num_days())
+    /// Local calendar days since `date_str` for a student.
+    fn calculate_days_since(
+        &self,
+        clock: &LocalClock,
+        student_id: &str,
+        date_str: &str,
+    ) -> Result<i64> {
+        let parsed = parse_timestamp(date_str)
+            .ok_or_else(|| anyhow::anyhow!("Unparseable timestamp: {}", date_str))?;
+        Ok(clock.days_since(student_id, parsed))
     }
 
     pub fn get_completions_by_day_of_week(
@@ -621,58 +639,17 @@ impl Database {
         class_id: &str,
         night: Option<&str>,
     ) -> Result<Vec<DayOfWeekStats>> {
-        let query = if night.is_some() {
-            "SELECT 
-                CASE CAST(strftime('%w', p.completed_at) AS INTEGER)
-                    WHEN 0 THEN 'Sunday'
-                    WHEN 1 THEN 'Monday'
-                    WHEN 2 THEN 'Tuesday'
-                    WHEN 3 THEN 'Wednesday'
-                    WHEN 4 THEN 'Thursday'
-                    WHEN 5 THEN 'Friday'
-                    WHEN 6 THEN 'Saturday'
-                END as day_name,
-                CAST(strftime('%w', p.completed_at) AS INTEGER) as day_num,
-                COUNT(*) as count
-             FROM progressions p
-             JOIN students s ON p.student_id = s.id
-             WHERE p.class_id = ? AND s.night = ? AND p.completed_at IS NOT NULL AND p.completed_at != ''
-             GROUP BY day_num
-             ORDER BY day_num"
-        } else {
-            "SELECT 
-                CASE CAST(strftime('%w', completed_at) AS INTEGER)
-                    WHEN 0 THEN 'Sunday'
-                    WHEN 1 THEN 'Monday'
-                    WHEN 2 THEN 'Tuesday'
-                    WHEN 3 THEN 'Wednesday'
-                    WHEN 4 THEN 'Thursday'
-                    WHEN 5 THEN 'Friday'
-                    WHEN 6 THEN 'Saturday'
-                END as day_name,
-                CAST(strftime('%w', completed_at) AS INTEGER) as day_num,
-                COUNT(*) as count
-             FROM progressions
-             WHERE class_id = ? AND completed_at IS NOT NULL AND completed_at != ''
-             GROUP BY day_num
-             ORDER BY day_num"
-        };
-
-        let stmt = self.conn.prepare(query)?;
-        let mut stmt = if let Some(night_val) = night {
-            stmt.bind(1, class_id)?.bind(2, night_val)?
-        } else {
-            stmt.bind(1, class_id)?
-        };
+        self.get_completions_by_day_of_week_filtered(class_id, &AnalyticsFilter::night(night))
+    }
 
-        let mut results = Vec::new();
-        while let sqlite::State::Row = stmt.next()? {
-            results.push(DayOfWeekStats {
-                day: stmt.read::<String>(0)?,
-                count: stmt.read::<i64>(2)?,
-            });
-        }
-        Ok(results)
+    /// Completions per local weekday, Sunday first.
+    pub fn get_completions_by_day_of_week_filtered(
+        &self,
+        class_id: &str,
+        filter: &AnalyticsFilter,
+    ) -> Result<Vec<DayOfWeekStats>> {
+        let times = self.local_completion_times(class_id, filter, None)?;
+        Ok(day_of_week_stats(&times))
     }
 
     pub fn get_student_completions_by_day_of_week(
@@ -680,34 +657,9 @@ impl Database {
         class_id: &str,
         student_id: &str,
     ) -> Result<Vec<DayOfWeekStats>> {
-        let stmt = self.conn.prepare(
-            "SELECT 
-                CASE CAST(strftime('%w', completed_at) AS INTEGER)
-                    WHEN 0 THEN 'Sunday'
-                    WHEN 1 THEN 'Monday'
-                    WHEN 2 THEN 'Tuesday'
-                    WHEN 3 THEN 'Wednesday'
-                    WHEN 4 THEN 'Thursday'
-                    WHEN 5 THEN 'Friday'
-                    WHEN 6 THEN 'Saturday'
-                END as day_name,
-                CAST(strftime('%w', completed_at) AS INTEGER) as day_num,
-                COUNT(*) as count
-             FROM progressions
-             WHERE class_id = ? AND student_id = ? AND completed_at IS NOT NULL AND completed_at != ''
-             GROUP BY day_num
-             ORDER BY day_num"
-        )?;
-        let mut stmt = stmt.bind(1, class_id)?.bind(2, student_id)?;
-
-        let mut results = Vec::new();
-        while let sqlite::State::Row = stmt.next()? {
-            results.push(DayOfWeekStats {
-                day: stmt.read::<String>(0)?,
-                count: stmt.read::<i64>(2)?,
-            });
-        }
-        Ok(results)
+        let times =
+            self.local_completion_times(class_id, &AnalyticsFilter::default(), Some(student_id))?;
+        Ok(day_of_week_stats(&times))
     }
 
     pub fn get_completions_by_time_of_day(
@@ -715,62 +667,17 @@ impl Database {
         class_id: &str,
         night: Option<&str>,
     ) -> Result<Vec<DayOfWeekStats>> {
-        let query = if night.is_some() {
-            "SELECT 
-                CASE 
-                    WHEN CAST(strftime('%H', p.completed_at) AS INTEGER) BETWEEN 6 AND 11 THEN 'Morning (6am-12pm)'
-                    WHEN CAST(strftime('%H', p.completed_at) AS INTEGER) BETWEEN 12 AND 17 THEN 'Afternoon (12pm-6pm)'
-                    WHEN CAST(strftime('%H', p.completed_at) AS INTEGER) BETWEEN 18 AND 23 THEN 'Evening (6pm-12am)'
-                    ELSE 'Night (12am-6am)'
-                END as time_period,
-                COUNT(*) as count
-             FROM progressions p
-             JOIN students s ON p.student_id = s.id
-             WHERE p.class_id = ? AND s.night = ? AND p.completed_at IS NOT NULL AND p.completed_at != ''
-             GROUP BY time_period
-             ORDER BY 
-                CASE time_period
-                    WHEN 'Morning (6am-12pm)' THEN 1
-                    WHEN 'Afternoon (12pm-6pm)' THEN 2
-                    WHEN 'Evening (6pm-12am)' THEN 3
-                    ELSE 4
-                END"
-        } else {
-            "SELECT 
-                CASE 
-                    WHEN CAST(strftime('%H', completed_at) AS INTEGER) BETWEEN 6 AND 11 THEN 'Morning (6am-12pm)'
-                    WHEN CAST(strftime('%H', completed_at) AS INTEGER) BETWEEN 12 AND 17 THEN 'Afternoon (12pm-6pm)'
-                    WHEN CAST(strftime('%H', completed_at) AS INTEGER) BETWEEN 18 AND 23 THEN 'Evening (6pm-12am)'
-                    ELSE 'Night (12am-6am)'
-                END as time_period,
-                COUNT(*) as count
-             FROM progressions
-             WHERE class_id = ? AND completed_at IS NOT NULL AND completed_at != ''
-             GROUP BY time_period
-             ORDER BY 
-                CASE time_period
-                    WHEN 'Morning (6am-12pm)' THEN 1
-                    WHEN 'Afternoon (12pm-6pm)' THEN 2
-                    WHEN 'Evening (6pm-12am)' THEN 3
-                    ELSE 4
-                END"
-        };
-
-        let stmt = self.conn.prepare(query)?;
-        let mut stmt = if let Some(night_val) = night {
-            stmt.bind(1, class_id)?.bind(2, night_val)?
-        } else {
-            stmt.bind(1, class_id)?
-        };
+        self.get_completions_by_time_of_day_filtered(class_id, &AnalyticsFilter::night(night))
+    }
 
-        let mut results = Vec::new();
-        while let sqlite::State::Row = stmt.next()? {
-            results.push(DayOfWeekStats {
-                day: stmt.read::<String>(0)?,
-                count: stmt.read::<i64>(1)?,
-            });
-        }
-        Ok(results)
+    /// Completions per local time-of-day period, morning first.
+    pub fn get_completions_by_time_of_day_filtered(
+        &self,
+        class_id: &str,
+        filter: &AnalyticsFilter,
+    ) -> Result<Vec<DayOfWeekStats>> {
+        let times = self.local_completion_times(class_id, filter, None)?;
+        Ok(time_of_day_stats(&times))
     }
 
     pub fn get_student_completions_by_time_of_day(
@@ -778,36 +685,9 @@ impl Database {
         class_id: &str,
         student_id: &str,
     ) -> Result<Vec<DayOfWeekStats>> {
-        let stmt = self.conn.prepare(
-            "SELECT 
-                CASE 
-                    WHEN CAST(strftime('%H', completed_at) AS INTEGER) BETWEEN 6 AND 11 THEN 'Morning (6am-12pm)'
-                    WHEN CAST(strftime('%H', completed_at) AS INTEGER) BETWEEN 12 AND 17 THEN 'Afternoon (12pm-6pm)'
-                    WHEN CAST(strftime('%H', completed_at) AS INTEGER) BETWEEN 18 AND 23 THEN 'Evening (6pm-12am)'
-                    ELSE 'Night (12am-6am)'
-                END as time_period,
-                COUNT(*) as count
-             FROM progressions
-             WHERE class_id = ? AND student_id = ? AND completed_at IS NOT NULL AND completed_at != ''
-             GROUP BY time_period
-             ORDER BY 
-                CASE time_period
-                    WHEN 'Morning (6am-12pm)' THEN 1
-                    WHEN 'Afternoon (12pm-6pm)' THEN 2
-                    WHEN 'Evening (6pm-12am)' THEN 3
-                    ELSE 4
-                END"
-        )?;
-        let mut stmt = stmt.bind(1, class_id)?.bind(2, student_id)?;
-
-        let mut results = Vec::new();
-        while let sqlite::State::Row = stmt.next()? {
-            results.push(DayOfWeekStats {
-                day: stmt.read::<String>(0)?,
-                count: stmt.read::<i64>(1)?,
-            });
-        }
-        Ok(results)
+        let times =
+            self.local_completion_times(class_id, &AnalyticsFilter::default(), Some(student_id))?;
+        Ok(time_of_day_stats(&times))
     }
 
     pub fn get_assignment_type_stats(
@@ -815,52 +695,32 @@ impl Database {
         class_id: &str,
         night: Option<&str>,
     ) -> Result<Vec<AssignmentTypeStats>> {
-        let total_students = if let Some(night_val) = night {
-            let stmt = self
-                .conn
-                .prepare("SELECT COUNT(*) FROM students WHERE class_id = ? AND night = ?")?;
-            let mut stmt = stmt.bind(1, class_id)?.bind(2, night_val)?;
-            match stmt.next()? {
-                sqlite::State::Row => stmt.read::<i64>(0)?,
-                sqlite::State::Done => 0,
-            }
-        } else {
-            self.get_student_count_by_class(class_id)?
-        };
+        self.get_assignment_type_stats_filtered(class_id, &AnalyticsFilter::night(night))
+    }
 
-        let query = if night.is_some() {
-            "SELECT 
-                a.type,
-                COUNT(DISTINCT a.id) as total_assignments,
-                COUNT(CASE WHEN s.night = ? AND s.class_id = a.class_id THEN p.id END) as total_completions,
-                AVG(CASE WHEN s.night = ? AND s.class_id = a.class_id THEN p.grade END) as avg_grade
-             FROM assignments a
-             LEFT JOIN progressions p ON a.id = p.assignment_id AND a.class_id = p.class_id
-             LEFT JOIN students s ON p.student_id = s.id
-             WHERE a.class_id = ?
-             GROUP BY a.type
-             ORDER BY a.type"
-        } else {
+    pub fn get_assignment_type_stats_filtered(
+        &self,
+        class_id: &str,
+        filter: &AnalyticsFilter,
+    ) -> Result<Vec<AssignmentTypeStats>> {
+        let total_students = self.count_students_filtered(class_id, filter)?;
+
+        let (progressions, values) = filtered_progressions(class_id, filter);
+        let (assignment_clause, assignment_values) = filter.assignment_clause();
+        let stmt = self.conn.prepare(format!(
             "SELECT 
                 a.type,
                 COUNT(DISTINCT a.id) as total_assignments,
                 COUNT(p.id) as total_completions,
                 AVG(p.grade) as avg_grade
              FROM assignments a
-             LEFT JOIN progressions p ON a.id = p.assignment_id AND a.class_id = p.class_id
-             WHERE a.class_id = ?
+             LEFT JOIN ({progressions}) p ON a.id = p.assignment_id
+             WHERE a.class_id = ?{assignment_clause}
              GROUP BY a.type
              ORDER BY a.type"
-        };
-
-        let stmt = self.conn.prepare(query)?;
-        let mut stmt = if let Some(night_val) = night {
-            stmt.bind(1, night_val)?
-                .bind(2, night_val)?
-                .bind(3, class_id)?
-        } else {
-            stmt.bind(1, class_id)?
-        };
+        ))?;
+        let (stmt, next) = bind_values(stmt, 1, &values)?;
+        let (mut stmt, _) = bind_values(stmt.bind(next, class_id)?, next + 1, &assignment_values)?;
 
         let mut results = Vec::new();
         while let sqlite::State::Row = stmt.next()? {
@@ -892,23 +752,23 @@ impl Database {
         class_id: &str,
         night: Option<&str>,
     ) -> Result<Vec<GradeDistribution>> {
-        let query = if night.is_some() {
+        self.get_grade_distribution_filtered(class_id, &AnalyticsFilter::night(night))
+    }
+
+    pub fn get_grade_distribution_filtered(
+        &self,
+        class_id: &str,
+        filter: &AnalyticsFilter,
+    ) -> Result<Vec<GradeDistribution>> {
+        let (clause, values) = filter.clause();
+        let stmt = self.conn.prepare(format!(
             "SELECT p.grade
              FROM progressions p
-             JOIN students s ON p.student_id = s.id AND p.class_id = s.class_id
-             WHERE p.class_id = ? AND s.night = ? AND p.grade IS NOT NULL"
-        } else {
-            "SELECT grade
-             FROM progressions
-             WHERE class_id = ? AND grade IS NOT NULL"
-        };
-
-        let stmt = self.conn.prepare(query)?;
-        let mut stmt = if let Some(night_val) = night {
-            stmt.bind(1, class_id)?.bind(2, night_val)?
-        } else {
-            stmt.bind(1, class_id)?
-        };
+             LEFT JOIN students s ON p.student_id = s.id AND p.class_id = s.class_id
+             WHERE p.class_id = ?{} AND p.grade IS NOT NULL",
+            clause
+        ))?;
+        let (mut stmt, _) = bind_values(stmt.bind(1, class_id)?, 2, &values)?;
 
         // Collect all grades
         let mut grades = Vec::new();
@@ -950,40 +810,38 @@ impl Database {
         class_id: &str,
         night: Option<&str>,
     ) -> Result<Vec<VelocityStats>> {
-        let query = if night.is_some() {
-            "SELECT 
-                strftime('%Y-%W', p.completed_at) as week,
-                COUNT(*) as total_completions,
-                COUNT(DISTINCT p.student_id) as active_students
-             FROM progressions p
-             JOIN students s ON p.student_id = s.id AND p.class_id = s.class_id
-             WHERE p.completed_at IS NOT NULL AND p.completed_at != '' 
-                   AND p.class_id = ? AND s.night = ?
-             GROUP BY week
-             ORDER BY week ASC"
-        } else {
-            "SELECT 
-                strftime('%Y-%W', completed_at) as week,
-                COUNT(*) as total_completions,
-                COUNT(DISTINCT student_id) as active_students
-             FROM progressions
-             WHERE completed_at IS NOT NULL AND completed_at != '' AND class_id = ?
-             GROUP BY week
-             ORDER BY week ASC"
-        };
+        self.get_velocity_stats_filtered(class_id, &AnalyticsFilter::night(night))
+    }
 
-        let stmt = self.conn.prepare(query)?;
-        let mut stmt = if let Some(night_val) = night {
-            stmt.bind(1, class_id)?.bind(2, night_val)?
-        } else {
-            stmt.bind(1, class_id)?
+    pub fn get_velocity_stats_filtered(
+        &self,
+        class_id: &str,
+        filter: &AnalyticsFilter,
+    ) -> Result<Vec<VelocityStats>> {
+        let Some(calendar) = self.get_class_calendar(class_id)? else {
+            return Ok(Vec::new());
         };
 
-        let mut results = Vec::new();
+        let (clause, values) = filter.clause();
+        let stmt = self.conn.prepare(format!(
+            "SELECT p.completed_at, p.student_id
+             FROM progressions p
+             LEFT JOIN students s ON p.student_id = s.id AND p.class_id = s.class_id
+             WHERE p.completed_at IS NOT NULL AND p.completed_at != '' AND p.class_id = ?{}",
+            clause
+        ))?;
+        let (mut stmt, _) = bind_values(stmt.bind(1, class_id)?, 2, &values)?;
+
+        let mut weeks = WeekBuckets::new(&calendar, self.local_clock(class_id)?);
         while let sqlite::State::Row = stmt.next()? {
-            let week = stmt.read::<String>(0)?;
-            let total_completions = stmt.read::<i64>(1)?;
-            let active_students = stmt.read::<i64>(2)?;
+            let student_id = stmt.read::<String>(1)?;
+            weeks.add(&student_id, &stmt.read::<String>(0)?, student_id.clone());
+        }
+
+        let mut results = Vec::new();
+        for (week, students) in weeks.into_weeks() {
+            let total_completions = students.len() as i64;
+            let active_students = students.iter().collect::<HashSet<_>>().len() as i64;
 
             let avg_completions_per_student = if active_students > 0 {
                 total_completions as f64 / active_students as f64
@@ -992,7 +850,10 @@ impl Database {
             };
 
             results.push(VelocityStats {
-                week,
+                week: week.label,
+                week_number: week.number,
+                week_start: week.start_date,
+                is_break: week.is_break,
                 avg_completions_per_student,
                 total_completions,
                 active_students,
@@ -1007,33 +868,31 @@ impl Database {
         class_id: &str,
         night: Option<&str>,
     ) -> Result<Vec<EngagementGap>> {
-        let total_assignments = self.get_assignment_count_by_class(class_id)?;
+        self.get_engagement_gaps_filtered(class_id, &AnalyticsFilter::night(night))
+    }
 
-        let query = if night.is_some() {
-            "SELECT s.id, s.first_name, s.last_name, s.email, s.night,
-                    MAX(p.completed_at) as last_activity,
-                    COUNT(p.id) as completed
-             FROM students s
-             LEFT JOIN progressions p ON s.id = p.student_id AND s.class_id = p.class_id
-             WHERE s.class_id = ? AND s.night = ?
-             GROUP BY s.id, s.first_name, s.last_name, s.email, s.night"
-        } else {
+    pub fn get_engagement_gaps_filtered(
+        &self,
+        class_id: &str,
+        filter: &AnalyticsFilter,
+    ) -> Result<Vec<EngagementGap>> {
+        let total_assignments = self.count_assignments_filtered(class_id, filter)?;
+
+        let (student_clause, student_values) = filter.student_clause();
+        let (progression_clause, progression_values) = filter.progression_clause();
+        let stmt = self.conn.prepare(format!(
             "SELECT s.id, s.first_name, s.last_name, s.email, s.night,
                     MAX(p.completed_at) as last_activity,
                     COUNT(p.id) as completed
              FROM students s
-             LEFT JOIN progressions p ON s.id = p.student_id AND s.class_id = p.class_id
-             WHERE s.class_id = ?
+             LEFT JOIN progressions p ON s.id = p.student_id AND s.class_id = p.class_id{progression_clause}
+             WHERE s.class_id = ?{student_clause}
              GROUP BY s.id, s.first_name, s.last_name, s.email, s.night"
-        };
-
-        let stmt = self.conn.prepare(query)?;
-        let mut stmt = if let Some(night_val) = night {
-            stmt.bind(1, class_id)?.bind(2, night_val)?
-        } else {
-            stmt.bind(1, class_id)?
-        };
+        ))?;
+        let (stmt, next) = bind_values(stmt, 1, &progression_values)?;
+        let (mut stmt, _) = bind_values(stmt.bind(next, class_id)?, next + 1, &student_values)?;
 
+        let clock = self.local_clock(class_id)?.frozen_at(filter.as_of);
         let mut gaps = Vec::new();
 
         while let sqlite::State::Row = stmt.next()? {
@@ -1050,7 +909,10 @@ impl Database {
             // 2. Have been inactive for 7-14 days (engagement gap)
             if completion_pct > 0.5 {
                 if let Some(ref date_str) = last_activity {
-                    if let Ok(days_inactive) = self.calculate_days_since(date_str) {
+                    let student_id = stmt.read::<String>(0)?;
+                    if let Ok(days_inactive) =
+                        self.calculate_days_since(&clock, &student_id, date_str)
+                    {
                         if (7..=14).contains(&days_inactive) {
                             gaps.push(EngagementGap {
                                 student_id: stmt.read::<String>(0)?,
@@ -1069,7 +931,7 @@ impl Database {
         }
 
         // Sort by days inactive (most concerning first)
-        gaps.sort_by(|a, b| b.days_inactive.cmp(&a.days_inactive));
+        gaps.sort_by_key(|g| std::cmp::Reverse(g.days_inactive));
 
         Ok(gaps)
     }
@@ -1079,46 +941,36 @@ impl Database {
         class_id: &str,
         night: Option<&str>,
     ) -> Result<Vec<AssignmentDifficulty>> {
-        let total_students = if let Some(night_val) = night {
-            let stmt = self
-                .conn
-                .prepare("SELECT COUNT(*) FROM students WHERE class_id = ? AND night = ?")?;
-            let mut stmt = stmt.bind(1, class_id)?.bind(2, night_val)?;
-            match stmt.next()? {
-                sqlite::State::Row => stmt.read::<i64>(0)?,
-                sqlite::State::Done => 0,
-            }
-        } else {
-            self.get_student_count_by_class(class_id)?
+        self.get_assignment_difficulty_filtered(class_id, &AnalyticsFilter::night(night))
+    }
+
+    pub fn get_assignment_difficulty_filtered(
+        &self,
+        class_id: &str,
+        filter: &AnalyticsFilter,
+    ) -> Result<Vec<AssignmentDifficulty>> {
+        let total_students = self.count_students_filtered(class_id, filter)?;
+        let median_minutes = self.median_minutes_by_assignment(class_id, filter)?;
+        // The class's typical assignment time, to compare each assignment to
+        let typical_minutes = {
+            let mut medians: Vec<f64> = median_minutes.values().copied().collect();
+            medians.sort_by(|a, b| a.partial_cmp(b).unwrap());
+            (!medians.is_empty()).then(|| percentile(&medians, 0.5))
         };
 
-        let query = if night.is_some() {
-            "SELECT a.id, a.name, a.section, a.type,
-                    COUNT(CASE WHEN s.night = ? AND s.class_id = a.class_id THEN p.id END) as completions,
-                    AVG(CASE WHEN s.night = ? AND s.class_id = a.class_id THEN p.grade END) as avg_grade
-             FROM assignments a
-             LEFT JOIN progressions p ON a.id = p.assignment_id AND a.class_id = p.class_id
-             LEFT JOIN students s ON p.student_id = s.id
-             WHERE a.class_id = ?
-             GROUP BY a.id, a.name, a.section, a.type"
-        } else {
+        let (progressions, values) = filtered_progressions(class_id, filter);
+        let (assignment_clause, assignment_values) = filter.assignment_clause();
+        let stmt = self.conn.prepare(format!(
             "SELECT a.id, a.name, a.section, a.type,
                     COUNT(p.id) as completions,
                     AVG(p.grade) as avg_grade
              FROM assignments a
-             LEFT JOIN progressions p ON a.id = p.assignment_id AND a.class_id = p.class_id
-             WHERE a.class_id = ?
+             LEFT JOIN ({progressions}) p ON a.id = p.assignment_id
+             WHERE a.class_id = ?{assignment_clause}
              GROUP BY a.id, a.name, a.section, a.type"
-        };
-
-        let stmt = self.conn.prepare(query)?;
-        let mut stmt = if let Some(night_val) = night {
-            stmt.bind(1, night_val)?
-                .bind(2, night_val)?
-                .bind(3, class_id)?
-        } else {
-            stmt.bind(1, class_id)?
-        };
+        ))?;
+        let (stmt, next) = bind_values(stmt, 1, &values)?;
+        let (mut stmt, _) = bind_values(stmt.bind(next, class_id)?, next + 1, &assignment_values)?;
 
         let mut difficulties = Vec::new();
 
@@ -1132,19 +984,36 @@ impl Database {
                 0.0
             };
 
+            let assignment_id = stmt.read::<String>(0)?;
+            let assignment_minutes = median_minutes.get(&assignment_id).copied();
+
             // Difficulty score: higher = more difficult
-            // Formula: (1 - completion_rate) * 0.6 + (1 - avg_grade) * 0.4
-            // Weights completion more heavily than grade
+            // Without timings: (1 - completion_rate) * 0.6 + (1 - avg_grade) * 0.4
+            // With timings: completion 0.5, grade 0.3, time on task 0.2, where
+            // twice the class's typical time or more scores the full 0.2
+            let (completion_weight, grade_weight) = if typical_minutes.is_some() {
+                (0.5, 0.3)
+            } else {
+                (0.6, 0.4)
+            };
             let grade_component = if let Some(grade) = avg_grade {
-                (1.0 - grade) * 0.4
+                (1.0 - grade) * grade_weight
             } else {
-                0.4 // Assume difficult if no grades
+                grade_weight // Assume difficult if no grades
+            };
+            let completion_component = (1.0 - completion_rate) * completion_weight;
+            let time_component = match typical_minutes {
+                Some(typical) => {
+                    // Untimed assignments count as typical
+                    let relative = assignment_minutes.map_or(0.5, |m| (m / typical / 2.0).min(1.0));
+                    relative * 0.2
+                }
+                None => 0.0,
             };
-            let completion_component = (1.0 - completion_rate) * 0.6;
-            let difficulty_score = completion_component + grade_component;
+            let difficulty_score = completion_component + grade_component + time_component;
 
             difficulties.push(AssignmentDifficulty {
-                assignment_id: stmt.read::<String>(0)?,
+                assignment_id,
                 name: stmt.read::<String>(1)?,
                 section: stmt.read::<Option<String>>(2)?,
                 assignment_type: stmt.read::<String>(3)?,
@@ -1153,6 +1022,7 @@ impl Database {
                 avg_grade,
                 total_students,
                 completions,
+                median_minutes: assignment_minutes,
             });
         }
 
@@ -1167,40 +1037,33 @@ impl Database {
         class_id: &str,
         night: Option<&str>,
     ) -> Result<Vec<SectionProgress>> {
-        let query = if night.is_some() {
-            "SELECT 
-                a.section,
-                COUNT(DISTINCT s.id) as total_students,
-                COUNT(DISTINCT CASE WHEN p.id IS NOT NULL THEN s.id END) as students_started,
-                COUNT(DISTINCT CASE WHEN p.id IS NOT NULL AND p.grade >= 0.7 THEN s.id END) as students_completed
-             FROM assignments a
-             CROSS JOIN students s ON s.class_id = ? AND s.night = ?
-             LEFT JOIN progressions p ON p.assignment_id = a.id AND p.student_id = s.id AND p.class_id = a.class_id
-             WHERE a.class_id = ? AND a.section IS NOT NULL AND a.section != ''
-             GROUP BY a.section
-             ORDER BY a.section"
-        } else {
+        self.get_section_progress_filtered(class_id, &AnalyticsFilter::night(night))
+    }
+
+    pub fn get_section_progress_filtered(
+        &self,
+        class_id: &str,
+        filter: &AnalyticsFilter,
+    ) -> Result<Vec<SectionProgress>> {
+        let (student_clause, student_values) = filter.student_clause();
+        let (progression_clause, progression_values) = filter.progression_clause();
+        let (assignment_clause, assignment_values) = filter.assignment_clause();
+        let stmt = self.conn.prepare(format!(
             "SELECT 
                 a.section,
                 COUNT(DISTINCT s.id) as total_students,
                 COUNT(DISTINCT CASE WHEN p.id IS NOT NULL THEN s.id END) as students_started,
                 COUNT(DISTINCT CASE WHEN p.id IS NOT NULL AND p.grade >= 0.7 THEN s.id END) as students_completed
              FROM assignments a
-             CROSS JOIN students s ON s.class_id = ?
-             LEFT JOIN progressions p ON p.assignment_id = a.id AND p.student_id = s.id AND p.class_id = a.class_id
-             WHERE a.class_id = ? AND a.section IS NOT NULL AND a.section != ''
+             CROSS JOIN students s ON s.class_id = ?{student_clause}
+             LEFT JOIN progressions p ON p.assignment_id = a.id AND p.student_id = s.id AND p.class_id = a.class_id{progression_clause}
+             WHERE a.class_id = ? AND a.section IS NOT NULL AND a.section != ''{assignment_clause}
              GROUP BY a.section
              ORDER BY a.section"
-        };
-
-        let stmt = self.conn.prepare(query)?;
-        let mut stmt = if let Some(night_val) = night {
-            stmt.bind(1, class_id)?
-                .bind(2, night_val)?
-                .bind(3, class_id)?
-        } else {
-            stmt.bind(1, class_id)?.bind(2, class_id)?
-        };
+        ))?;
+        let (stmt, next) = bind_values(stmt.bind(1, class_id)?, 2, &student_values)?;
+        let (stmt, next) = bind_values(stmt, next, &progression_values)?;
+        let (mut stmt, _) = bind_values(stmt.bind(next, class_id)?, next + 1, &assignment_values)?;
 
         let mut results = Vec::new();
         while let sqlite::State::Row = stmt.next()? {
@@ -1214,3 +1077,105 @@ impl Database {
         Ok(results)
     }
 }
+
+/// Subquery selecting the class's progressions that match the filter, with
+/// the values to bind for it (the class id first).
+fn filtered_progressions(class_id: &str, filter: &AnalyticsFilter) -> (String, Vec<String>) {
+    let (clause, filter_values) = filter.clause();
+    let sql = format!(
+        "SELECT p.* FROM progressions p
+         LEFT JOIN students s ON p.student_id = s.id AND p.class_id = s.class_id
+         WHERE p.class_id = ?{clause}"
+    );
+    let mut values = vec![class_id.to_string()];
+    values.extend(filter_values);
+    (sql, values)
+}
+
+/// Rows grouped by the class calendar week of the student's local date.
+/// Unparseable timestamps are dropped.
+struct WeekBuckets<'a, T> {
+    calendar: &'a ClassCalendar,
+    clock: LocalClock,
+    weeks: BTreeMap<i64, (CalendarWeek, Vec<T>)>,
+}
+
+impl<'a, T> WeekBuckets<'a, T> {
+    fn new(calendar: &'a ClassCalendar, clock: LocalClock) -> Self {
+        Self {
+            calendar,
+            clock,
+            weeks: BTreeMap::new(),
+        }
+    }
+
+    fn add(&mut self, student_id: &str, timestamp: &str, value: T) {
+        let Some(date) =
+            parse_timestamp(timestamp).map(|ts| self.clock.local(student_id, ts).date())
+        else {
+            return;
+        };
+        self.weeks
+            .entry(self.calendar.week_index(date))
+            .or_insert_with(|| (self.calendar.week_of(date), Vec::new()))
+            .1
+            .push(value);
+    }
+
+    fn into_weeks(self) -> impl Iterator<Item = (CalendarWeek, Vec<T>)> {
+        self.weeks.into_values()
+    }
+}
+
+const DAY_NAMES: [&str; 7] = [
+    "Sunday",
+    "Monday",
+    "Tuesday",
+    "Wednesday",
+    "Thursday",
+    "Friday",
+    "Saturday",
+];
+
+const TIME_PERIODS: [&str; 4] = [
+    "Morning (6am-12pm)",
+    "Afternoon (12pm-6pm)",
+    "Evening (6pm-12am)",
+    "Night (12am-6am)",
+];
+
+/// Counts per weekday, leaving out days with no completions.
+fn day_of_week_stats(times: &[NaiveDateTime]) -> Vec<DayOfWeekStats> {
+    let mut counts = [0i64; 7];
+    for at in times {
+        counts[at.weekday().num_days_from_sunday() as usize] += 1;
+    }
+    bucket_stats(&DAY_NAMES, &counts)
+}
+
+/// Counts per time-of-day period, leaving out empty periods.
+fn time_of_day_stats(times: &[NaiveDateTime]) -> Vec<DayOfWeekStats> {
+    let mut counts = [0i64; 4];
+    for at in times {
+        let period = match at.hour() {
+            6..=11 => 0,
+            12..=17 => 1,
+            18..=23 => 2,
+            _ => 3,
+        };
+        counts[period] += 1;
+    }
+    bucket_stats(&TIME_PERIODS, &counts)
+}
+
+fn bucket_stats(labels: &[&str], counts: &[i64]) -> Vec<DayOfWeekStats> {
+    labels
+        .iter()
+        .zip(counts)
+        .filter(|(_, count)| **count > 0)
+        .map(|(label, count)| DayOfWeekStats {
+            day: label.to_string(),
+            count: *count,
+        })
+        .collect()
+}
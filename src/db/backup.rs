@@ -0,0 +1,186 @@
+use super::{Database, SCHEMA_VERSION};
+use anyhow::{anyhow, Result};
+use std::fs;
+use std::path::{Path, PathBuf};
+
+/// How many automatic backups to keep when no config is available.
+pub const DEFAULT_BACKUP_KEEP: usize = 5;
+
+const REQUIRED_TABLES: [&str; 4] = ["classes", "students", "assignments", "progressions"];
+
+impl Database {
+    /// Write a consistent copy of the database to `dest`.
+    ///
+    /// Uses `VACUUM INTO`, which reads inside a transaction, so it is safe to
+    /// run while the server holds the database open.
+    pub fn backup_to(&self, dest: &str) -> Result<()> {
+        if Path::new(dest).exists() {
+            return Err(anyhow!("Backup destination already exists: {}", dest));
+        }
+        if let Some(parent) = Path::new(dest).parent() {
+            if !parent.as_os_str().is_empty() {
+                fs::create_dir_all(parent)?;
+            }
+        }
+
+        let stmt = self.conn.prepare("VACUUM INTO ?")?;
+        let mut stmt = stmt.bind(1, dest)?;
+        stmt.next()?;
+        Ok(())
+    }
+}
+
+/// Directory for automatic backups, next to the database file
+/// (e.g. `~/.cohort-tracker.db.backups/`).
+pub fn backup_dir(db_path: &Path) -> PathBuf {
+    let name = db_path
+        .file_name()
+        .map(|n| n.to_string_lossy().to_string())
+        .unwrap_or_else(|| "cohort-tracker.db".to_string());
+    db_path.with_file_name(format!("{}.backups", name))
+}
+
+/// Take a timestamped backup into the backup directory and prune the oldest
+/// ones taken for the same reason so that at most `keep` of those remain.
+/// A burst of automatic backups never prunes manual ones.
+pub fn rotate_backup(db: &Database, db_path: &Path, reason: &str, keep: usize) -> Result<PathBuf> {
+    let dir = backup_dir(db_path);
+    fs::create_dir_all(&dir)?;
+
+    // Millisecond timestamp, then a sequence number for backups taken in
+    // the same millisecond, so names sort in the order they were taken
+    let stamp = chrono::Utc::now().format("%Y%m%d-%H%M%S-%3f").to_string();
+    let taken = fs::read_dir(&dir)?
+        .flatten()
+        .filter(|e| e.file_name().to_string_lossy().starts_with(&stamp))
+        .count();
+    let dest = dir.join(format!("{}-{:03}-{}.db", stamp, taken, reason));
+
+    db.backup_to(
+        dest.to_str()
+            .ok_or_else(|| anyhow!("Invalid backup path: {}", dest.display()))?,
+    )?;
+
+    // Timestamps sort lexically, so the oldest backups come first
+    let mut backups: Vec<PathBuf> = fs::read_dir(&dir)?
+        .flatten()
+        .map(|e| e.path())
+        .filter(|p| backup_reason(p) == Some(reason))
+        .collect();
+    backups.sort();
+    let excess = backups.len().saturating_sub(keep.max(1));
+    for old in backups.into_iter().take(excess) {
+        fs::remove_file(old)?;
+    }
+
+    Ok(dest)
+}
+
+/// The reason a rotated backup was taken, from its
+/// `<date>-<time>-<millis>-<seq>-<reason>.db` name.
+fn backup_reason(path: &Path) -> Option<&str> {
+    if path.extension()? != "db" {
+        return None;
+    }
+    path.file_stem()?.to_str()?.splitn(5, '-').nth(4)
+}
+
+/// Check that a file is a tracker database this version can open.
+/// Returns the schema version stored in the file.
+pub fn validate_backup(path: &str) -> Result<i64> {
+    if !Path::new(path).is_file() {
+        return Err(anyhow!("Backup file not found: {}", path));
+    }
+
+    let conn = sqlite::Connection::open_with_flags(path, sqlite::OpenFlags::new().set_read_only())
+        .map_err(|e| anyhow!("Not a readable SQLite database: {}", e))?;
+
+    let mut stmt = conn
+        .prepare("PRAGMA quick_check")
+        .map_err(|e| anyhow!("Not a readable SQLite database: {}", e))?;
+    stmt.next()?;
+    let check = stmt.read::<String>(0)?;
+    if check != "ok" {
+        return Err(anyhow!("Backup failed integrity check: {}", check));
+    }
+
+    let mut stmt = conn.prepare("PRAGMA user_version")?;
+    stmt.next()?;
+    let version = stmt.read::<i64>(0)?;
+    if version > SCHEMA_VERSION {
+        return Err(anyhow!(
+            "Backup uses schema version {}, but this build only supports up to {}. Update cohort-tracker first.",
+            version,
+            SCHEMA_VERSION
+        ));
+    }
+
+    for table in REQUIRED_TABLES {
+        let stmt =
+            conn.prepare("SELECT COUNT(*) FROM sqlite_master WHERE type = 'table' AND name = ?")?;
+        let mut stmt = stmt.bind(1, table)?;
+        stmt.next()?;
+        if stmt.read::<i64>(0)? == 0 {
+            return Err(anyhow!("Backup is missing the '{}' table", table));
+        }
+    }
+
+    Ok(version)
+}
+
+/// Replace the database at `db_path` with the backup at `src`.
+///
+/// The current database is backed up first. Returns the path of that
+/// safety backup, if there was a database to save.
+///
+/// Refuses while another process holds the database open, since SQLite
+/// would replay its journal over the restored file.
+pub fn restore_database(src: &str, db_path: &Path, keep: usize) -> Result<Option<PathBuf>> {
+    validate_backup(src)?;
+
+    let safety = if db_path.exists() {
+        // Saved as it is, without migrating it first. Closing the connection
+        // checkpoints and removes the journal unless someone else has it open.
+        let current = Database::from_conn(sqlite::open(db_path)?);
+        let safety = rotate_backup(&current, db_path, "pre-restore", keep)?;
+        drop(current);
+        if let Some(sidecar) = sidecars(db_path).into_iter().find(|p| p.exists()) {
+            return Err(anyhow!(
+                "{} exists, so the database is still in use. Stop the server and try again.",
+                sidecar.display()
+            ));
+        }
+        Some(safety)
+    } else {
+        // Left over from a deleted database; they would be replayed over
+        // the restored one
+        for sidecar in sidecars(db_path) {
+            if sidecar.exists() {
+                fs::remove_file(sidecar)?;
+            }
+        }
+        None
+    };
+
+    // Copy next to the target, then rename so the swap is atomic
+    let staging = db_path.with_extension("restoring");
+    let swapped = fs::copy(src, &staging).and_then(|_| fs::rename(&staging, db_path));
+    if let Err(e) = swapped {
+        let _ = fs::remove_file(&staging);
+        return Err(e.into());
+    }
+
+    Ok(safety)
+}
+
+/// Journal files SQLite keeps next to the database.
+fn sidecars(db_path: &Path) -> Vec<PathBuf> {
+    ["-wal", "-journal"]
+        .iter()
+        .map(|suffix| {
+            let mut name = db_path.as_os_str().to_os_string();
+            name.push(suffix);
+            PathBuf::from(name)
+        })
+        .collect()
+}
@@ -0,0 +1,280 @@
+use super::time_on_task::percentile;
+use super::Database;
+use anyhow::{bail, Result};
+use chrono::NaiveDate;
+use std::collections::HashMap;
+
+use crate::calendar::ClassCalendar;
+use crate::models::*;
+
+/// A class as one run of its course.
+struct Run {
+    class: Class,
+    calendar: Option<ClassCalendar>,
+}
+
+impl Run {
+    fn start(&self) -> Option<NaiveDate> {
+        self.calendar.as_ref().map(|c| c.start)
+    }
+}
+
+impl Database {
+    fn get_explicit_course(&self, class_id: &str) -> Result<Option<String>> {
+        let stmt = self
+            .conn
+            .prepare("SELECT course FROM class_courses WHERE class_id = ?")?;
+        let mut stmt = stmt.bind(1, class_id)?;
+        match stmt.next()? {
+            sqlite::State::Row => Ok(Some(stmt.read::<String>(0)?)),
+            sqlite::State::Done => Ok(None),
+        }
+    }
+
+    /// Mark a class as a run of `course`, or go back to the course from its
+    /// friendly id with `None`.
+    pub fn set_class_course(&self, class_id: &str, course: Option<&str>) -> Result<()> {
+        match course.map(str::trim) {
+            Some("") => bail!("Course name can't be empty"),
+            Some(course) => {
+                let stmt = self.conn.prepare(
+                    "INSERT OR REPLACE INTO class_courses (class_id, course) VALUES (?, ?)",
+                )?;
+                let mut stmt = stmt.bind(1, class_id)?.bind(2, course)?;
+                stmt.next()?;
+            }
+            None => {
+                let stmt = self
+                    .conn
+                    .prepare("DELETE FROM class_courses WHERE class_id = ?")?;
+                let mut stmt = stmt.bind(1, class_id)?;
+                stmt.next()?;
+            }
+        }
+        Ok(())
+    }
+
+    /// The course a class is a run of: set explicitly, or its pathway and
+    /// module without the term (`data-analysis-pathway-module-2` for
+    /// `data-analysis-pathway-module-2-aug-2`).
+    fn course_of(&self, class: &Class) -> Result<String> {
+        if let Some(course) = self.get_explicit_course(&class.id)? {
+            return Ok(course);
+        }
+        let key = self.class_key(&class.friendly_id);
+        Ok(match key.module {
+            Some(module) => format!("{}-module-{}", key.pathway, module),
+            None => class.friendly_id.clone(),
+        })
+    }
+
+    /// Every class that is a run of `course`, oldest start first; runs with
+    /// nothing to date them come last.
+    fn course_runs(&self, course: &str) -> Result<Vec<Run>> {
+        let mut runs = Vec::new();
+        for class in self.get_classes()? {
+            if self.course_of(&class)? == course {
+                let calendar = self.get_class_calendar(&class.id)?;
+                runs.push(Run { class, calendar });
+            }
+        }
+        runs.sort_by(|a, b| match (a.start(), b.start()) {
+            (Some(a_start), Some(b_start)) => a_start.cmp(&b_start),
+            (Some(_), None) => std::cmp::Ordering::Less,
+            (None, Some(_)) => std::cmp::Ordering::Greater,
+            (None, None) => a.class.friendly_id.cmp(&b.class.friendly_id),
+        });
+        Ok(runs)
+    }
+
+    fn course_run_info(&self, run: &Run) -> CourseRun {
+        CourseRun {
+            class_id: run.class.id.clone(),
+            friendly_id: run.class.friendly_id.clone(),
+            name: run.class.name.clone(),
+            term: self.class_key(&run.class.friendly_id).term,
+            start_date: run.start().map(|d| d.format("%Y-%m-%d").to_string()),
+        }
+    }
+
+    /// The class's course and the runs of it. `None` if the class doesn't
+    /// exist.
+    pub fn get_class_course_info(&self, class_id: &str) -> Result<Option<ClassCourseInfo>> {
+        let Some(class) = self.get_classes()?.into_iter().find(|c| c.id == class_id) else {
+            return Ok(None);
+        };
+        let course = self.course_of(&class)?;
+        let runs = self.course_runs(&course)?;
+        Ok(Some(ClassCourseInfo {
+            class_id: class.id.clone(),
+            explicit: self.get_explicit_course(&class.id)?.is_some(),
+            runs: runs.iter().map(|run| self.course_run_info(run)).collect(),
+            course,
+        }))
+    }
+
+    /// Cumulative completion at the end of each teaching week, through the
+    /// class end date, today, or (for a deactivated class without an end
+    /// date) its last completion.
+    fn completion_curve(&self, run: &Run, today: NaiveDate) -> Result<Vec<f64>> {
+        let Some(calendar) = &run.calendar else {
+            return Ok(Vec::new());
+        };
+        let expected = self.get_student_count_by_class(&run.class.id)?
+            * self.get_assignment_count_by_class(&run.class.id)?;
+        if expected == 0 {
+            return Ok(Vec::new());
+        }
+
+        let completions = self.class_completions(&run.class.id)?;
+        let last_day = match calendar.end {
+            Some(end) => end.min(today),
+            None if run.class.is_active => today,
+            None => completions
+                .iter()
+                .map(|c| c.date)
+                .max()
+                .unwrap_or(calendar.start),
+        };
+        let last_week = calendar.week_of(last_day).number;
+        if last_week < 1 {
+            return Ok(Vec::new());
+        }
+
+        // Completions before the start count towards week 1, later ones
+        // towards the last week
+        let mut per_week = vec![0i64; last_week as usize];
+        for completion in &completions {
+            let week = calendar.week_of(completion.date).number.clamp(1, last_week);
+            per_week[week as usize - 1] += 1;
+        }
+
+        let mut cumulative = 0;
+        Ok(per_week
+            .into_iter()
+            .map(|count| {
+                cumulative += count;
+                cumulative as f64 / expected as f64
+            })
+            .collect())
+    }
+
+    /// The class's completion curve next to every run of its course, with the
+    /// previous run and the median and interquartile band of earlier runs
+    /// for each teaching week. `None` if the class doesn't exist.
+    pub fn get_cohort_benchmark(&self, class_id: &str) -> Result<Option<CohortBenchmark>> {
+        let Some(class) = self.get_classes()?.into_iter().find(|c| c.id == class_id) else {
+            return Ok(None);
+        };
+        let course = self.course_of(&class)?;
+        let runs = self.course_runs(&course)?;
+        let today = chrono::Utc::now().date_naive();
+
+        let mut curves = Vec::new();
+        for run in &runs {
+            curves.push(RunCurve {
+                class_id: run.class.id.clone(),
+                friendly_id: run.class.friendly_id.clone(),
+                completion_pct: self.completion_curve(run, today)?,
+            });
+        }
+
+        let Some(current) = runs.iter().position(|run| run.class.id == class_id) else {
+            return Ok(None);
+        };
+        let current_start = runs[current].start();
+        // Earlier runs: started before this one (all dated runs if this one
+        // hasn't started)
+        let historical: Vec<usize> = (0..runs.len())
+            .filter(|&i| {
+                i != current
+                    && runs[i]
+                        .start()
+                        .is_some_and(|start| current_start.is_none_or(|c| start < c))
+            })
+            .collect();
+        let previous = historical.last().copied();
+
+        let week_count = std::iter::once(current)
+            .chain(historical.iter().copied())
+            .map(|i| curves[i].completion_pct.len())
+            .max()
+            .unwrap_or(0);
+
+        let weeks = (0..week_count)
+            .map(|w| {
+                let mut values: Vec<f64> = historical
+                    .iter()
+                    .filter_map(|&i| curves[i].completion_pct.get(w).copied())
+                    .collect();
+                values.sort_by(|a, b| a.partial_cmp(b).unwrap());
+                let band = |p: f64| (!values.is_empty()).then(|| percentile(&values, p));
+                BenchmarkWeek {
+                    week: w as i64 + 1,
+                    current: curves[current].completion_pct.get(w).copied(),
+                    previous: previous.and_then(|i| curves[i].completion_pct.get(w).copied()),
+                    median: band(0.5),
+                    p25: band(0.25),
+                    p75: band(0.75),
+                    historical_runs: values.len() as i64,
+                }
+            })
+            .collect();
+
+        Ok(Some(CohortBenchmark {
+            class_id: class.id.clone(),
+            course,
+            previous_class_id: previous.map(|i| runs[i].class.id.clone()),
+            runs: runs.iter().map(|run| self.course_run_info(run)).collect(),
+            curves,
+            weeks,
+        }))
+    }
+
+    /// Difficulty of each of the class's assignments in every run of its
+    /// course, matching assignments across runs by name. `None` if the class
+    /// doesn't exist.
+    pub fn get_cohort_difficulty(
+        &self,
+        class_id: &str,
+    ) -> Result<Option<Vec<AssignmentBenchmark>>> {
+        let Some(class) = self.get_classes()?.into_iter().find(|c| c.id == class_id) else {
+            return Ok(None);
+        };
+        let course = self.course_of(&class)?;
+        let key = |name: &str| name.trim().to_lowercase();
+
+        let mut by_run = Vec::new();
+        for run in self.course_runs(&course)? {
+            let difficulty: HashMap<String, AssignmentDifficulty> = self
+                .get_assignment_difficulty(&run.class.id, None)?
+                .into_iter()
+                .map(|d| (key(&d.name), d))
+                .collect();
+            by_run.push((run.class, difficulty));
+        }
+
+        Ok(Some(
+            self.get_assignment_catalog(class_id)?
+                .into_iter()
+                .map(|assignment| AssignmentBenchmark {
+                    runs: by_run
+                        .iter()
+                        .filter_map(|(run, difficulty)| {
+                            let d = difficulty.get(&key(&assignment.name))?;
+                            Some(RunDifficulty {
+                                class_id: run.id.clone(),
+                                friendly_id: run.friendly_id.clone(),
+                                difficulty_score: d.difficulty_score,
+                                completion_rate: d.completion_rate,
+                                avg_grade: d.avg_grade,
+                            })
+                        })
+                        .collect(),
+                    name: assignment.name,
+                    section: assignment.section,
+                })
+                .collect(),
+        ))
+    }
+}
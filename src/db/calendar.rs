@@ -0,0 +1,139 @@
+use super::timestamps::parse_timestamp;
+use super::Database;
+use crate::calendar::{monday_of, parse_date, week_start, ClassCalendar};
+use anyhow::{bail, Result};
+use chrono::NaiveDate;
+
+use crate::models::*;
+
+/// Start, end and break weeks as stored for a class.
+#[derive(Debug, Clone, Default)]
+pub struct CalendarSettings {
+    pub start_date: Option<NaiveDate>,
+    pub end_date: Option<NaiveDate>,
+    pub break_weeks: Vec<NaiveDate>,
+}
+
+impl Database {
+    pub fn get_calendar_settings(&self, class_id: &str) -> Result<CalendarSettings> {
+        let stmt = self.conn.prepare(
+            "SELECT start_date, end_date, break_weeks FROM class_calendars WHERE class_id = ?",
+        )?;
+        let mut stmt = stmt.bind(1, class_id)?;
+
+        if let sqlite::State::Row = stmt.next()? {
+            let start_date = stmt.read::<Option<String>>(0)?;
+            let end_date = stmt.read::<Option<String>>(1)?;
+            let break_weeks = stmt.read::<String>(2)?;
+            Ok(CalendarSettings {
+                start_date: start_date.as_deref().map(parse_date).transpose()?,
+                end_date: end_date.as_deref().map(parse_date).transpose()?,
+                break_weeks: break_weeks
+                    .split(',')
+                    .filter(|d| !d.trim().is_empty())
+                    .map(parse_date)
+                    .collect::<Result<_>>()?,
+            })
+        } else {
+            Ok(CalendarSettings::default())
+        }
+    }
+
+    /// Replace the calendar for a class. Break weeks are stored as the first
+    /// day of each class week, or its Monday when weeks are inferred.
+    pub fn set_calendar_settings(&self, class_id: &str, settings: &CalendarSettings) -> Result<()> {
+        if let (Some(start), Some(end)) = (settings.start_date, settings.end_date) {
+            if end < start {
+                bail!("End date {} is before start date {}", end, start);
+            }
+        }
+
+        let mut breaks: Vec<NaiveDate> = settings
+            .break_weeks
+            .iter()
+            .map(|d| match settings.start_date {
+                Some(start) => week_start(start, *d),
+                None => monday_of(*d),
+            })
+            .collect();
+        breaks.sort();
+        breaks.dedup();
+        let breaks = breaks
+            .iter()
+            .map(|d| d.format("%Y-%m-%d").to_string())
+            .collect::<Vec<_>>()
+            .join(",");
+
+        let stmt = self.conn.prepare(
+            "INSERT OR REPLACE INTO class_calendars (class_id, start_date, end_date, break_weeks)
+             VALUES (?, ?, ?, ?)",
+        )?;
+        let mut stmt = stmt
+            .bind(1, class_id)?
+            .bind(
+                2,
+                settings
+                    .start_date
+                    .map(|d| d.format("%Y-%m-%d").to_string())
+                    .as_deref(),
+            )?
+            .bind(
+                3,
+                settings
+                    .end_date
+                    .map(|d| d.format("%Y-%m-%d").to_string())
+                    .as_deref(),
+            )?
+            .bind(4, breaks.as_str())?;
+        stmt.next()?;
+        Ok(())
+    }
+
+    /// The calendar weekly series are grouped by. Without a configured start
+    /// date, weeks count from the Monday of the class's first completion;
+    /// `None` means there is nothing to anchor to yet.
+    pub fn get_class_calendar(&self, class_id: &str) -> Result<Option<ClassCalendar>> {
+        let settings = self.get_calendar_settings(class_id)?;
+        let start = match settings.start_date {
+            Some(start) => Some(start),
+            None => self.first_completion_date(class_id)?.map(monday_of),
+        };
+
+        Ok(start.map(|start| ClassCalendar::new(start, settings.end_date, &settings.break_weeks)))
+    }
+
+    pub fn get_class_calendar_info(&self, class_id: &str) -> Result<ClassCalendarInfo> {
+        let settings = self.get_calendar_settings(class_id)?;
+        let calendar = self.get_class_calendar(class_id)?;
+        let today = chrono::Utc::now().date_naive();
+        let format = |d: &NaiveDate| d.format("%Y-%m-%d").to_string();
+
+        Ok(ClassCalendarInfo {
+            class_id: class_id.to_string(),
+            start_date: settings.start_date.as_ref().map(format),
+            end_date: settings.end_date.as_ref().map(format),
+            break_weeks: settings.break_weeks.iter().map(format).collect(),
+            inferred_start: settings.start_date.is_none() && calendar.is_some(),
+            weeks: calendar.map(|c| c.weeks(today)).unwrap_or_default(),
+        })
+    }
+
+    fn first_completion_date(&self, class_id: &str) -> Result<Option<NaiveDate>> {
+        let stmt = self.conn.prepare(
+            "SELECT completed_at FROM progressions
+             WHERE class_id = ? AND completed_at IS NOT NULL AND completed_at != ''",
+        )?;
+        let mut stmt = stmt.bind(1, class_id)?;
+
+        let mut first: Option<NaiveDate> = None;
+        while let sqlite::State::Row = stmt.next()? {
+            if let Some(ts) = parse_timestamp(&stmt.read::<String>(0)?) {
+                let date = ts.date();
+                if first.is_none_or(|f| date < f) {
+                    first = Some(date);
+                }
+            }
+        }
+        Ok(first)
+    }
+}
@@ -0,0 +1,257 @@
+use super::Database;
+use anyhow::Result;
+use chrono::{DateTime, Utc};
+use std::collections::HashMap;
+
+use crate::models::*;
+use crate::risk::level_rank;
+
+/// Digests kept per class; older ones are deleted.
+const DIGESTS_KEPT: i64 = 100;
+
+/// A student's state as of their latest snapshot.
+struct SnapshotState {
+    risk: String,
+    days_inactive: Option<i64>,
+}
+
+/// A class as it stood before a sync: which progressions were completed and
+/// reviewed, and each student's latest snapshot. Taken at the start of a
+/// sync and compared with the class afterwards.
+pub struct SyncBaseline {
+    class_id: String,
+    /// Progression id to (completed, reviewed)
+    progressions: HashMap<String, (bool, bool)>,
+    students: HashMap<String, SnapshotState>,
+    previous_sync_at: Option<String>,
+}
+
+fn present(value: Option<String>) -> Option<String> {
+    value.filter(|v| !v.is_empty())
+}
+
+impl Database {
+    pub fn sync_baseline(&self, class_id: &str) -> Result<SyncBaseline> {
+        let stmt = self
+            .conn
+            .prepare("SELECT id, completed_at, reviewed_at FROM progressions WHERE class_id = ?")?;
+        let mut stmt = stmt.bind(1, class_id)?;
+        let mut progressions = HashMap::new();
+        while let sqlite::State::Row = stmt.next()? {
+            let completed = present(stmt.read::<Option<String>>(1)?).is_some();
+            let reviewed = present(stmt.read::<Option<String>>(2)?).is_some();
+            progressions.insert(stmt.read::<String>(0)?, (completed, reviewed));
+        }
+
+        let stmt = self.conn.prepare(
+            "SELECT ss.student_id, ss.risk, ss.days_inactive
+             FROM student_snapshots ss
+             WHERE ss.class_id = ?
+               AND ss.snapshot_date = (
+                   SELECT MAX(snapshot_date) FROM student_snapshots
+                   WHERE class_id = ss.class_id AND student_id = ss.student_id
+               )",
+        )?;
+        let mut stmt = stmt.bind(1, class_id)?;
+        let mut students = HashMap::new();
+        while let sqlite::State::Row = stmt.next()? {
+            students.insert(
+                stmt.read::<String>(0)?,
+                SnapshotState {
+                    risk: stmt.read::<String>(1)?,
+                    days_inactive: stmt.read::<Option<i64>>(2)?,
+                },
+            );
+        }
+
+        Ok(SyncBaseline {
+            class_id: class_id.to_string(),
+            progressions,
+            students,
+            previous_sync_at: self.last_synced_at(class_id)?,
+        })
+    }
+
+    /// When the class was last synced: the later of its `synced_at` and its
+    /// newest sync history entry, which `sync --class` also writes.
+    fn last_synced_at(&self, class_id: &str) -> Result<Option<String>> {
+        let mut times: Vec<DateTime<Utc>> = Vec::new();
+
+        let stmt = self
+            .conn
+            .prepare("SELECT synced_at FROM classes WHERE id = ?")?;
+        let mut stmt = stmt.bind(1, class_id)?;
+        if let sqlite::State::Row = stmt.next()? {
+            if let Some(at) = present(stmt.read::<Option<String>>(0)?) {
+                times.extend(DateTime::parse_from_rfc3339(&at).map(|at| at.to_utc()));
+            }
+        }
+
+        // Stored as Unix seconds
+        let stmt = self.conn.prepare(
+            "SELECT MAX(CAST(synced_at AS INTEGER)) FROM sync_history WHERE class_id = ?",
+        )?;
+        let mut stmt = stmt.bind(1, class_id)?;
+        if let sqlite::State::Row = stmt.next()? {
+            if let Some(seconds) = stmt.read::<Option<i64>>(0)? {
+                times.extend(DateTime::from_timestamp(seconds, 0));
+            }
+        }
+
+        Ok(times.into_iter().max().map(|at| at.to_rfc3339()))
+    }
+
+    /// Compare the class now with `baseline`, store the result and return it.
+    /// Risk and silence changes need a snapshot from an earlier sync, so the
+    /// first sync of a class only reports completions and reviews.
+    pub fn record_sync_digest(&self, baseline: &SyncBaseline) -> Result<SyncDigest> {
+        let class_id = baseline.class_id.as_str();
+        let synced_at = chrono::Utc::now().to_rfc3339();
+
+        let stmt = self.conn.prepare(
+            "SELECT p.id, p.student_id, COALESCE(s.first_name, ''), COALESCE(s.last_name, ''),
+                    p.assignment_id, COALESCE(a.name, p.assignment_id),
+                    p.grade, p.completed_at, p.reviewed_at
+             FROM progressions p
+             LEFT JOIN students s ON s.id = p.student_id AND s.class_id = p.class_id
+             LEFT JOIN assignments a ON a.id = p.assignment_id AND a.class_id = p.class_id
+             WHERE p.class_id = ?
+             ORDER BY p.completed_at, s.last_name, s.first_name",
+        )?;
+        let mut stmt = stmt.bind(1, class_id)?;
+        let mut completions = Vec::new();
+        let mut newly_reviewed = Vec::new();
+        while let sqlite::State::Row = stmt.next()? {
+            let (was_completed, was_reviewed) = baseline
+                .progressions
+                .get(&stmt.read::<String>(0)?)
+                .copied()
+                .unwrap_or_default();
+            let completed_at = present(stmt.read::<Option<String>>(7)?);
+            let reviewed_at = present(stmt.read::<Option<String>>(8)?);
+            let student_id = stmt.read::<String>(1)?;
+            let first_name = stmt.read::<String>(2)?;
+            let last_name = stmt.read::<String>(3)?;
+            let assignment_id = stmt.read::<String>(4)?;
+            let assignment_name = stmt.read::<String>(5)?;
+            let grade = stmt.read::<Option<f64>>(6)?;
+
+            if let (Some(reviewed_at), false) = (reviewed_at, was_reviewed) {
+                newly_reviewed.push(DigestReview {
+                    student_id: student_id.clone(),
+                    first_name: first_name.clone(),
+                    last_name: last_name.clone(),
+                    assignment_id: assignment_id.clone(),
+                    assignment_name: assignment_name.clone(),
+                    grade,
+                    reviewed_at,
+                });
+            }
+            if let (Some(completed_at), false) = (completed_at, was_completed) {
+                completions.push(DigestCompletion {
+                    student_id,
+                    first_name,
+                    last_name,
+                    assignment_id,
+                    assignment_name,
+                    grade,
+                    completed_at,
+                });
+            }
+        }
+
+        let days_inactive: HashMap<String, Option<i64>> = self
+            .get_student_activity(class_id)?
+            .into_iter()
+            .map(|a| (a.student_id, a.days_inactive))
+            .collect();
+        let grace = self.risk.inactive_grace_days;
+        let silent = |days: Option<i64>| days.is_some_and(|d| d >= grace);
+
+        let mut risk_increases = Vec::new();
+        let mut went_silent = Vec::new();
+        let mut came_back = Vec::new();
+        for student in self.get_student_health(class_id, None)? {
+            let Some(before) = baseline.students.get(&student.student_id) else {
+                continue;
+            };
+            let days = days_inactive.get(&student.student_id).copied().flatten();
+            let entry = || DigestStudent {
+                student_id: student.student_id.clone(),
+                first_name: student.first_name.clone(),
+                last_name: student.last_name.clone(),
+                days_inactive: days,
+            };
+
+            // Students who never completed anything aren't "silent"
+            if silent(days) && before.days_inactive.is_some() && !silent(before.days_inactive) {
+                went_silent.push(entry());
+            } else if silent(before.days_inactive) && days.is_some() && !silent(days) {
+                came_back.push(entry());
+            }
+
+            if level_rank(&student.risk) > level_rank(&before.risk) {
+                risk_increases.push(DigestRiskChange {
+                    student_id: student.student_id.clone(),
+                    first_name: student.first_name.clone(),
+                    last_name: student.last_name.clone(),
+                    from: before.risk.clone(),
+                    to: student.risk.clone(),
+                });
+            }
+        }
+
+        let class_name = self
+            .get_classes()?
+            .into_iter()
+            .find(|c| c.id == class_id)
+            .map_or_else(|| class_id.to_string(), |c| c.name);
+
+        let digest = SyncDigest {
+            class_id: class_id.to_string(),
+            class_name,
+            synced_at,
+            previous_sync_at: baseline.previous_sync_at.clone(),
+            completions,
+            risk_increases,
+            went_silent,
+            came_back,
+            newly_reviewed,
+        };
+
+        let stmt = self
+            .conn
+            .prepare("INSERT INTO sync_digests (class_id, synced_at, digest) VALUES (?, ?, ?)")?;
+        let mut stmt = stmt
+            .bind(1, class_id)?
+            .bind(2, digest.synced_at.as_str())?
+            .bind(3, serde_json::to_string(&digest)?.as_str())?;
+        stmt.next()?;
+
+        let stmt = self.conn.prepare(
+            "DELETE FROM sync_digests WHERE class_id = ? AND id NOT IN (
+                 SELECT id FROM sync_digests WHERE class_id = ? ORDER BY id DESC LIMIT ?
+             )",
+        )?;
+        let mut stmt = stmt
+            .bind(1, class_id)?
+            .bind(2, class_id)?
+            .bind(3, DIGESTS_KEPT)?;
+        stmt.next()?;
+
+        Ok(digest)
+    }
+
+    /// The class's most recent digests, newest first.
+    pub fn get_sync_digests(&self, class_id: &str, limit: usize) -> Result<Vec<SyncDigest>> {
+        let stmt = self.conn.prepare(
+            "SELECT digest FROM sync_digests WHERE class_id = ? ORDER BY id DESC LIMIT ?",
+        )?;
+        let mut stmt = stmt.bind(1, class_id)?.bind(2, limit as i64)?;
+        let mut digests = Vec::new();
+        while let sqlite::State::Row = stmt.next()? {
+            digests.push(serde_json::from_str(&stmt.read::<String>(0)?)?);
+        }
+        Ok(digests)
+    }
+}
@@ -0,0 +1,401 @@
+use super::Database;
+use anyhow::{anyhow, Result};
+use chrono::{Duration, NaiveDate, NaiveDateTime};
+use std::cmp::Ordering;
+use std::collections::HashSet;
+
+use crate::models::StudentHealth;
+
+/// Days since a student's last completion within which they count as active.
+const ACTIVE_DAYS: i64 = 7;
+
+/// Where a student is in the course, for filtering analytics.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum StudentStatus {
+    /// Started, not finished, completed something in the last 7 days
+    Active,
+    /// Started, not finished, nothing completed in the last 7 days
+    Inactive,
+    NotStarted,
+    /// Has a progression for every assignment in the class
+    Completed,
+}
+
+impl StudentStatus {
+    pub fn parse(value: &str) -> Result<Self> {
+        match value.trim().to_lowercase().replace('-', "_").as_str() {
+            "active" => Ok(Self::Active),
+            "inactive" => Ok(Self::Inactive),
+            "not_started" => Ok(Self::NotStarted),
+            "completed" => Ok(Self::Completed),
+            _ => Err(anyhow!(
+                "Unknown status '{}': use active, inactive, not_started or completed",
+                value
+            )),
+        }
+    }
+}
+
+/// Orderings for the student health table, each worst first. Students
+/// without a value sort last.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum HealthSort {
+    /// Highest risk score first
+    Risk,
+    /// Least complete first
+    Completion,
+    /// Lowest average grade first
+    AvgGrade,
+    /// Most steeply declining grades first
+    GradeSlope,
+    /// Least consistent grades first
+    GradeVolatility,
+    /// Shortest active-week streak first
+    Streak,
+    /// Longest gap between completions first
+    LongestGap,
+    Name,
+}
+
+impl HealthSort {
+    pub fn parse(value: &str) -> Result<Self> {
+        match value.trim().to_lowercase().replace('-', "_").as_str() {
+            "risk" => Ok(Self::Risk),
+            "completion" => Ok(Self::Completion),
+            "avg_grade" | "grade" => Ok(Self::AvgGrade),
+            "grade_slope" | "slope" => Ok(Self::GradeSlope),
+            "grade_volatility" | "volatility" => Ok(Self::GradeVolatility),
+            "streak" => Ok(Self::Streak),
+            "longest_gap" | "gap" => Ok(Self::LongestGap),
+            "name" => Ok(Self::Name),
+            _ => Err(anyhow!(
+                "Unknown sort '{}': use risk, completion, avg_grade, grade_slope, grade_volatility, streak, longest_gap or name",
+                value
+            )),
+        }
+    }
+
+    pub fn apply(self, students: &mut [StudentHealth]) {
+        // Ascending with missing values last
+        fn low_first(a: Option<f64>, b: Option<f64>) -> Ordering {
+            match (a, b) {
+                (Some(a), Some(b)) => a.partial_cmp(&b).unwrap_or(Ordering::Equal),
+                (Some(_), None) => Ordering::Less,
+                (None, Some(_)) => Ordering::Greater,
+                (None, None) => Ordering::Equal,
+            }
+        }
+        let negate = |v: Option<f64>| v.map(|v| -v);
+
+        students.sort_by(|a, b| {
+            let order = match self {
+                Self::Risk => b.risk_score.cmp(&a.risk_score),
+                Self::Completion => low_first(Some(a.completion_pct), Some(b.completion_pct)),
+                Self::AvgGrade => low_first(a.avg_grade, b.avg_grade),
+                Self::GradeSlope => low_first(a.grade_slope, b.grade_slope),
+                Self::GradeVolatility => {
+                    low_first(negate(a.grade_volatility), negate(b.grade_volatility))
+                }
+                Self::Streak => a.active_week_streak.cmp(&b.active_week_streak),
+                Self::LongestGap => low_first(
+                    negate(a.longest_gap_days.map(|d| d as f64)),
+                    negate(b.longest_gap_days.map(|d| d as f64)),
+                ),
+                Self::Name => Ordering::Equal,
+            };
+            order
+                .then_with(|| a.last_name.cmp(&b.last_name))
+                .then_with(|| a.first_name.cmp(&b.first_name))
+        });
+    }
+}
+
+/// Which students, assignments and completions an analytics query covers.
+/// Conditions are ANDed together; an empty filter means the whole class.
+#[derive(Debug, Clone, Default)]
+pub struct AnalyticsFilter {
+    pub night: Option<String>,
+    pub region: Option<String>,
+    /// Mentor id, name or email
+    pub mentor: Option<String>,
+    pub section: Option<String>,
+    pub assignment_type: Option<String>,
+    pub status: Option<StudentStatus>,
+    /// First day of completions to count, inclusive
+    pub from: Option<NaiveDate>,
+    /// Last day of completions to count, inclusive
+    pub to: Option<NaiveDate>,
+    /// Compute metrics as they stood at the end of this day: later
+    /// completions are ignored and inactivity is measured from it
+    pub as_of: Option<NaiveDate>,
+}
+
+/// Condition on the students table (aliased `s`) for a status, counting
+/// only completions up to `as_of` when given.
+fn status_condition(
+    status: StudentStatus,
+    today: NaiveDate,
+    as_of: Option<NaiveDate>,
+) -> (String, Vec<String>) {
+    let (until, until_values) = match as_of {
+        Some(date) => (
+            " AND substr(sp.completed_at, 1, 10) <= ?",
+            vec![date.format("%Y-%m-%d").to_string()],
+        ),
+        None => ("", Vec::new()),
+    };
+    let started = (
+        format!(
+            "EXISTS (SELECT 1 FROM progressions sp
+                     WHERE sp.student_id = s.id AND sp.class_id = s.class_id{until})"
+        ),
+        until_values.clone(),
+    );
+    let finished = (
+        format!(
+            "(SELECT COUNT(*) FROM progressions sp
+              WHERE sp.student_id = s.id AND sp.class_id = s.class_id{until})
+             >= (SELECT COUNT(*) FROM assignments sa WHERE sa.class_id = s.class_id)"
+        ),
+        until_values.clone(),
+    );
+    let cutoff = today - Duration::days(ACTIVE_DAYS - 1);
+    let mut recent_values = vec![cutoff.format("%Y-%m-%d").to_string()];
+    recent_values.extend(until_values);
+    let recent = (
+        format!(
+            "EXISTS (SELECT 1 FROM progressions sp
+                     WHERE sp.student_id = s.id AND sp.class_id = s.class_id
+                       AND substr(sp.completed_at, 1, 10) >= ?{until})"
+        ),
+        recent_values,
+    );
+
+    let not = |(sql, values): (String, Vec<String>)| (format!("NOT {sql}"), values);
+    let parts = match status {
+        StudentStatus::NotStarted => vec![not(started)],
+        StudentStatus::Completed => vec![started, finished],
+        StudentStatus::Active => vec![started, not(finished), recent],
+        StudentStatus::Inactive => vec![started, not(finished), not(recent)],
+    };
+
+    let mut sql = Vec::new();
+    let mut values = Vec::new();
+    for (part, part_values) in parts {
+        sql.push(part);
+        values.extend(part_values);
+    }
+    (format!("({})", sql.join(" AND ")), values)
+}
+
+impl AnalyticsFilter {
+    pub fn night(night: Option<&str>) -> Self {
+        Self {
+            night: night.map(|n| n.to_string()),
+            ..Default::default()
+        }
+    }
+
+    /// Just the conditions on students and `as_of`, for metrics that always
+    /// look at a student's whole record (pace, projections).
+    pub fn students_only(&self) -> Self {
+        Self {
+            night: self.night.clone(),
+            region: self.region.clone(),
+            mentor: self.mentor.clone(),
+            status: self.status,
+            as_of: self.as_of,
+            ..Default::default()
+        }
+    }
+
+    /// The day metrics are computed for: `as_of`, or today (UTC).
+    pub fn today(&self) -> NaiveDate {
+        self.as_of
+            .unwrap_or_else(|| chrono::Utc::now().date_naive())
+    }
+
+    /// The moment metrics are computed for: the end of `as_of`, or now (UTC).
+    pub(crate) fn now(&self) -> NaiveDateTime {
+        match self.as_of {
+            Some(date) => date.and_hms_opt(23, 59, 59).unwrap_or_default(),
+            None => chrono::Utc::now().naive_utc(),
+        }
+    }
+
+    /// SQL conditions on the students table (aliased `s`), each starting with
+    /// ` AND `, and the values to bind for them in order.
+    pub(crate) fn student_clause(&self) -> (String, Vec<String>) {
+        let mut sql = String::new();
+        let mut values = Vec::new();
+
+        if let Some(night) = &self.night {
+            sql.push_str(" AND LOWER(s.night) = LOWER(?)");
+            values.push(night.clone());
+        }
+
+        if let Some(region) = &self.region {
+            sql.push_str(" AND LOWER(s.region) = LOWER(?)");
+            values.push(region.clone());
+        }
+
+        if let Some(mentor) = &self.mentor {
+            sql.push_str(
+                " AND s.id IN (
+                    SELECT ms.student_id FROM mentor_students ms
+                    JOIN mentors m ON m.id = ms.mentor_id
+                    WHERE ms.class_id = s.class_id
+                      AND (CAST(m.id AS TEXT) = ? OR LOWER(m.name) = LOWER(?) OR LOWER(m.email) = LOWER(?))
+                )",
+            );
+            values.extend([mentor.clone(), mentor.clone(), mentor.clone()]);
+        }
+
+        if let Some(status) = self.status {
+            let (condition, condition_values) = status_condition(status, self.today(), self.as_of);
+            sql.push_str(&format!(" AND {condition}"));
+            values.extend(condition_values);
+        }
+
+        (sql, values)
+    }
+
+    /// SQL conditions on the assignments table (aliased `a`).
+    pub(crate) fn assignment_clause(&self) -> (String, Vec<String>) {
+        let mut sql = String::new();
+        let mut values = Vec::new();
+
+        if let Some(section) = &self.section {
+            sql.push_str(" AND LOWER(a.section) = LOWER(?)");
+            values.push(section.clone());
+        }
+
+        if let Some(assignment_type) = &self.assignment_type {
+            sql.push_str(" AND LOWER(a.type) = LOWER(?)");
+            values.push(assignment_type.clone());
+        }
+
+        (sql, values)
+    }
+
+    /// SQL conditions on the progressions table (aliased `p`): the
+    /// assignment's section and type, and the completion date window and
+    /// `as_of`.
+    pub(crate) fn progression_clause(&self) -> (String, Vec<String>) {
+        let (assignment_sql, mut values) = self.assignment_clause();
+        let mut sql = String::new();
+        if !assignment_sql.is_empty() {
+            sql.push_str(&format!(
+                " AND p.assignment_id IN (
+                    SELECT a.id FROM assignments a
+                    WHERE a.class_id = p.class_id{assignment_sql}
+                )"
+            ));
+        }
+
+        let (date_sql, date_values) = self.date_clause("p.completed_at");
+        sql.push_str(&date_sql);
+        values.extend(date_values);
+
+        (sql, values)
+    }
+
+    /// The `from`/`to` window and `as_of` applied to a date or timestamp
+    /// column.
+    pub(crate) fn date_clause(&self, column: &str) -> (String, Vec<String>) {
+        let mut sql = String::new();
+        let mut values = Vec::new();
+
+        if let Some(as_of) = self.as_of {
+            sql.push_str(&format!(" AND substr({column}, 1, 10) <= ?"));
+            values.push(as_of.format("%Y-%m-%d").to_string());
+        }
+
+        if let Some(from) = self.from {
+            sql.push_str(&format!(" AND substr({column}, 1, 10) >= ?"));
+            values.push(from.format("%Y-%m-%d").to_string());
+        }
+
+        if let Some(to) = self.to {
+            sql.push_str(&format!(" AND substr({column}, 1, 10) <= ?"));
+            values.push(to.format("%Y-%m-%d").to_string());
+        }
+
+        (sql, values)
+    }
+
+    /// Student and progression conditions together, for queries over
+    /// progressions joined to students.
+    pub(crate) fn clause(&self) -> (String, Vec<String>) {
+        let (mut sql, mut values) = self.student_clause();
+        let (progression_sql, progression_values) = self.progression_clause();
+        sql.push_str(&progression_sql);
+        values.extend(progression_values);
+        (sql, values)
+    }
+}
+
+/// Bind `values` to consecutive parameters starting at `start`. Returns the
+/// statement and the next free parameter index.
+pub(crate) fn bind_values<'l>(
+    mut stmt: sqlite::Statement<'l>,
+    start: usize,
+    values: &[String],
+) -> Result<(sqlite::Statement<'l>, usize)> {
+    for (i, value) in values.iter().enumerate() {
+        stmt = stmt.bind(start + i, value.as_str())?;
+    }
+    Ok((stmt, start + values.len()))
+}
+
+impl Database {
+    /// Number of students in the class that match the filter.
+    pub fn count_students_filtered(&self, class_id: &str, filter: &AnalyticsFilter) -> Result<i64> {
+        let (clause, values) = filter.student_clause();
+        let stmt = self.conn.prepare(format!(
+            "SELECT COUNT(*) FROM students s WHERE s.class_id = ?{}",
+            clause
+        ))?;
+        let (mut stmt, _) = bind_values(stmt.bind(1, class_id)?, 2, &values)?;
+        match stmt.next()? {
+            sqlite::State::Row => Ok(stmt.read::<i64>(0)?),
+            sqlite::State::Done => Ok(0),
+        }
+    }
+
+    /// Ids of the assignments in the class in the filter's section and type.
+    pub(crate) fn filtered_assignment_ids(
+        &self,
+        class_id: &str,
+        filter: &AnalyticsFilter,
+    ) -> Result<HashSet<String>> {
+        let (clause, values) = filter.assignment_clause();
+        let stmt = self.conn.prepare(format!(
+            "SELECT a.id FROM assignments a WHERE a.class_id = ?{clause}"
+        ))?;
+        let (mut stmt, _) = bind_values(stmt.bind(1, class_id)?, 2, &values)?;
+        let mut ids = HashSet::new();
+        while let sqlite::State::Row = stmt.next()? {
+            ids.insert(stmt.read::<String>(0)?);
+        }
+        Ok(ids)
+    }
+
+    /// Number of assignments in the class in the filter's section and type.
+    pub fn count_assignments_filtered(
+        &self,
+        class_id: &str,
+        filter: &AnalyticsFilter,
+    ) -> Result<i64> {
+        let (clause, values) = filter.assignment_clause();
+        let stmt = self.conn.prepare(format!(
+            "SELECT COUNT(*) FROM assignments a WHERE a.class_id = ?{}",
+            clause
+        ))?;
+        let (mut stmt, _) = bind_values(stmt.bind(1, class_id)?, 2, &values)?;
+        match stmt.next()? {
+            sqlite::State::Row => Ok(stmt.read::<i64>(0)?),
+            sqlite::State::Done => Ok(0),
+        }
+    }
+}
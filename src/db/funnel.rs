@@ -0,0 +1,162 @@
+use super::filter::{bind_values, AnalyticsFilter};
+use super::Database;
+use anyhow::Result;
+use std::collections::{HashMap, HashSet};
+
+use crate::models::*;
+
+/// Steps kept in the biggest drop-off list.
+const DROP_OFF_LIMIT: usize = 5;
+
+/// A student's progress through the course order.
+struct Path {
+    student_id: String,
+    first_name: String,
+    last_name: String,
+    /// Whether each catalog assignment is completed
+    done: Vec<bool>,
+}
+
+impl Path {
+    fn in_sequence(&self) -> usize {
+        self.done.iter().take_while(|d| **d).count()
+    }
+
+    fn furthest(&self) -> usize {
+        self.done.iter().rposition(|d| *d).map_or(0, |i| i + 1)
+    }
+}
+
+impl Database {
+    /// How many students got through each assignment in course order, where
+    /// the biggest drops between consecutive assignments are, and who
+    /// completed assignments with earlier ones still open.
+    pub fn get_assignment_funnel(
+        &self,
+        class_id: &str,
+        filter: &AnalyticsFilter,
+    ) -> Result<AssignmentFunnel> {
+        let (assignment_clause, assignment_values) = filter.assignment_clause();
+        let stmt = self.conn.prepare(format!(
+            "SELECT a.id FROM assignments a WHERE a.class_id = ?{assignment_clause}"
+        ))?;
+        let (mut stmt, _) = bind_values(stmt.bind(1, class_id)?, 2, &assignment_values)?;
+        let mut included = HashSet::new();
+        while let sqlite::State::Row = stmt.next()? {
+            included.insert(stmt.read::<String>(0)?);
+        }
+        let catalog: Vec<Assignment> = self
+            .get_assignment_catalog(class_id)?
+            .into_iter()
+            .filter(|a| included.contains(&a.id))
+            .collect();
+        let positions: HashMap<&str, usize> = catalog
+            .iter()
+            .enumerate()
+            .map(|(i, a)| (a.id.as_str(), i))
+            .collect();
+
+        let (student_clause, student_values) = filter.student_clause();
+        let stmt = self.conn.prepare(format!(
+            "SELECT s.id, s.first_name, s.last_name FROM students s
+             WHERE s.class_id = ?{student_clause}
+             ORDER BY s.last_name, s.first_name"
+        ))?;
+        let (mut stmt, _) = bind_values(stmt.bind(1, class_id)?, 2, &student_values)?;
+        let mut paths = Vec::new();
+        while let sqlite::State::Row = stmt.next()? {
+            paths.push(Path {
+                student_id: stmt.read::<String>(0)?,
+                first_name: stmt.read::<String>(1)?,
+                last_name: stmt.read::<String>(2)?,
+                done: vec![false; catalog.len()],
+            });
+        }
+        let by_student: HashMap<String, usize> = paths
+            .iter()
+            .enumerate()
+            .map(|(i, p)| (p.student_id.clone(), i))
+            .collect();
+
+        let (progression_clause, progression_values) = filter.progression_clause();
+        let stmt = self.conn.prepare(format!(
+            "SELECT p.student_id, p.assignment_id FROM progressions p
+             WHERE p.class_id = ? AND p.completed_at IS NOT NULL AND p.completed_at != ''{progression_clause}"
+        ))?;
+        let (mut stmt, _) = bind_values(stmt.bind(1, class_id)?, 2, &progression_values)?;
+        while let sqlite::State::Row = stmt.next()? {
+            let student = by_student.get(&stmt.read::<String>(0)?);
+            let position = positions.get(stmt.read::<String>(1)?.as_str());
+            if let (Some(&student), Some(&position)) = (student, position) {
+                paths[student].done[position] = true;
+            }
+        }
+
+        let in_sequence: Vec<usize> = paths.iter().map(Path::in_sequence).collect();
+        let total_students = paths.len() as i64;
+        let mut previous = total_students;
+        let steps: Vec<FunnelStep> = catalog
+            .iter()
+            .enumerate()
+            .map(|(i, assignment)| {
+                let reached = in_sequence.iter().filter(|n| **n > i).count() as i64;
+                let completed = paths.iter().filter(|p| p.done[i]).count() as i64;
+                let drop_off = previous - reached;
+                let drop_off_rate = if previous > 0 {
+                    drop_off as f64 / previous as f64
+                } else {
+                    0.0
+                };
+                previous = reached;
+                FunnelStep {
+                    position: i as i64 + 1,
+                    assignment_id: assignment.id.clone(),
+                    name: assignment.name.clone(),
+                    section: assignment.section.clone(),
+                    reached,
+                    completed,
+                    skipped_ahead: completed - reached,
+                    drop_off,
+                    drop_off_rate,
+                }
+            })
+            .collect();
+
+        let mut biggest_drop_offs: Vec<FunnelStep> =
+            steps.iter().filter(|s| s.drop_off > 0).cloned().collect();
+        biggest_drop_offs.sort_by(|a, b| {
+            b.drop_off_rate
+                .partial_cmp(&a.drop_off_rate)
+                .unwrap_or(std::cmp::Ordering::Equal)
+                .then_with(|| b.drop_off.cmp(&a.drop_off))
+                .then_with(|| a.position.cmp(&b.position))
+        });
+        biggest_drop_offs.truncate(DROP_OFF_LIMIT);
+
+        let mut skipped_ahead: Vec<SkippedAheadStudent> = paths
+            .iter()
+            .zip(&in_sequence)
+            .filter_map(|(path, &in_sequence)| {
+                let furthest = path.furthest();
+                let missed = path.done[..furthest].iter().filter(|d| !**d).count();
+                (missed > 0).then(|| SkippedAheadStudent {
+                    student_id: path.student_id.clone(),
+                    first_name: path.first_name.clone(),
+                    last_name: path.last_name.clone(),
+                    in_sequence: in_sequence as i64,
+                    furthest_position: furthest as i64,
+                    missed: missed as i64,
+                    first_missed: catalog[in_sequence].name.clone(),
+                })
+            })
+            .collect();
+        skipped_ahead.sort_by_key(|s| std::cmp::Reverse(s.missed));
+
+        Ok(AssignmentFunnel {
+            total_students,
+            steps,
+            biggest_drop_offs,
+            skipped_ahead,
+        })
+    }
+}
@@ -0,0 +1,151 @@
+use super::filter::{bind_values, AnalyticsFilter};
+use super::Database;
+use anyhow::Result;
+use std::collections::{HashMap, HashSet};
+
+use crate::models::*;
+
+fn average(grades: impl Iterator<Item = f64>) -> Option<f64> {
+    let (sum, count) = grades.fold((0.0, 0), |(sum, count), g| (sum + g, count + 1));
+    (count > 0).then(|| sum / count as f64)
+}
+
+fn subtotal(section: &GradebookSection, cells: &[GradebookCell]) -> SectionSubtotal {
+    let cells = &cells[section.first_assignment..][..section.assignment_count];
+    let done = || cells.iter().filter(|c| c.state == "completed");
+    SectionSubtotal {
+        section: section.section.clone(),
+        completed: done().count() as i64,
+        total: cells.len() as i64,
+        avg_grade: average(done().filter_map(|c| c.grade)),
+    }
+}
+
+impl Database {
+    /// The class as a grid of students by assignments. Assignments are
+    /// grouped by section, sections in the order the course reaches them
+    /// (unsectioned last), and in course order within each section.
+    pub fn get_gradebook(&self, class_id: &str, filter: &AnalyticsFilter) -> Result<Gradebook> {
+        let (assignment_clause, assignment_values) = filter.assignment_clause();
+        let stmt = self.conn.prepare(format!(
+            "SELECT a.id FROM assignments a WHERE a.class_id = ?{assignment_clause}"
+        ))?;
+        let (mut stmt, _) = bind_values(stmt.bind(1, class_id)?, 2, &assignment_values)?;
+        let mut included = HashSet::new();
+        while let sqlite::State::Row = stmt.next()? {
+            included.insert(stmt.read::<String>(0)?);
+        }
+
+        let mut catalog: Vec<Assignment> = self
+            .get_assignment_catalog(class_id)?
+            .into_iter()
+            .filter(|a| included.contains(&a.id))
+            .collect();
+        let mut section_order: Vec<Option<String>> = Vec::new();
+        for assignment in &catalog {
+            if assignment.section.is_some() && !section_order.contains(&assignment.section) {
+                section_order.push(assignment.section.clone());
+            }
+        }
+        section_order.push(None);
+        catalog.sort_by_key(|a| section_order.iter().position(|s| *s == a.section));
+
+        let mut sections: Vec<GradebookSection> = Vec::new();
+        for (i, assignment) in catalog.iter().enumerate() {
+            match sections.last_mut() {
+                Some(last) if last.section == assignment.section => last.assignment_count += 1,
+                _ => sections.push(GradebookSection {
+                    section: assignment.section.clone(),
+                    first_assignment: i,
+                    assignment_count: 1,
+                }),
+            }
+        }
+        let columns: HashMap<&str, usize> = catalog
+            .iter()
+            .enumerate()
+            .map(|(i, a)| (a.id.as_str(), i))
+            .collect();
+
+        let (progression_clause, progression_values) = filter.progression_clause();
+        let stmt = self.conn.prepare(format!(
+            "SELECT p.student_id, p.assignment_id, p.grade, p.completed_at FROM progressions p
+             WHERE p.class_id = ?{progression_clause}"
+        ))?;
+        let (mut stmt, _) = bind_values(stmt.bind(1, class_id)?, 2, &progression_values)?;
+        let mut progress: HashMap<(String, usize), GradebookCell> = HashMap::new();
+        while let sqlite::State::Row = stmt.next()? {
+            let Some(&column) = columns.get(stmt.read::<String>(1)?.as_str()) else {
+                continue;
+            };
+            let completed_at = stmt.read::<Option<String>>(3)?.filter(|at| !at.is_empty());
+            let cell = GradebookCell {
+                state: if completed_at.is_some() {
+                    "completed"
+                } else {
+                    "in_progress"
+                }
+                .to_string(),
+                grade: stmt.read::<Option<f64>>(2)?,
+                completed_at,
+            };
+            let key = (stmt.read::<String>(0)?, column);
+            // A completed attempt wins over one still in progress
+            if progress
+                .get(&key)
+                .is_none_or(|existing| existing.state != "completed")
+            {
+                progress.insert(key, cell);
+            }
+        }
+
+        let (student_clause, student_values) = filter.student_clause();
+        let stmt = self.conn.prepare(format!(
+            "SELECT s.id, s.first_name, s.last_name, s.email FROM students s
+             WHERE s.class_id = ?{student_clause}
+             ORDER BY s.last_name, s.first_name"
+        ))?;
+        let (mut stmt, _) = bind_values(stmt.bind(1, class_id)?, 2, &student_values)?;
+        let mut students = Vec::new();
+        while let sqlite::State::Row = stmt.next()? {
+            let student_id = stmt.read::<String>(0)?;
+            let cells: Vec<GradebookCell> = (0..catalog.len())
+                .map(|column| {
+                    progress
+                        .remove(&(student_id.clone(), column))
+                        .unwrap_or(GradebookCell {
+                            state: "not_started".to_string(),
+                            grade: None,
+                            completed_at: None,
+                        })
+                })
+                .collect();
+            let done = || cells.iter().filter(|c| c.state == "completed");
+            students.push(GradebookRow {
+                first_name: stmt.read::<String>(1)?,
+                last_name: stmt.read::<String>(2)?,
+                email: stmt.read::<String>(3)?,
+                subtotals: sections.iter().map(|s| subtotal(s, &cells)).collect(),
+                completed: done().count() as i64,
+                avg_grade: average(done().filter_map(|c| c.grade)),
+                cells,
+                student_id,
+            });
+        }
+
+        Ok(Gradebook {
+            class_id: class_id.to_string(),
+            assignments: catalog
+                .into_iter()
+                .map(|a| GradebookAssignment {
+                    assignment_id: a.id,
+                    name: a.name,
+                    assignment_type: a.assignment_type,
+                    section: a.section,
+                })
+                .collect(),
+            sections,
+            students,
+        })
+    }
+}
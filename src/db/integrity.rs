@@ -0,0 +1,223 @@
+use super::timestamps::parse_timestamp;
+use super::Database;
+use anyhow::Result;
+
+use crate::models::{IntegrityIssue, IntegrityReport};
+
+// Issue kinds, in the order they are reported
+pub const ORPHANED_PROGRESSION_STUDENT: &str = "orphaned_progression_student";
+pub const ORPHANED_PROGRESSION_ASSIGNMENT: &str = "orphaned_progression_assignment";
+pub const STUDENT_WITHOUT_CLASS: &str = "student_without_class";
+pub const ASSIGNMENT_WITHOUT_CLASS: &str = "assignment_without_class";
+pub const DUPLICATE_EMAIL: &str = "duplicate_email";
+pub const EMPTY_COMPLETED_AT: &str = "empty_completed_at";
+pub const UNPARSEABLE_TIMESTAMP: &str = "unparseable_timestamp";
+pub const ASSIGNMENT_WITHOUT_SECTION: &str = "assignment_without_section";
+
+impl Database {
+    /// Look for rows the JOIN-based analytics silently drop or miscount.
+    pub fn check_integrity(&self) -> Result<IntegrityReport> {
+        let mut issues = Vec::new();
+
+        // Progressions whose student row is missing never show up in student analytics
+        let mut stmt = self.conn.prepare(
+            "SELECT p.id, p.class_id, p.student_id FROM progressions p
+             LEFT JOIN students s ON s.id = p.student_id AND s.class_id = p.class_id
+             WHERE s.id IS NULL
+             ORDER BY p.class_id, p.id",
+        )?;
+        while let sqlite::State::Row = stmt.next()? {
+            issues.push(IntegrityIssue {
+                kind: ORPHANED_PROGRESSION_STUDENT.to_string(),
+                class_id: Some(stmt.read::<String>(1)?),
+                record_id: stmt.read::<String>(0)?,
+                detail: format!("student {} not found", stmt.read::<String>(2)?),
+                fixable: true,
+            });
+        }
+
+        let mut stmt = self.conn.prepare(
+            "SELECT p.id, p.class_id, p.assignment_id FROM progressions p
+             LEFT JOIN assignments a ON a.id = p.assignment_id AND a.class_id = p.class_id
+             WHERE a.id IS NULL
+             ORDER BY p.class_id, p.id",
+        )?;
+        while let sqlite::State::Row = stmt.next()? {
+            issues.push(IntegrityIssue {
+                kind: ORPHANED_PROGRESSION_ASSIGNMENT.to_string(),
+                class_id: Some(stmt.read::<String>(1)?),
+                record_id: stmt.read::<String>(0)?,
+                detail: format!("assignment {} not found", stmt.read::<String>(2)?),
+                fixable: true,
+            });
+        }
+
+        let mut stmt = self.conn.prepare(
+            "SELECT s.id, s.class_id, s.first_name, s.last_name FROM students s
+             LEFT JOIN classes c ON c.id = s.class_id
+             WHERE c.id IS NULL
+             ORDER BY s.class_id, s.id",
+        )?;
+        while let sqlite::State::Row = stmt.next()? {
+            issues.push(IntegrityIssue {
+                kind: STUDENT_WITHOUT_CLASS.to_string(),
+                class_id: Some(stmt.read::<String>(1)?),
+                record_id: stmt.read::<String>(0)?,
+                detail: format!(
+                    "{} {} belongs to an unknown class",
+                    stmt.read::<String>(2)?,
+                    stmt.read::<String>(3)?
+                ),
+                fixable: true,
+            });
+        }
+
+        let mut stmt = self.conn.prepare(
+            "SELECT a.id, a.class_id, a.name FROM assignments a
+             LEFT JOIN classes c ON c.id = a.class_id
+             WHERE c.id IS NULL
+             ORDER BY a.class_id, a.id",
+        )?;
+        while let sqlite::State::Row = stmt.next()? {
+            issues.push(IntegrityIssue {
+                kind: ASSIGNMENT_WITHOUT_CLASS.to_string(),
+                class_id: Some(stmt.read::<String>(1)?),
+                record_id: stmt.read::<String>(0)?,
+                detail: format!("'{}' belongs to an unknown class", stmt.read::<String>(2)?),
+                fixable: true,
+            });
+        }
+
+        // The same email under different student ids usually means a duplicate account
+        let mut stmt = self.conn.prepare(
+            "SELECT LOWER(TRIM(email)) as normalized,
+                    COUNT(DISTINCT id) as accounts,
+                    GROUP_CONCAT(DISTINCT id) as ids
+             FROM students
+             WHERE TRIM(email) != ''
+             GROUP BY normalized
+             HAVING accounts > 1
+             ORDER BY normalized",
+        )?;
+        while let sqlite::State::Row = stmt.next()? {
+            issues.push(IntegrityIssue {
+                kind: DUPLICATE_EMAIL.to_string(),
+                class_id: None,
+                record_id: stmt.read::<String>(0)?,
+                detail: format!(
+                    "used by {} student ids: {}",
+                    stmt.read::<i64>(1)?,
+                    stmt.read::<String>(2)?
+                ),
+                fixable: false,
+            });
+        }
+
+        let mut stmt = self.conn.prepare(
+            "SELECT id, class_id, started_at, completed_at, reviewed_at FROM progressions
+             ORDER BY class_id, id",
+        )?;
+        while let sqlite::State::Row = stmt.next()? {
+            let id = stmt.read::<String>(0)?;
+            let class_id = stmt.read::<String>(1)?;
+            let completed_at = stmt.read::<Option<String>>(3)?.unwrap_or_default();
+
+            if completed_at.trim().is_empty() {
+                issues.push(IntegrityIssue {
+                    kind: EMPTY_COMPLETED_AT.to_string(),
+                    class_id: Some(class_id.clone()),
+                    record_id: id.clone(),
+                    detail: "completed_at is empty".to_string(),
+                    fixable: false,
+                });
+            }
+
+            let timestamps = [
+                ("started_at", stmt.read::<Option<String>>(2)?),
+                ("completed_at", Some(completed_at)),
+                ("reviewed_at", stmt.read::<Option<String>>(4)?),
+            ];
+            for (column, value) in timestamps {
+                if let Some(value) = value {
+                    if !value.trim().is_empty() && parse_timestamp(&value).is_none() {
+                        issues.push(IntegrityIssue {
+                            kind: UNPARSEABLE_TIMESTAMP.to_string(),
+                            class_id: Some(class_id.clone()),
+                            record_id: id.clone(),
+                            detail: format!("{} = '{}'", column, value),
+                            fixable: false,
+                        });
+                    }
+                }
+            }
+        }
+
+        let mut stmt = self.conn.prepare(
+            "SELECT id, class_id, name FROM assignments
+             WHERE section IS NULL OR TRIM(section) = ''
+             ORDER BY class_id, name",
+        )?;
+        while let sqlite::State::Row = stmt.next()? {
+            issues.push(IntegrityIssue {
+                kind: ASSIGNMENT_WITHOUT_SECTION.to_string(),
+                class_id: Some(stmt.read::<String>(1)?),
+                record_id: stmt.read::<String>(0)?,
+                detail: format!("'{}' has no section", stmt.read::<String>(2)?),
+                fixable: false,
+            });
+        }
+
+        Ok(IntegrityReport { issues, fixed: 0 })
+    }
+
+    /// Apply the safe repairs: delete rows no analytics query can reach.
+    ///
+    /// Orphaned progressions are deleted so the next `sync --full` re-fetches
+    /// them together with their student and assignment rows. Snapshots,
+    /// mentor assignments and person links of removed students go with them.
+    pub fn repair_integrity(&self) -> Result<IntegrityReport> {
+        self.conn.execute("BEGIN")?;
+
+        let result = (|| -> Result<i64> {
+            let mut fixed = 0i64;
+            for statement in [
+                "DELETE FROM progressions WHERE NOT EXISTS (
+                     SELECT 1 FROM students s
+                     WHERE s.id = progressions.student_id AND s.class_id = progressions.class_id)",
+                "DELETE FROM progressions WHERE NOT EXISTS (
+                     SELECT 1 FROM assignments a
+                     WHERE a.id = progressions.assignment_id AND a.class_id = progressions.class_id)",
+                "DELETE FROM students WHERE class_id NOT IN (SELECT id FROM classes)",
+                "DELETE FROM assignments WHERE class_id NOT IN (SELECT id FROM classes)",
+                // Progressions of the rows just removed are orphans too
+                "DELETE FROM progressions WHERE class_id NOT IN (SELECT id FROM classes)",
+                // As is everything else keyed by a removed student
+                "DELETE FROM student_snapshots WHERE NOT EXISTS (
+                     SELECT 1 FROM students s
+                     WHERE s.id = student_snapshots.student_id AND s.class_id = student_snapshots.class_id)",
+                "DELETE FROM mentor_students WHERE NOT EXISTS (
+                     SELECT 1 FROM students s
+                     WHERE s.id = mentor_students.student_id AND s.class_id = mentor_students.class_id)",
+                "DELETE FROM person_accounts WHERE lms_user_id NOT IN (SELECT id FROM students)",
+                "DELETE FROM people WHERE id NOT IN (SELECT person_id FROM person_accounts)",
+            ] {
+                self.conn.execute(statement)?;
+                fixed += self.conn.change_count() as i64;
+            }
+            Ok(fixed)
+        })();
+
+        match result {
+            Ok(fixed) => {
+                self.conn.execute("COMMIT")?;
+                let mut report = self.check_integrity()?;
+                report.fixed = fixed;
+                Ok(report)
+            }
+            Err(e) => {
+                self.conn.execute("ROLLBACK")?;
+                Err(e)
+            }
+        }
+    }
+}
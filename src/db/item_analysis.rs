@@ -0,0 +1,154 @@
+use super::filter::{bind_values, AnalyticsFilter};
+use super::Database;
+use anyhow::Result;
+use std::collections::HashMap;
+
+use crate::models::*;
+
+/// Graded students needed before an assignment gets statistics.
+const MIN_GRADED: usize = 5;
+/// Discrimination below this doesn't separate strong students from weak ones.
+const LOW_DISCRIMINATION: f64 = 0.2;
+/// Share of perfect scores at which an assignment counts as aced.
+const CEILING_SHARE: f64 = 0.8;
+/// Bimodality coefficient of a uniform distribution; higher suggests two clusters.
+const BIMODAL_COEFFICIENT: f64 = 5.0 / 9.0;
+
+fn mean(values: &[f64]) -> f64 {
+    values.iter().sum::<f64>() / values.len() as f64
+}
+
+/// Pearson correlation, `None` if either side doesn't vary.
+fn correlation(pairs: &[(f64, f64)]) -> Option<f64> {
+    let n = pairs.len() as f64;
+    let mean_x = pairs.iter().map(|(x, _)| x).sum::<f64>() / n;
+    let mean_y = pairs.iter().map(|(_, y)| y).sum::<f64>() / n;
+    let (mut covariance, mut variance_x, mut variance_y) = (0.0, 0.0, 0.0);
+    for (x, y) in pairs {
+        let (dx, dy) = (x - mean_x, y - mean_y);
+        covariance += dx * dy;
+        variance_x += dx * dx;
+        variance_y += dy * dy;
+    }
+    let denominator = (variance_x * variance_y).sqrt();
+    (denominator > 1e-12).then(|| covariance / denominator)
+}
+
+/// Sarle's bimodality coefficient from sample skewness and excess kurtosis.
+/// `None` for fewer than four values or no spread.
+fn bimodality(values: &[f64]) -> Option<f64> {
+    let n = values.len() as f64;
+    if values.len() < 4 {
+        return None;
+    }
+    let m = mean(values);
+    let moment = |k: i32| values.iter().map(|v| (v - m).powi(k)).sum::<f64>() / n;
+    let m2 = moment(2);
+    if m2 < 1e-12 {
+        return None;
+    }
+    let skew = moment(3) / m2.powf(1.5) * (n * (n - 1.0)).sqrt() / (n - 2.0);
+    let kurtosis =
+        ((n + 1.0) * (moment(4) / (m2 * m2) - 3.0) + 6.0) * (n - 1.0) / ((n - 2.0) * (n - 3.0));
+    let correction = 3.0 * (n - 1.0).powi(2) / ((n - 2.0) * (n - 3.0));
+    Some((skew * skew + 1.0) / (kurtosis + correction))
+}
+
+impl Database {
+    /// Item analysis of every assignment's grades, assignments that need
+    /// rework first, then in course order. A student's average covers the
+    /// assignments the filter includes, so `?type=quiz` compares quizzes
+    /// with quizzes.
+    pub fn get_item_analysis(
+        &self,
+        class_id: &str,
+        filter: &AnalyticsFilter,
+    ) -> Result<Vec<ItemAnalysis>> {
+        let included = self.filtered_assignment_ids(class_id, filter)?;
+
+        // Best grade per student and assignment
+        let (clause, values) = filter.clause();
+        let stmt = self.conn.prepare(format!(
+            "SELECT p.student_id, p.assignment_id, MAX(p.grade) FROM progressions p
+             LEFT JOIN students s ON p.student_id = s.id AND p.class_id = s.class_id
+             WHERE p.class_id = ? AND p.grade IS NOT NULL{clause}
+             GROUP BY p.student_id, p.assignment_id"
+        ))?;
+        let (mut stmt, _) = bind_values(stmt.bind(1, class_id)?, 2, &values)?;
+        let mut grades: HashMap<String, Vec<(String, f64)>> = HashMap::new();
+        let mut totals: HashMap<String, (f64, usize)> = HashMap::new();
+        while let sqlite::State::Row = stmt.next()? {
+            let assignment_id = stmt.read::<String>(1)?;
+            if !included.contains(&assignment_id) {
+                continue;
+            }
+            let student_id = stmt.read::<String>(0)?;
+            let grade = stmt.read::<f64>(2)?;
+            let total = totals.entry(student_id.clone()).or_default();
+            total.0 += grade;
+            total.1 += 1;
+            grades
+                .entry(assignment_id)
+                .or_default()
+                .push((student_id, grade));
+        }
+
+        let mut items: Vec<ItemAnalysis> = self
+            .get_assignment_catalog(class_id)?
+            .into_iter()
+            .filter(|a| included.contains(&a.id))
+            .map(|assignment| {
+                let graded = grades.remove(&assignment.id).unwrap_or_default();
+                let scores: Vec<f64> = graded.iter().map(|(_, g)| *g).collect();
+                let enough = scores.len() >= MIN_GRADED;
+
+                // Against the average of the student's other grades, so the
+                // assignment doesn't correlate with itself
+                let pairs: Vec<(f64, f64)> = graded
+                    .iter()
+                    .filter_map(|(student_id, grade)| {
+                        let (sum, count) = totals[student_id];
+                        (count > 1).then(|| (*grade, (sum - grade) / (count - 1) as f64))
+                    })
+                    .collect();
+                let discrimination = (pairs.len() >= MIN_GRADED)
+                    .then(|| correlation(&pairs))
+                    .flatten();
+                let ceiling_pct = enough.then(|| {
+                    scores.iter().filter(|g| **g >= 1.0).count() as f64 / scores.len() as f64
+                });
+                let bimodality = enough.then(|| bimodality(&scores)).flatten();
+
+                let mut flags = Vec::new();
+                if discrimination.is_some_and(|d| d < LOW_DISCRIMINATION) {
+                    flags.push("low_discrimination".to_string());
+                }
+                if ceiling_pct.is_some_and(|c| c >= CEILING_SHARE) {
+                    flags.push("ceiling".to_string());
+                }
+                if bimodality.is_some_and(|b| b > BIMODAL_COEFFICIENT) {
+                    flags.push("bimodal".to_string());
+                }
+
+                ItemAnalysis {
+                    assignment_id: assignment.id,
+                    name: assignment.name,
+                    section: assignment.section,
+                    assignment_type: assignment.assignment_type,
+                    graded: scores.len() as i64,
+                    avg_grade: (!scores.is_empty()).then(|| mean(&scores)),
+                    discrimination,
+                    ceiling_pct,
+                    bimodality,
+                    needs_rework: flags
+                        .iter()
+                        .any(|f| f == "low_discrimination" || f == "ceiling"),
+                    flags,
+                }
+            })
+            .collect();
+        items.sort_by_key(|item| !item.needs_rework);
+
+        Ok(items)
+    }
+}
@@ -1,16 +1,93 @@
 use anyhow::Result;
 use sqlite::Connection;
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+
+use crate::config::{BackupConfig, ReviewsConfig, RiskConfig};
 
 mod analytics;
+mod backup;
+mod benchmark;
+mod calendar;
+mod digest;
+mod filter;
+mod funnel;
+mod gradebook;
+pub mod integrity;
+mod item_analysis;
+mod pace;
+mod people;
+mod prediction;
+mod projection;
 mod queries;
+mod reviews;
+mod risk;
+mod rollup;
+mod snapshots;
+mod time_on_task;
+mod timestamps;
+mod timezone;
+
+pub use calendar::CalendarSettings;
+pub use digest::SyncBaseline;
+pub use filter::{AnalyticsFilter, HealthSort, StudentStatus};
+pub use prediction::DEFAULT_EARLY_WEEKS;
+pub use timezone::parse_timezone;
+
+pub use backup::{
+    backup_dir, restore_database, rotate_backup, validate_backup, DEFAULT_BACKUP_KEEP,
+};
+
+/// Bumped whenever the schema changes; stored in `PRAGMA user_version`.
+pub const SCHEMA_VERSION: i64 = 10;
+
+/// First schema version that links students to people.
+const PEOPLE_SCHEMA_VERSION: i64 = 2;
 
 pub struct Database {
     pub(crate) conn: Connection,
+    pub(crate) risk: RiskConfig,
+    /// Explicit pathway per class friendly id, overriding the prefix
+    pub(crate) pathways: HashMap<String, String>,
+    pub(crate) reviews: ReviewsConfig,
 }
 
 impl Database {
     pub fn new(path: &str) -> Result<Self> {
+        Self::migrate(sqlite::open(path)?)
+    }
+
+    /// Open the database like `new`, but back up an existing database that
+    /// needs migrating first, if automatic backups are on. Returns the path
+    /// of that backup.
+    pub fn open(path: &str, backup: &BackupConfig) -> Result<(Self, Option<PathBuf>)> {
         let conn = sqlite::open(path)?;
+        let version = read_user_version(&conn)?;
+        if !backup.auto || version >= SCHEMA_VERSION || !has_tables(&conn)? {
+            return Ok((Self::migrate(conn)?, None));
+        }
+
+        let db = Self::from_conn(conn);
+        let dest = rotate_backup(&db, Path::new(path), "pre-migration", backup.keep)?;
+        Ok((Self::migrate(db.conn)?, Some(dest)))
+    }
+
+    /// A database over `conn`, leaving its schema as it is.
+    fn from_conn(conn: Connection) -> Self {
+        Self {
+            conn,
+            risk: RiskConfig::default(),
+            pathways: HashMap::new(),
+            reviews: ReviewsConfig::default(),
+        }
+    }
+
+    fn migrate(conn: Connection) -> Result<Self> {
+        // Opening an up-to-date database doesn't write to it
+        let version = read_user_version(&conn)?;
+        if version >= SCHEMA_VERSION {
+            return Ok(Self::from_conn(conn));
+        }
 
         // Create classes table
         conn.execute(
@@ -36,14 +113,31 @@ impl Database {
                 PRIMARY KEY (id, class_id)
             )",
         )?;
+        // IANA timezone overriding the class's, for students elsewhere
+        add_column_if_missing(&conn, "students", "timezone", "TEXT")?;
 
         conn.execute(
             "CREATE TABLE IF NOT EXISTS mentors (
                 id INTEGER PRIMARY KEY AUTOINCREMENT,
                 name TEXT NOT NULL,
-                night TEXT NOT NULL
+                night TEXT NOT NULL,
+                email TEXT
             )",
         )?;
+        add_column_if_missing(&conn, "mentors", "email", "TEXT")?;
+
+        // Which students each mentor looks after, per class
+        conn.execute(
+            "CREATE TABLE IF NOT EXISTS mentor_students (
+                mentor_id INTEGER NOT NULL,
+                student_id TEXT NOT NULL,
+                class_id TEXT NOT NULL,
+                PRIMARY KEY (mentor_id, student_id, class_id)
+            )",
+        )?;
+        conn.execute(
+            "CREATE INDEX IF NOT EXISTS idx_mentor_students_student ON mentor_students(class_id, student_id)",
+        )?;
 
         conn.execute(
             "CREATE TABLE IF NOT EXISTS assignments (
@@ -87,6 +181,9 @@ impl Database {
             println!("✓ Migration complete");
         }
 
+        // Course order from the LMS class structure (1-based)
+        add_column_if_missing(&conn, "assignments", "position", "INTEGER")?;
+
         conn.execute(
             "CREATE TABLE IF NOT EXISTS sync_history (
                 id INTEGER PRIMARY KEY AUTOINCREMENT,
@@ -97,6 +194,146 @@ impl Database {
             )",
         )?;
 
-        Ok(Self { conn })
+        // One row per person, linked to their LMS accounts across classes
+        conn.execute(
+            "CREATE TABLE IF NOT EXISTS people (
+                id INTEGER PRIMARY KEY AUTOINCREMENT,
+                email TEXT,
+                first_name TEXT NOT NULL,
+                last_name TEXT NOT NULL
+            )",
+        )?;
+        conn.execute(
+            "CREATE TABLE IF NOT EXISTS person_accounts (
+                lms_user_id TEXT PRIMARY KEY,
+                person_id INTEGER NOT NULL
+            )",
+        )?;
+        conn.execute("CREATE INDEX IF NOT EXISTS idx_people_email ON people(email)")?;
+
+        // Per-student metrics captured on each sync (one row per student per day)
+        conn.execute(
+            "CREATE TABLE IF NOT EXISTS student_snapshots (
+                class_id TEXT NOT NULL,
+                student_id TEXT NOT NULL,
+                snapshot_date TEXT NOT NULL,
+                completed INTEGER NOT NULL,
+                completion_pct REAL NOT NULL,
+                avg_grade REAL,
+                days_inactive INTEGER,
+                risk TEXT NOT NULL,
+                created_at TEXT NOT NULL,
+                PRIMARY KEY (class_id, student_id, snapshot_date)
+            )",
+        )?;
+
+        // Teaching calendar per class; kept out of `classes` because syncs
+        // replace class rows wholesale
+        conn.execute(
+            "CREATE TABLE IF NOT EXISTS class_calendars (
+                class_id TEXT PRIMARY KEY,
+                start_date TEXT,
+                end_date TEXT,
+                break_weeks TEXT NOT NULL DEFAULT ''
+            )",
+        )?;
+
+        // Timezone completions are bucketed in; students can override it
+        conn.execute(
+            "CREATE TABLE IF NOT EXISTS class_timezones (
+                class_id TEXT PRIMARY KEY,
+                timezone TEXT NOT NULL
+            )",
+        )?;
+
+        // Explicit course for classes that are runs of the same module, when
+        // the friendly id doesn't say so
+        conn.execute(
+            "CREATE TABLE IF NOT EXISTS class_courses (
+                class_id TEXT PRIMARY KEY,
+                course TEXT NOT NULL
+            )",
+        )?;
+
+        // Trained dropout models; the newest row is used for predictions
+        conn.execute(
+            "CREATE TABLE IF NOT EXISTS dropout_models (
+                id INTEGER PRIMARY KEY AUTOINCREMENT,
+                trained_at TEXT NOT NULL,
+                early_weeks INTEGER NOT NULL,
+                model TEXT NOT NULL,
+                report TEXT NOT NULL
+            )",
+        )?;
+
+        // What changed on each sync, stored as JSON
+        conn.execute(
+            "CREATE TABLE IF NOT EXISTS sync_digests (
+                id INTEGER PRIMARY KEY AUTOINCREMENT,
+                class_id TEXT NOT NULL,
+                synced_at TEXT NOT NULL,
+                digest TEXT NOT NULL
+            )",
+        )?;
+
+        conn.execute(format!("PRAGMA user_version = {}", SCHEMA_VERSION))?;
+
+        let db = Self::from_conn(conn);
+        // Databases from before people were linked get linked once; after
+        // that sync and import keep the links up to date
+        if version < PEOPLE_SCHEMA_VERSION {
+            db.link_people()?;
+        }
+        Ok(db)
+    }
+
+    /// Weights and thresholds used for every risk level this database reports.
+    pub fn set_risk_config(&mut self, config: RiskConfig) {
+        self.risk = config;
+    }
+
+    /// Pathways for classes whose friendly id doesn't carry one, keyed by
+    /// friendly id.
+    pub fn set_pathways(&mut self, pathways: HashMap<String, String>) {
+        self.pathways = pathways;
+    }
+
+    /// Assignment types left out of review turnaround and the pending queue.
+    pub fn set_reviews_config(&mut self, config: ReviewsConfig) {
+        self.reviews = config;
+    }
+
+    pub fn schema_version(&self) -> Result<i64> {
+        read_user_version(&self.conn)
+    }
+}
+
+fn read_user_version(conn: &Connection) -> Result<i64> {
+    let mut stmt = conn.prepare("PRAGMA user_version")?;
+    stmt.next()?;
+    Ok(stmt.read::<i64>(0)?)
+}
+
+/// `CREATE TABLE IF NOT EXISTS` leaves older tables alone, so new columns
+/// have to be added explicitly.
+fn add_column_if_missing(conn: &Connection, table: &str, column: &str, decl: &str) -> Result<()> {
+    let mut stmt = conn.prepare(format!("PRAGMA table_info({})", table))?;
+    while let sqlite::State::Row = stmt.next()? {
+        if stmt.read::<String>(1)? == column {
+            return Ok(());
+        }
     }
+    drop(stmt);
+
+    conn.execute(format!(
+        "ALTER TABLE {} ADD COLUMN {} {}",
+        table, column, decl
+    ))?;
+    Ok(())
+}
+
+fn has_tables(conn: &Connection) -> Result<bool> {
+    let mut stmt = conn.prepare("SELECT COUNT(*) FROM sqlite_master WHERE type = 'table'")?;
+    stmt.next()?;
+    Ok(stmt.read::<i64>(0)? > 0)
 }
@@ -0,0 +1,209 @@
+use super::filter::{bind_values, AnalyticsFilter};
+use super::Database;
+use anyhow::Result;
+use chrono::{Duration, NaiveDate};
+use std::collections::{HashMap, HashSet};
+
+use crate::calendar::ClassCalendar;
+use crate::models::*;
+
+/// Whole assignments ahead (+) or behind (-) the expected count.
+pub(crate) fn schedule_delta(completed: i64, expected: f64) -> i64 {
+    completed - expected.round() as i64
+}
+
+/// How many of the `included` assignments a steady pace through `catalog`
+/// has completed by the end of `date`. Included assignments late in the
+/// course are expected late, not spread over the whole class.
+fn expected_within(
+    calendar: &ClassCalendar,
+    catalog: &[Assignment],
+    included: &HashSet<String>,
+    date: NaiveDate,
+) -> Option<f64> {
+    let position = calendar.teaching_fraction(date)? * catalog.len() as f64;
+    let whole = (position.floor() as usize).min(catalog.len());
+    let done = catalog[..whole]
+        .iter()
+        .filter(|a| included.contains(&a.id))
+        .count() as f64;
+    let partial = catalog
+        .get(whole)
+        .filter(|a| included.contains(&a.id))
+        .map_or(0.0, |_| position - whole as f64);
+    Some(done + partial)
+}
+
+impl Database {
+    /// Assignments a student on pace has completed by the end of `date`:
+    /// the catalog spread evenly over the class's teaching days. `None`
+    /// unless the class has an end date.
+    pub fn expected_completed_on(&self, class_id: &str, date: NaiveDate) -> Result<Option<f64>> {
+        let Some(calendar) = self.get_class_calendar(class_id)? else {
+            return Ok(None);
+        };
+        let total_assignments = self.get_assignment_count_by_class(class_id)?;
+        Ok(calendar
+            .teaching_fraction(date)
+            .map(|fraction| fraction * total_assignments as f64))
+    }
+
+    pub(crate) fn expected_completed_today(&self, class_id: &str) -> Result<Option<f64>> {
+        self.expected_completed_on(class_id, chrono::Utc::now().date_naive())
+    }
+
+    /// Like `expected_completed_on` for the filter's day, counting only the
+    /// assignments in its section and type that fall due within its
+    /// `from`/`to` window, so filtered completions are compared like with
+    /// like.
+    pub(crate) fn expected_completed_filtered(
+        &self,
+        class_id: &str,
+        filter: &AnalyticsFilter,
+    ) -> Result<Option<f64>> {
+        let Some(calendar) = self.get_class_calendar(class_id)? else {
+            return Ok(None);
+        };
+        let catalog = self.get_assignment_catalog(class_id)?;
+        let included = self.filtered_assignment_ids(class_id, filter)?;
+
+        let end = filter
+            .to
+            .map_or(filter.today(), |to| to.min(filter.today()));
+        let Some(by_end) = expected_within(&calendar, &catalog, &included, end) else {
+            return Ok(None);
+        };
+        let before = filter
+            .from
+            .and_then(|from| {
+                expected_within(&calendar, &catalog, &included, from - Duration::days(1))
+            })
+            .unwrap_or(0.0);
+        Ok(Some((by_end - before).max(0.0)))
+    }
+
+    /// Expected progress at the end of each class week through the
+    /// assignments in the filter's section and type, for the weeks that
+    /// overlap its `from`/`to` window.
+    pub fn get_expected_pace(
+        &self,
+        class_id: &str,
+        filter: &AnalyticsFilter,
+    ) -> Result<Vec<ExpectedPacePoint>> {
+        let Some(calendar) = self.get_class_calendar(class_id)? else {
+            return Ok(Vec::new());
+        };
+        let Some(end) = calendar.end else {
+            return Ok(Vec::new());
+        };
+
+        let catalog = self.get_assignment_catalog(class_id)?;
+        let included = self.filtered_assignment_ids(class_id, filter)?;
+        let in_order: Vec<&Assignment> = catalog
+            .iter()
+            .filter(|a| included.contains(&a.id))
+            .collect();
+        let total = in_order.len() as f64;
+
+        let mut points = Vec::new();
+        for week in calendar.weeks(end) {
+            let week_start = crate::calendar::parse_date(&week.start_date)?;
+            let week_end = crate::calendar::parse_date(&week.end_date)?;
+            if filter.from.is_some_and(|from| week_end < from)
+                || filter.to.is_some_and(|to| week_start > to)
+            {
+                continue;
+            }
+            let expected_completed =
+                expected_within(&calendar, &catalog, &included, week_end).unwrap_or(total);
+            let reached = expected_completed.round() as usize;
+
+            points.push(ExpectedPacePoint {
+                week: week.label,
+                week_number: week.number,
+                week_start: week.start_date,
+                is_break: week.is_break,
+                expected_completed,
+                expected_pct: if total > 0.0 {
+                    expected_completed / total
+                } else {
+                    0.0
+                },
+                expected_assignment: reached
+                    .checked_sub(1)
+                    .and_then(|i| in_order.get(i))
+                    .map(|a| a.name.clone()),
+            });
+        }
+
+        Ok(points)
+    }
+
+    pub fn get_schedule_status(
+        &self,
+        class_id: &str,
+        night: Option<&str>,
+    ) -> Result<Vec<ScheduleStatus>> {
+        self.get_schedule_status_filtered(class_id, &AnalyticsFilter::night(night))
+    }
+
+    /// Students ranked by how far behind the expected pace they are, most
+    /// behind first. Empty when the class has no end date.
+    pub fn get_schedule_status_filtered(
+        &self,
+        class_id: &str,
+        filter: &AnalyticsFilter,
+    ) -> Result<Vec<ScheduleStatus>> {
+        let Some(expected_completed) = self.expected_completed_on(class_id, filter.today())? else {
+            return Ok(Vec::new());
+        };
+
+        let catalog = self.get_assignment_catalog(class_id)?;
+
+        let filter = filter.students_only();
+        let (clause, values) = filter.progression_clause();
+        let stmt = self.conn.prepare(format!(
+            "SELECT p.student_id, p.assignment_id FROM progressions p
+             WHERE p.class_id = ? AND p.completed_at IS NOT NULL AND p.completed_at != ''{clause}"
+        ))?;
+        let (mut stmt, _) = bind_values(stmt.bind(1, class_id)?, 2, &values)?;
+        let mut done: HashMap<String, HashSet<String>> = HashMap::new();
+        while let sqlite::State::Row = stmt.next()? {
+            done.entry(stmt.read::<String>(0)?)
+                .or_default()
+                .insert(stmt.read::<String>(1)?);
+        }
+
+        let mut statuses: Vec<ScheduleStatus> = self
+            .get_student_health_filtered(class_id, &filter)?
+            .into_iter()
+            .map(|student| {
+                let completed_ids = done.get(&student.student_id);
+                let next_assignment = catalog
+                    .iter()
+                    .find(|a| !completed_ids.is_some_and(|ids| ids.contains(&a.id)))
+                    .map(|a| a.name.clone());
+
+                ScheduleStatus {
+                    schedule_delta: schedule_delta(student.completed, expected_completed),
+                    student_id: student.student_id,
+                    first_name: student.first_name,
+                    last_name: student.last_name,
+                    email: student.email,
+                    completed: student.completed,
+                    expected_completed,
+                    next_assignment,
+                }
+            })
+            .collect();
+
+        statuses.sort_by(|a, b| {
+            a.schedule_delta
+                .cmp(&b.schedule_delta)
+                .then_with(|| a.last_name.cmp(&b.last_name))
+                .then_with(|| a.first_name.cmp(&b.first_name))
+        });
+
+        Ok(statuses)
+    }
+}
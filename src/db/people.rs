@@ -0,0 +1,336 @@
+use super::Database;
+use anyhow::Result;
+use std::collections::{HashMap, HashSet};
+
+use crate::models::*;
+use crate::pathway::{parse_friendly_id, ClassKey};
+
+impl Database {
+    /// Link every LMS account that has no person yet, matching on email
+    /// before creating a new person. Returns the number of accounts linked.
+    pub fn link_people(&self) -> Result<i64> {
+        let mut stmt = self.conn.prepare(
+            "SELECT s.id, MAX(s.email), MAX(s.first_name), MAX(s.last_name)
+             FROM students s
+             LEFT JOIN person_accounts pa ON pa.lms_user_id = s.id
+             WHERE pa.lms_user_id IS NULL
+             GROUP BY s.id
+             ORDER BY s.id",
+        )?;
+
+        let mut unlinked = Vec::new();
+        while let sqlite::State::Row = stmt.next()? {
+            unlinked.push((
+                stmt.read::<String>(0)?,
+                stmt.read::<String>(1)?,
+                stmt.read::<String>(2)?,
+                stmt.read::<String>(3)?,
+            ));
+        }
+        drop(stmt);
+
+        let mut linked = 0i64;
+        for (lms_user_id, email, first_name, last_name) in unlinked {
+            let email = email.trim().to_lowercase();
+
+            let existing = if email.is_empty() {
+                None
+            } else {
+                let stmt = self
+                    .conn
+                    .prepare("SELECT id FROM people WHERE email = ? ORDER BY id LIMIT 1")?;
+                let mut stmt = stmt.bind(1, email.as_str())?;
+                match stmt.next()? {
+                    sqlite::State::Row => Some(stmt.read::<i64>(0)?),
+                    sqlite::State::Done => None,
+                }
+            };
+
+            let person_id = match existing {
+                Some(id) => id,
+                None => {
+                    let stmt = self.conn.prepare(
+                        "INSERT INTO people (email, first_name, last_name) VALUES (?, ?, ?)",
+                    )?;
+                    let stmt = if email.is_empty() {
+                        stmt.bind(1, ())?
+                    } else {
+                        stmt.bind(1, email.as_str())?
+                    };
+                    let mut stmt = stmt
+                        .bind(2, first_name.as_str())?
+                        .bind(3, last_name.as_str())?;
+                    stmt.next()?;
+
+                    let mut id_stmt = self.conn.prepare("SELECT last_insert_rowid()")?;
+                    id_stmt.next()?;
+                    id_stmt.read::<i64>(0)?
+                }
+            };
+
+            let stmt = self
+                .conn
+                .prepare("INSERT INTO person_accounts (lms_user_id, person_id) VALUES (?, ?)")?;
+            let mut stmt = stmt.bind(1, lms_user_id.as_str())?.bind(2, person_id)?;
+            stmt.next()?;
+            linked += 1;
+        }
+
+        Ok(linked)
+    }
+
+    pub fn get_person_for_student(&self, student_id: &str) -> Result<Option<Person>> {
+        let stmt = self.conn.prepare(
+            "SELECT p.id, p.first_name, p.last_name, p.email
+             FROM person_accounts pa
+             JOIN people p ON p.id = pa.person_id
+             WHERE pa.lms_user_id = ?",
+        )?;
+        let mut stmt = stmt.bind(1, student_id)?;
+
+        match stmt.next()? {
+            sqlite::State::Row => Ok(Some(Person {
+                id: stmt.read::<i64>(0)?,
+                first_name: stmt.read::<String>(1)?,
+                last_name: stmt.read::<String>(2)?,
+                email: stmt.read::<Option<String>>(3)?,
+            })),
+            sqlite::State::Done => Ok(None),
+        }
+    }
+
+    /// Pathway, module and term for a class, with the pathway taken from the
+    /// `[pathways]` config when the class is listed there.
+    pub(crate) fn class_key(&self, friendly_id: &str) -> ClassKey {
+        let mut key = parse_friendly_id(friendly_id);
+        if let Some(pathway) = self.pathways.get(friendly_id) {
+            key.pathway = pathway.clone();
+        }
+        key
+    }
+
+    /// Classes grouped by pathway (configured, or the prefix of their
+    /// friendly id), in module order.
+    pub fn get_pathways(&self) -> Result<Vec<Pathway>> {
+        let mut pathways: Vec<Pathway> = Vec::new();
+
+        for class in self.get_classes()? {
+            let key = self.class_key(&class.friendly_id);
+            let entry = PathwayClass {
+                class_id: class.id,
+                friendly_id: class.friendly_id,
+                name: class.name,
+                module: key.module,
+                term: key.term,
+            };
+
+            match pathways.iter_mut().find(|p| p.pathway == key.pathway) {
+                Some(pathway) => pathway.classes.push(entry),
+                None => pathways.push(Pathway {
+                    pathway: key.pathway,
+                    classes: vec![entry],
+                }),
+            }
+        }
+
+        for pathway in &mut pathways {
+            pathway.classes.sort_by(|a, b| {
+                a.module
+                    .cmp(&b.module)
+                    .then_with(|| a.friendly_id.cmp(&b.friendly_id))
+            });
+        }
+        pathways.sort_by(|a, b| a.pathway.cmp(&b.pathway));
+
+        Ok(pathways)
+    }
+
+    /// Every person enrolled in any class of the pathway, with their progress
+    /// per module, transition dates and the module they dropped out of.
+    pub fn get_pathway_students(&self, pathway: &str) -> Result<Vec<PathwayStudent>> {
+        let classes = match self
+            .get_pathways()?
+            .into_iter()
+            .find(|p| p.pathway == pathway)
+        {
+            Some(p) => p.classes,
+            None => return Ok(Vec::new()),
+        };
+
+        let active: HashSet<String> = self
+            .get_classes()?
+            .into_iter()
+            .filter(|c| c.is_active)
+            .map(|c| c.id)
+            .collect();
+
+        let mut enrollments: HashMap<i64, Vec<PathwayEnrollment>> = HashMap::new();
+        let mut class_last_activity: HashMap<String, Option<String>> = HashMap::new();
+        let mut finished: HashSet<String> = HashSet::new();
+
+        for class in &classes {
+            let total_assignments = self.get_assignment_count_by_class(&class.class_id)?;
+
+            // Over once its end date has passed; without one, once deactivated
+            let is_finished = match self.get_calendar_settings(&class.class_id)?.end_date {
+                Some(end) => end < self.local_clock(&class.class_id)?.class_today(),
+                None => !active.contains(&class.class_id),
+            };
+            if is_finished {
+                finished.insert(class.class_id.clone());
+            }
+
+            let stmt = self.conn.prepare(
+                "SELECT MAX(completed_at) FROM progressions
+                 WHERE class_id = ? AND completed_at IS NOT NULL AND completed_at != ''",
+            )?;
+            let mut stmt = stmt.bind(1, class.class_id.as_str())?;
+            stmt.next()?;
+            class_last_activity.insert(class.class_id.clone(), stmt.read::<Option<String>>(0)?);
+
+            let stmt = self.conn.prepare(
+                "SELECT pa.person_id, s.id,
+                        COUNT(p.id) as completed,
+                        AVG(p.grade) as avg_grade,
+                        MIN(NULLIF(p.completed_at, '')) as first_activity,
+                        MAX(NULLIF(p.completed_at, '')) as last_activity
+                 FROM students s
+                 JOIN person_accounts pa ON pa.lms_user_id = s.id
+                 LEFT JOIN progressions p ON s.id = p.student_id AND s.class_id = p.class_id
+                 WHERE s.class_id = ?
+                 GROUP BY pa.person_id, s.id",
+            )?;
+            let mut stmt = stmt.bind(1, class.class_id.as_str())?;
+
+            while let sqlite::State::Row = stmt.next()? {
+                let completed = stmt.read::<i64>(2)?;
+                let completion_pct = if total_assignments > 0 {
+                    completed as f64 / total_assignments as f64
+                } else {
+                    0.0
+                };
+
+                enrollments
+                    .entry(stmt.read::<i64>(0)?)
+                    .or_default()
+                    .push(PathwayEnrollment {
+                        class_id: class.class_id.clone(),
+                        friendly_id: class.friendly_id.clone(),
+                        class_name: class.name.clone(),
+                        module: class.module,
+                        student_id: stmt.read::<String>(1)?,
+                        completed,
+                        total_assignments,
+                        completion_pct,
+                        avg_grade: stmt.read::<Option<f64>>(3)?,
+                        first_activity: stmt.read::<Option<String>>(4)?,
+                        last_activity: stmt.read::<Option<String>>(5)?,
+                    });
+            }
+        }
+
+        let mut students = Vec::new();
+
+        for (person_id, mut person_enrollments) in enrollments {
+            let stmt = self
+                .conn
+                .prepare("SELECT first_name, last_name, email FROM people WHERE id = ?")?;
+            let mut stmt = stmt.bind(1, person_id)?;
+            if stmt.next()? != sqlite::State::Row {
+                continue;
+            }
+            let first_name = stmt.read::<String>(0)?;
+            let last_name = stmt.read::<String>(1)?;
+            let email = stmt.read::<Option<String>>(2)?;
+
+            person_enrollments.sort_by(|a, b| {
+                a.module
+                    .cmp(&b.module)
+                    .then_with(|| a.first_activity.cmp(&b.first_activity))
+            });
+
+            let transitions = person_enrollments
+                .windows(2)
+                .map(|pair| ModuleTransition {
+                    from_module: pair[0].module,
+                    to_module: pair[1].module,
+                    left_at: pair[0].last_activity.clone(),
+                    entered_at: pair[1].first_activity.clone(),
+                })
+                .collect();
+
+            let furthest = person_enrollments.last();
+            let furthest_module = furthest.and_then(|e| e.module);
+
+            // Dropped out: their furthest module is over and a later module of
+            // the same run kept going after their last activity in it, without
+            // them. Pathways span every term, so other runs don't count.
+            let dropped_out_module = furthest.and_then(|furthest| {
+                let module = furthest.module?;
+                if !finished.contains(&furthest.class_id) {
+                    return None;
+                }
+                let term = &classes
+                    .iter()
+                    .find(|c| c.class_id == furthest.class_id)?
+                    .term;
+                let later_module_ran_on = classes
+                    .iter()
+                    .filter(|c| c.term == *term && c.module.is_some_and(|m| m > module))
+                    .filter_map(|c| class_last_activity.get(&c.class_id).cloned().flatten())
+                    .any(|later_activity| match &furthest.last_activity {
+                        Some(left_at) => later_activity > *left_at,
+                        None => true,
+                    });
+                later_module_ran_on.then_some(module)
+            });
+
+            students.push(PathwayStudent {
+                person_id,
+                first_name,
+                last_name,
+                email,
+                enrollments: person_enrollments,
+                transitions,
+                furthest_module,
+                dropped_out_module,
+            });
+        }
+
+        students.sort_by(|a, b| {
+            a.last_name
+                .to_lowercase()
+                .cmp(&b.last_name.to_lowercase())
+                .then_with(|| {
+                    a.first_name
+                        .to_lowercase()
+                        .cmp(&b.first_name.to_lowercase())
+                })
+        });
+
+        Ok(students)
+    }
+
+    /// Pathway view for the person behind one class enrollment.
+    pub fn get_student_pathway(
+        &self,
+        class_id: &str,
+        student_id: &str,
+    ) -> Result<Option<PathwayStudent>> {
+        let person = match self.get_person_for_student(student_id)? {
+            Some(person) => person,
+            None => return Ok(None),
+        };
+
+        let class = match self.get_classes()?.into_iter().find(|c| c.id == class_id) {
+            Some(class) => class,
+            None => return Ok(None),
+        };
+        let pathway = self.class_key(&class.friendly_id).pathway;
+
+        Ok(self
+            .get_pathway_students(&pathway)?
+            .into_iter()
+            .find(|s| s.person_id == person.id))
+    }
+}
@@ -0,0 +1,303 @@
+use super::timestamps::parse_timestamp;
+use super::Database;
+use anyhow::{bail, Result};
+use chrono::{Duration, NaiveDate};
+use std::collections::HashMap;
+
+use crate::models::*;
+use crate::prediction::{LogisticModel, FEATURES};
+
+/// Days without a completion before the end of a class that count as having
+/// dropped out (for students who didn't finish every assignment).
+const DROPOUT_GAP_DAYS: i64 = 21;
+/// Every Nth sample is held out to measure accuracy.
+const HOLDOUT_EVERY: usize = 5;
+const MIN_SAMPLES: usize = 20;
+
+pub const DEFAULT_EARLY_WEEKS: i64 = 3;
+
+pub(crate) struct Completion {
+    pub student_id: String,
+    pub date: NaiveDate,
+    pub grade: Option<f64>,
+}
+
+impl Database {
+    pub(crate) fn class_completions(&self, class_id: &str) -> Result<Vec<Completion>> {
+        let stmt = self.conn.prepare(
+            "SELECT student_id, completed_at, grade FROM progressions
+             WHERE class_id = ? AND completed_at IS NOT NULL AND completed_at != ''",
+        )?;
+        let mut stmt = stmt.bind(1, class_id)?;
+        let mut completions = Vec::new();
+        while let sqlite::State::Row = stmt.next()? {
+            if let Some(at) = parse_timestamp(&stmt.read::<String>(1)?) {
+                completions.push(Completion {
+                    student_id: stmt.read::<String>(0)?,
+                    date: at.date(),
+                    grade: stmt.read::<Option<f64>>(2)?,
+                });
+            }
+        }
+        Ok(completions)
+    }
+
+    /// Features from a class's first `early_weeks` weeks (or up to `until`,
+    /// if that comes first) for every student in the class.
+    fn early_features(
+        &self,
+        class_id: &str,
+        early_weeks: i64,
+        until: NaiveDate,
+    ) -> Result<HashMap<String, Vec<f64>>> {
+        let Some(calendar) = self.get_class_calendar(class_id)? else {
+            return Ok(HashMap::new());
+        };
+        let start = calendar.start;
+        let cutoff = (start + Duration::weeks(early_weeks)).min(until + Duration::days(1));
+        let total_assignments = self.get_assignment_count_by_class(class_id)?.max(1) as f64;
+
+        let mut by_student: HashMap<String, Vec<Completion>> = HashMap::new();
+        for completion in self.class_completions(class_id)? {
+            if completion.date >= start && completion.date < cutoff {
+                by_student
+                    .entry(completion.student_id.clone())
+                    .or_default()
+                    .push(completion);
+            }
+        }
+
+        let mut rows = Vec::new();
+        for student in self.get_students_by_class(class_id)? {
+            let completions = by_student.remove(&student.id).unwrap_or_default();
+
+            let mut dates: Vec<NaiveDate> = completions.iter().map(|c| c.date).collect();
+            dates.sort();
+            dates.dedup();
+            let mut marks = vec![start];
+            marks.extend(dates);
+            marks.push(cutoff);
+            let longest_gap = marks
+                .windows(2)
+                .map(|pair| (pair[1] - pair[0]).num_days())
+                .max()
+                .unwrap_or(0);
+
+            let grades: Vec<f64> = completions.iter().filter_map(|c| c.grade).collect();
+            let avg_grade =
+                (!grades.is_empty()).then(|| grades.iter().sum::<f64>() / grades.len() as f64);
+
+            rows.push((
+                student.id,
+                completions.len() as f64 / total_assignments,
+                longest_gap as f64,
+                avg_grade,
+            ));
+        }
+
+        // Not graded yet isn't failing: give those students the class mean
+        // and let the indicator carry the difference
+        let graded: Vec<f64> = rows.iter().filter_map(|row| row.3).collect();
+        let class_avg = if graded.is_empty() {
+            0.0
+        } else {
+            graded.iter().sum::<f64>() / graded.len() as f64
+        };
+
+        Ok(rows
+            .into_iter()
+            .map(|(student_id, completion, gap, avg_grade)| {
+                let x = vec![
+                    completion,
+                    gap,
+                    avg_grade.unwrap_or(class_avg),
+                    if avg_grade.is_some() { 1.0 } else { 0.0 },
+                ];
+                (student_id, x)
+            })
+            .collect())
+    }
+
+    /// When a finished class ended: its end date if it has passed, or the last
+    /// completion of an archived class. `None` while the class is running.
+    fn class_end(&self, class: &Class, today: NaiveDate) -> Result<Option<NaiveDate>> {
+        let settings = self.get_calendar_settings(&class.id)?;
+        if let Some(end) = settings.end_date {
+            return Ok((end < today).then_some(end));
+        }
+        if class.is_active {
+            return Ok(None);
+        }
+        Ok(self
+            .class_completions(&class.id)?
+            .iter()
+            .map(|c| c.date)
+            .max())
+    }
+
+    /// Whether each student in a finished class dropped out.
+    fn dropout_labels(
+        &self,
+        class_id: &str,
+        class_end: NaiveDate,
+    ) -> Result<HashMap<String, bool>> {
+        let total_assignments = self.get_assignment_count_by_class(class_id)?;
+        let mut completed: HashMap<String, (i64, Option<NaiveDate>)> = HashMap::new();
+        for completion in self.class_completions(class_id)? {
+            let entry = completed.entry(completion.student_id).or_default();
+            entry.0 += 1;
+            entry.1 = entry.1.max(Some(completion.date));
+        }
+
+        Ok(self
+            .get_students_by_class(class_id)?
+            .into_iter()
+            .map(|student| {
+                let dropped = match completed.get(&student.id) {
+                    Some((count, _)) if *count >= total_assignments => false,
+                    Some((_, Some(last))) => (class_end - *last).num_days() > DROPOUT_GAP_DAYS,
+                    _ => true,
+                };
+                (student.id, dropped)
+            })
+            .collect())
+    }
+
+    /// Fit a dropout model on every finished class, report its accuracy on a
+    /// holdout set and store it for predictions.
+    pub fn train_dropout_model(&self, early_weeks: i64) -> Result<DropoutModelReport> {
+        let today = chrono::Utc::now().date_naive();
+
+        let mut rows: Vec<(String, String, Vec<f64>, f64)> = Vec::new();
+        let mut classes = 0;
+        for class in self.get_classes()? {
+            let Some(class_end) = self.class_end(&class, today)? else {
+                continue;
+            };
+            let features = self.early_features(&class.id, early_weeks, class_end)?;
+            if features.is_empty() {
+                continue;
+            }
+            classes += 1;
+            for (student_id, dropped) in self.dropout_labels(&class.id, class_end)? {
+                if let Some(x) = features.get(&student_id) {
+                    let label = if dropped { 1.0 } else { 0.0 };
+                    rows.push((class.id.clone(), student_id, x.clone(), label));
+                }
+            }
+        }
+
+        if rows.len() < MIN_SAMPLES {
+            bail!(
+                "Not enough history to train: {} student(s) in {} finished class(es), need at least {}. \
+                 Classes count as finished once their end date has passed or they are deactivated.",
+                rows.len(),
+                classes,
+                MIN_SAMPLES
+            );
+        }
+
+        // Deterministic split so retraining on the same data gives the same report
+        rows.sort_by(|a, b| (&a.0, &a.1).cmp(&(&b.0, &b.1)));
+        let (mut train_x, mut train_y, mut test_x, mut test_y) = (vec![], vec![], vec![], vec![]);
+        for (i, (_, _, x, y)) in rows.into_iter().enumerate() {
+            if i % HOLDOUT_EVERY == 0 {
+                test_x.push(x);
+                test_y.push(y);
+            } else {
+                train_x.push(x);
+                train_y.push(y);
+            }
+        }
+
+        let dropouts = train_y.iter().chain(&test_y).filter(|y| **y >= 0.5).count();
+        if !train_y.iter().any(|y| *y >= 0.5) || train_y.iter().all(|y| *y >= 0.5) {
+            bail!("Training data needs both students who finished and students who dropped out");
+        }
+
+        let model = LogisticModel::train(&train_x, &train_y);
+        let holdout_accuracy = model.accuracy(&test_x, &test_y);
+
+        // Accuracy of always predicting the training set's majority outcome
+        let majority_dropout = train_y.iter().sum::<f64>() * 2.0 > train_y.len() as f64;
+        let baseline_accuracy = test_y
+            .iter()
+            .filter(|y| (**y >= 0.5) == majority_dropout)
+            .count() as f64
+            / test_y.len().max(1) as f64;
+
+        let report = DropoutModelReport {
+            trained_at: chrono::Utc::now().to_rfc3339(),
+            early_weeks,
+            classes,
+            train_size: train_x.len(),
+            holdout_size: test_x.len(),
+            dropout_rate: dropouts as f64 / (train_x.len() + test_x.len()) as f64,
+            holdout_accuracy,
+            baseline_accuracy,
+            coefficients: FEATURES
+                .iter()
+                .zip(&model.weights)
+                .map(|(feature, weight)| ModelCoefficient {
+                    feature: feature.to_string(),
+                    weight: *weight,
+                })
+                .collect(),
+        };
+
+        let stmt = self.conn.prepare(
+            "INSERT INTO dropout_models (trained_at, early_weeks, model, report) VALUES (?, ?, ?, ?)",
+        )?;
+        let mut stmt = stmt
+            .bind(1, report.trained_at.as_str())?
+            .bind(2, early_weeks)?
+            .bind(3, serde_json::to_string(&model)?.as_str())?
+            .bind(4, serde_json::to_string(&report)?.as_str())?;
+        stmt.next()?;
+
+        Ok(report)
+    }
+
+    /// The most recently trained model and the number of early weeks it uses.
+    pub fn get_dropout_model(&self) -> Result<Option<(LogisticModel, i64)>> {
+        let mut stmt = self
+            .conn
+            .prepare("SELECT model, early_weeks FROM dropout_models ORDER BY id DESC LIMIT 1")?;
+        match stmt.next()? {
+            sqlite::State::Row => {
+                let model: LogisticModel = serde_json::from_str(&stmt.read::<String>(0)?)?;
+                // Trained on an older feature set; needs `model train` again
+                if model.weights.len() != FEATURES.len() {
+                    return Ok(None);
+                }
+                Ok(Some((model, stmt.read::<i64>(1)?)))
+            }
+            sqlite::State::Done => Ok(None),
+        }
+    }
+
+    /// Predicted dropout probability per student from the class's early
+    /// weeks, empty until a model has been trained and the class is past
+    /// the weeks it looks at.
+    pub(crate) fn dropout_probabilities(
+        &self,
+        class_id: &str,
+        today: NaiveDate,
+    ) -> Result<HashMap<String, f64>> {
+        let Some((model, early_weeks)) = self.get_dropout_model()? else {
+            return Ok(HashMap::new());
+        };
+        // A partial window looks like a long gap and few completions
+        let Some(calendar) = self.get_class_calendar(class_id)? else {
+            return Ok(HashMap::new());
+        };
+        if today < calendar.start + Duration::weeks(early_weeks) {
+            return Ok(HashMap::new());
+        }
+        Ok(self
+            .early_features(class_id, early_weeks, today)?
+            .into_iter()
+            .map(|(student_id, x)| (student_id, model.predict(&x)))
+            .collect())
+    }
+}
@@ -0,0 +1,184 @@
+use super::filter::AnalyticsFilter;
+use super::timestamps::parse_timestamp;
+use super::Database;
+use anyhow::Result;
+use chrono::{Duration, NaiveDate};
+use std::collections::HashMap;
+
+use crate::calendar::ClassCalendar;
+use crate::models::*;
+
+/// Rolling window (calendar days) for a student's recent velocity.
+const VELOCITY_WINDOW_DAYS: i64 = 21;
+/// Projections further out than this count as stalled.
+const MAX_PROJECTION_DAYS: i64 = 730;
+
+pub(crate) struct Projection {
+    pub remaining: i64,
+    /// Completions per teaching week
+    pub recent_velocity: f64,
+    pub finish: Option<NaiveDate>,
+    pub days_past_end: Option<i64>,
+}
+
+/// Project a finish date from recent velocity, counting only teaching days.
+/// Without a calendar every day counts.
+fn project(
+    calendar: Option<&ClassCalendar>,
+    today: NaiveDate,
+    remaining: i64,
+    recent_completions: i64,
+) -> Projection {
+    let mut window_start = today - Duration::days(VELOCITY_WINDOW_DAYS - 1);
+    let teaching_days = match calendar {
+        Some(calendar) => {
+            window_start = window_start.max(calendar.start);
+            calendar.teaching_days_between(window_start, today)
+        }
+        None => VELOCITY_WINDOW_DAYS,
+    };
+    let per_day = if teaching_days > 0 {
+        recent_completions as f64 / teaching_days as f64
+    } else {
+        0.0
+    };
+
+    let finish = if remaining > 0 && per_day > 0.0 {
+        let days = (remaining as f64 / per_day).ceil() as i64;
+        (days <= MAX_PROJECTION_DAYS).then(|| match calendar {
+            Some(calendar) => calendar.add_teaching_days(today, days),
+            None => today + Duration::days(days),
+        })
+    } else {
+        None
+    };
+
+    let end = calendar.and_then(|c| c.end);
+    Projection {
+        remaining,
+        recent_velocity: per_day * 7.0,
+        finish,
+        days_past_end: finish.zip(end).map(|(f, e)| (f - e).num_days()),
+    }
+}
+
+impl Database {
+    /// Completions per student within the velocity window ending today.
+    fn recent_completion_counts(
+        &self,
+        class_id: &str,
+        student_id: Option<&str>,
+        today: NaiveDate,
+    ) -> Result<HashMap<String, i64>> {
+        let since = today - Duration::days(VELOCITY_WINDOW_DAYS - 1);
+        let stmt = self.conn.prepare(
+            "SELECT student_id, completed_at FROM progressions
+             WHERE class_id = ? AND (? IS NULL OR student_id = ?)
+               AND completed_at IS NOT NULL AND completed_at != ''",
+        )?;
+        let mut stmt = stmt
+            .bind(1, class_id)?
+            .bind(2, student_id)?
+            .bind(3, student_id)?;
+
+        let mut counts = HashMap::new();
+        while let sqlite::State::Row = stmt.next()? {
+            let Some(at) = parse_timestamp(&stmt.read::<String>(1)?) else {
+                continue;
+            };
+            if at.date() >= since && at.date() <= today {
+                *counts.entry(stmt.read::<String>(0)?).or_insert(0) += 1;
+            }
+        }
+        Ok(counts)
+    }
+
+    /// Projected finish for one student who has completed `completed`
+    /// assignments.
+    pub(crate) fn project_student(
+        &self,
+        class_id: &str,
+        student_id: &str,
+        completed: i64,
+    ) -> Result<Projection> {
+        let today = chrono::Utc::now().date_naive();
+        let calendar = self.get_class_calendar(class_id)?;
+        let total_assignments = self.get_assignment_count_by_class(class_id)?;
+        let recent = self
+            .recent_completion_counts(class_id, Some(student_id), today)?
+            .get(student_id)
+            .copied()
+            .unwrap_or(0);
+        Ok(project(
+            calendar.as_ref(),
+            today,
+            (total_assignments - completed).max(0),
+            recent,
+        ))
+    }
+
+    pub fn get_projected_late(
+        &self,
+        class_id: &str,
+        night: Option<&str>,
+    ) -> Result<Vec<CompletionProjection>> {
+        self.get_projected_late_filtered(class_id, &AnalyticsFilter::night(night))
+    }
+
+    /// Students who, at their recent velocity, won't finish by the class end
+    /// date: stalled students first, then latest projected finish first.
+    /// Empty when the class has no end date.
+    pub fn get_projected_late_filtered(
+        &self,
+        class_id: &str,
+        filter: &AnalyticsFilter,
+    ) -> Result<Vec<CompletionProjection>> {
+        let Some(calendar) = self.get_class_calendar(class_id)? else {
+            return Ok(Vec::new());
+        };
+        let Some(end) = calendar.end else {
+            return Ok(Vec::new());
+        };
+
+        let today = filter.today();
+        let total_assignments = self.get_assignment_count_by_class(class_id)?;
+        let recent = self.recent_completion_counts(class_id, None, today)?;
+
+        let mut late: Vec<CompletionProjection> = self
+            .get_student_health_filtered(class_id, &filter.students_only())?
+            .into_iter()
+            .filter_map(|student| {
+                let projection = project(
+                    Some(&calendar),
+                    today,
+                    (total_assignments - student.completed).max(0),
+                    recent.get(&student.student_id).copied().unwrap_or(0),
+                );
+                let misses = projection.remaining > 0
+                    && projection.days_past_end.is_none_or(|days| days > 0);
+                misses.then(|| CompletionProjection {
+                    student_id: student.student_id,
+                    first_name: student.first_name,
+                    last_name: student.last_name,
+                    email: student.email,
+                    completed: student.completed,
+                    remaining: projection.remaining,
+                    recent_velocity: projection.recent_velocity,
+                    projected_finish: projection.finish.map(|d| d.format("%Y-%m-%d").to_string()),
+                    class_end: end.format("%Y-%m-%d").to_string(),
+                    days_past_end: projection.days_past_end,
+                })
+            })
+            .collect();
+
+        late.sort_by(|a, b| {
+            b.days_past_end
+                .unwrap_or(i64::MAX)
+                .cmp(&a.days_past_end.unwrap_or(i64::MAX))
+                .then_with(|| a.last_name.cmp(&b.last_name))
+                .then_with(|| a.first_name.cmp(&b.first_name))
+        });
+
+        Ok(late)
+    }
+}
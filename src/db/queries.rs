@@ -164,27 +164,43 @@ impl Database {
         Ok(students)
     }
 
-    pub fn update_student_night(
+    pub fn get_all_students(&self) -> Result<Vec<Student>> {
+        let mut stmt = self.conn.prepare("SELECT id, class_id, first_name, last_name, email, region, night FROM students ORDER BY last_name, first_name, class_id")?;
+        let mut students = Vec::new();
+
+        while let sqlite::State::Row = stmt.next()? {
+            students.push(Student {
+                id: stmt.read::<String>(0)?,
+                class_id: stmt.read::<String>(1)?,
+                first_name: stmt.read::<String>(2)?,
+                last_name: stmt.read::<String>(3)?,
+                email: stmt.read::<String>(4)?,
+                region: stmt.read::<Option<String>>(5)?,
+                night: stmt.read::<Option<String>>(6)?,
+            });
+        }
+
+        Ok(students)
+    }
+
+    /// Set region and night for one enrollment. Returns false if it doesn't exist.
+    pub fn set_student_night(
         &self,
-        first_name: &str,
-        last_name: &str,
+        student_id: &str,
+        class_id: &str,
         region: &str,
         night: &str,
     ) -> Result<bool> {
-        let stmt = self.conn.prepare(
-            "UPDATE students SET region = ?, night = ? WHERE LOWER(first_name) = LOWER(?) AND LOWER(last_name) = LOWER(?)"
-        )?;
+        let stmt = self
+            .conn
+            .prepare("UPDATE students SET region = ?, night = ? WHERE id = ? AND class_id = ?")?;
         let mut stmt = stmt
             .bind(1, region)?
             .bind(2, night)?
-            .bind(3, first_name)?
-            .bind(4, last_name)?;
+            .bind(3, student_id)?
+            .bind(4, class_id)?;
         stmt.next()?;
-
-        let mut check = self.conn.prepare("SELECT changes()")?;
-        check.next()?;
-        let changes = check.read::<i64>(0)?;
-        Ok(changes > 0)
+        Ok(self.conn.change_count() > 0)
     }
 
     // Assignment operations
@@ -196,8 +212,11 @@ impl Database {
         assignment_type: &str,
         section: Option<&str>,
     ) -> Result<()> {
+        // Upsert so the course position set from the class structure survives
         let stmt = self.conn.prepare(
-            "INSERT OR REPLACE INTO assignments (id, class_id, name, type, section) VALUES (?, ?, ?, ?, ?)"
+            "INSERT INTO assignments (id, class_id, name, type, section) VALUES (?, ?, ?, ?, ?)
+             ON CONFLICT(id, class_id) DO UPDATE SET
+                name = excluded.name, type = excluded.type, section = excluded.section",
         )?;
         let stmt = stmt
             .bind(1, id)?
@@ -215,6 +234,66 @@ impl Database {
         Ok(())
     }
 
+    /// Record course order: `ordered_ids[0]` is position 1. Assignments not
+    /// listed keep their previous position.
+    pub fn set_assignment_positions(&self, class_id: &str, ordered_ids: &[&str]) -> Result<()> {
+        self.conn.execute("BEGIN")?;
+
+        let result = (|| -> Result<()> {
+            for (i, id) in ordered_ids.iter().enumerate() {
+                let stmt = self
+                    .conn
+                    .prepare("UPDATE assignments SET position = ? WHERE id = ? AND class_id = ?")?;
+                let mut stmt = stmt
+                    .bind(1, i as i64 + 1)?
+                    .bind(2, *id)?
+                    .bind(3, class_id)?;
+                stmt.next()?;
+            }
+            Ok(())
+        })();
+
+        match result {
+            Ok(()) => {
+                self.conn.execute("COMMIT")?;
+                Ok(())
+            }
+            Err(e) => {
+                self.conn.execute("ROLLBACK")?;
+                Err(e)
+            }
+        }
+    }
+
+    /// Assignments in course order. Assignments without a position come
+    /// last, ordered by when the class first completed them.
+    pub fn get_assignment_catalog(&self, class_id: &str) -> Result<Vec<Assignment>> {
+        let stmt = self.conn.prepare(
+            "SELECT a.id, a.class_id, a.name, a.type, a.section
+             FROM assignments a
+             LEFT JOIN (
+                 SELECT assignment_id, MIN(completed_at) as first_completed
+                 FROM progressions
+                 WHERE class_id = ? AND completed_at != ''
+                 GROUP BY assignment_id
+             ) f ON f.assignment_id = a.id
+             WHERE a.class_id = ?
+             ORDER BY a.position IS NULL, a.position, f.first_completed IS NULL, f.first_completed, a.name",
+        )?;
+        let mut stmt = stmt.bind(1, class_id)?.bind(2, class_id)?;
+        let mut assignments = Vec::new();
+        while let sqlite::State::Row = stmt.next()? {
+            assignments.push(Assignment {
+                id: stmt.read::<String>(0)?,
+                class_id: stmt.read::<String>(1)?,
+                name: stmt.read::<String>(2)?,
+                assignment_type: stmt.read::<String>(3)?,
+                section: stmt.read::<Option<String>>(4)?,
+            });
+        }
+        Ok(assignments)
+    }
+
     pub fn get_assignments_by_class(&self, class_id: &str) -> Result<Vec<Assignment>> {
         let stmt = self.conn.prepare("SELECT id, class_id, name, type, section FROM assignments WHERE class_id = ? ORDER BY section, name")?;
         let mut stmt = stmt.bind(1, class_id)?;
@@ -330,15 +409,109 @@ impl Database {
         Ok(())
     }
 
-    pub fn clear_mentors(&self) -> Result<()> {
-        self.conn.execute("DELETE FROM mentors")?;
-        Ok(())
+    /// The mentor row for `night`, matched by email first and then by name.
+    /// A mentor who covers several nights has a row per night.
+    pub fn find_mentor_for_night(
+        &self,
+        name: &str,
+        email: Option<&str>,
+        night: &str,
+    ) -> Result<Option<i64>> {
+        let email = email
+            .map(|e| e.trim().to_lowercase())
+            .filter(|e| !e.is_empty());
+
+        if let Some(email) = &email {
+            let stmt = self.conn.prepare(
+                "SELECT id FROM mentors
+                 WHERE LOWER(email) = ? AND LOWER(TRIM(night)) = LOWER(?)
+                 ORDER BY id LIMIT 1",
+            )?;
+            let mut stmt = stmt.bind(1, email.as_str())?.bind(2, night.trim())?;
+            if let sqlite::State::Row = stmt.next()? {
+                return Ok(Some(stmt.read::<i64>(0)?));
+            }
+        }
+
+        let stmt = self.conn.prepare(
+            "SELECT id FROM mentors
+             WHERE LOWER(TRIM(name)) = LOWER(?) AND LOWER(TRIM(night)) = LOWER(?)
+             ORDER BY id LIMIT 1",
+        )?;
+        let mut stmt = stmt.bind(1, name.trim())?.bind(2, night.trim())?;
+        match stmt.next()? {
+            sqlite::State::Row => Ok(Some(stmt.read::<i64>(0)?)),
+            sqlite::State::Done => Ok(None),
+        }
+    }
+
+    /// Insert or update a mentor's row for one night (see
+    /// `find_mentor_for_night`) so ids stay stable across imports. Returns
+    /// the mentor id and whether it was newly created.
+    pub fn upsert_mentor(
+        &self,
+        name: &str,
+        email: Option<&str>,
+        night: &str,
+    ) -> Result<(i64, bool)> {
+        let email = email
+            .map(|e| e.trim().to_lowercase())
+            .filter(|e| !e.is_empty());
+
+        match self.find_mentor_for_night(name, email.as_deref(), night)? {
+            Some(id) => {
+                let stmt = self.conn.prepare(
+                    "UPDATE mentors SET name = ?, email = COALESCE(?, email) WHERE id = ?",
+                )?;
+                let mut stmt = stmt.bind(1, name)?.bind(2, email.as_deref())?.bind(3, id)?;
+                stmt.next()?;
+                Ok((id, false))
+            }
+            None => {
+                let stmt = self
+                    .conn
+                    .prepare("INSERT INTO mentors (name, night, email) VALUES (?, ?, ?)")?;
+                let mut stmt = stmt
+                    .bind(1, name)?
+                    .bind(2, night)?
+                    .bind(3, email.as_deref())?;
+                stmt.next()?;
+
+                let mut id_stmt = self.conn.prepare("SELECT last_insert_rowid()")?;
+                id_stmt.next()?;
+                Ok((id_stmt.read::<i64>(0)?, true))
+            }
+        }
     }
 
     pub fn get_all_mentors(&self) -> Result<Vec<Mentor>> {
         let mut stmt = self
             .conn
-            .prepare("SELECT id, name, night FROM mentors ORDER BY night, name")?;
+            .prepare("SELECT id, name, night, email FROM mentors ORDER BY night, name")?;
+        let mut mentors = Vec::new();
+
+        while let sqlite::State::Row = stmt.next()? {
+            mentors.push(Mentor {
+                id: stmt.read::<i64>(0)?,
+                name: stmt.read::<String>(1)?,
+                night: stmt.read::<String>(2)?,
+                email: stmt.read::<Option<String>>(3)?,
+            });
+        }
+
+        Ok(mentors)
+    }
+
+    /// Every mentor row with this id, email or name; a mentor who covers
+    /// several nights has one per night.
+    pub fn find_mentors(&self, key: &str) -> Result<Vec<Mentor>> {
+        let key = key.trim();
+        let stmt = self.conn.prepare(
+            "SELECT id, name, night, email FROM mentors
+             WHERE CAST(id AS TEXT) = ? OR LOWER(email) = LOWER(?) OR LOWER(TRIM(name)) = LOWER(?)
+             ORDER BY id",
+        )?;
+        let mut stmt = stmt.bind(1, key)?.bind(2, key)?.bind(3, key)?;
         let mut mentors = Vec::new();
 
         while let sqlite::State::Row = stmt.next()? {
@@ -346,12 +519,96 @@ impl Database {
                 id: stmt.read::<i64>(0)?,
                 name: stmt.read::<String>(1)?,
                 night: stmt.read::<String>(2)?,
+                email: stmt.read::<Option<String>>(3)?,
             });
         }
 
         Ok(mentors)
     }
 
+    /// Look a mentor up by id, email or name. An email or name shared by
+    /// several rows is an error rather than a guess; use the id instead.
+    pub fn find_mentor(&self, key: &str) -> Result<Option<Mentor>> {
+        let mut mentors = self.find_mentors(key)?;
+        if let Some(i) = mentors.iter().position(|m| m.id.to_string() == key.trim()) {
+            return Ok(Some(mentors.swap_remove(i)));
+        }
+        match mentors.len() {
+            0 | 1 => Ok(mentors.pop()),
+            n => Err(anyhow!(
+                "'{}' matches {} mentors ({}); use a mentor id",
+                key.trim(),
+                n,
+                mentors
+                    .iter()
+                    .map(|m| format!("{} on {}", m.id, m.night))
+                    .collect::<Vec<_>>()
+                    .join(", ")
+            )),
+        }
+    }
+
+    /// Assign a student (in one class) to a mentor. Returns false if already assigned.
+    pub fn assign_mentor_student(
+        &self,
+        mentor_id: i64,
+        student_id: &str,
+        class_id: &str,
+    ) -> Result<bool> {
+        let stmt = self.conn.prepare(
+            "INSERT OR IGNORE INTO mentor_students (mentor_id, student_id, class_id) VALUES (?, ?, ?)",
+        )?;
+        let mut stmt = stmt
+            .bind(1, mentor_id)?
+            .bind(2, student_id)?
+            .bind(3, class_id)?;
+        stmt.next()?;
+        Ok(self.conn.change_count() > 0)
+    }
+
+    pub fn unassign_mentor_student(
+        &self,
+        mentor_id: i64,
+        student_id: &str,
+        class_id: &str,
+    ) -> Result<bool> {
+        let stmt = self.conn.prepare(
+            "DELETE FROM mentor_students WHERE mentor_id = ? AND student_id = ? AND class_id = ?",
+        )?;
+        let mut stmt = stmt
+            .bind(1, mentor_id)?
+            .bind(2, student_id)?
+            .bind(3, class_id)?;
+        stmt.next()?;
+        Ok(self.conn.change_count() > 0)
+    }
+
+    pub fn get_mentor_students(&self, mentor_id: i64) -> Result<Vec<Student>> {
+        let stmt = self.conn.prepare(
+            "SELECT s.id, s.class_id, s.first_name, s.last_name, s.email, s.region, s.night
+             FROM mentor_students ms
+             JOIN students s ON s.id = ms.student_id AND s.class_id = ms.class_id
+             WHERE ms.mentor_id = ?
+             ORDER BY s.class_id, s.last_name, s.first_name",
+        )?;
+        let mut stmt = stmt.bind(1, mentor_id)?;
+        let mut students = Vec::new();
+
+        while let sqlite::State::Row = stmt.next()? {
+            students.push(Student {
+                id: stmt.read::<String>(0)?,
+                class_id: stmt.read::<String>(1)?,
+                first_name: stmt.read::<String>(2)?,
+                last_name: stmt.read::<String>(3)?,
+                email: stmt.read::<String>(4)?,
+                region: stmt.read::<Option<String>>(5)?,
+                night: stmt.read::<Option<String>>(6)?,
+            });
+        }
+
+        Ok(students)
+    }
+
     // Count operations
     pub fn get_student_count(&self) -> Result<i64> {
         let mut stmt = self
@@ -0,0 +1,246 @@
+use super::filter::{bind_values, AnalyticsFilter};
+use super::time_on_task::percentile;
+use super::timestamps::parse_timestamp;
+use super::Database;
+use anyhow::Result;
+use chrono::NaiveDateTime;
+use std::collections::{BTreeMap, HashMap};
+
+use crate::models::*;
+
+const UNASSIGNED: &str = "Unassigned";
+
+struct Submission {
+    progression_id: String,
+    student_id: String,
+    first_name: String,
+    last_name: String,
+    email: String,
+    night: Option<String>,
+    assignment_id: String,
+    assignment_name: String,
+    assignment_type: String,
+    completed_at: NaiveDateTime,
+    reviewed_at: Option<NaiveDateTime>,
+}
+
+#[derive(Default)]
+struct Latencies {
+    hours: Vec<f64>,
+    pending: i64,
+}
+
+fn summarize(groups: BTreeMap<String, Latencies>) -> Vec<ReviewTurnaround> {
+    groups
+        .into_iter()
+        .map(|(group, mut latencies)| {
+            latencies.hours.sort_by(|a, b| a.partial_cmp(b).unwrap());
+            let reviewed = latencies.hours.len();
+            ReviewTurnaround {
+                group,
+                reviewed: reviewed as i64,
+                pending: latencies.pending,
+                median_hours: (reviewed > 0).then(|| percentile(&latencies.hours, 0.5)),
+                p90_hours: (reviewed > 0).then(|| percentile(&latencies.hours, 0.9)),
+                avg_hours: (reviewed > 0)
+                    .then(|| latencies.hours.iter().sum::<f64>() / reviewed as f64),
+            }
+        })
+        .collect()
+}
+
+impl Database {
+    /// Completed submissions of assignment types that get reviewed, so
+    /// lessons that complete automatically never show up as waiting.
+    fn reviewable_submissions(
+        &self,
+        class_id: &str,
+        filter: &AnalyticsFilter,
+    ) -> Result<Vec<Submission>> {
+        let (clause, values) = filter.clause();
+        let stmt = self.conn.prepare(format!(
+            "SELECT p.id, p.student_id, s.first_name, s.last_name, s.email, s.night,
+                    p.assignment_id, a.name, a.type, p.completed_at, p.reviewed_at
+             FROM progressions p
+             JOIN students s ON p.student_id = s.id AND p.class_id = s.class_id
+             JOIN assignments a ON a.id = p.assignment_id AND a.class_id = p.class_id
+             WHERE p.class_id = ?{clause}"
+        ))?;
+        let stmt = stmt.bind(1, class_id)?;
+        let (mut stmt, _) = bind_values(stmt, 2, &values)?;
+
+        let mut submissions = Vec::new();
+        while let sqlite::State::Row = stmt.next()? {
+            let assignment_type = stmt.read::<String>(8)?;
+            if !self.reviews.is_reviewed(&assignment_type) {
+                continue;
+            }
+            let Some(completed_at) = parse_timestamp(&stmt.read::<String>(9)?) else {
+                continue;
+            };
+            submissions.push(Submission {
+                progression_id: stmt.read::<String>(0)?,
+                student_id: stmt.read::<String>(1)?,
+                first_name: stmt.read::<String>(2)?,
+                last_name: stmt.read::<String>(3)?,
+                email: stmt.read::<String>(4)?,
+                night: stmt.read::<Option<String>>(5)?,
+                assignment_id: stmt.read::<String>(6)?,
+                assignment_name: stmt.read::<String>(7)?,
+                assignment_type,
+                completed_at,
+                // Reviews after `as_of` hadn't happened yet
+                reviewed_at: stmt
+                    .read::<Option<String>>(10)?
+                    .as_deref()
+                    .and_then(parse_timestamp)
+                    .filter(|at| filter.as_of.is_none_or(|date| at.date() <= date)),
+            });
+        }
+        Ok(submissions)
+    }
+
+    /// Mentor names per student: their assigned mentors, or the mentors of
+    /// their night when nobody is assigned.
+    fn mentors_by_student(&self, class_id: &str) -> Result<HashMap<String, Vec<String>>> {
+        let stmt = self.conn.prepare(
+            "SELECT ms.student_id, m.name FROM mentor_students ms
+             JOIN mentors m ON m.id = ms.mentor_id
+             WHERE ms.class_id = ?
+             ORDER BY m.name",
+        )?;
+        let mut stmt = stmt.bind(1, class_id)?;
+        let mut assigned: HashMap<String, Vec<String>> = HashMap::new();
+        while let sqlite::State::Row = stmt.next()? {
+            assigned
+                .entry(stmt.read::<String>(0)?)
+                .or_default()
+                .push(stmt.read::<String>(1)?);
+        }
+
+        let mut by_night: HashMap<String, Vec<String>> = HashMap::new();
+        for mentor in self.get_all_mentors()? {
+            by_night
+                .entry(mentor.night.to_lowercase())
+                .or_default()
+                .push(mentor.name);
+        }
+
+        let mut mentors = HashMap::new();
+        for student in self.get_students_by_class(class_id)? {
+            let names = match assigned.remove(&student.id) {
+                Some(names) => names,
+                None => student
+                    .night
+                    .and_then(|night| by_night.get(&night.to_lowercase()).cloned())
+                    .unwrap_or_default(),
+            };
+            mentors.insert(student.id, names);
+        }
+        Ok(mentors)
+    }
+
+    pub fn get_review_turnaround(
+        &self,
+        class_id: &str,
+        night: Option<&str>,
+    ) -> Result<ReviewTurnaroundReport> {
+        self.get_review_turnaround_filtered(class_id, &AnalyticsFilter::night(night))
+    }
+
+    /// Hours from completion to review, grouped by assignment type, night and
+    /// mentor, with how many submissions in each group are still waiting.
+    pub fn get_review_turnaround_filtered(
+        &self,
+        class_id: &str,
+        filter: &AnalyticsFilter,
+    ) -> Result<ReviewTurnaroundReport> {
+        let mentors = self.mentors_by_student(class_id)?;
+
+        let mut by_type: BTreeMap<String, Latencies> = BTreeMap::new();
+        let mut by_night: BTreeMap<String, Latencies> = BTreeMap::new();
+        let mut by_mentor: BTreeMap<String, Latencies> = BTreeMap::new();
+
+        for submission in self.reviewable_submissions(class_id, filter)? {
+            let latency = submission
+                .reviewed_at
+                .map(|reviewed| (reviewed - submission.completed_at).num_seconds() as f64 / 3600.0);
+            // Reviewed before it was completed: a data problem, not a latency
+            if latency.is_some_and(|hours| hours < 0.0) {
+                continue;
+            }
+
+            let night = submission
+                .night
+                .clone()
+                .unwrap_or_else(|| UNASSIGNED.to_string());
+            let mentor_names = match mentors.get(&submission.student_id) {
+                Some(names) if !names.is_empty() => names.clone(),
+                _ => vec![UNASSIGNED.to_string()],
+            };
+
+            let record = |group: &mut Latencies| match latency {
+                Some(hours) => group.hours.push(hours),
+                None => group.pending += 1,
+            };
+            record(by_type.entry(submission.assignment_type).or_default());
+            record(by_night.entry(night).or_default());
+            for name in mentor_names {
+                record(by_mentor.entry(name).or_default());
+            }
+        }
+
+        Ok(ReviewTurnaroundReport {
+            by_assignment_type: summarize(by_type),
+            by_night: summarize(by_night),
+            by_mentor: summarize(by_mentor),
+        })
+    }
+
+    pub fn get_pending_reviews(
+        &self,
+        class_id: &str,
+        night: Option<&str>,
+    ) -> Result<Vec<PendingReview>> {
+        self.get_pending_reviews_filtered(class_id, &AnalyticsFilter::night(night))
+    }
+
+    /// Submissions still waiting for review, oldest first.
+    pub fn get_pending_reviews_filtered(
+        &self,
+        class_id: &str,
+        filter: &AnalyticsFilter,
+    ) -> Result<Vec<PendingReview>> {
+        let mentors = self.mentors_by_student(class_id)?;
+        let now = filter.now();
+
+        let mut pending: Vec<PendingReview> = self
+            .reviewable_submissions(class_id, filter)?
+            .into_iter()
+            .filter(|submission| submission.reviewed_at.is_none())
+            .map(|submission| PendingReview {
+                waiting_hours: (now - submission.completed_at).num_seconds() as f64 / 3600.0,
+                completed_at: submission
+                    .completed_at
+                    .format("%Y-%m-%dT%H:%M:%S")
+                    .to_string(),
+                mentors: mentors
+                    .get(&submission.student_id)
+                    .cloned()
+                    .unwrap_or_default(),
+                progression_id: submission.progression_id,
+                student_id: submission.student_id,
+                first_name: submission.first_name,
+                last_name: submission.last_name,
+                email: submission.email,
+                night: submission.night,
+                assignment_id: submission.assignment_id,
+                assignment_name: submission.assignment_name,
+                assignment_type: submission.assignment_type,
+            })
+            .collect();
+
+        pending.sort_by(|a, b| b.waiting_hours.partial_cmp(&a.waiting_hours).unwrap());
+        Ok(pending)
+    }
+}
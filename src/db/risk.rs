@@ -0,0 +1,205 @@
+use super::filter::{bind_values, AnalyticsFilter};
+use super::timestamps::parse_timestamp;
+use super::timezone::LocalClock;
+use super::Database;
+use anyhow::Result;
+use chrono::{Duration, NaiveDate, NaiveDateTime};
+use std::collections::{BTreeSet, HashMap};
+
+use crate::calendar::ClassCalendar;
+use crate::risk::{assess, RiskAssessment, RiskInputs};
+
+/// Graded completions compared against the ones before them for the trend.
+const RECENT_GRADES: usize = 3;
+/// Length of the two windows compared for a velocity drop.
+const VELOCITY_WINDOW_DAYS: i64 = 14;
+/// Grade slope (per assignment) at or below which grades count as declining.
+const DECLINING_SLOPE: f64 = -0.02;
+
+/// Per-student signals derived from completion history.
+#[derive(Debug, Clone, Default)]
+pub(crate) struct ActivitySignals {
+    pub days_inactive: Option<i64>,
+    pub grade_trend: Option<f64>,
+    pub velocity_drop: Option<f64>,
+    pub grade_slope: Option<f64>,
+    pub grade_volatility: Option<f64>,
+    pub active_week_streak: i64,
+    pub longest_gap_days: Option<i64>,
+}
+
+impl ActivitySignals {
+    pub fn declining_grades(&self) -> bool {
+        self.grade_slope
+            .is_some_and(|slope| slope <= DECLINING_SLOPE)
+    }
+}
+
+/// Least-squares slope of grades against their order, and their standard
+/// deviation. `None` with fewer than three grades.
+fn slope_and_volatility(grades: &[f64]) -> Option<(f64, f64)> {
+    if grades.len() < 3 {
+        return None;
+    }
+    let n = grades.len() as f64;
+    let mean_x = (n - 1.0) / 2.0;
+    let mean_y = grades.iter().sum::<f64>() / n;
+    let (mut covariance, mut variance_x, mut variance_y) = (0.0, 0.0, 0.0);
+    for (i, grade) in grades.iter().enumerate() {
+        let (dx, dy) = (i as f64 - mean_x, grade - mean_y);
+        covariance += dx * dy;
+        variance_x += dx * dx;
+        variance_y += dy * dy;
+    }
+    Some((covariance / variance_x, (variance_y / n).sqrt()))
+}
+
+#[derive(Default)]
+struct History {
+    completions: Vec<NaiveDateTime>,
+    grades: Vec<(NaiveDateTime, f64)>,
+}
+
+impl History {
+    fn signals(
+        mut self,
+        student_id: &str,
+        now: NaiveDateTime,
+        clock: &LocalClock,
+        calendar: Option<&ClassCalendar>,
+        grade_window: usize,
+    ) -> ActivitySignals {
+        let days_inactive = self
+            .completions
+            .iter()
+            .max()
+            .map(|last| clock.days_since(student_id, *last));
+
+        self.grades.sort_by_key(|(at, _)| *at);
+        let grade_trend = if self.grades.len() >= RECENT_GRADES + 2 {
+            let (earlier, recent) = self.grades.split_at(self.grades.len() - RECENT_GRADES);
+            let avg = |g: &[(NaiveDateTime, f64)]| {
+                g.iter().map(|(_, grade)| grade).sum::<f64>() / g.len() as f64
+            };
+            Some(avg(recent) - avg(earlier))
+        } else {
+            None
+        };
+
+        let window = Duration::days(VELOCITY_WINDOW_DAYS);
+        let count_between = |from: NaiveDateTime, to: NaiveDateTime| {
+            self.completions
+                .iter()
+                .filter(|at| **at > from && **at <= to)
+                .count() as f64
+        };
+        let recent = count_between(now - window, now);
+        let prior = count_between(now - window * 2, now - window);
+        // Two completions is the least that makes a "drop" meaningful
+        let velocity_drop = (prior >= 2.0).then(|| (1.0 - recent / prior).max(0.0));
+
+        let window: Vec<f64> = self
+            .grades
+            .iter()
+            .rev()
+            .take(grade_window)
+            .rev()
+            .map(|(_, grade)| *grade)
+            .collect();
+        let consistency = slope_and_volatility(&window);
+
+        // Local completion days; the current gap counts towards the longest
+        let today = clock.today(student_id);
+        let days: BTreeSet<NaiveDate> = self
+            .completions
+            .iter()
+            .map(|at| clock.local(student_id, *at).date())
+            .collect();
+        let longest_gap_days = days.last().map(|last| {
+            days.iter()
+                .zip(days.iter().skip(1))
+                .map(|(a, b)| (*b - *a).num_days())
+                .chain(std::iter::once((today - *last).num_days()))
+                .max()
+                .unwrap_or(0)
+        });
+
+        // Breaks share the preceding week's number, so they don't end a
+        // streak; neither does a current week without completions yet
+        let active_week_streak = calendar.map_or(0, |calendar| {
+            let weeks: BTreeSet<i64> = days.iter().map(|d| calendar.week_of(*d).number).collect();
+            let mut week = calendar.week_of(today).number;
+            if !weeks.contains(&week) {
+                week -= 1;
+            }
+            let mut streak = 0;
+            while weeks.contains(&week) {
+                streak += 1;
+                week -= 1;
+            }
+            streak
+        });
+
+        ActivitySignals {
+            days_inactive,
+            grade_trend,
+            velocity_drop,
+            grade_slope: consistency.map(|(slope, _)| slope),
+            grade_volatility: consistency.map(|(_, volatility)| volatility),
+            active_week_streak,
+            longest_gap_days,
+        }
+    }
+}
+
+impl Database {
+    /// Activity and grade-consistency signals for every student in the class
+    /// with at least one completion in the filter, or just `student_id` when
+    /// given. With `as_of`, it stands in for today.
+    pub(crate) fn activity_signals(
+        &self,
+        class_id: &str,
+        student_id: Option<&str>,
+        filter: &AnalyticsFilter,
+    ) -> Result<HashMap<String, ActivitySignals>> {
+        let (clause, values) = filter.progression_clause();
+        let stmt = self.conn.prepare(format!(
+            "SELECT p.student_id, p.completed_at, p.grade FROM progressions p
+             WHERE p.class_id = ? AND (? IS NULL OR p.student_id = ?)
+               AND p.completed_at IS NOT NULL AND p.completed_at != ''{clause}"
+        ))?;
+        let stmt = stmt
+            .bind(1, class_id)?
+            .bind(2, student_id)?
+            .bind(3, student_id)?;
+        let (mut stmt, _) = bind_values(stmt, 4, &values)?;
+
+        let mut histories: HashMap<String, History> = HashMap::new();
+        while let sqlite::State::Row = stmt.next()? {
+            let Some(completed_at) = parse_timestamp(&stmt.read::<String>(1)?) else {
+                continue;
+            };
+            let history = histories.entry(stmt.read::<String>(0)?).or_default();
+            history.completions.push(completed_at);
+            if let Some(grade) = stmt.read::<Option<f64>>(2)? {
+                history.grades.push((completed_at, grade));
+            }
+        }
+
+        let clock = self.local_clock(class_id)?.frozen_at(filter.as_of);
+        let calendar = self.get_class_calendar(class_id)?;
+        let now = filter.now();
+        Ok(histories
+            .into_iter()
+            .map(|(id, history)| {
+                let signals =
+                    history.signals(&id, now, &clock, calendar.as_ref(), self.risk.grade_window);
+                (id, signals)
+            })
+            .collect())
+    }
+
+    pub(crate) fn assess_risk(&self, inputs: &RiskInputs) -> RiskAssessment {
+        assess(&self.risk, inputs)
+    }
+}
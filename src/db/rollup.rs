@@ -0,0 +1,160 @@
+use super::Database;
+use anyhow::Result;
+use std::cmp::Ordering;
+use std::collections::BTreeMap;
+
+use crate::models::*;
+
+/// Entries kept in the combined blocker and worst-section lists.
+const ROLLUP_LIMIT: usize = 10;
+
+/// One active class's share of a rollup.
+struct ClassRollup {
+    module: ModuleRollup,
+    blockers: Vec<ProgramBlocker>,
+    sections: Vec<ProgramSection>,
+}
+
+fn add_risk(counts: &mut RiskCounts, level: &str) {
+    match level {
+        "critical" => counts.critical += 1,
+        "high" => counts.high += 1,
+        "medium" => counts.medium += 1,
+        _ => counts.low += 1,
+    }
+}
+
+fn lowest_first(a: f64, b: f64) -> Ordering {
+    a.partial_cmp(&b).unwrap_or(Ordering::Equal)
+}
+
+/// Combine class rollups: totals, modules in pathway and module order, and
+/// the lowest-completion blockers and sections across all of them.
+fn combine(pathway: Option<String>, classes: Vec<ClassRollup>) -> ProgramRollup {
+    let mut modules = Vec::new();
+    let mut blockers = Vec::new();
+    let mut sections = Vec::new();
+    let mut risk = RiskCounts::default();
+
+    for class in classes {
+        risk.critical += class.module.risk.critical;
+        risk.high += class.module.risk.high;
+        risk.medium += class.module.risk.medium;
+        risk.low += class.module.risk.low;
+        modules.push(class.module);
+        blockers.extend(class.blockers);
+        sections.extend(class.sections);
+    }
+
+    modules.sort_by(|a, b| {
+        a.pathway
+            .cmp(&b.pathway)
+            .then_with(|| a.module.cmp(&b.module))
+            .then_with(|| a.friendly_id.cmp(&b.friendly_id))
+    });
+    blockers.sort_by(|a, b| {
+        lowest_first(a.completion_rate, b.completion_rate)
+            .then_with(|| lowest_first(a.avg_grade.unwrap_or(0.0), b.avg_grade.unwrap_or(0.0)))
+    });
+    blockers.truncate(ROLLUP_LIMIT);
+    sections.sort_by(|a, b| lowest_first(a.completion_rate, b.completion_rate));
+    sections.truncate(ROLLUP_LIMIT);
+
+    ProgramRollup {
+        pathway,
+        total_students: modules.iter().map(|m| m.students).sum(),
+        modules,
+        risk,
+        blockers,
+        worst_sections: sections,
+    }
+}
+
+impl Database {
+    fn class_rollup(&self, class: &Class) -> Result<ClassRollup> {
+        let key = self.class_key(&class.friendly_id);
+        let summary = self.get_progress_summary(&class.id, None)?;
+
+        let mut risk = RiskCounts::default();
+        for student in self.get_student_health(&class.id, None)? {
+            add_risk(&mut risk, &student.risk);
+        }
+
+        // Classes without students would list every assignment at 0%
+        let blockers = self
+            .get_blockers(&class.id, ROLLUP_LIMIT, None)?
+            .into_iter()
+            .filter(|b| b.total_students > 0)
+            .map(|b| ProgramBlocker {
+                class_id: class.id.clone(),
+                friendly_id: class.friendly_id.clone(),
+                assignment_id: b.assignment_id,
+                name: b.name,
+                section: b.section,
+                completion_rate: b.completion_rate,
+                avg_grade: b.avg_grade,
+                completions: b.completions,
+                total_students: b.total_students,
+            })
+            .collect();
+
+        let sections = self
+            .get_section_progress(&class.id, None)?
+            .into_iter()
+            .filter(|s| s.total_students > 0)
+            .map(|s| ProgramSection {
+                class_id: class.id.clone(),
+                friendly_id: class.friendly_id.clone(),
+                completion_rate: s.students_completed as f64 / s.total_students as f64,
+                section: s.section,
+                total_students: s.total_students,
+                students_started: s.students_started,
+                students_completed: s.students_completed,
+            })
+            .collect();
+
+        Ok(ClassRollup {
+            module: ModuleRollup {
+                class_id: class.id.clone(),
+                friendly_id: class.friendly_id.clone(),
+                name: class.name.clone(),
+                pathway: key.pathway,
+                module: key.module,
+                students: summary.total_students,
+                completion_rate: summary.completion_rate,
+                avg_grade: summary.avg_grade,
+                risk,
+            },
+            blockers,
+            sections,
+        })
+    }
+
+    /// Students, risk, blockers and weakest sections across every active
+    /// class. Students are counted per enrollment, so someone in two
+    /// modules counts twice.
+    pub fn get_program_rollup(&self) -> Result<ProgramRollup> {
+        let classes = self
+            .get_active_classes()?
+            .iter()
+            .map(|class| self.class_rollup(class))
+            .collect::<Result<Vec<_>>>()?;
+        Ok(combine(None, classes))
+    }
+
+    /// The program rollup split by pathway, sorted by pathway name.
+    pub fn get_pathway_rollups(&self) -> Result<Vec<ProgramRollup>> {
+        let mut by_pathway: BTreeMap<String, Vec<ClassRollup>> = BTreeMap::new();
+        for class in self.get_active_classes()? {
+            let rollup = self.class_rollup(&class)?;
+            by_pathway
+                .entry(rollup.module.pathway.clone())
+                .or_default()
+                .push(rollup);
+        }
+        Ok(by_pathway
+            .into_iter()
+            .map(|(pathway, classes)| combine(Some(pathway), classes))
+            .collect())
+    }
+}
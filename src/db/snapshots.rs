@@ -0,0 +1,139 @@
+use super::filter::{bind_values, AnalyticsFilter};
+use super::Database;
+use anyhow::Result;
+use std::collections::HashMap;
+
+use crate::models::*;
+
+impl Database {
+    /// Store today's metrics for every student in the class, dated by the
+    /// class's local day. Re-running on the same day replaces that day's
+    /// snapshot.
+    pub fn record_student_snapshots(&self, class_id: &str) -> Result<usize> {
+        let snapshot_date = self
+            .local_clock(class_id)?
+            .class_today()
+            .format("%Y-%m-%d")
+            .to_string();
+        let created_at = chrono::Utc::now().to_rfc3339();
+
+        let days_inactive: HashMap<String, Option<i64>> = self
+            .get_student_activity(class_id)?
+            .into_iter()
+            .map(|a| (a.student_id, a.days_inactive))
+            .collect();
+
+        let health = self.get_student_health(class_id, None)?;
+
+        self.conn.execute("BEGIN")?;
+        let result = (|| -> Result<()> {
+            for student in &health {
+                let stmt = self.conn.prepare(
+                    "INSERT OR REPLACE INTO student_snapshots
+                     (class_id, student_id, snapshot_date, completed, completion_pct, avg_grade, days_inactive, risk, created_at)
+                     VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?)",
+                )?;
+                let mut stmt = stmt
+                    .bind(1, class_id)?
+                    .bind(2, student.student_id.as_str())?
+                    .bind(3, snapshot_date.as_str())?
+                    .bind(4, student.completed)?
+                    .bind(5, student.completion_pct)?
+                    .bind(6, student.avg_grade)?
+                    .bind(7, days_inactive.get(&student.student_id).copied().flatten())?
+                    .bind(8, student.risk.as_str())?
+                    .bind(9, created_at.as_str())?;
+                stmt.next()?;
+            }
+            Ok(())
+        })();
+
+        match result {
+            Ok(()) => {
+                self.conn.execute("COMMIT")?;
+                Ok(health.len())
+            }
+            Err(e) => {
+                self.conn.execute("ROLLBACK")?;
+                Err(e)
+            }
+        }
+    }
+
+    pub fn get_student_risk_history(
+        &self,
+        class_id: &str,
+        student_id: &str,
+    ) -> Result<Vec<StudentSnapshot>> {
+        let stmt = self.conn.prepare(
+            "SELECT student_id, snapshot_date, completed, completion_pct, avg_grade, days_inactive, risk
+             FROM student_snapshots
+             WHERE class_id = ? AND student_id = ?
+             ORDER BY snapshot_date ASC",
+        )?;
+        let mut stmt = stmt.bind(1, class_id)?.bind(2, student_id)?;
+
+        let mut history = Vec::new();
+        while let sqlite::State::Row = stmt.next()? {
+            history.push(StudentSnapshot {
+                student_id: stmt.read::<String>(0)?,
+                snapshot_date: stmt.read::<String>(1)?,
+                completed: stmt.read::<i64>(2)?,
+                completion_pct: stmt.read::<f64>(3)?,
+                avg_grade: stmt.read::<Option<f64>>(4)?,
+                days_inactive: stmt.read::<Option<i64>>(5)?,
+                risk: stmt.read::<String>(6)?,
+            });
+        }
+
+        Ok(history)
+    }
+
+    /// Number of students at each risk level on every snapshot date. A
+    /// filter's date window applies to the snapshot dates.
+    pub fn get_class_risk_history(
+        &self,
+        class_id: &str,
+        night: Option<&str>,
+    ) -> Result<Vec<RiskHistoryPoint>> {
+        self.get_class_risk_history_filtered(class_id, &AnalyticsFilter::night(night))
+    }
+
+    pub fn get_class_risk_history_filtered(
+        &self,
+        class_id: &str,
+        filter: &AnalyticsFilter,
+    ) -> Result<Vec<RiskHistoryPoint>> {
+        let (student_clause, mut values) = filter.student_clause();
+        let (date_clause, date_values) = filter.date_clause("ss.snapshot_date");
+        values.extend(date_values);
+        let stmt = self.conn.prepare(format!(
+            "SELECT ss.snapshot_date,
+                    SUM(CASE WHEN ss.risk = 'critical' THEN 1 ELSE 0 END),
+                    SUM(CASE WHEN ss.risk = 'high' THEN 1 ELSE 0 END),
+                    SUM(CASE WHEN ss.risk = 'medium' THEN 1 ELSE 0 END),
+                    SUM(CASE WHEN ss.risk = 'low' THEN 1 ELSE 0 END),
+                    COUNT(*)
+             FROM student_snapshots ss
+             LEFT JOIN students s ON s.id = ss.student_id AND s.class_id = ss.class_id
+             WHERE ss.class_id = ?{student_clause}{date_clause}
+             GROUP BY ss.snapshot_date
+             ORDER BY ss.snapshot_date ASC"
+        ))?;
+        let (mut stmt, _) = bind_values(stmt.bind(1, class_id)?, 2, &values)?;
+
+        let mut points = Vec::new();
+        while let sqlite::State::Row = stmt.next()? {
+            points.push(RiskHistoryPoint {
+                snapshot_date: stmt.read::<String>(0)?,
+                critical: stmt.read::<i64>(1)?,
+                high: stmt.read::<i64>(2)?,
+                medium: stmt.read::<i64>(3)?,
+                low: stmt.read::<i64>(4)?,
+                total: stmt.read::<i64>(5)?,
+            });
+        }
+
+        Ok(points)
+    }
+}
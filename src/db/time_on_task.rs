@@ -0,0 +1,249 @@
+use super::filter::{bind_values, AnalyticsFilter};
+use super::timestamps::parse_timestamp;
+use super::Database;
+use anyhow::Result;
+use std::collections::{BTreeMap, HashMap};
+
+use crate::models::*;
+
+/// Timings an assignment needs before any of them count as outliers.
+const MIN_OUTLIER_SAMPLES: usize = 5;
+/// How many times the assignment's median a timing must be to stand out.
+const OUTLIER_RATIO: f64 = 3.0;
+
+struct TaskTime {
+    student_id: String,
+    assignment_id: String,
+    minutes: f64,
+}
+
+/// Value at `p` (0.0 to 1.0) of sorted values, interpolating between ranks.
+pub(crate) fn percentile(sorted: &[f64], p: f64) -> f64 {
+    if sorted.is_empty() {
+        return 0.0;
+    }
+    let rank = p * (sorted.len() - 1) as f64;
+    let (lower, upper) = (rank.floor() as usize, rank.ceil() as usize);
+    sorted[lower] + (sorted[upper] - sorted[lower]) * (rank - lower as f64)
+}
+
+fn sorted(mut values: Vec<f64>) -> Vec<f64> {
+    values.sort_by(|a, b| a.partial_cmp(b).unwrap());
+    values
+}
+
+impl Database {
+    /// Minutes from `started_at` to `completed_at` for each completion.
+    /// Completions without a usable start (missing, or not before the
+    /// completion) are skipped.
+    fn task_times(&self, class_id: &str, filter: &AnalyticsFilter) -> Result<Vec<TaskTime>> {
+        let (clause, values) = filter.clause();
+        let stmt = self.conn.prepare(format!(
+            "SELECT p.student_id, p.assignment_id, p.started_at, p.completed_at
+             FROM progressions p
+             LEFT JOIN students s ON p.student_id = s.id AND p.class_id = s.class_id
+             WHERE p.class_id = ?{clause}"
+        ))?;
+        let (mut stmt, _) = bind_values(stmt.bind(1, class_id)?, 2, &values)?;
+
+        let mut times = Vec::new();
+        while let sqlite::State::Row = stmt.next()? {
+            let started = parse_timestamp(&stmt.read::<String>(2)?);
+            let completed = parse_timestamp(&stmt.read::<String>(3)?);
+            let Some((started, completed)) = started.zip(completed) else {
+                continue;
+            };
+            let minutes = (completed - started).num_seconds() as f64 / 60.0;
+            if minutes > 0.0 {
+                times.push(TaskTime {
+                    student_id: stmt.read::<String>(0)?,
+                    assignment_id: stmt.read::<String>(1)?,
+                    minutes,
+                });
+            }
+        }
+        Ok(times)
+    }
+
+    /// Sorted timings per assignment.
+    fn times_by_assignment(
+        &self,
+        class_id: &str,
+        filter: &AnalyticsFilter,
+    ) -> Result<HashMap<String, Vec<f64>>> {
+        let mut by_assignment: HashMap<String, Vec<f64>> = HashMap::new();
+        for time in self.task_times(class_id, filter)? {
+            by_assignment
+                .entry(time.assignment_id)
+                .or_default()
+                .push(time.minutes);
+        }
+        Ok(by_assignment
+            .into_iter()
+            .map(|(id, minutes)| (id, sorted(minutes)))
+            .collect())
+    }
+
+    /// Median minutes per assignment, for the difficulty score.
+    pub(crate) fn median_minutes_by_assignment(
+        &self,
+        class_id: &str,
+        filter: &AnalyticsFilter,
+    ) -> Result<HashMap<String, f64>> {
+        Ok(self
+            .times_by_assignment(class_id, filter)?
+            .into_iter()
+            .map(|(id, minutes)| (id, percentile(&minutes, 0.5)))
+            .collect())
+    }
+
+    pub fn get_time_on_task(&self, class_id: &str, night: Option<&str>) -> Result<Vec<TimeOnTask>> {
+        self.get_time_on_task_filtered(class_id, &AnalyticsFilter::night(night))
+    }
+
+    /// Median and P90 time to complete each assignment with timings, longest
+    /// median first.
+    pub fn get_time_on_task_filtered(
+        &self,
+        class_id: &str,
+        filter: &AnalyticsFilter,
+    ) -> Result<Vec<TimeOnTask>> {
+        let mut by_assignment = self.times_by_assignment(class_id, filter)?;
+
+        let mut stats: Vec<TimeOnTask> = self
+            .get_assignment_catalog(class_id)?
+            .into_iter()
+            .filter_map(|assignment| {
+                let minutes = by_assignment.remove(&assignment.id)?;
+                Some(TimeOnTask {
+                    assignment_id: assignment.id,
+                    name: assignment.name,
+                    section: assignment.section,
+                    assignment_type: assignment.assignment_type,
+                    samples: minutes.len() as i64,
+                    median_minutes: percentile(&minutes, 0.5),
+                    p90_minutes: percentile(&minutes, 0.9),
+                })
+            })
+            .collect();
+
+        stats.sort_by(|a, b| b.median_minutes.partial_cmp(&a.median_minutes).unwrap());
+        Ok(stats)
+    }
+
+    pub fn get_section_time_on_task(
+        &self,
+        class_id: &str,
+        night: Option<&str>,
+    ) -> Result<Vec<SectionTimeOnTask>> {
+        self.get_section_time_on_task_filtered(class_id, &AnalyticsFilter::night(night))
+    }
+
+    /// Median and P90 time per assignment within each section, pooled over
+    /// the section's assignments. Sorted by section name.
+    pub fn get_section_time_on_task_filtered(
+        &self,
+        class_id: &str,
+        filter: &AnalyticsFilter,
+    ) -> Result<Vec<SectionTimeOnTask>> {
+        let sections: HashMap<String, String> = self
+            .get_assignment_catalog(class_id)?
+            .into_iter()
+            .filter_map(|a| a.section.map(|section| (a.id, section)))
+            .collect();
+
+        let mut by_section: BTreeMap<String, Vec<f64>> = BTreeMap::new();
+        for time in self.task_times(class_id, filter)? {
+            if let Some(section) = sections.get(&time.assignment_id) {
+                by_section
+                    .entry(section.clone())
+                    .or_default()
+                    .push(time.minutes);
+            }
+        }
+
+        Ok(by_section
+            .into_iter()
+            .map(|(section, minutes)| {
+                let minutes = sorted(minutes);
+                SectionTimeOnTask {
+                    section,
+                    samples: minutes.len() as i64,
+                    median_minutes: percentile(&minutes, 0.5),
+                    p90_minutes: percentile(&minutes, 0.9),
+                }
+            })
+            .collect())
+    }
+
+    pub fn get_time_outliers(
+        &self,
+        class_id: &str,
+        night: Option<&str>,
+    ) -> Result<Vec<TimeOutlier>> {
+        self.get_time_outliers_filtered(class_id, &AnalyticsFilter::night(night))
+    }
+
+    /// Completions that took far longer than is typical for the assignment,
+    /// largest ratio first.
+    pub fn get_time_outliers_filtered(
+        &self,
+        class_id: &str,
+        filter: &AnalyticsFilter,
+    ) -> Result<Vec<TimeOutlier>> {
+        let times = self.task_times(class_id, filter)?;
+
+        let mut by_assignment: HashMap<&str, Vec<f64>> = HashMap::new();
+        for time in &times {
+            by_assignment
+                .entry(&time.assignment_id)
+                .or_default()
+                .push(time.minutes);
+        }
+        let medians: HashMap<&str, f64> = by_assignment
+            .into_iter()
+            .filter(|(_, minutes)| minutes.len() >= MIN_OUTLIER_SAMPLES)
+            .map(|(id, minutes)| (id, percentile(&sorted(minutes), 0.5)))
+            .collect();
+
+        let names: HashMap<String, String> = self
+            .get_assignment_catalog(class_id)?
+            .into_iter()
+            .map(|a| (a.id, a.name))
+            .collect();
+        let students: HashMap<String, Student> = self
+            .get_students_by_class(class_id)?
+            .into_iter()
+            .map(|s| (s.id.clone(), s))
+            .collect();
+
+        let mut outliers: Vec<TimeOutlier> = times
+            .iter()
+            .filter_map(|time| {
+                let median = *medians.get(time.assignment_id.as_str())?;
+                let ratio = time.minutes / median;
+                if ratio < OUTLIER_RATIO {
+                    return None;
+                }
+                let student = students.get(&time.student_id)?;
+                Some(TimeOutlier {
+                    student_id: student.id.clone(),
+                    first_name: student.first_name.clone(),
+                    last_name: student.last_name.clone(),
+                    email: student.email.clone(),
+                    assignment_id: time.assignment_id.clone(),
+                    assignment_name: names
+                        .get(&time.assignment_id)
+                        .cloned()
+                        .unwrap_or_else(|| time.assignment_id.clone()),
+                    minutes: time.minutes,
+                    median_minutes: median,
+                    ratio,
+                })
+            })
+            .collect();
+
+        outliers.sort_by(|a, b| b.ratio.partial_cmp(&a.ratio).unwrap());
+        Ok(outliers)
+    }
+}
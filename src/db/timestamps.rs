@@ -0,0 +1,26 @@
+use chrono::{DateTime, NaiveDate, NaiveDateTime};
+
+/// Parse the timestamp formats OpenClass and our own writes produce
+/// (RFC 3339 with or without fractional seconds, naive ISO 8601, or a bare date).
+/// Returns UTC wall-clock time.
+pub fn parse_timestamp(value: &str) -> Option<NaiveDateTime> {
+    let value = value.trim();
+    if value.is_empty() {
+        return None;
+    }
+
+    if let Ok(parsed) = DateTime::parse_from_rfc3339(value) {
+        return Some(parsed.naive_utc());
+    }
+
+    for format in ["%Y-%m-%dT%H:%M:%S%.f", "%Y-%m-%d %H:%M:%S%.f"] {
+        if let Ok(parsed) = NaiveDateTime::parse_from_str(value, format) {
+            return Some(parsed);
+        }
+    }
+
+    // A bare date; anything with a time part must have parsed above
+    NaiveDate::parse_from_str(value, "%Y-%m-%d")
+        .ok()
+        .and_then(|d| d.and_hms_opt(0, 0, 0))
+}
@@ -0,0 +1,198 @@
+use super::filter::{bind_values, AnalyticsFilter};
+use super::timestamps::parse_timestamp;
+use super::Database;
+use anyhow::{anyhow, Result};
+use chrono::{NaiveDate, NaiveDateTime, TimeZone};
+use chrono_tz::Tz;
+use std::collections::HashMap;
+
+use crate::models::*;
+
+/// Parse an IANA timezone name such as "America/New_York".
+pub fn parse_timezone(name: &str) -> Result<Tz> {
+    name.trim().parse::<Tz>().map_err(|_| {
+        anyhow!(
+            "Unknown timezone '{}': use an IANA name like America/New_York",
+            name
+        )
+    })
+}
+
+/// Converts stored UTC timestamps to each student's local time: their own
+/// timezone if set, otherwise the class's, otherwise UTC.
+pub(crate) struct LocalClock {
+    class: Tz,
+    students: HashMap<String, Tz>,
+    as_of: Option<NaiveDate>,
+}
+
+impl LocalClock {
+    /// Treat `date` as today, for metrics computed as of a past date.
+    pub fn frozen_at(self, date: Option<NaiveDate>) -> Self {
+        Self {
+            as_of: date,
+            ..self
+        }
+    }
+
+    fn tz(&self, student_id: &str) -> Tz {
+        self.students.get(student_id).copied().unwrap_or(self.class)
+    }
+
+    pub fn local(&self, student_id: &str, utc: NaiveDateTime) -> NaiveDateTime {
+        self.tz(student_id).from_utc_datetime(&utc).naive_local()
+    }
+
+    pub fn today(&self, student_id: &str) -> NaiveDate {
+        self.as_of.unwrap_or_else(|| {
+            chrono::Utc::now()
+                .with_timezone(&self.tz(student_id))
+                .date_naive()
+        })
+    }
+
+    /// Today in the class's own timezone.
+    pub fn class_today(&self) -> NaiveDate {
+        self.as_of
+            .unwrap_or_else(|| chrono::Utc::now().with_timezone(&self.class).date_naive())
+    }
+
+    /// Calendar days between the local date of `utc` and the local date today.
+    pub fn days_since(&self, student_id: &str, utc: NaiveDateTime) -> i64 {
+        (self.today(student_id) - self.local(student_id, utc).date()).num_days()
+    }
+}
+
+impl Database {
+    pub fn get_class_timezone(&self, class_id: &str) -> Result<Option<String>> {
+        let stmt = self
+            .conn
+            .prepare("SELECT timezone FROM class_timezones WHERE class_id = ?")?;
+        let mut stmt = stmt.bind(1, class_id)?;
+        match stmt.next()? {
+            sqlite::State::Row => Ok(Some(stmt.read::<String>(0)?)),
+            sqlite::State::Done => Ok(None),
+        }
+    }
+
+    /// Set the class's timezone, or clear it (back to UTC) with `None`.
+    pub fn set_class_timezone(&self, class_id: &str, timezone: Option<&str>) -> Result<()> {
+        match timezone {
+            Some(name) => {
+                let tz = parse_timezone(name)?;
+                let stmt = self.conn.prepare(
+                    "INSERT OR REPLACE INTO class_timezones (class_id, timezone) VALUES (?, ?)",
+                )?;
+                let mut stmt = stmt.bind(1, class_id)?.bind(2, tz.name())?;
+                stmt.next()?;
+            }
+            None => {
+                let stmt = self
+                    .conn
+                    .prepare("DELETE FROM class_timezones WHERE class_id = ?")?;
+                let mut stmt = stmt.bind(1, class_id)?;
+                stmt.next()?;
+            }
+        }
+        Ok(())
+    }
+
+    /// Override the timezone for one student, or clear the override with
+    /// `None`. Returns false if the student isn't in the class.
+    pub fn set_student_timezone(
+        &self,
+        class_id: &str,
+        student_id: &str,
+        timezone: Option<&str>,
+    ) -> Result<bool> {
+        let timezone = timezone.map(parse_timezone).transpose()?;
+        let stmt = self
+            .conn
+            .prepare("UPDATE students SET timezone = ? WHERE id = ? AND class_id = ?")?;
+        let mut stmt = stmt
+            .bind(1, timezone.map(|tz| tz.name()))?
+            .bind(2, student_id)?
+            .bind(3, class_id)?;
+        stmt.next()?;
+        Ok(self.conn.change_count() > 0)
+    }
+
+    pub fn get_class_timezone_info(&self, class_id: &str) -> Result<ClassTimezoneInfo> {
+        let stmt = self.conn.prepare(
+            "SELECT id, timezone FROM students
+             WHERE class_id = ? AND timezone IS NOT NULL
+             ORDER BY last_name, first_name",
+        )?;
+        let mut stmt = stmt.bind(1, class_id)?;
+        let mut student_overrides = Vec::new();
+        while let sqlite::State::Row = stmt.next()? {
+            student_overrides.push(StudentTimezone {
+                student_id: stmt.read::<String>(0)?,
+                timezone: stmt.read::<String>(1)?,
+            });
+        }
+
+        Ok(ClassTimezoneInfo {
+            class_id: class_id.to_string(),
+            timezone: self.get_class_timezone(class_id)?,
+            student_overrides,
+        })
+    }
+
+    pub(crate) fn local_clock(&self, class_id: &str) -> Result<LocalClock> {
+        // Names are validated on the way in; anything unreadable falls back
+        let class = self
+            .get_class_timezone(class_id)?
+            .and_then(|name| parse_timezone(&name).ok())
+            .unwrap_or(Tz::UTC);
+
+        let stmt = self.conn.prepare(
+            "SELECT id, timezone FROM students WHERE class_id = ? AND timezone IS NOT NULL",
+        )?;
+        let mut stmt = stmt.bind(1, class_id)?;
+        let mut students = HashMap::new();
+        while let sqlite::State::Row = stmt.next()? {
+            if let Ok(tz) = parse_timezone(&stmt.read::<String>(1)?) {
+                students.insert(stmt.read::<String>(0)?, tz);
+            }
+        }
+
+        Ok(LocalClock {
+            class,
+            students,
+            as_of: None,
+        })
+    }
+
+    /// Local completion times for students matching the filter, or for just
+    /// `student_id` when given.
+    pub(crate) fn local_completion_times(
+        &self,
+        class_id: &str,
+        filter: &AnalyticsFilter,
+        student_id: Option<&str>,
+    ) -> Result<Vec<NaiveDateTime>> {
+        let clock = self.local_clock(class_id)?;
+        let (clause, values) = filter.clause();
+        let stmt = self.conn.prepare(format!(
+            "SELECT p.student_id, p.completed_at
+             FROM progressions p
+             LEFT JOIN students s ON p.student_id = s.id AND p.class_id = s.class_id
+             WHERE p.class_id = ? AND (? IS NULL OR p.student_id = ?){clause}
+               AND p.completed_at IS NOT NULL AND p.completed_at != ''"
+        ))?;
+        let stmt = stmt
+            .bind(1, class_id)?
+            .bind(2, student_id)?
+            .bind(3, student_id)?;
+        let (mut stmt, _) = bind_values(stmt, 4, &values)?;
+
+        let mut times = Vec::new();
+        while let sqlite::State::Row = stmt.next()? {
+            if let Some(at) = parse_timestamp(&stmt.read::<String>(1)?) {
+                times.push(clock.local(&stmt.read::<String>(0)?, at));
+            }
+        }
+        Ok(times)
+    }
+}
@@ -0,0 +1,279 @@
+//! CSV and XLSX exports of the gradebook.
+
+use anyhow::{anyhow, Result};
+use rust_xlsxwriter::{Color, Format, FormatAlign, Workbook};
+
+use crate::models::{Gradebook, GradebookCell};
+
+/// Fixed columns before the assignments: last name, first name, email.
+const NAME_COLUMNS: usize = 3;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ExportFormat {
+    Csv,
+    Xlsx,
+}
+
+impl ExportFormat {
+    pub fn parse(value: &str) -> Result<Self> {
+        match value.trim().to_lowercase().as_str() {
+            "csv" => Ok(Self::Csv),
+            "xlsx" => Ok(Self::Xlsx),
+            _ => Err(anyhow!("Unknown format '{}': use csv or xlsx", value)),
+        }
+    }
+
+    /// The format matching a file name's extension.
+    pub fn from_path(path: &str) -> Result<Self> {
+        let extension = std::path::Path::new(path)
+            .extension()
+            .and_then(|e| e.to_str())
+            .ok_or_else(|| anyhow!("Can't tell the format of '{}': use .csv or .xlsx", path))?;
+        Self::parse(extension)
+    }
+
+    pub fn extension(self) -> &'static str {
+        match self {
+            Self::Csv => "csv",
+            Self::Xlsx => "xlsx",
+        }
+    }
+
+    pub fn content_type(self) -> &'static str {
+        match self {
+            Self::Csv => "text/csv; charset=utf-8",
+            Self::Xlsx => "application/vnd.openxmlformats-officedocument.spreadsheetml.sheet",
+        }
+    }
+
+    pub fn export(self, gradebook: &Gradebook) -> Result<Vec<u8>> {
+        match self {
+            Self::Csv => to_csv(gradebook),
+            Self::Xlsx => to_xlsx(gradebook),
+        }
+    }
+}
+
+/// One exported column after the names.
+enum Column {
+    Assignment(usize),
+    /// Completed out of total for a section
+    SectionCompleted(usize),
+    SectionGrade(usize),
+    Completed,
+    AvgGrade,
+}
+
+fn section_name(section: &Option<String>) -> &str {
+    section.as_deref().unwrap_or("No section")
+}
+
+/// Assignments section by section, each followed by its subtotals, then the
+/// overall totals.
+fn columns(gradebook: &Gradebook) -> Vec<Column> {
+    let mut columns = Vec::new();
+    for (i, section) in gradebook.sections.iter().enumerate() {
+        columns.extend(
+            (section.first_assignment..section.first_assignment + section.assignment_count)
+                .map(Column::Assignment),
+        );
+        columns.push(Column::SectionCompleted(i));
+        columns.push(Column::SectionGrade(i));
+    }
+    columns.push(Column::Completed);
+    columns.push(Column::AvgGrade);
+    columns
+}
+
+fn header(gradebook: &Gradebook, column: &Column) -> String {
+    match column {
+        Column::Assignment(i) => gradebook.assignments[*i].name.clone(),
+        Column::SectionCompleted(i) => {
+            format!(
+                "{} completed",
+                section_name(&gradebook.sections[*i].section)
+            )
+        }
+        Column::SectionGrade(i) => {
+            format!(
+                "{} avg grade",
+                section_name(&gradebook.sections[*i].section)
+            )
+        }
+        Column::Completed => "Completed".to_string(),
+        Column::AvgGrade => "Avg grade".to_string(),
+    }
+}
+
+fn percent(grade: f64) -> String {
+    format!("{:.0}%", grade * 100.0)
+}
+
+/// Grade if there is one, otherwise whether it's done or underway.
+fn cell_text(cell: &GradebookCell) -> String {
+    match (cell.state.as_str(), cell.grade) {
+        ("completed", Some(grade)) => percent(grade),
+        ("completed", None) => "done".to_string(),
+        ("in_progress", _) => "in progress".to_string(),
+        _ => String::new(),
+    }
+}
+
+fn completed_text(completed: i64, total: i64) -> String {
+    format!("{}/{}", completed, total)
+}
+
+pub fn to_csv(gradebook: &Gradebook) -> Result<Vec<u8>> {
+    let columns = columns(gradebook);
+    let mut writer = csv::Writer::from_writer(Vec::new());
+
+    let mut record = vec![
+        "Last name".to_string(),
+        "First name".to_string(),
+        "Email".to_string(),
+    ];
+    record.extend(columns.iter().map(|c| header(gradebook, c)));
+    writer.write_record(&record)?;
+
+    for row in &gradebook.students {
+        let mut record = vec![
+            row.last_name.clone(),
+            row.first_name.clone(),
+            row.email.clone(),
+        ];
+        record.extend(columns.iter().map(|column| match column {
+            Column::Assignment(i) => cell_text(&row.cells[*i]),
+            Column::SectionCompleted(i) => {
+                let s = &row.subtotals[*i];
+                completed_text(s.completed, s.total)
+            }
+            Column::SectionGrade(i) => row.subtotals[*i].avg_grade.map(percent).unwrap_or_default(),
+            Column::Completed => completed_text(row.completed, row.cells.len() as i64),
+            Column::AvgGrade => row.avg_grade.map(percent).unwrap_or_default(),
+        }));
+        writer.write_record(&record)?;
+    }
+
+    writer
+        .into_inner()
+        .map_err(|e| anyhow!("Failed to write CSV: {}", e))
+}
+
+/// A single sheet with section names above their columns, grades stored as
+/// numbers formatted as percentages, and the header and names frozen.
+pub fn to_xlsx(gradebook: &Gradebook) -> Result<Vec<u8>> {
+    let columns = columns(gradebook);
+    let mut workbook = Workbook::new();
+    let sheet = workbook.add_worksheet();
+    sheet.set_name("Gradebook")?;
+
+    let bold = Format::new().set_bold();
+    let section_format = Format::new().set_bold().set_align(FormatAlign::Center);
+    let subtotal_header = Format::new()
+        .set_bold()
+        .set_background_color(Color::RGB(0xEEEEEE));
+    let grade_format = Format::new().set_num_format("0%");
+    let subtotal_grade = Format::new()
+        .set_num_format("0%")
+        .set_background_color(Color::RGB(0xEEEEEE));
+    let subtotal_format = Format::new().set_background_color(Color::RGB(0xEEEEEE));
+    let pending_format = Format::new().set_font_color(Color::Gray);
+
+    // Row 0: section names over their assignment and subtotal columns
+    let mut first = NAME_COLUMNS;
+    for section in &gradebook.sections {
+        let last = first + section.assignment_count + 1;
+        let name = section_name(&section.section);
+        sheet.merge_range(0, first as u16, 0, last as u16, name, &section_format)?;
+        first = last + 1;
+    }
+
+    // Row 1: column headers
+    for (col, name) in ["Last name", "First name", "Email"].iter().enumerate() {
+        sheet.write_string_with_format(1, col as u16, *name, &bold)?;
+    }
+    for (i, column) in columns.iter().enumerate() {
+        let format = match column {
+            Column::Assignment(_) => &bold,
+            _ => &subtotal_header,
+        };
+        sheet.write_string_with_format(
+            1,
+            (NAME_COLUMNS + i) as u16,
+            header(gradebook, column),
+            format,
+        )?;
+    }
+
+    for (r, row) in gradebook.students.iter().enumerate() {
+        let r = r as u32 + 2;
+        sheet.write_string(r, 0, &row.last_name)?;
+        sheet.write_string(r, 1, &row.first_name)?;
+        sheet.write_string(r, 2, &row.email)?;
+
+        for (i, column) in columns.iter().enumerate() {
+            let col = (NAME_COLUMNS + i) as u16;
+            match column {
+                Column::Assignment(a) => {
+                    let cell = &row.cells[*a];
+                    match (cell.state.as_str(), cell.grade) {
+                        ("completed", Some(grade)) => {
+                            sheet.write_number_with_format(r, col, grade, &grade_format)?;
+                        }
+                        ("in_progress", _) => {
+                            sheet.write_string_with_format(
+                                r,
+                                col,
+                                cell_text(cell),
+                                &pending_format,
+                            )?;
+                        }
+                        _ => {
+                            sheet.write_string(r, col, cell_text(cell))?;
+                        }
+                    }
+                }
+                Column::SectionCompleted(s) => {
+                    let s = &row.subtotals[*s];
+                    sheet.write_string_with_format(
+                        r,
+                        col,
+                        completed_text(s.completed, s.total),
+                        &subtotal_format,
+                    )?;
+                }
+                Column::SectionGrade(s) => match row.subtotals[*s].avg_grade {
+                    Some(grade) => {
+                        sheet.write_number_with_format(r, col, grade, &subtotal_grade)?;
+                    }
+                    None => {
+                        sheet.write_blank(r, col, &subtotal_format)?;
+                    }
+                },
+                Column::Completed => {
+                    sheet.write_string_with_format(
+                        r,
+                        col,
+                        completed_text(row.completed, row.cells.len() as i64),
+                        &bold,
+                    )?;
+                }
+                Column::AvgGrade => {
+                    if let Some(grade) = row.avg_grade {
+                        sheet.write_number_with_format(r, col, grade, &grade_format)?;
+                    }
+                }
+            }
+        }
+    }
+
+    sheet.set_column_width(0, 16)?;
+    sheet.set_column_width(1, 14)?;
+    sheet.set_column_width(2, 28)?;
+    for i in 0..columns.len() {
+        sheet.set_column_width((NAME_COLUMNS + i) as u16, 12)?;
+    }
+    sheet.set_freeze_panes(2, NAME_COLUMNS as u16)?;
+
+    Ok(workbook.save_to_buffer()?)
+}
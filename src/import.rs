@@ -0,0 +1,624 @@
+use anyhow::{anyhow, Result};
+use std::collections::HashMap;
+use std::fs;
+
+use crate::config::ImportConfig;
+use crate::db::Database;
+use crate::models::{Mentor, Student};
+
+pub const FIELD_FIRST_NAME: &str = "first_name";
+pub const FIELD_LAST_NAME: &str = "last_name";
+pub const FIELD_REGION: &str = "region";
+pub const FIELD_NIGHT: &str = "night";
+pub const FIELD_EMAIL: &str = "email";
+pub const FIELD_MENTOR: &str = "mentor";
+
+/// Header names recognized without any configuration.
+fn default_aliases(field: &str) -> &'static [&'static str] {
+    match field {
+        FIELD_FIRST_NAME => &["First Name", "First", "Given Name"],
+        FIELD_LAST_NAME => &["Last Name", "Last", "Surname", "Family Name"],
+        FIELD_REGION => &["Region", "Location", "City"],
+        FIELD_NIGHT => &["Night", "Class Night", "Cohort Night"],
+        FIELD_EMAIL => &["Email", "Email Address", "E-mail"],
+        FIELD_MENTOR => &["Mentor Name", "Mentor", "Name"],
+        _ => &[],
+    }
+}
+
+/// Compare headers ignoring case, spaces and punctuation ("First_Name" == "first name").
+fn normalize_header(header: &str) -> String {
+    header
+        .chars()
+        .filter(|c| c.is_alphanumeric())
+        .flat_map(|c| c.to_lowercase())
+        .collect()
+}
+
+/// A row that could not be imported, with its line number in the file.
+#[derive(Debug, Clone)]
+pub struct SkippedRow {
+    pub line: u64,
+    pub reason: String,
+}
+
+#[derive(Debug, Clone)]
+pub struct StudentRow {
+    pub line: u64,
+    pub first_name: String,
+    pub last_name: String,
+    /// Empty when the file has no email column
+    pub email: String,
+    pub region: String,
+    pub night: String,
+}
+
+#[derive(Debug, Clone)]
+pub struct MentorRow {
+    pub line: u64,
+    pub name: String,
+    /// Empty when the file has no email column
+    pub email: String,
+    pub night: String,
+}
+
+/// One line of a mentor assignment CSV: a mentor (id, name or email) and a student.
+#[derive(Debug, Clone)]
+pub struct MentorStudentRow {
+    pub mentor: String,
+    pub student: StudentRow,
+}
+
+struct CsvFile {
+    columns: HashMap<&'static str, usize>,
+    records: Vec<(u64, csv::StringRecord)>,
+}
+
+impl CsvFile {
+    fn field(&self, record: &csv::StringRecord, field: &str) -> String {
+        self.columns
+            .get(field)
+            .and_then(|&i| record.get(i))
+            .unwrap_or("")
+            .trim()
+            .to_string()
+    }
+}
+
+/// Read a CSV file and locate each of `required` (and any `optional`) fields
+/// by header name.
+fn read_csv(
+    path: &str,
+    required: &[&'static str],
+    optional: &[&'static str],
+    config: &ImportConfig,
+) -> Result<CsvFile> {
+    let content =
+        fs::read_to_string(path).map_err(|e| anyhow!("Failed to read {}: {}", path, e))?;
+    // Spreadsheet exports often start with a UTF-8 byte order mark
+    let content = content.trim_start_matches('\u{feff}');
+
+    let mut reader = csv::ReaderBuilder::new()
+        .flexible(true)
+        .from_reader(content.as_bytes());
+
+    let headers: Vec<String> = reader
+        .headers()
+        .map_err(|e| anyhow!("Failed to parse {}: {}", path, e))?
+        .iter()
+        .map(normalize_header)
+        .collect();
+
+    let mut columns = HashMap::new();
+    for &field in required.iter().chain(optional) {
+        let mut aliases: Vec<String> = config.aliases(field).to_vec();
+        aliases.extend(default_aliases(field).iter().map(|a| a.to_string()));
+
+        let index = aliases.iter().find_map(|alias| {
+            let alias = normalize_header(alias);
+            headers.iter().position(|h| *h == alias)
+        });
+
+        match index {
+            Some(i) => {
+                columns.insert(field, i);
+            }
+            None if optional.contains(&field) => {}
+            None => {
+                return Err(anyhow!(
+                    "{}: no column for '{}' (accepted headers: {}). Add more under [import.columns] in the config.",
+                    path,
+                    field,
+                    aliases.join(", ")
+                ))
+            }
+        }
+    }
+
+    let mut records = Vec::new();
+    for record in reader.records() {
+        let record = record.map_err(|e| anyhow!("Failed to parse {}: {}", path, e))?;
+        let line = record
+            .position()
+            .map(|p| line_number(content, p.byte() as usize))
+            .unwrap_or(0);
+        if record.iter().all(|f| f.trim().is_empty()) {
+            continue;
+        }
+        records.push((line, record));
+    }
+
+    Ok(CsvFile { columns, records })
+}
+
+/// 1-based line of the first content at `offset`. csv reports a record as
+/// starting before any blank lines that precede it, so skip past those.
+fn line_number(content: &str, offset: usize) -> u64 {
+    let bytes = content.as_bytes();
+    let mut start = offset.min(bytes.len());
+    while start < bytes.len() && (bytes[start] == b'\n' || bytes[start] == b'\r') {
+        start += 1;
+    }
+    bytes[..start].iter().filter(|&&b| b == b'\n').count() as u64 + 1
+}
+
+/// Parse a students CSV (first name, last name, region, night and optionally email).
+pub fn read_students_csv(
+    path: &str,
+    config: &ImportConfig,
+) -> Result<(Vec<StudentRow>, Vec<SkippedRow>)> {
+    let csv = read_csv(
+        path,
+        &[FIELD_FIRST_NAME, FIELD_LAST_NAME, FIELD_REGION, FIELD_NIGHT],
+        &[FIELD_EMAIL],
+        config,
+    )?;
+
+    let mut rows = Vec::new();
+    let mut skipped = Vec::new();
+
+    for (line, record) in &csv.records {
+        let row = StudentRow {
+            line: *line,
+            first_name: csv.field(record, FIELD_FIRST_NAME),
+            last_name: csv.field(record, FIELD_LAST_NAME),
+            email: csv.field(record, FIELD_EMAIL),
+            region: csv.field(record, FIELD_REGION),
+            night: csv.field(record, FIELD_NIGHT),
+        };
+
+        if row.first_name.is_empty() || row.last_name.is_empty() {
+            skipped.push(SkippedRow {
+                line: *line,
+                reason: "missing first or last name".to_string(),
+            });
+        } else if row.night.is_empty() {
+            skipped.push(SkippedRow {
+                line: *line,
+                reason: format!("no night for {} {}", row.first_name, row.last_name),
+            });
+        } else {
+            rows.push(row);
+        }
+    }
+
+    Ok((rows, skipped))
+}
+
+/// Parse a mentors CSV (mentor name, night and optionally email).
+pub fn read_mentors_csv(
+    path: &str,
+    config: &ImportConfig,
+) -> Result<(Vec<MentorRow>, Vec<SkippedRow>)> {
+    let csv = read_csv(path, &[FIELD_MENTOR, FIELD_NIGHT], &[FIELD_EMAIL], config)?;
+
+    let mut rows = Vec::new();
+    let mut skipped = Vec::new();
+
+    for (line, record) in &csv.records {
+        let name = csv.field(record, FIELD_MENTOR);
+        let email = csv.field(record, FIELD_EMAIL);
+        let night = csv.field(record, FIELD_NIGHT);
+
+        if name.is_empty() || night.is_empty() {
+            skipped.push(SkippedRow {
+                line: *line,
+                reason: "missing mentor name or night".to_string(),
+            });
+        } else {
+            rows.push(MentorRow {
+                line: *line,
+                name,
+                email,
+                night,
+            });
+        }
+    }
+
+    Ok((rows, skipped))
+}
+
+/// Parse a mentor assignment CSV (mentor, student first and last name and
+/// optionally the student's email).
+pub fn read_mentor_students_csv(
+    path: &str,
+    config: &ImportConfig,
+) -> Result<(Vec<MentorStudentRow>, Vec<SkippedRow>)> {
+    let csv = read_csv(
+        path,
+        &[FIELD_MENTOR, FIELD_FIRST_NAME, FIELD_LAST_NAME],
+        &[FIELD_EMAIL],
+        config,
+    )?;
+
+    let mut rows = Vec::new();
+    let mut skipped = Vec::new();
+
+    for (line, record) in &csv.records {
+        let mentor = csv.field(record, FIELD_MENTOR);
+        let student = StudentRow {
+            line: *line,
+            first_name: csv.field(record, FIELD_FIRST_NAME),
+            last_name: csv.field(record, FIELD_LAST_NAME),
+            email: csv.field(record, FIELD_EMAIL),
+            region: String::new(),
+            night: String::new(),
+        };
+
+        if mentor.is_empty() {
+            skipped.push(SkippedRow {
+                line: *line,
+                reason: "missing mentor".to_string(),
+            });
+        } else if student.email.is_empty()
+            && (student.first_name.is_empty() || student.last_name.is_empty())
+        {
+            skipped.push(SkippedRow {
+                line: *line,
+                reason: "missing student name or email".to_string(),
+            });
+        } else {
+            rows.push(MentorStudentRow { mentor, student });
+        }
+    }
+
+    Ok((rows, skipped))
+}
+
+/// How an import row was tied to a student.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum MatchMethod {
+    Email,
+    Name,
+    /// Accents, punctuation, small typos or a shortened first name
+    Fuzzy,
+    /// The name matches (exactly or fuzzily) but the row's email differs
+    /// from the one on file, e.g. a personal address
+    EmailMismatch,
+}
+
+/// What importing a row would do to the database.
+#[derive(Debug, Clone)]
+pub enum StudentMatch {
+    /// Exactly one enrollment; the only outcome that gets written
+    Update {
+        student: Student,
+        method: MatchMethod,
+    },
+    NotFound,
+    /// Several enrollments match; listed for review and not written
+    Ambiguous {
+        candidates: Vec<Student>,
+        method: MatchMethod,
+    },
+}
+
+#[derive(Debug, Clone)]
+pub struct PlannedStudent {
+    pub row: StudentRow,
+    pub outcome: StudentMatch,
+}
+
+impl PlannedStudent {
+    /// Rows a person should look at before trusting the import.
+    pub fn needs_review(&self) -> bool {
+        !matches!(
+            self.outcome,
+            StudentMatch::Update {
+                method: MatchMethod::Email | MatchMethod::Name,
+                ..
+            }
+        )
+    }
+}
+
+/// Lowercase, strip accents and punctuation, and collapse whitespace
+/// ("  José  O'Brien-Smith" -> "jose obrien smith").
+pub fn normalize_name(name: &str) -> String {
+    use unicode_normalization::{char::is_combining_mark, UnicodeNormalization};
+
+    let folded: String = name
+        .nfd()
+        .filter(|c| !is_combining_mark(*c))
+        .map(|c| if c == '-' { ' ' } else { c })
+        .filter(|c| c.is_alphanumeric() || c.is_whitespace())
+        .flat_map(|c| c.to_lowercase())
+        .collect();
+    folded.split_whitespace().collect::<Vec<_>>().join(" ")
+}
+
+/// Typos allowed for a name of this length.
+fn max_edits(name: &str) -> usize {
+    match name.chars().count() {
+        0..=3 => 0,
+        4..=6 => 1,
+        _ => 2,
+    }
+}
+
+fn close(a: &str, b: &str) -> bool {
+    a == b || strsim::levenshtein(a, b) <= max_edits(a).min(max_edits(b))
+}
+
+/// "Chris" / "Christopher", "Sam" / "Samantha"
+fn shortened(a: &str, b: &str) -> bool {
+    let (short, long) = if a.len() <= b.len() { (a, b) } else { (b, a) };
+    short.chars().count() >= 3 && long.starts_with(short)
+}
+
+/// One name part must match exactly; the other may have a typo or be shortened.
+fn names_similar(first: &str, last: &str, student: &Student) -> bool {
+    let student_first = normalize_name(&student.first_name);
+    let student_last = normalize_name(&student.last_name);
+
+    (last == student_last && (close(first, &student_first) || shortened(first, &student_first)))
+        || (first == student_first && close(last, &student_last))
+}
+
+fn resolve(mut hits: Vec<Student>, method: MatchMethod) -> StudentMatch {
+    if hits.len() == 1 {
+        StudentMatch::Update {
+            student: hits.remove(0),
+            method,
+        }
+    } else {
+        StudentMatch::Ambiguous {
+            candidates: hits,
+            method,
+        }
+    }
+}
+
+/// Match one row against the candidate enrollments: email first, then the
+/// normalized name, then a fuzzy name. Names of students with a different
+/// email on file are only used when nothing else matches.
+pub fn match_student(row: &StudentRow, candidates: &[Student]) -> StudentMatch {
+    let email = row.email.trim().to_lowercase();
+    if !email.is_empty() {
+        let hits: Vec<Student> = candidates
+            .iter()
+            .filter(|s| s.email.trim().to_lowercase() == email)
+            .cloned()
+            .collect();
+        if !hits.is_empty() {
+            return resolve(hits, MatchMethod::Email);
+        }
+    }
+
+    let first = normalize_name(&row.first_name);
+    let last = normalize_name(&row.last_name);
+
+    // A student with a different email on file is more likely someone else
+    // with the same name, so they only count once nobody else matches
+    let (compatible, mismatched): (Vec<&Student>, Vec<&Student>) =
+        candidates.iter().partition(|s| {
+            let student_email = s.email.trim();
+            email.is_empty() || student_email.is_empty() || student_email.to_lowercase() == email
+        });
+
+    let same_name = |s: &&&Student| {
+        normalize_name(&s.first_name) == first && normalize_name(&s.last_name) == last
+    };
+    let similar_name = |s: &&&Student| names_similar(&first, &last, s);
+
+    let hits: Vec<Student> = compatible
+        .iter()
+        .filter(same_name)
+        .map(|&s| s.clone())
+        .collect();
+    if !hits.is_empty() {
+        return resolve(hits, MatchMethod::Name);
+    }
+
+    let hits: Vec<Student> = compatible
+        .iter()
+        .filter(similar_name)
+        .map(|&s| s.clone())
+        .collect();
+    if !hits.is_empty() {
+        return resolve(hits, MatchMethod::Fuzzy);
+    }
+
+    let mut hits: Vec<Student> = mismatched
+        .iter()
+        .filter(same_name)
+        .map(|&s| s.clone())
+        .collect();
+    if hits.is_empty() {
+        hits = mismatched
+            .iter()
+            .filter(similar_name)
+            .map(|&s| s.clone())
+            .collect();
+    }
+    if !hits.is_empty() {
+        return resolve(hits, MatchMethod::EmailMismatch);
+    }
+
+    StudentMatch::NotFound
+}
+
+/// Work out which enrollment in `class_id` each row matches without
+/// writing anything.
+pub fn plan_student_import(
+    db: &Database,
+    rows: Vec<StudentRow>,
+    class_id: &str,
+) -> Result<Vec<PlannedStudent>> {
+    let candidates = db.get_students_by_class(class_id)?;
+
+    Ok(rows
+        .into_iter()
+        .map(|row| {
+            let outcome = match_student(&row, &candidates);
+            PlannedStudent { row, outcome }
+        })
+        .collect())
+}
+
+/// Write the rows that matched exactly one enrollment. Returns how many were updated.
+pub fn apply_student_import(db: &Database, plan: &[PlannedStudent]) -> Result<usize> {
+    let mut updated = 0;
+    for planned in plan {
+        if let StudentMatch::Update { student, .. } = &planned.outcome {
+            let row = &planned.row;
+            db.set_student_night(&student.id, &student.class_id, &row.region, &row.night)?;
+            updated += 1;
+        }
+    }
+    Ok(updated)
+}
+
+/// Write every row that needs a human decision to a CSV, one line per candidate.
+pub fn write_review_report(path: &str, plan: &[PlannedStudent]) -> Result<usize> {
+    let mut writer =
+        csv::Writer::from_path(path).map_err(|e| anyhow!("Failed to write {}: {}", path, e))?;
+    writer.write_record([
+        "line",
+        "first_name",
+        "last_name",
+        "email",
+        "status",
+        "candidate_id",
+        "candidate_class_id",
+        "candidate_name",
+        "candidate_email",
+    ])?;
+
+    let mut count = 0;
+    for planned in plan.iter().filter(|p| p.needs_review()) {
+        let row = &planned.row;
+        let (status, candidates) = match &planned.outcome {
+            StudentMatch::Update {
+                student,
+                method: MatchMethod::EmailMismatch,
+            } => ("email mismatch (applied)", vec![student]),
+            StudentMatch::Update { student, .. } => ("fuzzy match (applied)", vec![student]),
+            StudentMatch::Ambiguous { candidates, .. } => {
+                ("ambiguous (not applied)", candidates.iter().collect())
+            }
+            StudentMatch::NotFound => ("not found", Vec::new()),
+        };
+
+        let line = row.line.to_string();
+        let prefix = [
+            line.as_str(),
+            &row.first_name,
+            &row.last_name,
+            &row.email,
+            status,
+        ];
+        if candidates.is_empty() {
+            writer.write_record(prefix.iter().copied().chain(["", "", "", ""]))?;
+        }
+        for student in candidates {
+            let name = format!("{} {}", student.first_name, student.last_name);
+            writer.write_record(prefix.iter().copied().chain([
+                student.id.as_str(),
+                student.class_id.as_str(),
+                name.as_str(),
+                student.email.as_str(),
+            ]))?;
+        }
+        count += 1;
+    }
+
+    writer.flush()?;
+    Ok(count)
+}
+
+#[derive(Debug, Clone)]
+pub struct PlannedMentorStudent {
+    pub row: MentorStudentRow,
+    pub mentor: Option<Mentor>,
+    pub outcome: StudentMatch,
+}
+
+/// Resolve the mentor and student for each assignment row without writing anything.
+pub fn plan_mentor_students(
+    db: &Database,
+    rows: Vec<MentorStudentRow>,
+    class_id: Option<&str>,
+) -> Result<Vec<PlannedMentorStudent>> {
+    let candidates = match class_id {
+        Some(class_id) => db.get_students_by_class(class_id)?,
+        None => db.get_all_students()?,
+    };
+
+    let mut plan = Vec::new();
+    for row in rows {
+        let outcome = match_student(&row.student, &candidates);
+        let mut mentors = db.find_mentors(&row.mentor)?;
+        if let Some(i) = mentors
+            .iter()
+            .position(|m| m.id.to_string() == row.mentor.trim())
+        {
+            mentors = vec![mentors.swap_remove(i)];
+        }
+        // A mentor covering several nights takes this student on their night
+        if let (true, StudentMatch::Update { student, .. }) = (mentors.len() > 1, &outcome) {
+            let night = student.night.as_deref().unwrap_or("").trim();
+            let on_night: Vec<Mentor> = mentors
+                .iter()
+                .filter(|m| m.night.trim().eq_ignore_ascii_case(night))
+                .cloned()
+                .collect();
+            if !on_night.is_empty() {
+                mentors = on_night;
+            }
+        }
+        let mentor = match mentors.len() {
+            0 | 1 => mentors.pop(),
+            n => {
+                return Err(anyhow!(
+                    "Line {}: '{}' matches {} mentors; use a mentor id",
+                    row.student.line,
+                    row.mentor,
+                    n
+                ))
+            }
+        };
+        plan.push(PlannedMentorStudent {
+            row,
+            mentor,
+            outcome,
+        });
+    }
+    Ok(plan)
+}
+
+/// Assign every row with a known mentor and a single matching enrollment.
+/// Returns how many new assignments were made.
+pub fn apply_mentor_students(db: &Database, plan: &[PlannedMentorStudent]) -> Result<usize> {
+    let mut assigned = 0;
+    for planned in plan {
+        if let (Some(mentor), StudentMatch::Update { student, .. }) =
+            (&planned.mentor, &planned.outcome)
+        {
+            if db.assign_mentor_student(mentor.id, &student.id, &student.class_id)? {
+                assigned += 1;
+            }
+        }
+    }
+    Ok(assigned)
+}
@@ -1,8 +1,15 @@
 pub mod api;
+pub mod calendar;
 pub mod cli;
 pub mod config;
 pub mod db;
+pub mod gradebook;
+pub mod import;
 pub mod lms;
 pub mod models;
+pub mod notify;
+pub mod pathway;
+pub mod prediction;
+pub mod risk;
 pub mod sync;
 pub mod update;
@@ -8,14 +8,17 @@ pub mod openclass;
 pub trait LmsProvider: Send + Sync {
     async fn authenticate(&mut self) -> Result<()>;
     async fn fetch_classes(&self) -> Result<Vec<Class>>;
-    async fn fetch_class_structure(
-        &self,
-        class_id: &str,
-    ) -> Result<std::collections::HashMap<String, String>>;
+    /// Assignments in course order with the section each belongs to.
+    async fn fetch_class_structure(&self, class_id: &str) -> Result<Vec<CourseItem>>;
     async fn fetch_progressions(&self, class_id: &str, page: i32) -> Result<ProgressionBatch>;
     fn provider_name(&self) -> &str;
 }
 
+pub struct CourseItem {
+    pub assignment_id: String,
+    pub section: String,
+}
+
 pub struct ProgressionBatch {
     pub progressions: Vec<Progression>,
     pub can_load_more: bool,
@@ -1,5 +1,6 @@
 use super::types::ProgressionResponse;
 use super::OpenClassProvider;
+use crate::lms::CourseItem;
 use crate::models::Class;
 use anyhow::{anyhow, Result};
 
@@ -80,10 +81,7 @@ impl OpenClassProvider {
         Ok(classes)
     }
 
-    pub async fn fetch_class_details(
-        &self,
-        class_id: &str,
-    ) -> Result<std::collections::HashMap<String, String>> {
+    pub async fn fetch_class_details(&self, class_id: &str) -> Result<Vec<CourseItem>> {
         let token = self
             .token
             .as_ref()
@@ -120,22 +118,24 @@ impl OpenClassProvider {
                         if let Some(class_obj) = data_array.first() {
                             class_obj.clone()
                         } else {
-                            return Ok(std::collections::HashMap::new());
+                            return Ok(Vec::new());
                         }
                     } else {
-                        return Ok(std::collections::HashMap::new());
+                        return Ok(Vec::new());
                     }
                 } else {
-                    return Ok(std::collections::HashMap::new());
+                    return Ok(Vec::new());
                 }
             } else {
-                return Ok(std::collections::HashMap::new());
+                return Ok(Vec::new());
             }
         } else {
-            return Ok(std::collections::HashMap::new());
+            return Ok(Vec::new());
         };
 
-        let mut assignment_sections = std::collections::HashMap::new();
+        // Units and their assignments are listed in course order. An
+        // assignment listed in more than one unit belongs to the last one.
+        let mut items: Vec<CourseItem> = Vec::new();
 
         if let Some(units) = class_data.get("units").and_then(|u| u.as_array()) {
             println!("Found {} units", units.len());
@@ -148,8 +148,11 @@ impl OpenClassProvider {
                 if let Some(assignments) = unit.get("assignments").and_then(|a| a.as_array()) {
                     for assignment in assignments {
                         if let Some(assignment_id) = assignment.as_str() {
-                            assignment_sections
-                                .insert(assignment_id.to_string(), section_name.to_string());
+                            items.retain(|i| i.assignment_id != assignment_id);
+                            items.push(CourseItem {
+                                assignment_id: assignment_id.to_string(),
+                                section: section_name.to_string(),
+                            });
                         }
                     }
                 }
@@ -158,7 +161,7 @@ impl OpenClassProvider {
             println!("No units array found in class data");
         }
 
-        Ok(assignment_sections)
+        Ok(items)
     }
 
     pub async fn fetch_progressions(
@@ -1,5 +1,5 @@
 use crate::config::Config;
-use crate::lms::{LmsProvider, Progression, ProgressionBatch};
+use crate::lms::{CourseItem, LmsProvider, Progression, ProgressionBatch};
 use crate::models;
 use anyhow::Result;
 use async_trait::async_trait;
@@ -47,10 +47,7 @@ impl LmsProvider for OpenClassProvider {
         OpenClassProvider::fetch_classes(self).await
     }
 
-    async fn fetch_class_structure(
-        &self,
-        class_id: &str,
-    ) -> Result<std::collections::HashMap<String, String>> {
+    async fn fetch_class_structure(&self, class_id: &str) -> Result<Vec<CourseItem>> {
         OpenClassProvider::fetch_class_details(self, class_id).await
     }
 
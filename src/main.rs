@@ -1,7 +1,7 @@
 use anyhow::Result;
 use clap::Parser;
 use cohort_tracker::cli;
-use cohort_tracker::config::Config;
+use cohort_tracker::config::{Config, Paths};
 
 #[tokio::main]
 async fn main() -> Result<()> {
@@ -9,15 +9,14 @@ async fn main() -> Result<()> {
 
     let cli = cli::Cli::parse();
 
-    // Check for updates in background (non-blocking)
-    let config_path = cli.config.clone().unwrap_or_else(|| {
-        Config::default_path()
-            .to_str()
-            .unwrap_or(".cohort-tracker.toml")
-            .to_string()
-    });
+    let paths = Paths::resolve(
+        cli.config.as_deref(),
+        cli.db.as_deref(),
+        cli.workspace.as_deref(),
+    )?;
 
-    if let Ok(config) = Config::from_file(&config_path) {
+    // Check for updates in background (non-blocking)
+    if let Ok(config) = Config::from_file(paths.config_str()) {
         if config.check_for_updates {
             tokio::spawn(async {
                 cohort_tracker::update::check_and_notify().await;
@@ -31,28 +30,59 @@ async fn main() -> Result<()> {
             password,
             api_base,
         } => {
-            cli::handle_init(email, password, api_base).await?;
+            cli::handle_init(&paths, cli.db, email, password, api_base).await?;
         }
         cli::Commands::List { all } => {
-            cli::handle_list(all).await?;
+            cli::handle_list(&paths, all).await?;
         }
         cli::Commands::Activate { friendly_ids } => {
-            cli::handle_activate(friendly_ids).await?;
+            cli::handle_activate(&paths, friendly_ids).await?;
         }
         cli::Commands::Deactivate { friendly_ids } => {
-            cli::handle_deactivate(friendly_ids).await?;
+            cli::handle_deactivate(&paths, friendly_ids).await?;
         }
         cli::Commands::Sync { class, full } => {
-            cli::handle_sync(cli.config, class, full).await?;
+            cli::handle_sync(&paths, class, full).await?;
         }
         cli::Commands::Status => {
-            cli::handle_status(cli.config).await?;
+            cli::handle_status(&paths).await?;
         }
         cli::Commands::Server { port } => {
-            cli::handle_server(cli.config, port).await?;
+            cli::handle_server(&paths, port).await?;
+        }
+        cli::Commands::Import {
+            students,
+            mentors,
+            mentor_students,
+            class,
+            review,
+            dry_run,
+        } => {
+            cli::handle_import(
+                &paths,
+                students,
+                mentors,
+                mentor_students,
+                class,
+                review,
+                dry_run,
+            )
+            .await?;
+        }
+        cli::Commands::Db { command } => {
+            cli::handle_db(&paths, command).await?;
+        }
+        cli::Commands::Class { command } => {
+            cli::handle_class(&paths, command).await?;
+        }
+        cli::Commands::Model { command } => {
+            cli::handle_model(&paths, command).await?;
+        }
+        cli::Commands::Report { by_pathway } => {
+            cli::handle_report(&paths, by_pathway).await?;
         }
-        cli::Commands::Import { students, mentors } => {
-            cli::handle_import(students, mentors).await?;
+        cli::Commands::Workspaces => {
+            cli::handle_workspaces().await?;
         }
         cli::Commands::Update => {
             cohort_tracker::update::perform_update().await?;
@@ -25,6 +25,7 @@ pub struct Mentor {
     pub id: i64,
     pub name: String,
     pub night: String,
+    pub email: Option<String>,
 }
 
 #[derive(Debug, Clone, Serialize)]
@@ -99,11 +100,39 @@ pub struct StudentHealth {
     pub completion_pct: f64,
     pub avg_grade: Option<f64>,
     pub risk: String,
+    pub risk_score: i64,
+    pub risk_factors: Vec<RiskFactor>,
+    /// Assignments ahead (+) or behind (-) the expected pace; `None` when
+    /// the class has no start and end date
+    pub schedule_delta: Option<i64>,
+    /// From the trained dropout model; `None` until `model train` has run
+    pub dropout_probability: Option<f64>,
+    /// Change in grade per assignment over the last five graded ones
+    /// (least squares); `None` with fewer than three grades
+    pub grade_slope: Option<f64>,
+    /// Standard deviation of the same grades
+    pub grade_volatility: Option<f64>,
+    /// Consecutive class weeks with a completion, up to the current one
+    pub active_week_streak: i64,
+    /// Most days between completions, counting the current gap
+    pub longest_gap_days: Option<i64>,
+    pub declining_grades: bool,
+}
+
+/// One reason behind a risk score, e.g. "inactive 12 days (+10)".
+#[derive(Debug, Clone, Serialize)]
+pub struct RiskFactor {
+    pub factor: String,
+    pub points: i64,
+    pub description: String,
 }
 
 #[derive(Debug, Clone, Serialize)]
 pub struct WeeklyProgress {
     pub week: String,
+    pub week_number: i64,
+    pub week_start: String,
+    pub is_break: bool,
     pub completed: i64,
     pub cumulative: i64,
 }
@@ -144,8 +173,29 @@ pub struct StudentDetail {
     pub completion_pct: f64,
     pub avg_grade: Option<f64>,
     pub risk: String,
+    pub risk_score: i64,
+    pub risk_factors: Vec<RiskFactor>,
     pub last_activity: Option<String>,
     pub days_inactive: Option<i64>,
+    pub schedule_delta: Option<i64>,
+    pub dropout_probability: Option<f64>,
+    pub assignments_remaining: i64,
+    /// Completions per teaching week over the recent window
+    pub recent_velocity: f64,
+    /// `None` once finished or when there's no recent activity to project from
+    pub projected_finish: Option<String>,
+    /// Days between the projected finish and the class end date (negative
+    /// when finishing early)
+    pub projected_days_past_end: Option<i64>,
+    /// Change in grade per assignment over the last five graded ones
+    pub grade_slope: Option<f64>,
+    /// Standard deviation of the same grades
+    pub grade_volatility: Option<f64>,
+    /// Consecutive class weeks with a completion, up to the current one
+    pub active_week_streak: i64,
+    /// Most days between completions, counting the current gap
+    pub longest_gap_days: Option<i64>,
+    pub declining_grades: bool,
 }
 
 #[derive(Debug, Clone, Serialize)]
@@ -162,6 +212,9 @@ pub struct StudentAssignmentStatus {
 #[derive(Debug, Clone, Serialize)]
 pub struct StudentProgressPoint {
     pub week: String,
+    pub week_number: i64,
+    pub week_start: String,
+    pub is_break: bool,
     pub completed: i64,
     pub cumulative: i64,
     pub avg_grade: Option<f64>,
@@ -192,6 +245,9 @@ pub struct GradeDistribution {
 #[derive(Debug, Clone, Serialize)]
 pub struct VelocityStats {
     pub week: String,
+    pub week_number: i64,
+    pub week_start: String,
+    pub is_break: bool,
     pub avg_completions_per_student: f64,
     pub total_completions: i64,
     pub active_students: i64,
@@ -220,6 +276,67 @@ pub struct AssignmentDifficulty {
     pub avg_grade: Option<f64>,
     pub total_students: i64,
     pub completions: i64,
+    /// Median minutes from start to completion
+    pub median_minutes: Option<f64>,
+}
+
+/// Classical item statistics for one assignment's grades. The statistics
+/// are `None` until enough students have a grade.
+#[derive(Debug, Clone, Serialize)]
+pub struct ItemAnalysis {
+    pub assignment_id: String,
+    pub name: String,
+    pub section: Option<String>,
+    pub assignment_type: String,
+    /// Students with a grade on it
+    pub graded: i64,
+    pub avg_grade: Option<f64>,
+    /// Correlation of the grade with the student's average on their other
+    /// graded assignments; point-biserial when grades are pass/fail
+    pub discrimination: Option<f64>,
+    /// Share of graded students scoring 100%
+    pub ceiling_pct: Option<f64>,
+    /// Sarle's bimodality coefficient; above 5/9 suggests two clusters
+    pub bimodality: Option<f64>,
+    /// `low_discrimination`, `ceiling` and/or `bimodal`
+    pub flags: Vec<String>,
+    /// Doesn't separate strong students from weak ones, or nearly everyone aces it
+    pub needs_rework: bool,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct TimeOnTask {
+    pub assignment_id: String,
+    pub name: String,
+    pub section: Option<String>,
+    pub assignment_type: String,
+    /// Completions with a usable start time
+    pub samples: i64,
+    pub median_minutes: f64,
+    pub p90_minutes: f64,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct SectionTimeOnTask {
+    pub section: String,
+    pub samples: i64,
+    /// Per assignment, pooled over the section
+    pub median_minutes: f64,
+    pub p90_minutes: f64,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct TimeOutlier {
+    pub student_id: String,
+    pub first_name: String,
+    pub last_name: String,
+    pub email: String,
+    pub assignment_id: String,
+    pub assignment_name: String,
+    pub minutes: f64,
+    pub median_minutes: f64,
+    /// `minutes` divided by the assignment's median
+    pub ratio: f64,
 }
 
 #[derive(Debug, Clone, Serialize)]
@@ -229,3 +346,524 @@ pub struct SectionProgress {
     pub students_started: i64,
     pub students_completed: i64,
 }
+
+#[derive(Debug, Clone, Serialize)]
+pub struct IntegrityIssue {
+    pub kind: String,
+    pub class_id: Option<String>,
+    pub record_id: String,
+    pub detail: String,
+    pub fixable: bool,
+}
+
+#[derive(Debug, Clone, Serialize, Default)]
+pub struct IntegrityReport {
+    pub issues: Vec<IntegrityIssue>,
+    pub fixed: i64,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct Person {
+    pub id: i64,
+    pub first_name: String,
+    pub last_name: String,
+    pub email: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct PathwayClass {
+    pub class_id: String,
+    pub friendly_id: String,
+    pub name: String,
+    pub module: Option<u32>,
+    pub term: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct Pathway {
+    pub pathway: String,
+    pub classes: Vec<PathwayClass>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct PathwayEnrollment {
+    pub class_id: String,
+    pub friendly_id: String,
+    pub class_name: String,
+    pub module: Option<u32>,
+    pub student_id: String,
+    pub completed: i64,
+    pub total_assignments: i64,
+    pub completion_pct: f64,
+    pub avg_grade: Option<f64>,
+    pub first_activity: Option<String>,
+    pub last_activity: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct ModuleTransition {
+    pub from_module: Option<u32>,
+    pub to_module: Option<u32>,
+    pub left_at: Option<String>,
+    pub entered_at: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct PathwayStudent {
+    pub person_id: i64,
+    pub first_name: String,
+    pub last_name: String,
+    pub email: Option<String>,
+    pub enrollments: Vec<PathwayEnrollment>,
+    pub transitions: Vec<ModuleTransition>,
+    pub furthest_module: Option<u32>,
+    pub dropped_out_module: Option<u32>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct StudentSnapshot {
+    pub student_id: String,
+    pub snapshot_date: String,
+    pub completed: i64,
+    pub completion_pct: f64,
+    pub avg_grade: Option<f64>,
+    pub days_inactive: Option<i64>,
+    pub risk: String,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct RiskHistoryPoint {
+    pub snapshot_date: String,
+    pub critical: i64,
+    pub high: i64,
+    pub medium: i64,
+    pub low: i64,
+    pub total: i64,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct CalendarWeek {
+    /// Teaching week number; break weeks carry the number of the week before
+    pub number: i64,
+    pub label: String,
+    pub start_date: String,
+    pub end_date: String,
+    pub is_break: bool,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct ClassCalendarInfo {
+    pub class_id: String,
+    pub start_date: Option<String>,
+    pub end_date: Option<String>,
+    pub break_weeks: Vec<String>,
+    /// True when no start date is set and weeks count from the first completion
+    pub inferred_start: bool,
+    pub weeks: Vec<CalendarWeek>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct ExpectedPacePoint {
+    pub week: String,
+    pub week_number: i64,
+    pub week_start: String,
+    pub is_break: bool,
+    /// Assignments a student on pace has completed by the end of the week
+    pub expected_completed: f64,
+    pub expected_pct: f64,
+    /// Furthest assignment in course order a student on pace has reached
+    pub expected_assignment: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct CompletionProjection {
+    pub student_id: String,
+    pub first_name: String,
+    pub last_name: String,
+    pub email: String,
+    pub completed: i64,
+    pub remaining: i64,
+    pub recent_velocity: f64,
+    /// `None` when the student has stalled
+    pub projected_finish: Option<String>,
+    pub class_end: String,
+    pub days_past_end: Option<i64>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct ReviewTurnaround {
+    /// Assignment type, night or mentor name
+    pub group: String,
+    pub reviewed: i64,
+    /// Submissions still waiting for review
+    pub pending: i64,
+    pub median_hours: Option<f64>,
+    pub p90_hours: Option<f64>,
+    pub avg_hours: Option<f64>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct ReviewTurnaroundReport {
+    pub by_assignment_type: Vec<ReviewTurnaround>,
+    pub by_night: Vec<ReviewTurnaround>,
+    pub by_mentor: Vec<ReviewTurnaround>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct PendingReview {
+    pub progression_id: String,
+    pub student_id: String,
+    pub first_name: String,
+    pub last_name: String,
+    pub email: String,
+    pub night: Option<String>,
+    pub assignment_id: String,
+    pub assignment_name: String,
+    pub assignment_type: String,
+    pub completed_at: String,
+    pub waiting_hours: f64,
+    pub mentors: Vec<String>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct StudentTimezone {
+    pub student_id: String,
+    pub timezone: String,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct ClassTimezoneInfo {
+    pub class_id: String,
+    /// `None` means UTC
+    pub timezone: Option<String>,
+    pub student_overrides: Vec<StudentTimezone>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct ScheduleStatus {
+    pub student_id: String,
+    pub first_name: String,
+    pub last_name: String,
+    pub email: String,
+    pub completed: i64,
+    pub expected_completed: f64,
+    pub schedule_delta: i64,
+    /// First assignment in course order the student hasn't completed
+    pub next_assignment: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct DropoutModelReport {
+    pub trained_at: String,
+    pub early_weeks: i64,
+    pub classes: usize,
+    pub train_size: usize,
+    pub holdout_size: usize,
+    pub dropout_rate: f64,
+    pub holdout_accuracy: f64,
+    /// Holdout accuracy of always predicting the most common outcome
+    pub baseline_accuracy: f64,
+    pub coefficients: Vec<ModelCoefficient>,
+}
+
+/// Weight of one standardized feature; positive means more likely to drop out.
+#[derive(Debug, Clone, Serialize)]
+pub struct ModelCoefficient {
+    pub feature: String,
+    pub weight: f64,
+}
+
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct RiskCounts {
+    pub critical: i64,
+    pub high: i64,
+    pub medium: i64,
+    pub low: i64,
+}
+
+/// One active class in a program rollup.
+#[derive(Debug, Clone, Serialize)]
+pub struct ModuleRollup {
+    pub class_id: String,
+    pub friendly_id: String,
+    pub name: String,
+    pub pathway: String,
+    pub module: Option<u32>,
+    pub students: i64,
+    pub completion_rate: f64,
+    pub avg_grade: Option<f64>,
+    pub risk: RiskCounts,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct ProgramBlocker {
+    pub class_id: String,
+    pub friendly_id: String,
+    pub assignment_id: String,
+    pub name: String,
+    pub section: Option<String>,
+    pub completion_rate: f64,
+    pub avg_grade: Option<f64>,
+    pub completions: i64,
+    pub total_students: i64,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct ProgramSection {
+    pub class_id: String,
+    pub friendly_id: String,
+    pub section: String,
+    pub total_students: i64,
+    pub students_started: i64,
+    pub students_completed: i64,
+    /// Share of the class's students who have completed the section
+    pub completion_rate: f64,
+}
+
+/// Aggregates over active classes: all of them, or one pathway's.
+#[derive(Debug, Clone, Serialize)]
+pub struct ProgramRollup {
+    /// `None` for the whole program
+    pub pathway: Option<String>,
+    pub total_students: i64,
+    pub modules: Vec<ModuleRollup>,
+    pub risk: RiskCounts,
+    pub blockers: Vec<ProgramBlocker>,
+    pub worst_sections: Vec<ProgramSection>,
+}
+
+/// One run (term) of a course.
+#[derive(Debug, Clone, Serialize)]
+pub struct CourseRun {
+    pub class_id: String,
+    pub friendly_id: String,
+    pub name: String,
+    pub term: Option<String>,
+    pub start_date: Option<String>,
+}
+
+/// Cumulative completion (0.0 to 1.0) at the end of each teaching week,
+/// starting with week 1.
+#[derive(Debug, Clone, Serialize)]
+pub struct RunCurve {
+    pub class_id: String,
+    pub friendly_id: String,
+    pub completion_pct: Vec<f64>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct BenchmarkWeek {
+    pub week: i64,
+    pub current: Option<f64>,
+    /// The most recent earlier run
+    pub previous: Option<f64>,
+    /// Median and interquartile band over earlier runs that reached this week
+    pub median: Option<f64>,
+    pub p25: Option<f64>,
+    pub p75: Option<f64>,
+    pub historical_runs: i64,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct CohortBenchmark {
+    pub class_id: String,
+    pub course: String,
+    pub previous_class_id: Option<String>,
+    /// Every run of the course, oldest first
+    pub runs: Vec<CourseRun>,
+    pub curves: Vec<RunCurve>,
+    pub weeks: Vec<BenchmarkWeek>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct RunDifficulty {
+    pub class_id: String,
+    pub friendly_id: String,
+    pub difficulty_score: f64,
+    pub completion_rate: f64,
+    pub avg_grade: Option<f64>,
+}
+
+/// One assignment's difficulty in each run of the course, matched by name.
+#[derive(Debug, Clone, Serialize)]
+pub struct AssignmentBenchmark {
+    pub name: String,
+    pub section: Option<String>,
+    pub runs: Vec<RunDifficulty>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct ClassCourseInfo {
+    pub class_id: String,
+    pub course: String,
+    /// False when the course comes from the friendly id
+    pub explicit: bool,
+    pub runs: Vec<CourseRun>,
+}
+
+/// One assignment in the course-order funnel.
+#[derive(Debug, Clone, Serialize)]
+pub struct FunnelStep {
+    /// 1-based place in course order
+    pub position: i64,
+    pub assignment_id: String,
+    pub name: String,
+    pub section: Option<String>,
+    /// Students who completed this assignment and everything before it
+    pub reached: i64,
+    /// Students who completed it at all, in sequence or not
+    pub completed: i64,
+    /// Students who completed it with an earlier assignment still open
+    pub skipped_ahead: i64,
+    /// Students who reached the previous step but not this one
+    pub drop_off: i64,
+    /// `drop_off` as a share of the previous step's `reached`
+    pub drop_off_rate: f64,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct SkippedAheadStudent {
+    pub student_id: String,
+    pub first_name: String,
+    pub last_name: String,
+    /// Assignments completed in sequence before the first gap
+    pub in_sequence: i64,
+    /// Position of the furthest assignment completed
+    pub furthest_position: i64,
+    /// Earlier assignments left open before the furthest one
+    pub missed: i64,
+    pub first_missed: String,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct AssignmentFunnel {
+    pub total_students: i64,
+    pub steps: Vec<FunnelStep>,
+    /// Steps with the largest drop-off rate, worst first
+    pub biggest_drop_offs: Vec<FunnelStep>,
+    pub skipped_ahead: Vec<SkippedAheadStudent>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct GradebookAssignment {
+    pub assignment_id: String,
+    pub name: String,
+    pub assignment_type: String,
+    pub section: Option<String>,
+}
+
+/// A run of consecutive gradebook columns sharing a section.
+#[derive(Debug, Clone, Serialize)]
+pub struct GradebookSection {
+    pub section: Option<String>,
+    /// Index of the section's first assignment in `Gradebook::assignments`
+    pub first_assignment: usize,
+    pub assignment_count: usize,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct GradebookCell {
+    /// "completed", "in_progress" or "not_started"
+    pub state: String,
+    pub grade: Option<f64>,
+    pub completed_at: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct SectionSubtotal {
+    pub section: Option<String>,
+    pub completed: i64,
+    pub total: i64,
+    pub avg_grade: Option<f64>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct GradebookRow {
+    pub student_id: String,
+    pub first_name: String,
+    pub last_name: String,
+    pub email: String,
+    /// One per assignment, in `Gradebook::assignments` order
+    pub cells: Vec<GradebookCell>,
+    /// One per section, in `Gradebook::sections` order
+    pub subtotals: Vec<SectionSubtotal>,
+    pub completed: i64,
+    pub avg_grade: Option<f64>,
+}
+
+/// Every student against every assignment, grouped by section in course
+/// order.
+#[derive(Debug, Clone, Serialize)]
+pub struct Gradebook {
+    pub class_id: String,
+    pub assignments: Vec<GradebookAssignment>,
+    pub sections: Vec<GradebookSection>,
+    pub students: Vec<GradebookRow>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DigestCompletion {
+    pub student_id: String,
+    pub first_name: String,
+    pub last_name: String,
+    pub assignment_id: String,
+    pub assignment_name: String,
+    pub grade: Option<f64>,
+    pub completed_at: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DigestReview {
+    pub student_id: String,
+    pub first_name: String,
+    pub last_name: String,
+    pub assignment_id: String,
+    pub assignment_name: String,
+    pub grade: Option<f64>,
+    pub reviewed_at: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DigestRiskChange {
+    pub student_id: String,
+    pub first_name: String,
+    pub last_name: String,
+    pub from: String,
+    pub to: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DigestStudent {
+    pub student_id: String,
+    pub first_name: String,
+    pub last_name: String,
+    pub days_inactive: Option<i64>,
+}
+
+/// What changed in a class between two consecutive syncs.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SyncDigest {
+    pub class_id: String,
+    pub class_name: String,
+    pub synced_at: String,
+    /// `None` on the first sync with a digest
+    pub previous_sync_at: Option<String>,
+    pub completions: Vec<DigestCompletion>,
+    /// Students whose risk level went up
+    pub risk_increases: Vec<DigestRiskChange>,
+    /// Students who passed the inactivity grace period since the last sync
+    pub went_silent: Vec<DigestStudent>,
+    /// Students who were past the grace period and completed something
+    pub came_back: Vec<DigestStudent>,
+    pub newly_reviewed: Vec<DigestReview>,
+}
+
+impl SyncDigest {
+    pub fn is_empty(&self) -> bool {
+        self.completions.is_empty()
+            && self.risk_increases.is_empty()
+            && self.went_silent.is_empty()
+            && self.came_back.is_empty()
+            && self.newly_reviewed.is_empty()
+    }
+}
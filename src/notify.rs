@@ -0,0 +1,89 @@
+//! Sync digests as text, and sending them to the notifications webhook.
+
+use anyhow::{bail, Result};
+
+use crate::models::SyncDigest;
+
+/// Names listed per section of a summary before "and N more".
+const NAMES_SHOWN: usize = 10;
+
+fn section<T>(lines: &mut Vec<String>, title: &str, items: &[T], line: impl Fn(&T) -> String) {
+    if items.is_empty() {
+        return;
+    }
+    lines.push(format!("{} ({}):", title, items.len()));
+    lines.extend(
+        items
+            .iter()
+            .take(NAMES_SHOWN)
+            .map(|i| format!("  {}", line(i))),
+    );
+    if items.len() > NAMES_SHOWN {
+        lines.push(format!("  ...and {} more", items.len() - NAMES_SHOWN));
+    }
+}
+
+fn grade_suffix(grade: Option<f64>) -> String {
+    grade
+        .map(|g| format!(" ({:.0}%)", g * 100.0))
+        .unwrap_or_default()
+}
+
+/// The digest as plain text lines, headed by the class name.
+pub fn summary_lines(digest: &SyncDigest) -> Vec<String> {
+    let mut lines = vec![format!("What's new in {}", digest.class_name)];
+    if digest.is_empty() {
+        lines.push("  Nothing changed since the last sync".to_string());
+        return lines;
+    }
+    section(&mut lines, "Completions", &digest.completions, |c| {
+        format!(
+            "{} {} completed {}{}",
+            c.first_name,
+            c.last_name,
+            c.assignment_name,
+            grade_suffix(c.grade)
+        )
+    });
+    section(&mut lines, "Higher risk", &digest.risk_increases, |r| {
+        format!("{} {}: {} -> {}", r.first_name, r.last_name, r.from, r.to)
+    });
+    section(&mut lines, "Went silent", &digest.went_silent, |s| {
+        format!(
+            "{} {} ({} days inactive)",
+            s.first_name,
+            s.last_name,
+            s.days_inactive.unwrap_or(0)
+        )
+    });
+    section(&mut lines, "Came back", &digest.came_back, |s| {
+        format!("{} {}", s.first_name, s.last_name)
+    });
+    section(&mut lines, "Newly reviewed", &digest.newly_reviewed, |r| {
+        format!(
+            "{} {}: {}{}",
+            r.first_name,
+            r.last_name,
+            r.assignment_name,
+            grade_suffix(r.grade)
+        )
+    });
+    lines
+}
+
+/// POST the digest to `url` as `{"text": ..., "digest": ...}`, which chat
+/// webhooks show as a message and other receivers can read as data.
+pub async fn send_digest(url: &str, digest: &SyncDigest) -> Result<()> {
+    let client = reqwest::Client::builder()
+        .timeout(std::time::Duration::from_secs(10))
+        .build()?;
+    let body = serde_json::json!({
+        "text": summary_lines(digest).join("\n"),
+        "digest": digest,
+    });
+    let response = client.post(url).json(&body).send().await?;
+    if !response.status().is_success() {
+        bail!("Webhook returned {}", response.status());
+    }
+    Ok(())
+}
@@ -0,0 +1,39 @@
+//! Pathway and module information derived from class friendly ids.
+//!
+//! OpenClass friendly ids look like `data-analysis-pathway-module-2-aug-2`:
+//! a pathway prefix, a module number and a term suffix.
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ClassKey {
+    pub pathway: String,
+    pub module: Option<u32>,
+    pub term: Option<String>,
+}
+
+pub fn parse_friendly_id(friendly_id: &str) -> ClassKey {
+    let parts: Vec<&str> = friendly_id.split('-').collect();
+
+    for (i, window) in parts.windows(2).enumerate() {
+        if window[0] == "module" {
+            if let Ok(module) = window[1].parse::<u32>() {
+                let pathway = parts[..i].join("-");
+                let term = parts[i + 2..].join("-");
+                return ClassKey {
+                    pathway: if pathway.is_empty() {
+                        friendly_id.to_string()
+                    } else {
+                        pathway
+                    },
+                    module: Some(module),
+                    term: if term.is_empty() { None } else { Some(term) },
+                };
+            }
+        }
+    }
+
+    ClassKey {
+        pathway: friendly_id.to_string(),
+        module: None,
+        term: None,
+    }
+}
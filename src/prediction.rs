@@ -0,0 +1,116 @@
+use serde::{Deserialize, Serialize};
+
+/// Early-weeks features, in the order the model's weights use.
+pub const FEATURES: [&str; 4] = [
+    "early_completion_pct",
+    "longest_gap_days",
+    "early_avg_grade",
+    "early_graded",
+];
+
+const ITERATIONS: usize = 2000;
+const LEARNING_RATE: f64 = 0.1;
+const L2: f64 = 0.01;
+
+/// Logistic regression over standardized features.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct LogisticModel {
+    pub means: Vec<f64>,
+    pub stds: Vec<f64>,
+    pub weights: Vec<f64>,
+    pub intercept: f64,
+}
+
+fn sigmoid(z: f64) -> f64 {
+    1.0 / (1.0 + (-z).exp())
+}
+
+impl LogisticModel {
+    /// Fit by batch gradient descent. `labels` are 1.0 for dropped out.
+    pub fn train(samples: &[Vec<f64>], labels: &[f64]) -> Self {
+        let n = samples.len().max(1) as f64;
+        let width = samples.first().map(|s| s.len()).unwrap_or(0);
+
+        let means: Vec<f64> = (0..width)
+            .map(|j| samples.iter().map(|s| s[j]).sum::<f64>() / n)
+            .collect();
+        let stds: Vec<f64> = (0..width)
+            .map(|j| {
+                let var = samples
+                    .iter()
+                    .map(|s| (s[j] - means[j]).powi(2))
+                    .sum::<f64>()
+                    / n;
+                // A constant feature carries no signal; keep it from dividing by zero
+                if var > 0.0 {
+                    var.sqrt()
+                } else {
+                    1.0
+                }
+            })
+            .collect();
+
+        let mut model = Self {
+            means,
+            stds,
+            weights: vec![0.0; width],
+            intercept: 0.0,
+        };
+        let scaled: Vec<Vec<f64>> = samples.iter().map(|s| model.scale(s)).collect();
+
+        for _ in 0..ITERATIONS {
+            let mut grad_w = vec![0.0; width];
+            let mut grad_b = 0.0;
+            for (x, y) in scaled.iter().zip(labels) {
+                let error = model.predict_scaled(x) - y;
+                for (g, xi) in grad_w.iter_mut().zip(x) {
+                    *g += error * xi;
+                }
+                grad_b += error;
+            }
+            for (w, g) in model.weights.iter_mut().zip(&grad_w) {
+                *w -= LEARNING_RATE * (g / n + L2 * *w);
+            }
+            model.intercept -= LEARNING_RATE * grad_b / n;
+        }
+
+        model
+    }
+
+    fn scale(&self, features: &[f64]) -> Vec<f64> {
+        features
+            .iter()
+            .zip(self.means.iter().zip(&self.stds))
+            .map(|(x, (mean, std))| (x - mean) / std)
+            .collect()
+    }
+
+    fn predict_scaled(&self, scaled: &[f64]) -> f64 {
+        let z: f64 = self
+            .weights
+            .iter()
+            .zip(scaled)
+            .map(|(w, x)| w * x)
+            .sum::<f64>()
+            + self.intercept;
+        sigmoid(z)
+    }
+
+    /// Probability of dropping out, 0.0 to 1.0.
+    pub fn predict(&self, features: &[f64]) -> f64 {
+        self.predict_scaled(&self.scale(features))
+    }
+
+    /// Share of samples classified correctly at a 0.5 cutoff.
+    pub fn accuracy(&self, samples: &[Vec<f64>], labels: &[f64]) -> f64 {
+        if samples.is_empty() {
+            return 0.0;
+        }
+        let correct = samples
+            .iter()
+            .zip(labels)
+            .filter(|(x, y)| (self.predict(x) >= 0.5) == (**y >= 0.5))
+            .count();
+        correct as f64 / samples.len() as f64
+    }
+}
@@ -0,0 +1,142 @@
+use crate::config::RiskConfig;
+use crate::models::RiskFactor;
+
+/// Everything the risk score looks at for one student. Signals that can't be
+/// measured (no grades yet, no calendar) are `None` and add no points.
+#[derive(Debug, Clone, Default)]
+pub struct RiskInputs {
+    pub completion_pct: f64,
+    /// Assignments ahead (+) or behind (-) the expected pace
+    pub schedule_delta: Option<i64>,
+    pub avg_grade: Option<f64>,
+    /// Recent average grade minus the earlier average (negative when falling)
+    pub grade_trend: Option<f64>,
+    pub days_inactive: Option<i64>,
+    /// Fractional drop in completions over the last two weeks versus the two
+    /// before (0.0 to 1.0)
+    pub velocity_drop: Option<f64>,
+}
+
+#[derive(Debug, Clone)]
+pub struct RiskAssessment {
+    pub score: i64,
+    pub level: String,
+    pub factors: Vec<RiskFactor>,
+}
+
+/// Score a student and explain the score, largest contribution first.
+pub fn assess(config: &RiskConfig, inputs: &RiskInputs) -> RiskAssessment {
+    let weights = &config.weights;
+    let mut factors = Vec::new();
+    let mut add = |factor: &str, points: f64, describe: &dyn Fn() -> String| {
+        if points.round() >= 1.0 {
+            factors.push((factor.to_string(), points, describe()));
+        }
+    };
+
+    let completion_pct = inputs.completion_pct.clamp(0.0, 1.0);
+    add(
+        "completion",
+        weights.completion * (1.0 - completion_pct),
+        &|| format!("{:.0}% complete", completion_pct * 100.0),
+    );
+
+    // Pace and activity only matter while there is work left to do
+    let finished = completion_pct >= 1.0;
+
+    if let Some(delta) = inputs.schedule_delta.filter(|d| *d < 0 && !finished) {
+        let behind = -delta;
+        add(
+            "behind_schedule",
+            (weights.behind_schedule * behind as f64).min(weights.behind_schedule_max),
+            &|| {
+                format!(
+                    "{} assignment{} behind schedule",
+                    behind,
+                    if behind == 1 { "" } else { "s" }
+                )
+            },
+        );
+    }
+
+    if let Some(grade) = inputs.avg_grade {
+        if config.passing_grade > 0.0 && grade < config.passing_grade {
+            add(
+                "grade",
+                weights.grade * (config.passing_grade - grade) / config.passing_grade,
+                &|| format!("average grade {:.0}%", grade * 100.0),
+            );
+        }
+    }
+
+    if let Some(trend) = inputs.grade_trend.filter(|t| *t < 0.0) {
+        add(
+            "grade_trend",
+            weights.grade_trend * (-trend / 0.2).min(1.0),
+            &|| format!("recent grades down {:.0} points", -trend * 100.0),
+        );
+    }
+
+    if let Some(days) = inputs.days_inactive.filter(|_| !finished) {
+        let over = days - config.inactive_grace_days;
+        if over > 0 {
+            add(
+                "inactivity",
+                (weights.inactivity * over as f64).min(weights.inactivity_max),
+                &|| format!("inactive {} days", days),
+            );
+        }
+    }
+
+    if let Some(drop) = inputs.velocity_drop.filter(|d| *d > 0.0 && !finished) {
+        add(
+            "velocity_drop",
+            weights.velocity_drop * drop.min(1.0),
+            &|| {
+                format!(
+                    "completions down {:.0}% on the previous two weeks",
+                    drop * 100.0
+                )
+            },
+        );
+    }
+
+    let score: f64 = factors.iter().map(|(_, points, _)| points).sum();
+    let thresholds = &config.thresholds;
+    let level = if score > thresholds.critical {
+        "critical"
+    } else if score > thresholds.high {
+        "high"
+    } else if score > thresholds.medium {
+        "medium"
+    } else {
+        "low"
+    };
+
+    factors.sort_by(|a, b| b.1.total_cmp(&a.1));
+    RiskAssessment {
+        score: score.round() as i64,
+        level: level.to_string(),
+        factors: factors
+            .into_iter()
+            .map(|(factor, points, description)| {
+                let points = points.round() as i64;
+                RiskFactor {
+                    description: format!("{} (+{})", description, points),
+                    factor,
+                    points,
+                }
+            })
+            .collect(),
+    }
+}
+
+/// Order of risk levels from "low" (0) to "critical" (3).
+pub fn level_rank(level: &str) -> u8 {
+    match level {
+        "critical" => 3,
+        "high" => 2,
+        "medium" => 1,
+        _ => 0,
+    }
+}
@@ -53,18 +53,23 @@ impl SyncEngine {
         full: bool,
     ) -> Result<SyncStats> {
         let mut stats = SyncStats::default();
+        let baseline = db.sync_baseline(class_id)?;
 
         println!("Fetching class structure...");
-        let assignment_sections = match self.provider.fetch_class_structure(class_id).await {
-            Ok(sections) => {
-                println!("Found {} assignments with section info", sections.len());
-                sections
+        let course_items = match self.provider.fetch_class_structure(class_id).await {
+            Ok(items) => {
+                println!("Found {} assignments with section info", items.len());
+                items
             }
             Err(e) => {
                 println!("Warning: Could not fetch class structure: {}. Continuing without section info.", e);
-                std::collections::HashMap::new()
+                Vec::new()
             }
         };
+        let assignment_sections: std::collections::HashMap<&str, &str> = course_items
+            .iter()
+            .map(|i| (i.assignment_id.as_str(), i.section.as_str()))
+            .collect();
 
         let mut existing_progressions = db.get_progression_ids_by_class(class_id)?;
         println!(
@@ -109,8 +114,8 @@ impl SyncEngine {
                 stats.students_inserted += 1;
 
                 let section = assignment_sections
-                    .get(&progression.assignment.id)
-                    .map(|s| s.as_str());
+                    .get(progression.assignment.id.as_str())
+                    .copied();
                 db.insert_assignment(
                     &progression.assignment.id,
                     class_id,
@@ -169,6 +174,24 @@ impl SyncEngine {
             tokio::time::sleep(Duration::from_millis(500)).await;
         }
 
+        // Keep the previous order when the class structure couldn't be fetched
+        if !course_items.is_empty() {
+            let order: Vec<&str> = course_items
+                .iter()
+                .map(|i| i.assignment_id.as_str())
+                .collect();
+            db.set_assignment_positions(class_id, &order)?;
+        }
+
+        let linked = db.link_people()?;
+        if linked > 0 {
+            println!("Linked {} new student account(s) to people", linked);
+        }
+
+        let snapshots = db.record_student_snapshots(class_id)?;
+        println!("Recorded {} student snapshot(s)", snapshots);
+        stats.digests.push(db.record_sync_digest(&baseline)?);
+
         println!("\n✓ Class sync complete:");
         println!("  Pages fetched: {}", stats.pages_fetched);
         println!("  Total records: {}", stats.total_records);
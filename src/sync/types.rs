@@ -1,3 +1,5 @@
+use crate::models::SyncDigest;
+
 #[derive(Debug, Default)]
 pub struct SyncStats {
     pub total_records: i32,
@@ -5,6 +7,8 @@ pub struct SyncStats {
     pub assignments_inserted: i32,
     pub progressions_inserted: i32,
     pub pages_fetched: i32,
+    /// What changed in each synced class
+    pub digests: Vec<SyncDigest>,
 }
 
 impl SyncStats {
@@ -14,5 +18,6 @@ impl SyncStats {
         self.assignments_inserted += other.assignments_inserted;
         self.progressions_inserted += other.progressions_inserted;
         self.pages_fetched += other.pages_fetched;
+        self.digests.extend(other.digests);
     }
 }
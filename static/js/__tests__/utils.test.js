@@ -1,4 +1,4 @@
-import { maskName, maskEmail, formatPercent, formatDate, getWeekDateRange } from '../utils.js';
+import { maskName, maskEmail, formatPercent, formatDate, calendarWeekLabel } from '../utils.js';
 
 describe('maskName', () => {
   test('returns actual name when demo mode is off', () => {
@@ -47,15 +47,14 @@ describe('formatDate', () => {
   });
 });
 
-describe('getWeekDateRange', () => {
-  test('converts ISO week to date range', () => {
-    const result = getWeekDateRange('2025-35');
-    // Just check format, not specific month (week calculation can vary)
-    expect(result).toMatch(/\w+ \d+ - \w+ \d+/);
+describe('calendarWeekLabel', () => {
+  test('labels a class week with its date range', () => {
+    const result = calendarWeekLabel({ week: 'Week 3', week_start: '2025-09-15' });
+    expect(result).toEqual(['Week 3', 'Sep 15 - Sep 21']);
   });
-  
-  test('handles different weeks', () => {
-    const result = getWeekDateRange('2025-01');
-    expect(result).toMatch(/\w+ \d+ - \w+ \d+/);
+
+  test('handles weeks that span a month boundary', () => {
+    const result = calendarWeekLabel({ week: 'Break (after Week 4)', week_start: '2025-09-29' });
+    expect(result).toEqual(['Break (after Week 4)', 'Sep 29 - Oct 5']);
   });
 });
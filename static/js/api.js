@@ -93,3 +93,124 @@ export async function deactivateClass(classId) {
   if (!response.ok) throw new Error('Failed to deactivate');
   return response.json();
 }
+
+/**
+ * Get a student's progress across every class in their pathway
+ * @param {string} classId - Class ID
+ * @param {string} studentId - Student ID
+ * @returns {Promise<Object>} Enrollments, transitions and dropout module
+ */
+export async function getStudentPathway(classId, studentId) {
+  return fetchData(`/classes/${classId}/students/${studentId}/pathway`);
+}
+
+/**
+ * Get every student in a pathway with their per-module progress
+ * @param {string} pathway - Pathway name (e.g., "data-analysis-pathway")
+ * @returns {Promise<Array>} Students with enrollments per module
+ */
+export async function getPathwayStudents(pathway) {
+  return fetchData(`/pathways/${encodeURIComponent(pathway)}/students`);
+}
+
+/**
+ * Get a student's daily snapshots (risk, completion, grade) over time
+ * @param {string} classId - Class ID
+ * @param {string} studentId - Student ID
+ * @returns {Promise<Array>} Snapshots ordered by date
+ */
+export async function getStudentRiskHistory(classId, studentId) {
+  return fetchData(`/classes/${classId}/students/${studentId}/risk-history`);
+}
+
+/**
+ * Get the number of students at each risk level per snapshot date
+ * @param {string} classId - Class ID
+ * @param {string} night - Optional night filter
+ * @returns {Promise<Array>} Risk counts ordered by date
+ */
+export async function getRiskHistory(classId, night = null) {
+  const endpoint = night
+    ? `/classes/${classId}/metrics/risk-history?night=${night}`
+    : `/classes/${classId}/metrics/risk-history`;
+  return fetchData(endpoint);
+}
+
+/**
+ * Get the students assigned to a mentor
+ * @param {string} mentor - Mentor id, name or email
+ * @returns {Promise<Array>} Students across classes
+ */
+export async function getMentorStudents(mentor) {
+  return fetchData(`/mentors/${encodeURIComponent(mentor)}/students`);
+}
+
+/**
+ * Get a class's teaching calendar (start, end, break weeks and week list)
+ * @param {string} classId - Class ID
+ * @returns {Promise<Object>} Calendar settings and weeks
+ */
+export async function getClassCalendar(classId) {
+  return fetchData(`/classes/${classId}/calendar`);
+}
+
+/**
+ * Get the expected cumulative progress at the end of each class week
+ * @param {string} classId - Class ID
+ * @returns {Promise<Array>} Expected completions per week
+ */
+export async function getExpectedPace(classId) {
+  return fetchData(`/classes/${classId}/metrics/expected-pace`);
+}
+
+/**
+ * Get students ranked by how far behind the expected pace they are
+ * @param {string} classId - Class ID
+ * @param {string} night - Optional night filter
+ * @returns {Promise<Array>} Students with schedule delta, most behind first
+ */
+export async function getBehindSchedule(classId, night = null) {
+  const endpoint = night
+    ? `/classes/${classId}/metrics/behind-schedule?night=${night}`
+    : `/classes/${classId}/metrics/behind-schedule`;
+  return fetchData(endpoint);
+}
+
+/**
+ * Get students projected to miss the class end date at their recent pace
+ * @param {string} classId - Class ID
+ * @param {string} night - Optional night filter
+ * @returns {Promise<Array>} Stalled students first, then latest projected finish
+ */
+export async function getProjectedLate(classId, night = null) {
+  const endpoint = night
+    ? `/classes/${classId}/metrics/projected-late?night=${night}`
+    : `/classes/${classId}/metrics/projected-late`;
+  return fetchData(endpoint);
+}
+
+/**
+ * Get review turnaround (completion to review) by assignment type, night and mentor
+ * @param {string} classId - Class ID
+ * @param {string} night - Optional night filter
+ * @returns {Promise<Object>} Median/P90 hours and pending counts per group
+ */
+export async function getReviewTurnaround(classId, night = null) {
+  const endpoint = night
+    ? `/classes/${classId}/metrics/review-turnaround?night=${night}`
+    : `/classes/${classId}/metrics/review-turnaround`;
+  return fetchData(endpoint);
+}
+
+/**
+ * Get submissions still waiting for review
+ * @param {string} classId - Class ID
+ * @param {string} night - Optional night filter
+ * @returns {Promise<Array>} Pending submissions, oldest first
+ */
+export async function getPendingReviews(classId, night = null) {
+  const endpoint = night
+    ? `/classes/${classId}/reviews/pending?night=${night}`
+    : `/classes/${classId}/reviews/pending`;
+  return fetchData(endpoint);
+}
@@ -284,7 +284,7 @@ async function loadAssignmentDifficulty() {
   const tbody = document.getElementById("difficulty-body");
 
   if (!data || data.length === 0) {
-    tbody.innerHTML = '<tr><td colspan="6">No data available</td></tr>';
+    tbody.innerHTML = '<tr><td colspan="7">No data available</td></tr>';
     return;
   }
 
@@ -332,6 +332,11 @@ async function loadAssignmentDifficulty() {
                     <td>${
                       item.avg_grade ? formatPercent(item.avg_grade) : "N/A"
                     }</td>
+                    <td>${
+                      item.median_minutes != null
+                        ? formatMinutes(item.median_minutes)
+                        : "N/A"
+                    }</td>
                 </tr>
                 `;
     })
@@ -356,8 +361,11 @@ async function loadStudentHealth() {
     return;
   }
 
-  // Show only at-risk students (not low risk)
-  const atRisk = data.filter((s) => s.risk !== "low").slice(0, 10);
+  // Show only at-risk students (not low risk), highest score first
+  const atRisk = data
+    .filter((s) => s.risk !== "low")
+    .sort((a, b) => b.risk_score - a.risk_score)
+    .slice(0, 10);
 
   if (atRisk.length === 0) {
     tbody.innerHTML =
@@ -384,9 +392,9 @@ async function loadStudentHealth() {
       }</span>
                         </div>
                     </td>
-                    <td><span class="risk-badge risk-${student.risk}">${
-        student.risk
-      }</span></td>
+                    <td><span class="risk-badge risk-${student.risk}" title="${student.risk_factors
+                      .map((f) => f.description)
+                      .join("\n")}">${student.risk}</span></td>
                 </tr>
             `
     )
@@ -580,10 +588,83 @@ async function openStudentModal(studentId) {
                     }</div>
                     <div class="label">Days Inactive</div>
                 </div>
-                <div class="modal-stat ${riskClass}">
+                ${
+                  detail.schedule_delta !== null
+                    ? `<div class="modal-stat ${
+                        detail.schedule_delta >= 0
+                          ? "success"
+                          : detail.schedule_delta >= -2
+                          ? "warning"
+                          : "danger"
+                      }">
+                    <div class="value">${
+                      detail.schedule_delta > 0 ? "+" : ""
+                    }${detail.schedule_delta}</div>
+                    <div class="label">Vs. Schedule</div>
+                </div>`
+                    : ""
+                }
+                <div class="modal-stat ${riskClass}" title="${detail.risk_factors
+                  .map((f) => f.description)
+                  .join("\n")}">
                     <div class="value">${detail.risk.toUpperCase()}</div>
-                    <div class="label">Risk Level</div>
+                    <div class="label">Risk Level (${detail.risk_score})</div>
+                </div>
+                ${
+                  detail.dropout_probability != null
+                    ? `<div class="modal-stat ${
+                        detail.dropout_probability >= 0.5 ? "danger" : ""
+                      }" title="Predicted by the model trained on past cohorts">
+                    <div class="value">${Math.round(
+                      detail.dropout_probability * 100
+                    )}%</div>
+                    <div class="label">Dropout Chance</div>
+                </div>`
+                    : ""
+                }
+                ${
+                  detail.grade_slope != null
+                    ? `<div class="modal-stat ${
+                        detail.declining_grades ? "danger" : ""
+                      }" title="Change per assignment over the last five grades (spread ${Math.round(
+                        detail.grade_volatility * 100
+                      )} points)">
+                    <div class="value">${detail.grade_slope > 0 ? "+" : ""}${(
+                      detail.grade_slope * 100
+                    ).toFixed(1)}</div>
+                    <div class="label">Grade Trend</div>
+                </div>`
+                    : ""
+                }
+                <div class="modal-stat" title="Longest gap: ${
+                  detail.longest_gap_days ?? "-"
+                } days">
+                    <div class="value">${detail.active_week_streak}w</div>
+                    <div class="label">Active Streak</div>
                 </div>
+                ${
+                  detail.assignments_remaining > 0
+                    ? `<div class="modal-stat ${
+                        detail.projected_finish == null ||
+                        detail.projected_days_past_end > 0
+                          ? "danger"
+                          : ""
+                      }" title="${detail.recent_velocity.toFixed(
+                        1
+                      )} assignments/week recently, ${
+                        detail.assignments_remaining
+                      } remaining">
+                    <div class="value">${
+                      detail.projected_finish
+                        ? parseLocalDate(
+                            detail.projected_finish
+                          ).toLocaleDateString()
+                        : "Stalled"
+                    }</div>
+                    <div class="label">Projected Finish</div>
+                </div>`
+                    : ""
+                }
             `;
 
   // Load assignments and timeline in parallel
@@ -683,30 +764,11 @@ async function openStudentModal(studentId) {
       studentProgressChart.destroy();
     }
 
-    // Helper to convert ISO week to date range
-    function getWeekDateRange(isoWeek) {
-      const [year, week] = isoWeek.split("-");
-      const weekNum = parseInt(week);
-
-      const jan1 = new Date(parseInt(year), 0, 1);
-      const daysToMonday = (8 - jan1.getDay()) % 7;
-      const firstMonday = new Date(jan1);
-      firstMonday.setDate(jan1.getDate() + daysToMonday);
-
-      const weekStart = new Date(firstMonday);
-      weekStart.setDate(firstMonday.getDate() + (weekNum - 1) * 7);
-      const weekEnd = new Date(weekStart);
-      weekEnd.setDate(weekStart.getDate() + 6);
-
-      const fmt = (d) =>
-        d.toLocaleDateString("en-US", { month: "short", day: "numeric" });
-      return `${fmt(weekStart)} - ${fmt(weekEnd)}`;
-    }
 
     studentProgressChart = new Chart(ctx, {
       type: "bar",
       data: {
-        labels: timeline.map((d) => getWeekDateRange(d.week)),
+        labels: timeline.map((d) => calendarWeekLabel(d)),
         datasets: [
           {
             label: "Weekly Completions",
@@ -985,33 +1047,11 @@ async function loadProgressChart() {
 
   const ctx = document.getElementById("progress-chart").getContext("2d");
 
-  // Helper to convert ISO week (YYYY-WW) to date range
-  function getWeekDateRange(isoWeek) {
-    const [year, week] = isoWeek.split("-");
-    const weekNum = parseInt(week);
-
-    // Get first day of year
-    const jan1 = new Date(parseInt(year), 0, 1);
-    // Find first Monday of the year
-    const daysToMonday = (8 - jan1.getDay()) % 7;
-    const firstMonday = new Date(jan1);
-    firstMonday.setDate(jan1.getDate() + daysToMonday);
-
-    // Calculate week start
-    const weekStart = new Date(firstMonday);
-    weekStart.setDate(firstMonday.getDate() + (weekNum - 1) * 7);
-    const weekEnd = new Date(weekStart);
-    weekEnd.setDate(weekStart.getDate() + 6);
-
-    const fmt = (d) =>
-      d.toLocaleDateString("en-US", { month: "short", day: "numeric" });
-    return `${fmt(weekStart)} - ${fmt(weekEnd)}`;
-  }
 
   new Chart(ctx, {
     type: "line",
     data: {
-      labels: data.map((d) => getWeekDateRange(d.week)),
+      labels: data.map((d) => calendarWeekLabel(d)),
       datasets: [
         {
           label: "Weekly Completions",
@@ -1069,33 +1109,47 @@ async function loadProgressChart() {
         if (elements.length > 0) {
           const index = elements[0].index;
           const weekData = data[index];
-          openWeekModal(weekData.week);
+          openWeekModal(weekData);
         }
       },
     },
   });
 }
 
-async function openWeekModal(isoWeek) {
+// "YYYY-MM-DD" as a local date (new Date() would read it as UTC midnight)
+function formatMinutes(minutes) {
+  const rounded = Math.round(minutes);
+  if (rounded < 60) return `${rounded}m`;
+  return `${Math.floor(rounded / 60)}h ${rounded % 60}m`;
+}
+
+function parseLocalDate(value) {
+  const [year, month, day] = value.split("-").map(Number);
+  return new Date(year, month - 1, day);
+}
+
+// Chart label for a point in a weekly series: class week plus its dates
+function calendarWeekLabel(point) {
+  const weekStart = parseLocalDate(point.week_start);
+  const weekEnd = new Date(weekStart);
+  weekEnd.setDate(weekStart.getDate() + 6);
+  const fmt = (d) =>
+    d.toLocaleDateString("en-US", { month: "short", day: "numeric" });
+  return [point.week, `${fmt(weekStart)} - ${fmt(weekEnd)}`];
+}
+
+async function openWeekModal(weekData) {
   const modal = document.getElementById("week-modal");
   modal.style.display = "flex";
 
-  // Convert ISO week to date range
-  const [year, week] = isoWeek.split('-');
-  const weekNum = parseInt(week);
-  const jan1 = new Date(parseInt(year), 0, 1);
-  const daysToMonday = (8 - jan1.getDay()) % 7;
-  const firstMonday = new Date(jan1);
-  firstMonday.setDate(jan1.getDate() + daysToMonday);
-  const weekStart = new Date(firstMonday);
-  weekStart.setDate(firstMonday.getDate() + (weekNum - 1) * 7);
+  const weekStart = parseLocalDate(weekData.week_start);
   const weekEnd = new Date(weekStart);
   weekEnd.setDate(weekStart.getDate() + 6);
-  
+
   const fmt = (d) => d.toLocaleDateString('en-US', { month: 'short', day: 'numeric', year: 'numeric' });
   const dateRange = `${fmt(weekStart)} - ${fmt(weekEnd)}`;
 
-  document.getElementById("modal-week-name").textContent = `Week ${weekNum} (${dateRange})`;
+  document.getElementById("modal-week-name").textContent = `${weekData.week} (${dateRange})`;
   document.getElementById("modal-week-info").textContent = "Loading...";
 
   // Fetch progressions for this week
@@ -1120,8 +1174,7 @@ async function openWeekModal(isoWeek) {
   const weekProgressions = progressions.filter(p => {
     if (!p.completed_at) return false;
     if (filteredStudentIds && !filteredStudentIds.has(p.student_id)) return false;
-    const completedWeek = new Date(p.completed_at).toISOString().split('T')[0];
-    const completedDate = new Date(completedWeek);
+    const completedDate = parseLocalDate(p.completed_at.slice(0, 10));
     return completedDate >= weekStart && completedDate <= weekEnd;
   });
 
@@ -1596,27 +1649,11 @@ async function loadVelocityChart() {
     existingChart.destroy();
   }
 
-  // Helper to convert ISO week to date range
-  function getWeekDateRange(isoWeek) {
-    const [year, week] = isoWeek.split("-");
-    const weekNum = parseInt(week);
-    const jan1 = new Date(parseInt(year), 0, 1);
-    const daysToMonday = (8 - jan1.getDay()) % 7;
-    const firstMonday = new Date(jan1);
-    firstMonday.setDate(jan1.getDate() + daysToMonday);
-    const weekStart = new Date(firstMonday);
-    weekStart.setDate(firstMonday.getDate() + (weekNum - 1) * 7);
-    const weekEnd = new Date(weekStart);
-    weekEnd.setDate(weekStart.getDate() + 6);
-    const fmt = (d) =>
-      d.toLocaleDateString("en-US", { month: "short", day: "numeric" });
-    return `${fmt(weekStart)} - ${fmt(weekEnd)}`;
-  }
 
   new Chart(ctx, {
     type: "line",
     data: {
-      labels: data.map(d => getWeekDateRange(d.week)),
+      labels: data.map(d => calendarWeekLabel(d)),
       datasets: [
         {
           label: "Avg Assignments/Student/Week",
@@ -46,24 +46,16 @@ export function formatDate(timestamp) {
 }
 
 /**
- * Convert ISO week (YYYY-WW) to date range string
- * @param {string} isoWeek - ISO week format (e.g., "2025-35")
- * @returns {string} Date range (e.g., "Aug 25 - Aug 31")
+ * Chart label for a point in a weekly series
+ * @param {{week: string, week_start: string}} point - e.g. {week: "Week 3", week_start: "2025-09-15"}
+ * @returns {string[]} Class week and date range (e.g., ["Week 3", "Sep 15 - Sep 21"])
  */
-export function getWeekDateRange(isoWeek) {
-  const [year, week] = isoWeek.split('-');
-  const weekNum = parseInt(week);
-  
-  const jan1 = new Date(parseInt(year), 0, 1);
-  const daysToMonday = (8 - jan1.getDay()) % 7;
-  const firstMonday = new Date(jan1);
-  firstMonday.setDate(jan1.getDate() + daysToMonday);
-  
-  const weekStart = new Date(firstMonday);
-  weekStart.setDate(firstMonday.getDate() + (weekNum - 1) * 7);
+export function calendarWeekLabel(point) {
+  const [year, month, day] = point.week_start.split('-').map(Number);
+  const weekStart = new Date(year, month - 1, day);
   const weekEnd = new Date(weekStart);
   weekEnd.setDate(weekStart.getDate() + 6);
-  
+
   const fmt = (d) => d.toLocaleDateString('en-US', { month: 'short', day: 'numeric' });
-  return `${fmt(weekStart)} - ${fmt(weekEnd)}`;
+  return [point.week, `${fmt(weekStart)} - ${fmt(weekEnd)}`];
 }
@@ -0,0 +1,143 @@
+use cohort_tracker::db::{AnalyticsFilter, StudentStatus};
+
+mod common;
+use common::{date, TestDb, CLASS};
+
+/// John finishes three projects between January and March; Jane does one in
+/// January. John's second project and Jane's are reviewed.
+fn class_over_time() -> TestDb {
+    let db = TestDb::new();
+    db.student("s1", "John", "Doe")
+        .student("s2", "Jane", "Smith");
+    for id in ["a1", "a2", "a3"] {
+        db.assignment(id, id, "project", Some("Basics"));
+    }
+    for (id, student, assignment, completed_at, reviewed_at) in [
+        ("p1", "s1", "a1", "2024-01-05T10:00:00", None),
+        (
+            "p2",
+            "s1",
+            "a2",
+            "2024-01-20T10:00:00",
+            Some("2024-01-28T10:00:00"),
+        ),
+        ("p3", "s1", "a3", "2024-03-01T10:00:00", None),
+        (
+            "p4",
+            "s2",
+            "a1",
+            "2024-01-06T10:00:00",
+            Some("2024-01-07T10:00:00"),
+        ),
+    ] {
+        db.progression(
+            id,
+            student,
+            assignment,
+            Some(0.9),
+            completed_at,
+            completed_at,
+            reviewed_at,
+        );
+    }
+    db
+}
+
+fn midpoint() -> AnalyticsFilter {
+    AnalyticsFilter {
+        as_of: Some(date("2024-01-25")),
+        ..Default::default()
+    }
+}
+
+fn with_status(db: &TestDb, status: &str, as_of: &AnalyticsFilter) -> Vec<String> {
+    let filter = AnalyticsFilter {
+        status: Some(StudentStatus::parse(status).unwrap()),
+        ..as_of.clone()
+    };
+    db.get_student_health_filtered(CLASS, &filter)
+        .unwrap()
+        .into_iter()
+        .map(|h| h.student_id)
+        .collect()
+}
+
+#[test]
+fn test_as_of_ignores_later_completions() {
+    let db = class_over_time();
+    let summary = db
+        .get_progress_summary_filtered(CLASS, &midpoint())
+        .unwrap();
+    assert_eq!(summary.total_progressions, 3);
+
+    let health = db.get_student_health_filtered(CLASS, &midpoint()).unwrap();
+    let s1 = health.iter().find(|h| h.student_id == "s1").unwrap();
+    assert_eq!(s1.completed, 2);
+}
+
+#[test]
+fn test_as_of_measures_inactivity_from_that_day() {
+    let db = class_over_time();
+    let activity = db
+        .get_student_activity_filtered(CLASS, &midpoint())
+        .unwrap();
+    let days = |id: &str| {
+        activity
+            .iter()
+            .find(|a| a.student_id == id)
+            .unwrap()
+            .days_inactive
+    };
+    assert_eq!(days("s1"), Some(5));
+    assert_eq!(days("s2"), Some(19));
+}
+
+#[test]
+fn test_as_of_status_is_the_status_on_that_day() {
+    let db = class_over_time();
+    // John has finished now, but was still active at the midpoint
+    assert_eq!(
+        with_status(&db, "completed", &AnalyticsFilter::default()),
+        ["s1"]
+    );
+    assert!(with_status(&db, "completed", &midpoint()).is_empty());
+    assert_eq!(with_status(&db, "active", &midpoint()), ["s1"]);
+    assert_eq!(with_status(&db, "inactive", &midpoint()), ["s2"]);
+}
+
+#[test]
+fn test_as_of_treats_later_reviews_as_pending() {
+    let db = class_over_time();
+    let pending = db.get_pending_reviews_filtered(CLASS, &midpoint()).unwrap();
+    let ids: Vec<&str> = pending.iter().map(|p| p.progression_id.as_str()).collect();
+    assert_eq!(ids, ["p1", "p2"]);
+    assert!((pending[1].waiting_hours - (5.0 * 24.0 + 13.0 + 59.0 / 60.0)).abs() < 0.1);
+}
+
+#[test]
+fn test_as_of_completion_metrics_count_completions_up_to_that_day() {
+    let db = class_over_time();
+    let count = |filter: &AnalyticsFilter, id: &str| {
+        db.get_completion_metrics(CLASS, filter)
+            .unwrap()
+            .assignments
+            .into_iter()
+            .find(|a| a.assignment_id == id)
+            .unwrap()
+            .completions
+    };
+    assert_eq!(count(&AnalyticsFilter::default(), "a3"), 1);
+    assert_eq!(count(&midpoint(), "a3"), 0);
+    assert_eq!(count(&midpoint(), "a1"), 2);
+
+    let metrics = db.get_completion_metrics(CLASS, &midpoint()).unwrap();
+    assert_eq!(metrics.assignments_with_zero_completions, 1);
+    assert_eq!(metrics.avg_students_per_assignment, 1.0);
+
+    let early = AnalyticsFilter {
+        as_of: Some(date("2024-01-01")),
+        ..Default::default()
+    };
+    let metrics = db.get_completion_metrics(CLASS, &early).unwrap();
+    assert_eq!(metrics.assignments_with_zero_completions, 3);
+}
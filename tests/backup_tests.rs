@@ -0,0 +1,213 @@
+mod common;
+
+use cohort_tracker::config::BackupConfig;
+use cohort_tracker::db::{
+    backup_dir, restore_database, rotate_backup, validate_backup, Database, SCHEMA_VERSION,
+};
+use common::TestDb;
+use tempfile::NamedTempFile;
+
+/// A database left behind by an older build, so opening it migrates.
+fn outdated_database() -> TestDb {
+    let test = TestDb::new();
+    test.student("s1", "John", "Doe");
+    sqlite::open(test.path())
+        .unwrap()
+        .execute("PRAGMA user_version = 1")
+        .unwrap();
+    test
+}
+
+fn backups(test: &TestDb) -> Vec<String> {
+    let dir = backup_dir(&test.path());
+    if !dir.exists() {
+        return Vec::new();
+    }
+    let mut names: Vec<String> = std::fs::read_dir(dir)
+        .unwrap()
+        .map(|e| e.unwrap().file_name().to_string_lossy().to_string())
+        .collect();
+    names.sort();
+    names
+}
+
+#[test]
+fn test_backup_and_restore_roundtrip() {
+    let test = TestDb::new();
+    test.student("s1", "John", "Doe");
+
+    let backup_path = test.dir.path().join("backup.db");
+    test.backup_to(backup_path.to_str().unwrap()).unwrap();
+    assert_eq!(
+        validate_backup(backup_path.to_str().unwrap()).unwrap(),
+        SCHEMA_VERSION
+    );
+
+    // Changes after the backup are rolled back by the restore
+    test.student("s2", "Jane", "Smith");
+    let TestDb { db, dir } = test;
+    drop(db);
+    let db_path = dir.path().join("tracker.db");
+
+    let safety = restore_database(backup_path.to_str().unwrap(), &db_path, 5).unwrap();
+    assert!(safety.unwrap().exists());
+
+    let db = Database::new(db_path.to_str().unwrap()).unwrap();
+    assert_eq!(db.get_student_count().unwrap(), 1);
+}
+
+/// A backup of a one-student database, with a second student added since.
+fn changed_since_backup() -> (TestDb, std::path::PathBuf) {
+    let test = TestDb::new();
+    test.student("s1", "John", "Doe");
+    let backup_path = test.dir.path().join("backup.db");
+    test.backup_to(backup_path.to_str().unwrap()).unwrap();
+    test.student("s2", "Jane", "Smith");
+    (test, backup_path)
+}
+
+#[test]
+fn test_restore_refuses_while_database_is_in_use() {
+    let (test, backup_path) = changed_since_backup();
+    // Another process is halfway through a write, so its journal is live
+    let writer = sqlite::open(test.path()).unwrap();
+    writer
+        .execute("BEGIN IMMEDIATE; DELETE FROM students WHERE id = 's2'")
+        .unwrap();
+    assert!(test.dir.path().join("tracker.db-journal").exists());
+
+    let err = restore_database(backup_path.to_str().unwrap(), &test.path(), 5).unwrap_err();
+    assert!(err.to_string().contains("still in use"));
+    writer.execute("ROLLBACK").unwrap();
+    assert_eq!(test.get_student_count().unwrap(), 2);
+    assert!(!test.dir.path().join("tracker.restoring").exists());
+}
+
+#[test]
+fn test_restore_removes_journals_left_by_a_deleted_database() {
+    let (test, backup_path) = changed_since_backup();
+    let TestDb { db, dir } = test;
+    drop(db);
+    let db_path = dir.path().join("tracker.db");
+    std::fs::remove_file(&db_path).unwrap();
+    let journal = dir.path().join("tracker.db-journal");
+    std::fs::write(&journal, "stale").unwrap();
+
+    let safety = restore_database(backup_path.to_str().unwrap(), &db_path, 5).unwrap();
+    assert!(safety.is_none());
+    assert!(!journal.exists());
+    let db = Database::new(db_path.to_str().unwrap()).unwrap();
+    assert_eq!(db.get_student_count().unwrap(), 1);
+}
+
+#[test]
+fn test_validate_backup_rejects_non_database() {
+    let temp_file = NamedTempFile::new().unwrap();
+    std::fs::write(temp_file.path(), "not a database").unwrap();
+
+    assert!(validate_backup(temp_file.path().to_str().unwrap()).is_err());
+}
+
+#[test]
+fn test_rotate_backup_keeps_newest() {
+    let test = TestDb::new();
+
+    // Taken back to back, so several share a timestamp
+    let taken: Vec<String> = (0..4)
+        .map(|_| {
+            let path = rotate_backup(&test, &test.path(), "manual", 2).unwrap();
+            path.file_name().unwrap().to_string_lossy().to_string()
+        })
+        .collect();
+
+    assert_eq!(backups(&test), taken[2..].to_vec());
+}
+
+#[test]
+fn test_backup_names_sort_in_the_order_taken() {
+    let test = TestDb::new();
+
+    let reasons = ["pre-restore", "manual", "auto", "pre-full-sync"];
+    let taken: Vec<String> = reasons
+        .iter()
+        .map(|reason| {
+            let path = rotate_backup(&test, &test.path(), reason, 10).unwrap();
+            path.file_name().unwrap().to_string_lossy().to_string()
+        })
+        .collect();
+
+    let mut sorted = taken.clone();
+    sorted.sort();
+    assert_eq!(sorted, taken);
+}
+
+#[test]
+fn test_open_backs_up_before_migrating() {
+    let test = outdated_database();
+    let config = BackupConfig::default();
+
+    let (db, backup) = Database::open(test.path().to_str().unwrap(), &config).unwrap();
+    let backup = backup.unwrap();
+    assert!(backup.to_string_lossy().contains("pre-migration"));
+    // The backup holds the database as it was before the migration
+    assert_eq!(validate_backup(backup.to_str().unwrap()).unwrap(), 1);
+    assert_eq!(db.schema_version().unwrap(), SCHEMA_VERSION);
+}
+
+#[test]
+fn test_open_skips_backup_when_disabled() {
+    let test = outdated_database();
+    let config = BackupConfig {
+        auto: false,
+        ..Default::default()
+    };
+
+    let (db, backup) = Database::open(test.path().to_str().unwrap(), &config).unwrap();
+    assert!(backup.is_none());
+    assert!(backups(&test).is_empty());
+    assert_eq!(db.schema_version().unwrap(), SCHEMA_VERSION);
+}
+
+#[test]
+fn test_open_prunes_migration_backups_to_configured_keep() {
+    let test = outdated_database();
+    for _ in 0..3 {
+        rotate_backup(&test, &test.path(), "pre-migration", 10).unwrap();
+    }
+    let config = BackupConfig {
+        auto: true,
+        keep: 2,
+    };
+
+    Database::open(test.path().to_str().unwrap(), &config).unwrap();
+    assert_eq!(backups(&test).len(), 2);
+}
+
+#[test]
+fn test_rotation_keeps_manual_backups() {
+    let test = TestDb::new();
+    let manual = rotate_backup(&test, &test.path(), "manual", 2).unwrap();
+    for _ in 0..3 {
+        rotate_backup(&test, &test.path(), "pre-full-sync", 2).unwrap();
+    }
+
+    let names = backups(&test);
+    assert_eq!(names.len(), 3);
+    assert!(manual.exists());
+    assert_eq!(
+        names
+            .iter()
+            .filter(|n| n.ends_with("-pre-full-sync.db"))
+            .count(),
+        2
+    );
+}
+
+#[test]
+fn test_open_current_database_takes_no_backup() {
+    let test = TestDb::new();
+
+    let (_, backup) =
+        Database::open(test.path().to_str().unwrap(), &BackupConfig::default()).unwrap();
+    assert!(backup.is_none());
+}
@@ -0,0 +1,177 @@
+use cohort_tracker::db::CalendarSettings;
+use cohort_tracker::models::Class;
+
+mod common;
+use common::{date, TestDb};
+
+/// Four four-week runs of module 1, October's still active, plus a module 2
+/// class from a different course. Each run has two lessons and one student.
+fn four_runs() -> TestDb {
+    let db = TestDb::new();
+    let runs = [
+        (
+            "jan",
+            "2024-01-01",
+            "2024-01-28",
+            false,
+            vec!["2024-01-02", "2024-01-09"],
+        ),
+        (
+            "apr",
+            "2024-04-01",
+            "2024-04-28",
+            false,
+            vec!["2024-04-09", "2024-04-23"],
+        ),
+        ("jul", "2024-07-01", "2024-07-28", false, vec!["2024-07-02"]),
+        ("oct", "2024-10-07", "2024-11-03", true, vec!["2024-10-22"]),
+    ];
+    for (term, start, end, is_active, completions) in &runs {
+        let class_id = format!("c-{}", term);
+        insert_class(
+            &db,
+            &class_id,
+            &format!("web-module-1-{}", term),
+            *is_active,
+        );
+        db.set_calendar_settings(
+            &class_id,
+            &CalendarSettings {
+                start_date: Some(date(start)),
+                end_date: Some(date(end)),
+                break_weeks: Vec::new(),
+            },
+        )
+        .unwrap();
+        let student_id = format!("{}-s1", class_id);
+        db.student_in(&class_id, &student_id, "Ann", "Lee");
+        for (i, name) in ["Lesson 1", "Lesson 2"].iter().enumerate() {
+            db.insert_assignment(
+                &format!("{}-a{}", class_id, i),
+                &class_id,
+                name,
+                "lesson",
+                None,
+            )
+            .unwrap();
+        }
+        for (i, day) in completions.iter().enumerate() {
+            db.insert_progression(
+                &format!("{}-p{}", class_id, i),
+                &class_id,
+                &student_id,
+                &format!("{}-a{}", class_id, i),
+                Some(0.8),
+                &format!("{}T09:00:00", day),
+                &format!("{}T10:00:00", day),
+                None,
+            )
+            .unwrap();
+        }
+    }
+    insert_class(&db, "c-other", "web-module-2-oct", true);
+    db
+}
+
+fn insert_class(db: &TestDb, id: &str, friendly_id: &str, is_active: bool) {
+    db.insert_class(&Class {
+        id: id.to_string(),
+        name: friendly_id.to_string(),
+        friendly_id: friendly_id.to_string(),
+        is_active,
+        synced_at: None,
+    })
+    .unwrap();
+}
+
+fn run_count(db: &TestDb, class_id: &str) -> usize {
+    db.get_class_course_info(class_id)
+        .unwrap()
+        .unwrap()
+        .runs
+        .len()
+}
+
+#[test]
+fn test_course_runs_come_from_the_friendly_id() {
+    let db = four_runs();
+    let info = db.get_class_course_info("c-oct").unwrap().unwrap();
+    assert_eq!(info.course, "web-module-1");
+    assert!(!info.explicit);
+    let ids: Vec<&str> = info.runs.iter().map(|r| r.class_id.as_str()).collect();
+    assert_eq!(ids, ["c-jan", "c-apr", "c-jul", "c-oct"]);
+    assert_eq!(info.runs[0].term.as_deref(), Some("jan"));
+}
+
+#[test]
+fn test_benchmark_curves_by_class_week() {
+    let db = four_runs();
+    let benchmark = db.get_cohort_benchmark("c-oct").unwrap().unwrap();
+    assert_eq!(benchmark.previous_class_id.as_deref(), Some("c-jul"));
+    assert_eq!(benchmark.curves[0].completion_pct, [0.5, 1.0, 1.0, 1.0]);
+    assert_eq!(benchmark.curves[1].completion_pct, [0.0, 0.5, 0.5, 1.0]);
+    assert_eq!(benchmark.weeks.len(), 4);
+}
+
+#[test]
+fn test_benchmark_compares_with_earlier_runs() {
+    let db = four_runs();
+    let benchmark = db.get_cohort_benchmark("c-oct").unwrap().unwrap();
+
+    let week1 = &benchmark.weeks[0];
+    assert_eq!(week1.current, Some(0.0));
+    assert_eq!(week1.previous, Some(0.5));
+    assert_eq!(week1.historical_runs, 3);
+    assert_eq!(week1.median, Some(0.5));
+    assert_eq!(week1.p25, Some(0.25));
+    assert_eq!(week1.p75, Some(0.5));
+
+    let week3 = &benchmark.weeks[2];
+    assert_eq!(week3.current, Some(0.5));
+    assert_eq!(week3.median, Some(0.5));
+    assert_eq!(week3.p75, Some(0.75));
+}
+
+#[test]
+fn test_earliest_run_has_nothing_to_compare_with() {
+    let db = four_runs();
+    let first = db.get_cohort_benchmark("c-jan").unwrap().unwrap();
+    assert_eq!(first.previous_class_id, None);
+    assert!(first.weeks.iter().all(|w| w.median.is_none()));
+}
+
+#[test]
+fn test_cohort_difficulty_per_run() {
+    let db = four_runs();
+    let difficulty = db.get_cohort_difficulty("c-oct").unwrap().unwrap();
+    let names: Vec<&str> = difficulty.iter().map(|a| a.name.as_str()).collect();
+    assert_eq!(names, ["Lesson 1", "Lesson 2"]);
+    assert_eq!(difficulty[0].runs.len(), 4);
+    assert_eq!(difficulty[0].runs[0].class_id, "c-jan");
+}
+
+#[test]
+fn test_explicit_course_overrides_the_friendly_id() {
+    let db = four_runs();
+    db.set_class_course("c-oct", Some("web-fast-track"))
+        .unwrap();
+    let info = db.get_class_course_info("c-oct").unwrap().unwrap();
+    assert!(info.explicit);
+    assert_eq!(info.runs.len(), 1);
+
+    db.set_class_course("c-oct", None).unwrap();
+    assert_eq!(run_count(&db, "c-oct"), 4);
+}
+
+#[test]
+fn test_blank_course_is_rejected() {
+    let db = four_runs();
+    assert!(db.set_class_course("c-oct", Some("  ")).is_err());
+    assert_eq!(run_count(&db, "c-oct"), 4);
+}
+
+#[test]
+fn test_benchmark_for_unknown_class() {
+    let db = four_runs();
+    assert!(db.get_cohort_benchmark("missing").unwrap().is_none());
+}
@@ -0,0 +1,136 @@
+use cohort_tracker::calendar::ClassCalendar;
+use cohort_tracker::db::CalendarSettings;
+
+mod common;
+use common::{date, TestDb, CLASS};
+
+/// One student with a completion before the start, in weeks 1 and 2, in
+/// the break week and two in week 3 of a calendar starting 2024-01-01.
+fn class_with_completions() -> TestDb {
+    let db = TestDb::new();
+    db.student("s1", "John", "Doe")
+        .assignment("a1", "Test", "lesson", None)
+        .complete("p0", "s1", "a1", Some(0.8), "2023-12-30T10:00:00")
+        .complete("p1", "s1", "a1", Some(0.8), "2024-01-03T10:00:00")
+        .complete("p2", "s1", "a1", Some(0.8), "2024-01-10T10:00:00")
+        .complete("p3", "s1", "a1", Some(0.8), "2024-01-17T10:00:00")
+        .complete("p4", "s1", "a1", Some(0.8), "2024-01-24T10:00:00")
+        .complete("p5", "s1", "a1", Some(0.8), "2024-01-25T10:00:00");
+    db
+}
+
+fn set_calendar(db: &TestDb) {
+    db.set_calendar_settings(
+        CLASS,
+        &CalendarSettings {
+            start_date: Some(date("2024-01-01")),
+            end_date: Some(date("2024-02-02")),
+            break_weeks: vec![date("2024-01-18")],
+        },
+    )
+    .unwrap();
+}
+
+#[test]
+fn test_weeks_without_a_start_count_from_the_first_completion_monday() {
+    let db = class_with_completions();
+    let weekly = db.get_progress_over_time(CLASS, None).unwrap();
+    assert_eq!(weekly[0].week, "Week 1");
+    assert_eq!(weekly[0].week_start, "2023-12-25");
+}
+
+#[test]
+fn test_progress_over_time_uses_class_weeks_and_breaks() {
+    let db = class_with_completions();
+    set_calendar(&db);
+
+    let weekly = db.get_progress_over_time(CLASS, None).unwrap();
+    let labels: Vec<&str> = weekly.iter().map(|w| w.week.as_str()).collect();
+    assert_eq!(
+        labels,
+        vec![
+            "Before start",
+            "Week 1",
+            "Week 2",
+            "Break (after Week 2)",
+            "Week 3"
+        ]
+    );
+    assert!(weekly[3].is_break);
+    assert_eq!(weekly[4].week_number, 3);
+    assert_eq!(weekly[4].week_start, "2024-01-22");
+    assert_eq!(weekly[4].completed, 2);
+    assert_eq!(weekly[4].cumulative, 6);
+}
+
+#[test]
+fn test_velocity_and_timeline_use_class_weeks() {
+    let db = class_with_completions();
+    set_calendar(&db);
+
+    let velocity = db.get_velocity_stats(CLASS, None).unwrap();
+    assert_eq!(velocity.len(), 5);
+    assert_eq!(velocity[4].week, "Week 3");
+
+    let timeline = db.get_student_progress_timeline(CLASS, "s1").unwrap();
+    assert_eq!(timeline[1].week, "Week 1");
+}
+
+#[test]
+fn test_syncing_the_class_row_keeps_the_calendar() {
+    let db = class_with_completions();
+    set_calendar(&db);
+    db.class(CLASS, "m1");
+
+    let info = db.get_class_calendar_info(CLASS).unwrap();
+    assert_eq!(info.start_date.as_deref(), Some("2024-01-01"));
+    assert_eq!(info.break_weeks, vec!["2024-01-15"]);
+    assert!(!info.inferred_start);
+    assert_eq!(info.weeks.len(), 5);
+    assert_eq!(info.weeks[4].label, "Week 4");
+}
+
+#[test]
+fn test_end_before_start_is_rejected() {
+    let db = TestDb::new();
+    let result = db.set_calendar_settings(
+        CLASS,
+        &CalendarSettings {
+            start_date: Some(date("2024-02-01")),
+            end_date: Some(date("2024-01-01")),
+            break_weeks: vec![],
+        },
+    );
+    assert!(result.is_err());
+}
+
+#[test]
+fn test_breaks_snap_to_class_weeks_when_the_start_is_not_a_monday() {
+    let db = TestDb::new();
+    // Wednesday start: the third class week runs Wed 15th to Tue 21st
+    db.set_calendar_settings(
+        CLASS,
+        &CalendarSettings {
+            start_date: Some(date("2025-01-01")),
+            end_date: Some(date("2025-02-28")),
+            break_weeks: vec![date("2025-01-20")],
+        },
+    )
+    .unwrap();
+
+    let info = db.get_class_calendar_info(CLASS).unwrap();
+    assert_eq!(info.break_weeks, vec!["2025-01-15"]);
+    assert!(!info.weeks[1].is_break);
+    assert!(info.weeks[2].is_break);
+    assert_eq!(info.weeks[2].start_date, "2025-01-15");
+    assert_eq!(info.weeks[3].label, "Week 3");
+}
+
+#[test]
+fn test_calendar_break_index_with_a_non_monday_start() {
+    let calendar = ClassCalendar::new(date("2025-01-01"), None, &[date("2025-01-15")]);
+    assert_eq!(calendar.week_index(date("2025-01-15")), 2);
+    assert!(calendar.week_of(date("2025-01-21")).is_break);
+    assert!(!calendar.week_of(date("2025-01-14")).is_break);
+    assert_eq!(calendar.week_of(date("2025-01-22")).label, "Week 3");
+}
@@ -0,0 +1,116 @@
+//! Shared fixtures for the database tests.
+#![allow(dead_code)]
+
+use cohort_tracker::db::Database;
+use cohort_tracker::models::Class;
+use std::ops::Deref;
+use tempfile::TempDir;
+
+pub const CLASS: &str = "class1";
+
+/// A fresh database in its own temporary directory, deleted on drop.
+pub struct TestDb {
+    pub db: Database,
+    pub dir: TempDir,
+}
+
+impl Deref for TestDb {
+    type Target = Database;
+
+    fn deref(&self) -> &Database {
+        &self.db
+    }
+}
+
+impl TestDb {
+    pub fn new() -> Self {
+        let dir = tempfile::tempdir().unwrap();
+        let db = Database::new(dir.path().join("tracker.db").to_str().unwrap()).unwrap();
+        Self { db, dir }
+    }
+
+    pub fn path(&self) -> std::path::PathBuf {
+        self.dir.path().join("tracker.db")
+    }
+
+    pub fn class(&self, id: &str, friendly_id: &str) -> &Self {
+        self.db
+            .insert_class(&Class {
+                id: id.to_string(),
+                name: friendly_id.to_string(),
+                friendly_id: friendly_id.to_string(),
+                is_active: true,
+                synced_at: None,
+            })
+            .unwrap();
+        self
+    }
+
+    /// A student in `CLASS` with an email made from the id.
+    pub fn student(&self, id: &str, first: &str, last: &str) -> &Self {
+        self.student_in(CLASS, id, first, last)
+    }
+
+    pub fn student_in(&self, class_id: &str, id: &str, first: &str, last: &str) -> &Self {
+        self.db
+            .insert_student(id, class_id, first, last, &format!("{}@example.com", id))
+            .unwrap();
+        self
+    }
+
+    pub fn assignment(&self, id: &str, name: &str, kind: &str, section: Option<&str>) -> &Self {
+        self.db
+            .insert_assignment(id, CLASS, name, kind, section)
+            .unwrap();
+        self
+    }
+
+    /// A completion in `CLASS` started and finished at `at`.
+    pub fn complete(
+        &self,
+        id: &str,
+        student: &str,
+        assignment: &str,
+        grade: Option<f64>,
+        at: &str,
+    ) -> &Self {
+        self.progression(id, student, assignment, grade, at, at, None)
+    }
+
+    #[allow(clippy::too_many_arguments)]
+    pub fn progression(
+        &self,
+        id: &str,
+        student: &str,
+        assignment: &str,
+        grade: Option<f64>,
+        started_at: &str,
+        completed_at: &str,
+        reviewed_at: Option<&str>,
+    ) -> &Self {
+        self.db
+            .insert_progression(
+                id,
+                CLASS,
+                student,
+                assignment,
+                grade,
+                started_at,
+                completed_at,
+                reviewed_at,
+            )
+            .unwrap();
+        self
+    }
+}
+
+/// `days` days before now as a naive UTC timestamp.
+pub fn days_ago(days: i64) -> String {
+    (chrono::Utc::now() - chrono::Duration::days(days))
+        .format("%Y-%m-%dT%H:%M:%S")
+        .to_string()
+}
+
+pub fn date(s: &str) -> chrono::NaiveDate {
+    chrono::NaiveDate::parse_from_str(s, "%Y-%m-%d").unwrap()
+}
@@ -1,5 +1,6 @@
-use cohort_tracker::config::Config;
+use cohort_tracker::config::{Config, Paths};
 use std::fs;
+use std::path::PathBuf;
 use tempfile::NamedTempFile;
 
 #[test]
@@ -9,6 +10,13 @@ fn test_config_serialization() {
         password: "password123".to_string(),
         api_base: "https://api.openclass.ai".to_string(),
         check_for_updates: true,
+        database_path: None,
+        backup: Default::default(),
+        import: Default::default(),
+        risk: Default::default(),
+        pathways: Default::default(),
+        notifications: Default::default(),
+        reviews: Default::default(),
     };
 
     let toml_str = toml::to_string(&config).unwrap();
@@ -26,6 +34,13 @@ fn test_config_save_and_load() {
         password: "password123".to_string(),
         api_base: "https://api.openclass.ai".to_string(),
         check_for_updates: true,
+        database_path: None,
+        backup: Default::default(),
+        import: Default::default(),
+        risk: Default::default(),
+        pathways: Default::default(),
+        notifications: Default::default(),
+        reviews: Default::default(),
     };
 
     let temp_file = NamedTempFile::new().unwrap();
@@ -58,3 +73,37 @@ fn test_config_load_invalid_toml() {
     let result = Config::from_file(path);
     assert!(result.is_err());
 }
+
+#[test]
+fn test_paths_use_database_path_from_config() {
+    let config = Config {
+        email: "test@example.com".to_string(),
+        password: "password123".to_string(),
+        api_base: "https://api.openclass.ai".to_string(),
+        check_for_updates: false,
+        database_path: Some("/tmp/spring-2026.db".to_string()),
+        backup: Default::default(),
+        import: Default::default(),
+        risk: Default::default(),
+        pathways: Default::default(),
+        notifications: Default::default(),
+        reviews: Default::default(),
+    };
+
+    let temp_file = NamedTempFile::new().unwrap();
+    let path = temp_file.path().to_str().unwrap();
+    config.save(path).unwrap();
+
+    let paths = Paths::resolve(Some(path), None, None).unwrap();
+    assert_eq!(paths.db, PathBuf::from("/tmp/spring-2026.db"));
+
+    // --db always wins over the config file
+    let paths = Paths::resolve(Some(path), Some("/tmp/override.db"), None).unwrap();
+    assert_eq!(paths.db, PathBuf::from("/tmp/override.db"));
+}
+
+#[test]
+fn test_paths_reject_invalid_workspace_name() {
+    assert!(Paths::resolve(None, None, Some("../escape")).is_err());
+    assert!(Paths::resolve(None, None, Some("")).is_err());
+}
@@ -0,0 +1,144 @@
+use cohort_tracker::config::RiskConfig;
+use cohort_tracker::db::{AnalyticsFilter, CalendarSettings, HealthSort};
+use cohort_tracker::models::StudentHealth;
+
+mod common;
+use common::{date, TestDb, CLASS};
+
+/// Weeks from 2024-01-01. Decline's grades fall every week, Steady's go up
+/// and down with gaps, Idle has nothing.
+fn class_with_grades() -> TestDb {
+    let db = TestDb::new();
+    db.set_calendar_settings(
+        CLASS,
+        &CalendarSettings {
+            start_date: Some(date("2024-01-01")),
+            end_date: None,
+            break_weeks: Vec::new(),
+        },
+    )
+    .unwrap();
+
+    let history = [
+        (
+            "s1",
+            "Decline",
+            vec![
+                ("2024-01-08", 0.9),
+                ("2024-01-15", 0.85),
+                ("2024-01-22", 0.8),
+                ("2024-01-29", 0.7),
+                ("2024-02-05", 0.6),
+            ],
+        ),
+        (
+            "s2",
+            "Steady",
+            vec![
+                ("2024-01-03", 0.7),
+                ("2024-01-13", 0.9),
+                ("2024-01-30", 0.8),
+            ],
+        ),
+        ("s3", "Idle", vec![]),
+    ];
+    for (student, last_name, completions) in &history {
+        db.student(student, "Test", last_name);
+        for (i, (day, grade)) in completions.iter().enumerate() {
+            let assignment = format!("a{}", i);
+            db.assignment(&assignment, &assignment, "project", None)
+                .progression(
+                    &format!("{}-{}", student, assignment),
+                    student,
+                    &assignment,
+                    Some(*grade),
+                    &format!("{}T09:00:00", day),
+                    &format!("{}T10:00:00", day),
+                    None,
+                );
+        }
+    }
+    db
+}
+
+fn health_on_feb_5(db: &TestDb) -> Vec<StudentHealth> {
+    let filter = AnalyticsFilter {
+        as_of: Some(date("2024-02-05")),
+        ..Default::default()
+    };
+    db.get_student_health_filtered(CLASS, &filter).unwrap()
+}
+
+fn get(health: &[StudentHealth], id: &str) -> StudentHealth {
+    health.iter().find(|h| h.student_id == id).unwrap().clone()
+}
+
+fn order(health: &[StudentHealth]) -> Vec<String> {
+    health.iter().map(|h| h.student_id.clone()).collect()
+}
+
+#[test]
+fn test_falling_grades_are_declining() {
+    let db = class_with_grades();
+    let s1 = get(&health_on_feb_5(&db), "s1");
+    assert!((s1.grade_slope.unwrap() + 0.075).abs() < 1e-9);
+    assert!(s1.declining_grades);
+    assert_eq!(s1.active_week_streak, 5);
+    assert_eq!(s1.longest_gap_days, Some(7));
+}
+
+#[test]
+fn test_uneven_grades_have_volatility_and_gaps() {
+    let db = class_with_grades();
+    let s2 = get(&health_on_feb_5(&db), "s2");
+    assert!((s2.grade_slope.unwrap() - 0.05).abs() < 1e-9);
+    assert!((s2.grade_volatility.unwrap() - (0.02f64 / 3.0).sqrt()).abs() < 1e-9);
+    assert!(!s2.declining_grades);
+    // Week 6 has nothing yet, so the streak counts back from week 5
+    assert_eq!(s2.active_week_streak, 1);
+    assert_eq!(s2.longest_gap_days, Some(17));
+}
+
+#[test]
+fn test_student_without_grades_has_no_consistency_metrics() {
+    let db = class_with_grades();
+    let s3 = get(&health_on_feb_5(&db), "s3");
+    assert_eq!(s3.grade_slope, None);
+    assert_eq!(s3.active_week_streak, 0);
+    assert_eq!(s3.longest_gap_days, None);
+}
+
+#[test]
+fn test_health_sorts_put_the_most_worrying_first() {
+    let db = class_with_grades();
+    let mut health = health_on_feb_5(&db);
+    HealthSort::GradeSlope.apply(&mut health);
+    assert_eq!(order(&health), ["s1", "s2", "s3"]);
+    HealthSort::LongestGap.apply(&mut health);
+    assert_eq!(order(&health), ["s2", "s1", "s3"]);
+    assert_eq!(
+        HealthSort::parse("grade-volatility").unwrap(),
+        HealthSort::GradeVolatility
+    );
+    assert!(HealthSort::parse("shoe size").is_err());
+}
+
+#[test]
+fn test_student_detail_shows_the_grade_trend() {
+    let db = class_with_grades();
+    let detail = db.get_student_detail(CLASS, "s1").unwrap().unwrap();
+    assert!(detail.declining_grades);
+    assert!((detail.grade_slope.unwrap() + 0.075).abs() < 1e-9);
+}
+
+#[test]
+fn test_grade_window_is_configurable() {
+    let mut db = class_with_grades();
+    db.db.set_risk_config(RiskConfig {
+        grade_window: 3,
+        ..Default::default()
+    });
+    // Only 0.8, 0.7, 0.6 count
+    let s1 = get(&health_on_feb_5(&db), "s1");
+    assert!((s1.grade_slope.unwrap() + 0.1).abs() < 1e-9);
+}
@@ -0,0 +1,180 @@
+use cohort_tracker::db::SyncBaseline;
+use cohort_tracker::models::SyncDigest;
+use cohort_tracker::risk::level_rank;
+
+mod common;
+use common::{days_ago, TestDb, CLASS};
+
+/// Ada, Ben and Cy with a lesson and a project, nothing completed yet.
+fn web_dev() -> TestDb {
+    let db = TestDb::new();
+    db.class(CLASS, "web-dev");
+    for (id, first) in [("s1", "Ada"), ("s2", "Ben"), ("s3", "Cy")] {
+        db.student(id, first, "Test");
+    }
+    db.assignment("a1", "Lesson 1", "lesson", None)
+        .assignment("a2", "Project 1", "project", None);
+    db
+}
+
+fn complete(
+    db: &TestDb,
+    id: &str,
+    student: &str,
+    assignment: &str,
+    days: i64,
+    reviewed: Option<&str>,
+) {
+    let at = days_ago(days);
+    db.progression(id, student, assignment, Some(0.8), &at, &at, reviewed);
+}
+
+/// Ada finished the project two days ago, Ben the lesson twenty days ago
+/// and Cy the lesson three days ago.
+fn first_completions(db: &TestDb) {
+    complete(db, "p1", "s1", "a2", 2, None);
+    complete(db, "p2", "s2", "a1", 20, None);
+    complete(db, "p3", "s3", "a1", 3, None);
+}
+
+/// The class as a sync finishing now leaves it: snapshotted and marked
+/// synced.
+fn synced(db: &TestDb) -> SyncBaseline {
+    db.record_student_snapshots(CLASS).unwrap();
+    db.update_class_sync_time(CLASS, &chrono::Utc::now().to_rfc3339())
+        .unwrap();
+    db.sync_baseline(CLASS).unwrap()
+}
+
+/// Ada's project is reviewed, Ben comes back and Cy's only completion turns
+/// out to be a month old.
+fn changes_since(db: &TestDb, baseline: &SyncBaseline) -> SyncDigest {
+    let reviewed = days_ago(0);
+    complete(db, "p1", "s1", "a2", 2, Some(&reviewed));
+    complete(db, "p4", "s2", "a2", 0, None);
+    complete(db, "p3", "s3", "a1", 30, None);
+    db.record_sync_digest(baseline).unwrap()
+}
+
+fn ids<T>(items: &[T], id: impl Fn(&T) -> &str) -> Vec<&str> {
+    items.iter().map(id).collect()
+}
+
+#[test]
+fn test_first_digest_lists_everything_without_risk_changes() {
+    let db = web_dev();
+    let empty = db.sync_baseline(CLASS).unwrap();
+    first_completions(&db);
+
+    let first = db.record_sync_digest(&empty).unwrap();
+    assert_eq!(first.class_name, "web-dev");
+    assert!(first.previous_sync_at.is_none());
+    assert_eq!(first.completions.len(), 3);
+    assert!(first.risk_increases.is_empty());
+}
+
+#[test]
+fn test_digest_without_changes_is_empty() {
+    let db = web_dev();
+    first_completions(&db);
+    let baseline = synced(&db);
+    let unchanged = db.record_sync_digest(&baseline).unwrap();
+    assert!(unchanged.previous_sync_at.is_some());
+    assert!(unchanged.is_empty());
+}
+
+#[test]
+fn test_digest_lists_new_completions_and_reviews() {
+    let db = web_dev();
+    first_completions(&db);
+    let baseline = synced(&db);
+    let digest = changes_since(&db, &baseline);
+
+    let completions: Vec<(&str, &str)> = digest
+        .completions
+        .iter()
+        .map(|c| (c.student_id.as_str(), c.assignment_name.as_str()))
+        .collect();
+    assert_eq!(completions, vec![("s2", "Project 1")]);
+    assert_eq!(ids(&digest.newly_reviewed, |r| &r.student_id), ["s1"]);
+}
+
+#[test]
+fn test_digest_lists_students_who_came_back_or_went_silent() {
+    let db = web_dev();
+    first_completions(&db);
+    let baseline = synced(&db);
+    let digest = changes_since(&db, &baseline);
+
+    assert_eq!(ids(&digest.came_back, |s| &s.student_id), ["s2"]);
+    assert_eq!(ids(&digest.went_silent, |s| &s.student_id), ["s3"]);
+    assert_eq!(digest.went_silent[0].days_inactive, Some(30));
+}
+
+#[test]
+fn test_digest_lists_higher_risk_levels() {
+    let db = web_dev();
+    first_completions(&db);
+    let baseline = synced(&db);
+    let digest = changes_since(&db, &baseline);
+
+    let s3 = digest
+        .risk_increases
+        .iter()
+        .find(|r| r.student_id == "s3")
+        .unwrap();
+    assert!(level_rank(&s3.to) > level_rank(&s3.from));
+    assert!(digest.risk_increases.iter().all(|r| r.student_id != "s2"));
+}
+
+#[test]
+fn test_digests_are_stored_newest_first() {
+    let db = web_dev();
+    first_completions(&db);
+    let baseline = synced(&db);
+    db.record_sync_digest(&baseline).unwrap();
+    let digest = changes_since(&db, &baseline);
+
+    let stored = db.get_sync_digests(CLASS, 10).unwrap();
+    assert_eq!(stored.len(), 2);
+    assert_eq!(stored[0].synced_at, digest.synced_at);
+    assert_eq!(stored[0].completions.len(), 1);
+}
+
+#[test]
+fn test_previous_sync_is_the_class_sync_time() {
+    let db = web_dev();
+    db.update_class_sync_time(CLASS, "2024-05-01T10:00:00+00:00")
+        .unwrap();
+    let digest = db
+        .record_sync_digest(&db.sync_baseline(CLASS).unwrap())
+        .unwrap();
+    assert_eq!(
+        digest.previous_sync_at.as_deref(),
+        Some("2024-05-01T10:00:00+00:00")
+    );
+}
+
+#[test]
+fn test_previous_sync_falls_back_to_sync_history() {
+    // `sync --class` records pages without touching the class row
+    let db = web_dev();
+    db.record_sync(CLASS, 0, 3).unwrap();
+    db.record_sync("other-class", 0, 3).unwrap();
+    let digest = db
+        .record_sync_digest(&db.sync_baseline(CLASS).unwrap())
+        .unwrap();
+    let previous = chrono::DateTime::parse_from_rfc3339(&digest.previous_sync_at.unwrap()).unwrap();
+    assert!((chrono::Utc::now() - previous.to_utc()).num_seconds() < 60);
+}
+
+#[test]
+fn test_snapshot_without_a_sync_is_not_a_previous_sync() {
+    let db = web_dev();
+    first_completions(&db);
+    db.record_student_snapshots(CLASS).unwrap();
+    let digest = db
+        .record_sync_digest(&db.sync_baseline(CLASS).unwrap())
+        .unwrap();
+    assert!(digest.previous_sync_at.is_none());
+}
@@ -0,0 +1,265 @@
+use chrono::{Duration, NaiveDate};
+use cohort_tracker::db::{AnalyticsFilter, CalendarSettings, StudentStatus};
+
+mod common;
+use common::{date, days_ago, TestDb, CLASS};
+
+/// Finished (East, Mon) has done everything, Recent (West, Tues) completed
+/// a lesson today, Unstarted (East, Mon) has nothing and Stalled (West,
+/// Tues) stopped in January. Basics has a lesson and a project, Advanced a
+/// lesson.
+fn four_students() -> TestDb {
+    let db = TestDb::new();
+    for (id, first, region, night) in [
+        ("s1", "Finished", "East", "Mon"),
+        ("s2", "Recent", "West", "Tues"),
+        ("s3", "Unstarted", "East", "Mon"),
+        ("s4", "Stalled", "West", "Tues"),
+    ] {
+        db.student(id, first, "Student");
+        assert!(db.set_student_night(id, CLASS, region, night).unwrap());
+    }
+    db.assignment("a1", "Lesson 1", "lesson", Some("Basics"))
+        .assignment("a2", "Project 1", "project", Some("Basics"))
+        .assignment("a3", "Lesson 2", "lesson", Some("Advanced"));
+
+    let today = chrono::Utc::now().format("%Y-%m-%dT10:00:00").to_string();
+    db.complete("p1", "s1", "a1", Some(0.9), "2024-01-05T10:00:00")
+        .complete("p2", "s1", "a2", Some(0.5), "2024-02-10T10:00:00")
+        .complete("p3", "s1", "a3", Some(0.8), "2024-03-01T10:00:00")
+        .complete("p4", "s2", "a1", Some(0.7), &today)
+        .complete("p5", "s4", "a1", Some(0.6), "2024-01-10T10:00:00");
+    db
+}
+
+/// Halfway through a 20-day class with two lessons in Basics and then a
+/// project and a lesson in Functions. John did the Basics lesson eight days
+/// ago and the Basics project two days ago, so he is on pace.
+fn paced_class() -> TestDb {
+    let db = TestDb::new();
+    db.student("s1", "John", "Doe")
+        .assignment("a1", "Lesson 1", "lesson", Some("Basics"))
+        .assignment("a2", "Project 1", "project", Some("Basics"))
+        .assignment("a3", "Project 2", "project", Some("Functions"))
+        .assignment("a4", "Lesson 2", "lesson", Some("Functions"))
+        .complete("p1", "s1", "a1", Some(0.9), &days_ago(8))
+        .complete("p2", "s1", "a2", Some(0.5), &days_ago(2));
+    db.set_assignment_positions(CLASS, &["a1", "a2", "a3", "a4"])
+        .unwrap();
+    let today = chrono::Utc::now().date_naive();
+    db.set_calendar_settings(
+        CLASS,
+        &CalendarSettings {
+            start_date: Some(today - Duration::days(9)),
+            end_date: Some(today + Duration::days(10)),
+            break_weeks: vec![],
+        },
+    )
+    .unwrap();
+    db
+}
+
+fn section(name: &str) -> AnalyticsFilter {
+    AnalyticsFilter {
+        section: Some(name.to_string()),
+        ..Default::default()
+    }
+}
+
+fn assignment_type(name: &str) -> AnalyticsFilter {
+    AnalyticsFilter {
+        assignment_type: Some(name.to_string()),
+        ..Default::default()
+    }
+}
+
+fn between(from: NaiveDate, to: NaiveDate) -> AnalyticsFilter {
+    AnalyticsFilter {
+        from: Some(from),
+        to: Some(to),
+        ..Default::default()
+    }
+}
+
+fn schedule_delta(db: &TestDb, filter: &AnalyticsFilter) -> Option<i64> {
+    db.get_student_health_filtered(CLASS, filter).unwrap()[0].schedule_delta
+}
+
+#[test]
+fn test_empty_filter_covers_the_class() {
+    let db = four_students();
+    let summary = db
+        .get_progress_summary_filtered(CLASS, &AnalyticsFilter::default())
+        .unwrap();
+    assert_eq!(summary.total_students, 4);
+    assert_eq!(summary.total_assignments, 3);
+    assert_eq!(summary.total_progressions, 5);
+}
+
+#[test]
+fn test_region_filter_is_case_insensitive() {
+    let db = four_students();
+    let east = AnalyticsFilter {
+        region: Some("east".to_string()),
+        ..Default::default()
+    };
+    let summary = db.get_progress_summary_filtered(CLASS, &east).unwrap();
+    assert_eq!(summary.total_students, 2);
+    assert_eq!(summary.total_progressions, 3);
+}
+
+#[test]
+fn test_section_filter_limits_assignments_not_students() {
+    let db = four_students();
+    let basics = section("Basics");
+    let summary = db.get_progress_summary_filtered(CLASS, &basics).unwrap();
+    assert_eq!(summary.total_assignments, 2);
+    assert_eq!(summary.total_progressions, 4);
+
+    let health = db.get_student_health_filtered(CLASS, &basics).unwrap();
+    assert_eq!(health.len(), 4);
+    let s1 = health.iter().find(|h| h.student_id == "s1").unwrap();
+    assert_eq!(s1.completed, 2);
+    assert_eq!(s1.total_assignments, 2);
+}
+
+#[test]
+fn test_type_filter_on_blockers() {
+    let db = four_students();
+    let blockers = db
+        .get_blockers_filtered(CLASS, 10, &assignment_type("project"))
+        .unwrap();
+    assert_eq!(blockers.len(), 1);
+    assert_eq!(blockers[0].assignment_id, "a2");
+    assert_eq!(blockers[0].completions, 1);
+}
+
+#[test]
+fn test_date_window_counts_completions_inside_it() {
+    let db = four_students();
+    let february = between(date("2024-02-01"), date("2024-02-29"));
+    let summary = db.get_progress_summary_filtered(CLASS, &february).unwrap();
+    assert_eq!(summary.total_progressions, 1);
+    assert_eq!(summary.avg_grade, Some(0.5));
+
+    let difficulty = db
+        .get_assignment_difficulty_filtered(CLASS, &february)
+        .unwrap();
+    assert_eq!(difficulty.len(), 3);
+    assert_eq!(difficulty.iter().map(|d| d.completions).sum::<i64>(), 1);
+}
+
+#[test]
+fn test_status_filter() {
+    let db = four_students();
+    for (status, expected) in [
+        ("completed", "s1"),
+        ("active", "s2"),
+        ("not-started", "s3"),
+        ("inactive", "s4"),
+    ] {
+        let filter = AnalyticsFilter {
+            status: Some(StudentStatus::parse(status).unwrap()),
+            ..Default::default()
+        };
+        let health = db.get_student_health_filtered(CLASS, &filter).unwrap();
+        assert_eq!(health.len(), 1, "status {}", status);
+        assert_eq!(health[0].student_id, expected, "status {}", status);
+    }
+    assert!(StudentStatus::parse("sleeping").is_err());
+}
+
+#[test]
+fn test_completion_metrics_follow_the_filter() {
+    let db = four_students();
+    let east_basics = AnalyticsFilter {
+        region: Some("East".to_string()),
+        section: Some("Basics".to_string()),
+        ..Default::default()
+    };
+    let metrics = db.get_completion_metrics(CLASS, &east_basics).unwrap();
+    assert_eq!(metrics.total_assignments, 2);
+    assert_eq!(metrics.assignments.len(), 2);
+    let lesson = metrics
+        .assignments
+        .iter()
+        .find(|a| a.assignment_id == "a1")
+        .unwrap();
+    assert_eq!(lesson.completions, 1);
+    assert_eq!(lesson.completion_rate, 0.5);
+    assert_eq!(metrics.avg_students_per_assignment, 1.0);
+}
+
+#[test]
+fn test_night_summary_follows_the_filter() {
+    let db = four_students();
+    let summary = db.get_night_summary(CLASS, &section("Basics")).unwrap();
+    let nights: Vec<(&str, i64, f64)> = summary
+        .iter()
+        .map(|n| (n.night.as_str(), n.total_completions, n.avg_completion_pct))
+        .collect();
+    assert_eq!(nights, vec![("Mon", 2, 0.5), ("Tues", 2, 0.5)]);
+
+    let west = AnalyticsFilter {
+        region: Some("West".to_string()),
+        ..Default::default()
+    };
+    let summary = db.get_night_summary(CLASS, &west).unwrap();
+    assert_eq!(summary.len(), 1);
+    assert_eq!(summary[0].night, "Tues");
+}
+
+#[test]
+fn test_unfiltered_health_is_on_pace() {
+    let db = paced_class();
+    assert_eq!(schedule_delta(&db, &AnalyticsFilter::default()), Some(0));
+}
+
+#[test]
+fn test_section_filter_expects_only_that_sections_assignments() {
+    let db = paced_class();
+    // Functions comes after the halfway point, so nothing is due yet
+    assert_eq!(schedule_delta(&db, &section("Functions")), Some(0));
+    assert_eq!(schedule_delta(&db, &section("Basics")), Some(0));
+}
+
+#[test]
+fn test_type_filter_expects_only_that_types_assignments() {
+    let db = paced_class();
+    // Halfway means both Basics assignments: one lesson, one project
+    assert_eq!(schedule_delta(&db, &assignment_type("lesson")), Some(0));
+    assert_eq!(schedule_delta(&db, &assignment_type("project")), Some(0));
+}
+
+#[test]
+fn test_date_window_expects_only_what_falls_due_inside_it() {
+    let db = paced_class();
+    let today = chrono::Utc::now().date_naive();
+    // One assignment falls due in the last five days, and John did one
+    let last_five_days = between(today - Duration::days(4), today);
+    assert_eq!(schedule_delta(&db, &last_five_days), Some(0));
+}
+
+#[test]
+fn test_activity_signals_follow_the_filter() {
+    let db = paced_class();
+    let gap = |filter: &AnalyticsFilter| {
+        db.get_student_health_filtered(CLASS, filter).unwrap()[0].longest_gap_days
+    };
+    assert_eq!(gap(&AnalyticsFilter::default()), Some(6));
+    assert_eq!(gap(&assignment_type("lesson")), Some(8));
+    assert_eq!(gap(&section("Functions")), None);
+}
+
+#[test]
+fn test_expected_pace_follows_the_section() {
+    let db = paced_class();
+    let pace = db.get_expected_pace(CLASS, &section("Functions")).unwrap();
+    let last = pace.last().unwrap();
+    assert_eq!(last.expected_completed, 2.0);
+    assert_eq!(last.expected_pct, 1.0);
+    assert_eq!(last.expected_assignment.as_deref(), Some("Lesson 2"));
+    // Nothing in Functions is due in the first week
+    assert_eq!(pace[0].expected_completed, 0.0);
+    assert_eq!(pace[0].expected_assignment, None);
+}
@@ -0,0 +1,115 @@
+use cohort_tracker::db::AnalyticsFilter;
+
+mod common;
+use common::{TestDb, CLASS};
+
+/// Setup, Variables, Loops and Bonus in course order. s1 finished
+/// everything, s2 and s3 stopped early and s4 skipped ahead.
+fn course() -> TestDb {
+    let db = TestDb::new();
+    for (id, name, section) in [
+        ("a1", "Variables", "Core"),
+        ("a2", "Loops", "Core"),
+        ("a3", "Setup", "Core"),
+        ("a4", "Bonus", "Extra"),
+    ] {
+        db.assignment(id, name, "lesson", Some(section));
+    }
+    db.set_assignment_positions(CLASS, &["a3", "a1", "a2", "a4"])
+        .unwrap();
+
+    let completions = [
+        ("s1", vec!["a3", "a1", "a2", "a4"]),
+        ("s2", vec!["a3", "a1"]),
+        ("s3", vec!["a3"]),
+        ("s4", vec!["a1", "a4"]),
+    ];
+    for (student, done) in &completions {
+        db.student(student, "Test", student);
+        for assignment in done {
+            db.progression(
+                &format!("{}-{}", student, assignment),
+                student,
+                assignment,
+                None,
+                "2024-01-01T09:00:00",
+                "2024-01-01T10:00:00",
+                None,
+            );
+        }
+    }
+    db
+}
+
+fn funnel(db: &TestDb) -> cohort_tracker::models::AssignmentFunnel {
+    db.get_assignment_funnel(CLASS, &AnalyticsFilter::default())
+        .unwrap()
+}
+
+#[test]
+fn test_funnel_steps_follow_course_order() {
+    let db = course();
+    let funnel = funnel(&db);
+    assert_eq!(funnel.total_students, 4);
+    let steps: Vec<(&str, i64, i64, i64, i64)> = funnel
+        .steps
+        .iter()
+        .map(|s| {
+            (
+                s.name.as_str(),
+                s.reached,
+                s.completed,
+                s.skipped_ahead,
+                s.drop_off,
+            )
+        })
+        .collect();
+    assert_eq!(
+        steps,
+        [
+            ("Setup", 3, 3, 0, 1),
+            ("Variables", 2, 3, 1, 1),
+            ("Loops", 1, 1, 0, 1),
+            ("Bonus", 1, 2, 1, 0),
+        ]
+    );
+    assert_eq!(funnel.steps[1].drop_off_rate, 1.0 / 3.0);
+}
+
+#[test]
+fn test_biggest_drop_offs_by_rate() {
+    let db = course();
+    let funnel = funnel(&db);
+    let worst: Vec<&str> = funnel
+        .biggest_drop_offs
+        .iter()
+        .map(|s| s.name.as_str())
+        .collect();
+    assert_eq!(worst, ["Loops", "Variables", "Setup"]);
+}
+
+#[test]
+fn test_students_who_skipped_ahead() {
+    let db = course();
+    let funnel = funnel(&db);
+    assert_eq!(funnel.skipped_ahead.len(), 1);
+    let skipped = &funnel.skipped_ahead[0];
+    assert_eq!(skipped.student_id, "s4");
+    assert_eq!(skipped.in_sequence, 0);
+    assert_eq!(skipped.furthest_position, 4);
+    assert_eq!(skipped.missed, 2);
+    assert_eq!(skipped.first_missed, "Setup");
+}
+
+#[test]
+fn test_section_filter_keeps_course_order() {
+    let db = course();
+    let filter = AnalyticsFilter {
+        section: Some("core".to_string()),
+        ..Default::default()
+    };
+    let funnel = db.get_assignment_funnel(CLASS, &filter).unwrap();
+    assert_eq!(funnel.steps.len(), 3);
+    assert_eq!(funnel.skipped_ahead[0].missed, 1);
+    assert_eq!(funnel.skipped_ahead[0].furthest_position, 2);
+}
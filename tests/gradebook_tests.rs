@@ -0,0 +1,131 @@
+use cohort_tracker::db::AnalyticsFilter;
+use cohort_tracker::gradebook::ExportFormat;
+use cohort_tracker::models::Gradebook;
+
+mod common;
+use common::{TestDb, CLASS};
+
+/// Two Basics projects around a Control one, plus an unsectioned extra.
+/// Ann has finished three; Bob has only started the first.
+fn class_gradebook() -> Gradebook {
+    let db = TestDb::new();
+    for (id, name, section) in [
+        ("a1", "Intro", Some("Basics")),
+        ("a2", "Loops", Some("Control")),
+        ("a3", "Variables", Some("Basics")),
+        ("a4", "Extra", None),
+    ] {
+        db.assignment(id, name, "project", section);
+    }
+    db.set_assignment_positions(CLASS, &["a1", "a2", "a3", "a4"])
+        .unwrap();
+    db.insert_student("s1", CLASS, "Ann", "Lee", "ann@example.com")
+        .unwrap();
+    db.insert_student("s2", CLASS, "Bob", "Ray", "bob@example.com")
+        .unwrap();
+    for (id, student, assignment, grade, completed_at) in [
+        ("p1", "s1", "a1", Some(0.8), "2024-01-05T10:00:00"),
+        ("p2", "s1", "a3", Some(0.6), "2024-01-12T10:00:00"),
+        ("p3", "s1", "a2", None, "2024-01-19T10:00:00"),
+        ("p4", "s2", "a1", None, ""),
+    ] {
+        db.progression(
+            id,
+            student,
+            assignment,
+            grade,
+            "2024-01-01T10:00:00",
+            completed_at,
+            None,
+        );
+    }
+    db.get_gradebook(CLASS, &AnalyticsFilter::default())
+        .unwrap()
+}
+
+#[test]
+fn test_columns_group_sections_in_course_order() {
+    // Sections in the order the course reaches them, unsectioned last
+    let gradebook = class_gradebook();
+    let names: Vec<&str> = gradebook
+        .assignments
+        .iter()
+        .map(|a| a.name.as_str())
+        .collect();
+    assert_eq!(names, ["Intro", "Variables", "Loops", "Extra"]);
+    let sections: Vec<(Option<&str>, usize, usize)> = gradebook
+        .sections
+        .iter()
+        .map(|s| (s.section.as_deref(), s.first_assignment, s.assignment_count))
+        .collect();
+    assert_eq!(
+        sections,
+        [
+            (Some("Basics"), 0, 2),
+            (Some("Control"), 2, 1),
+            (None, 3, 1)
+        ]
+    );
+}
+
+#[test]
+fn test_student_cells_and_subtotals() {
+    let gradebook = class_gradebook();
+    let ann = &gradebook.students[0];
+    assert_eq!(ann.student_id, "s1");
+    assert_eq!(ann.completed, 3);
+    assert!((ann.avg_grade.unwrap() - 0.7).abs() < 1e-9);
+    assert_eq!(ann.cells[1].grade, Some(0.6));
+    assert_eq!(
+        ann.cells[1].completed_at.as_deref(),
+        Some("2024-01-12T10:00:00")
+    );
+    assert_eq!(ann.cells[3].state, "not_started");
+    assert_eq!(ann.subtotals[0].completed, 2);
+    assert_eq!(ann.subtotals[0].total, 2);
+}
+
+#[test]
+fn test_started_assignment_is_in_progress() {
+    let gradebook = class_gradebook();
+    let bob = &gradebook.students[1];
+    assert_eq!(bob.cells[0].state, "in_progress");
+    assert_eq!(bob.completed, 0);
+    assert_eq!(bob.avg_grade, None);
+}
+
+#[test]
+fn test_csv_export() {
+    let gradebook = class_gradebook();
+    let csv = String::from_utf8(ExportFormat::Csv.export(&gradebook).unwrap()).unwrap();
+    let lines: Vec<&str> = csv.lines().collect();
+    assert_eq!(
+        lines[0],
+        "Last name,First name,Email,Intro,Variables,Basics completed,Basics avg grade,\
+         Loops,Control completed,Control avg grade,Extra,No section completed,\
+         No section avg grade,Completed,Avg grade"
+    );
+    assert_eq!(
+        lines[1],
+        "Lee,Ann,ann@example.com,80%,60%,2/2,70%,done,1/1,,,0/1,,3/4,70%"
+    );
+    assert_eq!(
+        lines[2],
+        "Ray,Bob,bob@example.com,in progress,,0/2,,,0/1,,,0/1,,0/4,"
+    );
+}
+
+#[test]
+fn test_xlsx_export() {
+    let xlsx = ExportFormat::Xlsx.export(&class_gradebook()).unwrap();
+    assert!(xlsx.starts_with(b"PK"));
+}
+
+#[test]
+fn test_export_format_from_extension() {
+    assert_eq!(
+        ExportFormat::from_path("report.XLSX").unwrap(),
+        ExportFormat::Xlsx
+    );
+    assert!(ExportFormat::from_path("report.pdf").is_err());
+}
@@ -0,0 +1,263 @@
+use cohort_tracker::config::ImportConfig;
+use cohort_tracker::db::Database;
+use cohort_tracker::import::{
+    apply_mentor_students, apply_student_import, normalize_name, plan_mentor_students,
+    plan_student_import, read_mentor_students_csv, read_mentors_csv, read_students_csv,
+    write_review_report, MatchMethod, PlannedStudent, StudentMatch,
+};
+use std::collections::HashMap;
+use std::io::Write;
+use tempfile::NamedTempFile;
+
+mod common;
+use common::TestDb;
+
+fn write_csv(content: &str) -> NamedTempFile {
+    let mut file = NamedTempFile::new().unwrap();
+    file.write_all(content.as_bytes()).unwrap();
+    file
+}
+
+#[test]
+fn test_read_students_csv_by_header() {
+    // BOM, reordered columns, a quoted comma and a blank line
+    let file = write_csv(
+        "\u{feff}Night,Last Name,First Name,Region\n\
+         Monday,\"Garcia, Jr.\",Luis,Louisville\n\
+         \n\
+         Tuesday,,Ann,Lexington\n",
+    );
+
+    let (rows, skipped) =
+        read_students_csv(file.path().to_str().unwrap(), &ImportConfig::default()).unwrap();
+
+    assert_eq!(rows.len(), 1);
+    assert_eq!(rows[0].first_name, "Luis");
+    assert_eq!(rows[0].last_name, "Garcia, Jr.");
+    assert_eq!(rows[0].region, "Louisville");
+    assert_eq!(rows[0].night, "Monday");
+    assert_eq!(skipped.len(), 1);
+    assert_eq!(skipped[0].line, 4);
+}
+
+#[test]
+fn test_configured_header_aliases() {
+    let file = write_csv("Mentor,Cohort Day\nSam,Wednesday\n");
+    let path = file.path().to_str().unwrap();
+
+    assert!(read_mentors_csv(path, &ImportConfig::default()).is_err());
+
+    let config = ImportConfig {
+        columns: HashMap::from([("night".to_string(), vec!["Cohort Day".to_string()])]),
+    };
+    let (rows, _) = read_mentors_csv(path, &config).unwrap();
+    assert_eq!(rows.len(), 1);
+    assert_eq!(rows[0].name, "Sam");
+    assert_eq!(rows[0].night, "Wednesday");
+}
+
+#[test]
+fn test_plan_student_import() {
+    let db_file = NamedTempFile::new().unwrap();
+    let db = Database::new(db_file.path().to_str().unwrap()).unwrap();
+    db.insert_student("s1", "class1", "Luis", "Garcia, Jr.", "luis@example.com")
+        .unwrap();
+    db.insert_student("s2", "class1", "Sam", "Lee", "sam1@example.com")
+        .unwrap();
+    db.insert_student("s3", "class1", "Sam", "Lee", "sam2@example.com")
+        .unwrap();
+
+    let file = write_csv(
+        "First Name,Last Name,Region,Night\n\
+         Luis,\"Garcia, Jr.\",Louisville,Monday\n\
+         Sam,Lee,Louisville,Tuesday\n\
+         Nobody,Here,Louisville,Tuesday\n",
+    );
+    let (rows, _) =
+        read_students_csv(file.path().to_str().unwrap(), &ImportConfig::default()).unwrap();
+    let plan = plan_student_import(&db, rows, "class1").unwrap();
+
+    assert!(matches!(plan[0].outcome, StudentMatch::Update { .. }));
+    assert!(
+        matches!(&plan[1].outcome, StudentMatch::Ambiguous { candidates, .. } if candidates.len() == 2)
+    );
+    assert!(matches!(plan[2].outcome, StudentMatch::NotFound));
+
+    assert_eq!(apply_student_import(&db, &plan).unwrap(), 1);
+    let students = db.get_students_by_class("class1").unwrap();
+    let luis = students.iter().find(|s| s.id == "s1").unwrap();
+    assert_eq!(luis.night.as_deref(), Some("Monday"));
+    // Ambiguous matches are left alone
+    assert!(students
+        .iter()
+        .filter(|s| s.id != "s1")
+        .all(|s| s.night.is_none()));
+}
+
+#[test]
+fn test_normalize_name() {
+    assert_eq!(
+        normalize_name("  José   O'Brien-Smith "),
+        "jose obrien smith"
+    );
+    assert_eq!(normalize_name("ZOË"), "zoe");
+}
+
+/// Chris enrolled in both classes, plus a different Sam Lee in each, and
+/// a CSV with Chris by name, one Sam by email and one Sam by name.
+fn two_classes() -> (TestDb, NamedTempFile) {
+    let db = TestDb::new();
+    db.insert_student("s1", "class1", "Christopher", "Nuñez", "chris@example.com")
+        .unwrap();
+    db.insert_student("s1", "class2", "Christopher", "Nuñez", "chris@example.com")
+        .unwrap();
+    db.insert_student("s2", "class1", "Sam", "Lee", "sam.one@example.com")
+        .unwrap();
+    db.insert_student("s3", "class2", "Sam", "Lee", "sam.two@example.com")
+        .unwrap();
+    let file = write_csv(
+        "First Name,Last Name,Email,Region,Night\n\
+         Chris,Nunez,,Louisville,Monday\n\
+         Sam,Lee,SAM.TWO@example.com,Louisville,Tuesday\n\
+         Sam,Lee,,Louisville,Tuesday\n",
+    );
+    (db, file)
+}
+
+fn plan(db: &Database, file: &NamedTempFile, class_id: &str) -> Vec<PlannedStudent> {
+    let (rows, _) =
+        read_students_csv(file.path().to_str().unwrap(), &ImportConfig::default()).unwrap();
+    plan_student_import(db, rows, class_id).unwrap()
+}
+
+#[test]
+fn test_email_match_ignores_case() {
+    let (db, file) = two_classes();
+    let plan = plan(&db, &file, "class2");
+    assert!(matches!(
+        &plan[1].outcome,
+        StudentMatch::Update { student, method: MatchMethod::Email } if student.id == "s3"
+    ));
+}
+
+#[test]
+fn test_class_scope_resolves_names_to_single_enrollments() {
+    let (db, file) = two_classes();
+    let plan = plan(&db, &file, "class1");
+    assert!(matches!(
+        &plan[0].outcome,
+        StudentMatch::Update { student, method: MatchMethod::Fuzzy } if student.class_id == "class1"
+    ));
+    assert!(matches!(
+        &plan[2].outcome,
+        StudentMatch::Update { student, method: MatchMethod::Name } if student.id == "s2"
+    ));
+}
+
+#[test]
+fn test_different_email_falls_back_to_the_name() {
+    // The class1 Sam Lee has another address on file
+    let (db, file) = two_classes();
+    let plan = plan(&db, &file, "class1");
+    assert!(matches!(
+        &plan[1].outcome,
+        StudentMatch::Update { student, method: MatchMethod::EmailMismatch } if student.id == "s2"
+    ));
+    assert!(plan[1].needs_review());
+}
+
+#[test]
+fn test_review_report_lists_fuzzy_and_email_mismatch_rows() {
+    let (db, file) = two_classes();
+    let plan = plan(&db, &file, "class1");
+    let review = NamedTempFile::new().unwrap();
+    assert_eq!(
+        write_review_report(review.path().to_str().unwrap(), &plan).unwrap(),
+        2
+    );
+    let report = std::fs::read_to_string(review.path()).unwrap();
+    assert!(report.contains("fuzzy match (applied)"));
+    assert!(report.contains("email mismatch (applied)"));
+}
+
+#[test]
+fn test_scoped_import_only_updates_that_class() {
+    let (db, file) = two_classes();
+    let plan = plan(&db, &file, "class1");
+    apply_student_import(&db, &plan).unwrap();
+    let class2 = db.get_students_by_class("class2").unwrap();
+    assert!(class2.iter().all(|s| s.night.is_none()));
+    let class1 = db.get_students_by_class("class1").unwrap();
+    assert!(class1
+        .iter()
+        .all(|s| s.night.as_deref() == Some("Monday") || s.night.as_deref() == Some("Tuesday")));
+}
+
+#[test]
+fn test_mentor_students_import() {
+    let db_file = NamedTempFile::new().unwrap();
+    let db = Database::new(db_file.path().to_str().unwrap()).unwrap();
+    db.insert_student("s1", "class1", "Luis", "Garcia", "luis@example.com")
+        .unwrap();
+    let (mentor_id, _) = db
+        .upsert_mentor("Ada King", Some("ada@example.com"), "Mon")
+        .unwrap();
+
+    let file = write_csv(
+        "Mentor,First Name,Last Name,Email\n\
+         ada@example.com,Luis,Garcia,\n\
+         Nobody,Luis,Garcia,\n\
+         Ada King,Jane,Roe,\n",
+    );
+    let (rows, skipped) =
+        read_mentor_students_csv(file.path().to_str().unwrap(), &ImportConfig::default()).unwrap();
+    assert!(skipped.is_empty());
+
+    let plan = plan_mentor_students(&db, rows, None).unwrap();
+    assert!(plan[0].mentor.is_some());
+    assert!(plan[1].mentor.is_none());
+    assert!(matches!(plan[2].outcome, StudentMatch::NotFound));
+
+    assert_eq!(apply_mentor_students(&db, &plan).unwrap(), 1);
+    let students = db.get_mentor_students(mentor_id).unwrap();
+    assert_eq!(students.len(), 1);
+    assert_eq!(students[0].id, "s1");
+}
+
+#[test]
+fn test_mentor_on_two_nights_takes_students_on_their_night() {
+    let db = TestDb::new();
+    db.student("s1", "Luis", "Garcia")
+        .student("s2", "Jane", "Roe")
+        .student("s3", "Sam", "Lee");
+    db.set_student_night("s1", "class1", "Louisville", "Mon")
+        .unwrap();
+    db.set_student_night("s2", "class1", "Louisville", "Tues")
+        .unwrap();
+    let (monday, _) = db.upsert_mentor("Ada King", None, "Mon").unwrap();
+    let (tuesday, _) = db.upsert_mentor("Ada King", None, "Tues").unwrap();
+
+    let rows = |csv: &str| {
+        let file = write_csv(csv);
+        read_mentor_students_csv(file.path().to_str().unwrap(), &ImportConfig::default())
+            .unwrap()
+            .0
+    };
+    let plan = plan_mentor_students(
+        &db,
+        rows("Mentor,First Name,Last Name\nAda King,Luis,Garcia\nAda King,Jane,Roe\n"),
+        None,
+    )
+    .unwrap();
+    assert_eq!(plan[0].mentor.as_ref().unwrap().id, monday);
+    assert_eq!(plan[1].mentor.as_ref().unwrap().id, tuesday);
+
+    // Sam has no night, so there is no telling which row is meant
+    let err = plan_mentor_students(
+        &db,
+        rows("Mentor,First Name,Last Name\nAda King,Sam,Lee\n"),
+        None,
+    )
+    .unwrap_err();
+    assert!(err.to_string().contains("matches 2 mentors"));
+}
@@ -47,6 +47,13 @@ async fn test_config_with_mock_server() {
         password: "password".to_string(),
         api_base: mock_server.uri(),
         check_for_updates: false,
+        database_path: None,
+        backup: Default::default(),
+        import: Default::default(),
+        risk: Default::default(),
+        pathways: Default::default(),
+        notifications: Default::default(),
+        reviews: Default::default(),
     };
 
     // Test config serialization
@@ -0,0 +1,129 @@
+use cohort_tracker::db::integrity::*;
+
+mod common;
+use common::{TestDb, CLASS};
+
+fn count(db: &TestDb, kind: &str) -> usize {
+    let report = db.check_integrity().unwrap();
+    report.issues.iter().filter(|i| i.kind == kind).count()
+}
+
+/// A class with one student and a sectioned lesson.
+fn module() -> TestDb {
+    let db = TestDb::new();
+    db.class(CLASS, "module-1")
+        .student("s1", "John", "Doe")
+        .assignment("a1", "Lesson 1", "lesson", Some("Week 1"));
+    db
+}
+
+#[test]
+fn test_clean_database_has_no_issues() {
+    let db = module();
+    db.complete("p1", "s1", "a1", Some(0.9), "2024-01-01T10:00:00");
+    assert!(db.check_integrity().unwrap().issues.is_empty());
+}
+
+#[test]
+fn test_progression_for_missing_student_is_orphaned() {
+    let db = module();
+    db.complete("p1", "missing", "a1", Some(0.9), "2024-01-01T10:00:00");
+    assert_eq!(count(&db, ORPHANED_PROGRESSION_STUDENT), 1);
+}
+
+#[test]
+fn test_student_in_unknown_class() {
+    let db = module();
+    db.student_in("ghost", "s3", "Jane", "Smith");
+    assert_eq!(count(&db, STUDENT_WITHOUT_CLASS), 1);
+}
+
+#[test]
+fn test_duplicate_emails_ignore_case() {
+    let db = module();
+    db.insert_student("s2", CLASS, "Johnny", "Doe", "S1@EXAMPLE.COM")
+        .unwrap();
+    assert_eq!(count(&db, DUPLICATE_EMAIL), 1);
+}
+
+#[test]
+fn test_empty_and_unparseable_completion_times() {
+    let db = module();
+    db.assignment("a2", "Lesson 2", "lesson", Some("Week 1"));
+    db.progression("p1", "s1", "a1", None, "2024-01-01T09:00:00", "", None)
+        .progression(
+            "p2",
+            "s1",
+            "a2",
+            Some(0.8),
+            "2024-01-01T09:00:00",
+            "last tuesday",
+            None,
+        );
+    assert_eq!(count(&db, EMPTY_COMPLETED_AT), 1);
+    assert_eq!(count(&db, UNPARSEABLE_TIMESTAMP), 1);
+}
+
+#[test]
+fn test_corrupt_time_part_is_unparseable() {
+    let db = module();
+    db.progression(
+        "p1",
+        "s1",
+        "a1",
+        Some(0.9),
+        "2024-01-01T09:00:00",
+        "2024-01-01Tgarbage",
+        None,
+    );
+    assert_eq!(count(&db, UNPARSEABLE_TIMESTAMP), 1);
+}
+
+#[test]
+fn test_assignment_without_section() {
+    let db = module();
+    db.assignment("a2", "Lesson 2", "lesson", None);
+    assert_eq!(count(&db, ASSIGNMENT_WITHOUT_SECTION), 1);
+}
+
+#[test]
+fn test_repair_removes_only_fixable_issues() {
+    let db = module();
+    db.student_in("ghost", "s3", "Jane", "Smith")
+        .assignment("a2", "Lesson 2", "lesson", None)
+        .complete("p1", "missing", "a1", Some(0.9), "2024-01-01T10:00:00")
+        .progression("p2", "s1", "a1", None, "2024-01-01T09:00:00", "", None);
+
+    let report = db.repair_integrity().unwrap();
+    assert_eq!(report.fixed, 2);
+    assert!(report.issues.iter().all(|i| !i.fixable));
+    assert_eq!(db.get_progression_count().unwrap(), 1);
+    assert_eq!(count(&db, ASSIGNMENT_WITHOUT_SECTION), 1);
+}
+
+fn rows(db: &TestDb, table: &str) -> i64 {
+    let conn = sqlite::open(db.path()).unwrap();
+    let mut stmt = conn
+        .prepare(format!("SELECT COUNT(*) FROM {}", table))
+        .unwrap();
+    stmt.next().unwrap();
+    stmt.read::<i64>(0).unwrap()
+}
+
+#[test]
+fn test_repair_removes_rows_of_removed_students() {
+    let db = module();
+    db.student_in("ghost", "s3", "Jane", "Smith");
+    db.record_student_snapshots("ghost").unwrap();
+    let (mentor, _) = db.upsert_mentor("Mo", None, "Monday").unwrap();
+    db.assign_mentor_student(mentor, "s3", "ghost").unwrap();
+    db.assign_mentor_student(mentor, "s1", CLASS).unwrap();
+    db.link_people().unwrap();
+    assert_eq!(rows(&db, "student_snapshots"), 1);
+
+    db.repair_integrity().unwrap();
+    assert_eq!(rows(&db, "student_snapshots"), 0);
+    assert_eq!(rows(&db, "mentor_students"), 1);
+    assert_eq!(rows(&db, "person_accounts"), 1);
+    assert_eq!(rows(&db, "people"), 1);
+}
@@ -0,0 +1,114 @@
+use cohort_tracker::db::AnalyticsFilter;
+use cohort_tracker::models::ItemAnalysis;
+
+mod common;
+use common::{TestDb, CLASS};
+
+/// Twelve students, strongest last, and four quizzes with telling grade
+/// patterns. One lesson has a single grade, too few for statistics.
+fn quizzed_class() -> TestDb {
+    let db = TestDb::new();
+    let ability: [f64; 12] = std::array::from_fn(|i| 0.45 + i as f64 * 0.05);
+    for i in 0..ability.len() {
+        let id = format!("s{}", i + 1);
+        db.student(&id, "Student", &id);
+    }
+    let quizzes: [(&str, [f64; 12]); 4] = [
+        ("Tracks ability", ability),
+        ("Everyone aces", [1.0; 12]),
+        ("Noise", std::array::from_fn(|i| [0.9, 0.6][i % 2])),
+        (
+            "Pass or fail",
+            std::array::from_fn(|i| if i < 6 { 0.0 } else { 1.0 }),
+        ),
+    ];
+    for (q, (name, grades)) in quizzes.iter().enumerate() {
+        let assignment = format!("q{}", q + 1);
+        db.assignment(&assignment, name, "quiz", None);
+        let at = format!("2024-01-0{}T10:00:00", q + 1);
+        for (s, grade) in grades.iter().enumerate() {
+            db.complete(
+                &format!("p{}-{}", q + 1, s + 1),
+                &format!("s{}", s + 1),
+                &assignment,
+                Some(*grade),
+                &at,
+            );
+        }
+    }
+    db.assignment("l1", "Lesson", "lesson", None).complete(
+        "pl1",
+        "s1",
+        "l1",
+        Some(0.2),
+        "2024-01-05T10:00:00",
+    );
+    db
+}
+
+fn items() -> Vec<ItemAnalysis> {
+    quizzed_class()
+        .get_item_analysis(CLASS, &AnalyticsFilter::default())
+        .unwrap()
+}
+
+fn by_name(items: &[ItemAnalysis], name: &str) -> ItemAnalysis {
+    items.iter().find(|i| i.name == name).unwrap().clone()
+}
+
+#[test]
+fn test_item_tracking_ability_discriminates() {
+    let tracks = by_name(&items(), "Tracks ability");
+    assert!(tracks.discrimination.unwrap() > 0.5);
+    assert!(tracks.flags.is_empty());
+    assert!(!tracks.needs_rework);
+}
+
+#[test]
+fn test_item_everyone_aces_hits_the_ceiling() {
+    let aced = by_name(&items(), "Everyone aces");
+    assert_eq!(aced.ceiling_pct, Some(1.0));
+    assert_eq!(aced.discrimination, None);
+    assert_eq!(aced.flags, vec!["ceiling"]);
+}
+
+#[test]
+fn test_noisy_item_has_low_discrimination() {
+    let noise = by_name(&items(), "Noise");
+    assert!(noise.discrimination.unwrap() < 0.2);
+    assert!(noise.flags.contains(&"low_discrimination".to_string()));
+}
+
+#[test]
+fn test_pass_or_fail_item_is_bimodal_but_fine() {
+    let split = by_name(&items(), "Pass or fail");
+    assert!(split.discrimination.unwrap() > 0.5);
+    assert!(split.bimodality.unwrap() > 5.0 / 9.0);
+    assert_eq!(split.flags, vec!["bimodal"]);
+    assert!(!split.needs_rework);
+}
+
+#[test]
+fn test_too_few_grades_have_no_statistics() {
+    let lesson = by_name(&items(), "Lesson");
+    assert_eq!(lesson.graded, 1);
+    assert_eq!(lesson.discrimination, None);
+    assert!(lesson.flags.is_empty());
+}
+
+#[test]
+fn test_items_needing_rework_come_first() {
+    let rework: Vec<bool> = items().iter().map(|i| i.needs_rework).collect();
+    assert_eq!(rework, vec![true, true, false, false, false]);
+}
+
+#[test]
+fn test_item_analysis_respects_the_type_filter() {
+    let lessons = AnalyticsFilter {
+        assignment_type: Some("lesson".to_string()),
+        ..Default::default()
+    };
+    let items = quizzed_class().get_item_analysis(CLASS, &lessons).unwrap();
+    assert_eq!(items.len(), 1);
+    assert_eq!(items[0].name, "Lesson");
+}
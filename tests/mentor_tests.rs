@@ -0,0 +1,142 @@
+use cohort_tracker::db::AnalyticsFilter;
+
+mod common;
+use common::{TestDb, CLASS};
+
+/// John with one completion, Jane with none, and a mentor for Jane.
+fn mentored_class() -> (TestDb, i64) {
+    let db = TestDb::new();
+    db.student("s1", "John", "Doe")
+        .student("s2", "Jane", "Smith")
+        .assignment("a1", "Lesson 1", "lesson", None)
+        .complete("p1", "s1", "a1", Some(0.8), "2024-01-01T10:00:00");
+    let (id, _) = db
+        .upsert_mentor("Ada Lovelace", Some("ada@example.com"), "Mon")
+        .unwrap();
+    db.assign_mentor_student(id, "s2", CLASS).unwrap();
+    (db, id)
+}
+
+fn by_mentor(mentor: &str) -> AnalyticsFilter {
+    AnalyticsFilter {
+        mentor: Some(mentor.to_string()),
+        ..Default::default()
+    }
+}
+
+#[test]
+fn test_upsert_mentor_matches_by_name_case_insensitively() {
+    let db = TestDb::new();
+    let (id, created) = db.upsert_mentor("Ada Lovelace", None, "Mon").unwrap();
+    assert!(created);
+
+    let (same, created) = db
+        .upsert_mentor("ada lovelace", Some("ada@example.com"), "Mon")
+        .unwrap();
+    assert_eq!(same, id);
+    assert!(!created);
+    assert_eq!(
+        db.get_all_mentors().unwrap()[0].email.as_deref(),
+        Some("ada@example.com")
+    );
+}
+
+#[test]
+fn test_upsert_mentor_matches_a_renamed_mentor_by_email() {
+    let db = TestDb::new();
+    let (id, _) = db
+        .upsert_mentor("Ada Lovelace", Some("ada@example.com"), "Mon")
+        .unwrap();
+    let (same, created) = db
+        .upsert_mentor("Ada King", Some("ADA@example.com"), "Mon")
+        .unwrap();
+    assert_eq!(same, id);
+    assert!(!created);
+
+    let mentors = db.get_all_mentors().unwrap();
+    assert_eq!(mentors.len(), 1);
+    assert_eq!(mentors[0].name, "Ada King");
+}
+
+#[test]
+fn test_mentor_covering_two_nights_keeps_a_row_per_night() {
+    let db = TestDb::new();
+    db.student("s1", "John", "Doe")
+        .student("s2", "Jane", "Smith");
+    db.set_student_night("s1", CLASS, "Louisville", "Mon")
+        .unwrap();
+    db.set_student_night("s2", CLASS, "Louisville", "Tues")
+        .unwrap();
+    let (monday, _) = db.upsert_mentor("Ada Lovelace", None, "Mon").unwrap();
+    let (tuesday, created) = db.upsert_mentor("Ada Lovelace", None, "Tues").unwrap();
+    assert!(created);
+    assert_ne!(monday, tuesday);
+
+    // Importing the same file again updates both rows in place
+    assert_eq!(
+        db.upsert_mentor("Ada Lovelace", None, "Mon").unwrap(),
+        (monday, false)
+    );
+    assert_eq!(
+        db.upsert_mentor("Ada Lovelace", None, "Tues").unwrap(),
+        (tuesday, false)
+    );
+
+    let nights = db
+        .get_night_summary(CLASS, &AnalyticsFilter::default())
+        .unwrap();
+    assert_eq!(nights.len(), 2);
+    assert!(nights.iter().all(|n| n.mentors == ["Ada Lovelace"]));
+}
+
+#[test]
+fn test_find_mentor_refuses_to_guess_between_rows() {
+    let db = TestDb::new();
+    let (monday, _) = db.upsert_mentor("Ada Lovelace", None, "Mon").unwrap();
+    db.upsert_mentor("Ada Lovelace", None, "Tues").unwrap();
+
+    let err = db.find_mentor("ada lovelace").unwrap_err();
+    assert!(err.to_string().contains("matches 2 mentors"));
+    let found = db.find_mentor(&monday.to_string()).unwrap().unwrap();
+    assert_eq!(found.night, "Mon");
+}
+
+#[test]
+fn test_assigning_a_student_twice_is_a_no_op() {
+    let (db, id) = mentored_class();
+    assert!(!db.assign_mentor_student(id, "s2", CLASS).unwrap());
+    assert_eq!(db.get_mentor_students(id).unwrap().len(), 1);
+}
+
+#[test]
+fn test_mentor_filter_by_email_limits_metrics_to_their_students() {
+    let (db, _) = mentored_class();
+    let filter = by_mentor("ada@example.com");
+
+    let health = db.get_student_health_filtered(CLASS, &filter).unwrap();
+    assert_eq!(health.len(), 1);
+    assert_eq!(health[0].student_id, "s2");
+
+    let summary = db.get_progress_summary_filtered(CLASS, &filter).unwrap();
+    assert_eq!(summary.total_students, 1);
+    assert_eq!(summary.total_progressions, 0);
+}
+
+#[test]
+fn test_mentor_filter_by_id() {
+    let (db, id) = mentored_class();
+    let activity = db
+        .get_student_activity_filtered(CLASS, &by_mentor(&id.to_string()))
+        .unwrap();
+    assert_eq!(activity.len(), 1);
+}
+
+#[test]
+fn test_unassigned_student_leaves_the_mentor_filter() {
+    let (db, id) = mentored_class();
+    assert!(db.unassign_mentor_student(id, "s2", CLASS).unwrap());
+    assert!(db
+        .get_student_health_filtered(CLASS, &by_mentor("ada@example.com"))
+        .unwrap()
+        .is_empty());
+}
@@ -0,0 +1,129 @@
+use chrono::Duration;
+use cohort_tracker::calendar::ClassCalendar;
+use cohort_tracker::db::{AnalyticsFilter, CalendarSettings};
+
+mod common;
+use common::{date, TestDb, CLASS};
+
+/// Four lessons in the order a3, a1, a2, a4. John has done a3, a1 and a4;
+/// Jane hasn't started.
+fn class_with_course_order() -> TestDb {
+    let db = TestDb::new();
+    db.student("s1", "John", "Doe")
+        .student("s2", "Jane", "Smith");
+    for id in ["a1", "a2", "a3", "a4"] {
+        db.assignment(id, &format!("Lesson {}", id), "lesson", None);
+    }
+    db.set_assignment_positions(CLASS, &["a3", "a1", "a2", "a4"])
+        .unwrap();
+    for (i, assignment) in ["a3", "a1", "a4"].iter().enumerate() {
+        db.complete(
+            &format!("p{}", i),
+            "s1",
+            assignment,
+            Some(0.9),
+            "2024-01-01T10:00:00",
+        );
+    }
+    db
+}
+
+/// A 20-day class, halfway through today.
+fn halfway_through(db: &TestDb) {
+    let today = chrono::Utc::now().date_naive();
+    db.set_calendar_settings(
+        CLASS,
+        &CalendarSettings {
+            start_date: Some(today - Duration::days(9)),
+            end_date: Some(today + Duration::days(10)),
+            break_weeks: vec![],
+        },
+    )
+    .unwrap();
+}
+
+#[test]
+fn test_break_weeks_are_not_teaching_time() {
+    let calendar = ClassCalendar::new(
+        date("2024-01-01"),
+        Some(date("2024-01-21")),
+        &[date("2024-01-10")],
+    );
+    assert_eq!(calendar.teaching_fraction(date("2024-01-14")), Some(0.5));
+}
+
+#[test]
+fn test_catalog_follows_course_order_after_a_resync() {
+    let db = class_with_course_order();
+    db.assignment("a3", "Lesson a3", "lesson", Some("Unit 1"));
+    let catalog: Vec<String> = db
+        .get_assignment_catalog(CLASS)
+        .unwrap()
+        .into_iter()
+        .map(|a| a.id)
+        .collect();
+    assert_eq!(catalog, vec!["a3", "a1", "a2", "a4"]);
+}
+
+#[test]
+fn test_repeated_assignment_takes_its_last_position() {
+    let db = class_with_course_order();
+    db.set_assignment_positions(CLASS, &["a3", "a1", "a2", "a3", "a4"])
+        .unwrap();
+    let catalog: Vec<String> = db
+        .get_assignment_catalog(CLASS)
+        .unwrap()
+        .into_iter()
+        .map(|a| a.id)
+        .collect();
+    assert_eq!(catalog, vec!["a1", "a2", "a3", "a4"]);
+}
+
+#[test]
+fn test_no_schedule_delta_without_an_end_date() {
+    let db = class_with_course_order();
+    assert!(db.get_schedule_status(CLASS, None).unwrap().is_empty());
+    let health = db.get_student_health(CLASS, None).unwrap();
+    assert!(health.iter().all(|h| h.schedule_delta.is_none()));
+}
+
+#[test]
+fn test_schedule_status_ranks_students_furthest_behind_first() {
+    let db = class_with_course_order();
+    halfway_through(&db);
+
+    // On pace means 2 of 4 assignments
+    let status = db.get_schedule_status(CLASS, None).unwrap();
+    assert_eq!(status.len(), 2);
+    assert_eq!(status[0].student_id, "s2");
+    assert_eq!(status[0].expected_completed, 2.0);
+    assert_eq!(status[0].schedule_delta, -2);
+    assert_eq!(status[0].next_assignment.as_deref(), Some("Lesson a3"));
+    assert_eq!(status[1].schedule_delta, 1);
+    assert_eq!(status[1].next_assignment.as_deref(), Some("Lesson a2"));
+}
+
+#[test]
+fn test_detail_and_health_show_the_schedule_delta() {
+    let db = class_with_course_order();
+    halfway_through(&db);
+
+    let detail = db.get_student_detail(CLASS, "s1").unwrap().unwrap();
+    assert_eq!(detail.schedule_delta, Some(1));
+    let health = db.get_student_health(CLASS, None).unwrap();
+    let jane = health.iter().find(|h| h.student_id == "s2").unwrap();
+    assert_eq!(jane.schedule_delta, Some(-2));
+}
+
+#[test]
+fn test_expected_pace_ends_at_every_assignment() {
+    let db = class_with_course_order();
+    halfway_through(&db);
+
+    let pace = db
+        .get_expected_pace(CLASS, &AnalyticsFilter::default())
+        .unwrap();
+    let last = pace.last().unwrap();
+    assert_eq!(last.expected_completed, 4.0);
+    assert_eq!(last.expected_assignment.as_deref(), Some("Lesson a4"));
+}
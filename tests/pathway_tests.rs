@@ -0,0 +1,209 @@
+mod common;
+
+use cohort_tracker::db::{CalendarSettings, Database};
+use cohort_tracker::models::PathwayStudent;
+use cohort_tracker::pathway::parse_friendly_id;
+use common::{date, TestDb};
+
+const PATHWAY: &str = "data-analysis-pathway";
+
+/// Modules 1 and 2 of a pathway, each with one lesson.
+fn two_modules() -> TestDb {
+    let test = TestDb::new();
+    for (id, module) in [("m1", 1), ("m2", 2)] {
+        test.class(id, &format!("{}-module-{}-aug-2", PATHWAY, module));
+        test.insert_assignment("a1", id, "Lesson 1", "lesson", None)
+            .unwrap();
+    }
+    test
+}
+
+/// Give a class an end date, so it is over once that date has passed.
+fn ends(test: &TestDb, class_id: &str, end: &str) {
+    test.set_calendar_settings(
+        class_id,
+        &CalendarSettings {
+            start_date: None,
+            end_date: Some(date(end)),
+            break_weeks: Vec::new(),
+        },
+    )
+    .unwrap();
+}
+
+fn john(test: &TestDb) -> PathwayStudent {
+    test.link_people().unwrap();
+    test.get_pathway_students(PATHWAY)
+        .unwrap()
+        .into_iter()
+        .find(|s| s.first_name == "John")
+        .unwrap()
+}
+
+fn enroll(test: &TestDb, class_id: &str, id: &str, first: &str, email: &str, at: &str) {
+    test.insert_student(id, class_id, first, "Doe", email)
+        .unwrap();
+    test.insert_progression(
+        &format!("p-{}", id),
+        class_id,
+        id,
+        "a1",
+        Some(0.9),
+        at,
+        at,
+        None,
+    )
+    .unwrap();
+}
+
+#[test]
+fn test_parse_friendly_id() {
+    let key = parse_friendly_id("data-analysis-pathway-module-2-aug-2");
+    assert_eq!(key.pathway, PATHWAY);
+    assert_eq!(key.module, Some(2));
+    assert_eq!(key.term.as_deref(), Some("aug-2"));
+}
+
+#[test]
+fn test_accounts_with_the_same_email_are_one_person() {
+    let test = two_modules();
+    // Jane moves on to Module 2 under a new account
+    enroll(
+        &test,
+        "m1",
+        "jane1",
+        "Jane",
+        "jane@example.com",
+        "2025-09-01T10:00:00Z",
+    );
+    enroll(
+        &test,
+        "m2",
+        "jane2",
+        "Jane",
+        "Jane@Example.com",
+        "2025-11-01T10:00:00Z",
+    );
+
+    assert_eq!(test.link_people().unwrap(), 2);
+
+    let students = test.get_pathway_students(PATHWAY).unwrap();
+    assert_eq!(students.len(), 1);
+    let jane = &students[0];
+    assert_eq!(jane.enrollments.len(), 2);
+    assert_eq!(jane.furthest_module, Some(2));
+    assert_eq!(jane.dropped_out_module, None);
+    assert_eq!(
+        jane.transitions[0].entered_at.as_deref(),
+        Some("2025-11-01T10:00:00Z")
+    );
+
+    let pathway = test.get_student_pathway("m2", "jane2").unwrap().unwrap();
+    assert_eq!(pathway.person_id, jane.person_id);
+}
+
+#[test]
+fn test_student_missing_from_the_next_module_dropped_out() {
+    let test = two_modules();
+    enroll(
+        &test,
+        "m1",
+        "john",
+        "John",
+        "john@example.com",
+        "2025-09-02T10:00:00Z",
+    );
+    enroll(
+        &test,
+        "m2",
+        "jane",
+        "Jane",
+        "jane@example.com",
+        "2025-11-01T10:00:00Z",
+    );
+    ends(&test, "m1", "2025-10-31");
+
+    let john = john(&test);
+    assert_eq!(john.furthest_module, Some(1));
+    assert_eq!(john.dropped_out_module, Some(1));
+}
+
+#[test]
+fn test_student_in_a_running_module_has_not_dropped_out() {
+    let test = two_modules();
+    ends(&test, "m1", "2099-12-31");
+    test.insert_student("john", "m1", "John", "Doe", "john@example.com")
+        .unwrap();
+    enroll(
+        &test,
+        "m2",
+        "jane",
+        "Jane",
+        "jane@example.com",
+        "2025-11-01T10:00:00Z",
+    );
+
+    assert_eq!(john(&test).dropped_out_module, None);
+}
+
+#[test]
+fn test_later_module_of_another_term_is_not_a_dropout() {
+    let test = two_modules();
+    test.class("m2-old", &format!("{}-module-2-aug-1", PATHWAY));
+    test.insert_assignment("a1", "m2-old", "Lesson 1", "lesson", None)
+        .unwrap();
+    enroll(
+        &test,
+        "m1",
+        "john",
+        "John",
+        "john@example.com",
+        "2025-09-02T10:00:00Z",
+    );
+    enroll(
+        &test,
+        "m2-old",
+        "jane",
+        "Jane",
+        "jane@example.com",
+        "2025-11-01T10:00:00Z",
+    );
+    ends(&test, "m1", "2025-10-31");
+
+    assert_eq!(john(&test).dropped_out_module, None);
+}
+
+#[test]
+fn test_linking_again_links_nothing_new() {
+    let test = two_modules();
+    enroll(
+        &test,
+        "m1",
+        "jane1",
+        "Jane",
+        "jane@example.com",
+        "2025-09-01T10:00:00Z",
+    );
+    assert_eq!(test.link_people().unwrap(), 1);
+    assert_eq!(test.link_people().unwrap(), 0);
+}
+
+#[test]
+fn test_opening_a_current_database_does_not_write() {
+    let test = two_modules();
+    enroll(
+        &test,
+        "m1",
+        "jane1",
+        "Jane",
+        "jane@example.com",
+        "2025-09-01T10:00:00Z",
+    );
+
+    // Another connection holding the write lock makes any write fail
+    let other = sqlite::open(test.path()).unwrap();
+    other.execute("BEGIN IMMEDIATE").unwrap();
+    let db = Database::new(test.path().to_str().unwrap()).unwrap();
+    assert_eq!(db.get_student_count().unwrap(), 1);
+    other.execute("ROLLBACK").unwrap();
+}
@@ -0,0 +1,110 @@
+use cohort_tracker::models::Class;
+
+mod common;
+use common::TestDb;
+
+/// Two web modules, an intro class configured into the web pathway, a data
+/// module and an inactive web module. Each has one lesson and one student;
+/// web module 1 also has Bob and the only completion.
+fn program() -> TestDb {
+    let mut db = TestDb::new();
+    db.db.set_pathways(
+        [("intro-jan".to_string(), "web".to_string())]
+            .into_iter()
+            .collect(),
+    );
+
+    for (id, friendly_id, is_active) in [
+        ("c1", "web-module-1-jan", true),
+        ("c2", "web-module-2-jan", true),
+        ("c3", "intro-jan", true),
+        ("c4", "data-module-1-jan", true),
+        ("c5", "web-module-1-old", false),
+    ] {
+        db.insert_class(&Class {
+            id: id.to_string(),
+            name: friendly_id.to_string(),
+            friendly_id: friendly_id.to_string(),
+            is_active,
+            synced_at: None,
+        })
+        .unwrap();
+        db.insert_assignment(
+            &format!("{}-a1", id),
+            id,
+            "Lesson 1",
+            "lesson",
+            Some("Basics"),
+        )
+        .unwrap();
+        db.student_in(id, &format!("{}-s1", id), "Ann", "Lee");
+    }
+    db.student_in("c1", "c1-s2", "Bob", "Ray");
+    db.insert_progression(
+        "p1",
+        "c1",
+        "c1-s1",
+        "c1-a1",
+        Some(0.9),
+        "2024-01-01T09:00:00",
+        "2024-01-01T10:00:00",
+        None,
+    )
+    .unwrap();
+    db
+}
+
+#[test]
+fn test_program_covers_active_classes_only() {
+    let db = program();
+    let program = db.get_program_rollup().unwrap();
+    assert_eq!(program.pathway, None);
+    assert_eq!(program.modules.len(), 4);
+    assert_eq!(program.total_students, 5);
+    let risk = &program.risk;
+    assert_eq!(risk.critical + risk.high + risk.medium + risk.low, 5);
+}
+
+#[test]
+fn test_program_blockers_rank_least_completed_first() {
+    // Only c1 has a completion, so its blocker and section rank last
+    let db = program();
+    let program = db.get_program_rollup().unwrap();
+    assert_eq!(program.blockers.len(), 4);
+    assert_eq!(program.blockers[3].class_id, "c1");
+    assert_eq!(program.blockers[3].completion_rate, 0.5);
+    assert_eq!(program.worst_sections.len(), 4);
+    assert_eq!(program.worst_sections[3].class_id, "c1");
+}
+
+#[test]
+fn test_pathway_rollups_order_modules() {
+    let db = program();
+    let pathways = db.get_pathway_rollups().unwrap();
+    let names: Vec<Option<&str>> = pathways.iter().map(|p| p.pathway.as_deref()).collect();
+    assert_eq!(names, [Some("data"), Some("web")]);
+
+    let web = &pathways[1];
+    let modules: Vec<(&str, Option<u32>)> = web
+        .modules
+        .iter()
+        .map(|m| (m.friendly_id.as_str(), m.module))
+        .collect();
+    assert_eq!(
+        modules,
+        [
+            ("intro-jan", None),
+            ("web-module-1-jan", Some(1)),
+            ("web-module-2-jan", Some(2)),
+        ]
+    );
+    assert_eq!(web.total_students, 4);
+}
+
+#[test]
+fn test_configured_pathway_applies_to_the_listing() {
+    let db = program();
+    let listed = db.get_pathways().unwrap();
+    let web = listed.iter().find(|p| p.pathway == "web").unwrap();
+    assert_eq!(web.classes.len(), 4);
+}
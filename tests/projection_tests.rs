@@ -0,0 +1,99 @@
+use chrono::{Duration, NaiveDate};
+use cohort_tracker::db::CalendarSettings;
+
+mod common;
+use common::{TestDb, CLASS};
+
+fn today() -> NaiveDate {
+    chrono::Utc::now().date_naive()
+}
+
+/// Ten lessons. Fast did six in the last three weeks, Slow two and Stalled
+/// only one, 25 days ago.
+fn class_of_three() -> TestDb {
+    let db = TestDb::new();
+    for (id, name) in [("fast", "Fast"), ("slow", "Slow"), ("stalled", "Stalled")] {
+        db.student(id, name, "Student");
+    }
+    for a in 1..=10 {
+        db.assignment(&format!("a{}", a), "Lesson", "lesson", None);
+    }
+    let complete = |student: &str, assignment: i64, days_ago: i64| {
+        let at = format!("{}T10:00:00", today() - Duration::days(days_ago));
+        db.complete(
+            &format!("{}-a{}", student, assignment),
+            student,
+            &format!("a{}", assignment),
+            Some(0.9),
+            &at,
+        );
+    };
+    for a in 1..=6 {
+        complete("fast", a, a * 3);
+    }
+    complete("slow", 1, 2);
+    complete("slow", 2, 10);
+    complete("stalled", 1, 25);
+    db
+}
+
+/// Started four weeks ago, ends in two.
+fn with_end_date(db: &TestDb) {
+    db.set_calendar_settings(
+        CLASS,
+        &CalendarSettings {
+            start_date: Some(today() - Duration::days(28)),
+            end_date: Some(today() + Duration::days(14)),
+            break_weeks: vec![],
+        },
+    )
+    .unwrap();
+}
+
+#[test]
+fn test_velocity_counts_the_last_three_weeks() {
+    let db = class_of_three();
+    with_end_date(&db);
+    let fast = db.get_student_detail(CLASS, "fast").unwrap().unwrap();
+    assert_eq!(fast.assignments_remaining, 4);
+    assert_eq!(fast.recent_velocity, 2.0);
+}
+
+#[test]
+fn test_projected_finish_on_the_end_date() {
+    let db = class_of_three();
+    with_end_date(&db);
+    let fast = db.get_student_detail(CLASS, "fast").unwrap().unwrap();
+    let finish = today() + Duration::days(14);
+    assert_eq!(
+        fast.projected_finish,
+        Some(finish.format("%Y-%m-%d").to_string())
+    );
+    assert_eq!(fast.projected_days_past_end, Some(0));
+}
+
+#[test]
+fn test_stalled_student_has_no_projection() {
+    let db = class_of_three();
+    with_end_date(&db);
+    let stalled = db.get_student_detail(CLASS, "stalled").unwrap().unwrap();
+    assert_eq!(stalled.recent_velocity, 0.0);
+    assert_eq!(stalled.projected_finish, None);
+}
+
+#[test]
+fn test_projected_late_lists_stalled_students_first() {
+    let db = class_of_three();
+    with_end_date(&db);
+    let late = db.get_projected_late(CLASS, None).unwrap();
+    let ids: Vec<&str> = late.iter().map(|p| p.student_id.as_str()).collect();
+    assert_eq!(ids, vec!["stalled", "slow"]);
+    // 8 left at 2 every 3 weeks
+    assert_eq!(late[1].days_past_end, Some(84 - 14));
+}
+
+#[test]
+fn test_nobody_is_late_without_an_end_date() {
+    let db = class_of_three();
+    assert!(db.get_projected_late(CLASS, None).unwrap().is_empty());
+}
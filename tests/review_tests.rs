@@ -0,0 +1,152 @@
+use cohort_tracker::config::ReviewsConfig;
+use cohort_tracker::db::AnalyticsFilter;
+
+mod common;
+use common::{date, TestDb, CLASS};
+
+/// John (Monday night, Ana's) and Jane (Tuesday night, assigned to Ben) with
+/// a lesson and two projects. Project 1 is reviewed for both, Project 2 is
+/// waiting for both.
+fn class_with_reviews() -> TestDb {
+    let db = TestDb::new();
+    db.student("s1", "John", "Doe")
+        .student("s2", "Jane", "Smith");
+    db.set_student_night("s1", CLASS, "North", "Monday")
+        .unwrap();
+    db.set_student_night("s2", CLASS, "North", "Tuesday")
+        .unwrap();
+    db.upsert_mentor("Ana", None, "Monday").unwrap();
+    let (ben, _) = db.upsert_mentor("Ben", None, "Tuesday").unwrap();
+    db.upsert_mentor("Cal", None, "Tuesday").unwrap();
+    // An explicit assignment wins over the night's mentors
+    db.assign_mentor_student(ben, "s2", CLASS).unwrap();
+
+    db.assignment("l1", "Lesson", "lesson", None)
+        .assignment("p1", "Project 1", "project", None)
+        .assignment("p2", "Project 2", "project", None);
+    let submit = |id: &str, student: &str, assignment: &str, completed: &str, reviewed| {
+        db.progression(
+            id, student, assignment, None, completed, completed, reviewed,
+        );
+    };
+    submit(
+        "r1",
+        "s1",
+        "p1",
+        "2024-01-01T10:00:00",
+        Some("2024-01-02T10:00:00"),
+    );
+    submit(
+        "r2",
+        "s2",
+        "p1",
+        "2024-01-01T10:00:00",
+        Some("2024-01-01T16:00:00"),
+    );
+    submit("r3", "s1", "p2", "2024-01-03T10:00:00", None);
+    submit("r4", "s2", "p2", "2024-01-04T10:00:00", None);
+    // Lessons never get reviewed, so they never wait
+    submit("r5", "s1", "l1", "2024-01-01T09:00:00", None);
+    db
+}
+
+fn pending_ids(db: &TestDb, filter: &AnalyticsFilter) -> Vec<String> {
+    db.get_pending_reviews_filtered(CLASS, filter)
+        .unwrap()
+        .into_iter()
+        .map(|p| p.progression_id)
+        .collect()
+}
+
+#[test]
+fn test_turnaround_by_assignment_type_leaves_out_lessons() {
+    let db = class_with_reviews();
+    let report = db.get_review_turnaround(CLASS, None).unwrap();
+    assert_eq!(report.by_assignment_type.len(), 1);
+    let projects = &report.by_assignment_type[0];
+    assert_eq!(projects.group, "project");
+    assert_eq!(projects.reviewed, 2);
+    assert_eq!(projects.pending, 2);
+    assert_eq!(projects.median_hours, Some(15.0));
+}
+
+#[test]
+fn test_turnaround_by_night_and_mentor() {
+    let db = class_with_reviews();
+    let report = db.get_review_turnaround(CLASS, None).unwrap();
+
+    let nights: Vec<(&str, Option<f64>)> = report
+        .by_night
+        .iter()
+        .map(|t| (t.group.as_str(), t.median_hours))
+        .collect();
+    assert_eq!(nights, vec![("Monday", Some(24.0)), ("Tuesday", Some(6.0))]);
+
+    let mentors: Vec<&str> = report.by_mentor.iter().map(|t| t.group.as_str()).collect();
+    assert_eq!(mentors, vec!["Ana", "Ben"]);
+}
+
+#[test]
+fn test_pending_queue_is_oldest_first_with_mentors() {
+    let db = class_with_reviews();
+    let queue = db.get_pending_reviews(CLASS, None).unwrap();
+    let ids: Vec<&str> = queue.iter().map(|p| p.progression_id.as_str()).collect();
+    assert_eq!(ids, vec!["r3", "r4"]);
+    assert_eq!(queue[0].mentors, vec!["Ana"]);
+    assert_eq!(queue[1].mentors, vec!["Ben"]);
+
+    let tuesday = db.get_pending_reviews(CLASS, Some("tuesday")).unwrap();
+    assert_eq!(tuesday.len(), 1);
+}
+
+#[test]
+fn test_reviewable_type_with_no_reviews_yet_is_pending() {
+    let db = class_with_reviews();
+    db.assignment("q1", "Quiz 1", "quiz", None).complete(
+        "r6",
+        "s1",
+        "q1",
+        Some(0.9),
+        "2024-01-05T10:00:00",
+    );
+
+    assert_eq!(
+        pending_ids(&db, &AnalyticsFilter::default()),
+        vec!["r3", "r4", "r6"]
+    );
+    let report = db.get_review_turnaround(CLASS, None).unwrap();
+    let quizzes = report
+        .by_assignment_type
+        .iter()
+        .find(|t| t.group == "quiz")
+        .unwrap();
+    assert_eq!(quizzes.reviewed, 0);
+    assert_eq!(quizzes.pending, 1);
+    assert_eq!(quizzes.median_hours, None);
+}
+
+#[test]
+fn test_configured_auto_complete_types_never_wait() {
+    let mut test_db = class_with_reviews();
+    test_db.db.set_reviews_config(ReviewsConfig {
+        auto_complete_types: vec!["lesson".to_string(), "Project".to_string()],
+    });
+    assert!(pending_ids(&test_db, &AnalyticsFilter::default()).is_empty());
+    let report = test_db.get_review_turnaround(CLASS, None).unwrap();
+    assert!(report.by_assignment_type.is_empty());
+}
+
+#[test]
+fn test_as_of_treats_later_reviews_as_pending() {
+    let db = class_with_reviews();
+    // Jane's review came the same day, John's the next
+    let filter = AnalyticsFilter {
+        as_of: Some(date("2024-01-01")),
+        ..Default::default()
+    };
+    assert_eq!(pending_ids(&db, &filter), vec!["r1"]);
+
+    let report = db.get_review_turnaround_filtered(CLASS, &filter).unwrap();
+    assert_eq!(report.by_assignment_type[0].reviewed, 1);
+    assert_eq!(report.by_assignment_type[0].pending, 1);
+}
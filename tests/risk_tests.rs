@@ -0,0 +1,350 @@
+use chrono::{Duration, NaiveDate};
+use cohort_tracker::config::{Config, RiskConfig};
+use cohort_tracker::db::{CalendarSettings, Database};
+use cohort_tracker::risk::{assess, RiskInputs};
+use tempfile::NamedTempFile;
+
+mod common;
+use common::{date, TestDb};
+
+fn completion_only(completion_pct: f64) -> RiskInputs {
+    RiskInputs {
+        completion_pct,
+        ..Default::default()
+    }
+}
+
+#[test]
+fn test_default_completion_bands() {
+    let config = RiskConfig::default();
+    let level = |pct| assess(&config, &completion_only(pct)).level;
+
+    assert_eq!(level(0.0), "critical");
+    assert_eq!(level(0.2), "critical");
+    assert_eq!(level(0.25), "high");
+    assert_eq!(level(0.4), "high");
+    assert_eq!(level(0.6), "medium");
+    assert_eq!(level(0.8), "low");
+    assert_eq!(level(1.0), "low");
+}
+
+#[test]
+fn test_factors_explain_score() {
+    let config = RiskConfig::default();
+    let risk = assess(
+        &config,
+        &RiskInputs {
+            completion_pct: 0.8,
+            schedule_delta: Some(-3),
+            avg_grade: Some(0.9),
+            grade_trend: Some(-0.1),
+            days_inactive: Some(12),
+            velocity_drop: None,
+        },
+    );
+
+    let descriptions: Vec<&str> = risk
+        .factors
+        .iter()
+        .map(|f| f.description.as_str())
+        .collect();
+    assert_eq!(
+        descriptions,
+        vec![
+            "3 assignments behind schedule (+15)",
+            "80% complete (+12)",
+            "inactive 12 days (+10)",
+            "recent grades down 10 points (+5)",
+        ]
+    );
+    assert_eq!(risk.score, 42);
+    assert_eq!(risk.level, "high");
+
+    // Nothing left to do: inactivity and pace don't count
+    let finished = assess(
+        &config,
+        &RiskInputs {
+            completion_pct: 1.0,
+            schedule_delta: Some(5),
+            days_inactive: Some(200),
+            velocity_drop: Some(1.0),
+            ..Default::default()
+        },
+    );
+    assert!(finished.factors.is_empty());
+    assert_eq!(finished.level, "low");
+}
+
+#[test]
+fn test_risk_config_from_toml() {
+    let config: Config = toml::from_str(
+        r#"
+        email = "test@example.com"
+        password = "secret"
+        api_base = "https://api.openclass.ai"
+
+        [risk]
+        inactive_grace_days = 3
+        grade_window = 4
+
+        [risk.weights]
+        inactivity = 5.0
+
+        [risk.thresholds]
+        medium = 10.0
+        "#,
+    )
+    .unwrap();
+
+    assert_eq!(config.risk.inactive_grace_days, 3);
+    assert_eq!(config.risk.grade_window, 4);
+    assert_eq!(config.risk.weights.inactivity, 5.0);
+    // Unset values keep their defaults
+    assert_eq!(config.risk.weights.completion, 60.0);
+    assert_eq!(config.risk.thresholds.critical, 45.0);
+    assert_eq!(config.risk.thresholds.medium, 10.0);
+
+    // Default risk settings aren't written back to the config file
+    let mut config = config;
+    config.risk = RiskConfig::default();
+    assert!(!toml::to_string(&config).unwrap().contains("risk"));
+}
+
+#[test]
+fn test_health_and_detail_use_configured_risk() {
+    let temp_file = NamedTempFile::new().unwrap();
+    let mut db = Database::new(temp_file.path().to_str().unwrap()).unwrap();
+    db.insert_student("s1", "class1", "John", "Doe", "john@example.com")
+        .unwrap();
+    db.insert_assignment("a1", "class1", "Lesson 1", "lesson", None)
+        .unwrap();
+    db.insert_assignment("a2", "class1", "Lesson 2", "lesson", None)
+        .unwrap();
+    db.insert_progression(
+        "p1",
+        "class1",
+        "s1",
+        "a1",
+        Some(0.35),
+        "2024-01-01T09:00:00",
+        "2024-01-01T10:00:00",
+        None,
+    )
+    .unwrap();
+
+    // 50% complete (+30), long inactive (+30), average grade 35% (+10)
+    let health = db.get_student_health("class1", None).unwrap();
+    assert_eq!(health[0].risk, "critical");
+    assert_eq!(health[0].risk_score, 70);
+    assert_eq!(health[0].risk_factors.len(), 3);
+    assert_eq!(
+        health[0].risk_factors[2].description,
+        "average grade 35% (+10)"
+    );
+
+    let detail = db.get_student_detail("class1", "s1").unwrap().unwrap();
+    assert_eq!(detail.risk, health[0].risk);
+    assert_eq!(detail.risk_score, health[0].risk_score);
+
+    let mut config = RiskConfig::default();
+    config.weights.inactivity = 0.0;
+    config.weights.grade = 0.0;
+    db.set_risk_config(config);
+
+    let health = db.get_student_health("class1", None).unwrap();
+    assert_eq!(health[0].risk, "medium");
+    assert_eq!(health[0].risk_factors[0].description, "50% complete (+30)");
+}
+
+#[test]
+fn test_detail_inactivity_matches_health_with_an_unreadable_timestamp() {
+    let db = TestDb::new();
+    db.student("s1", "John", "Doe")
+        .assignment("a1", "Lesson 1", "lesson", None)
+        .assignment("a2", "Lesson 2", "lesson", None)
+        .assignment("a3", "Lesson 3", "lesson", None)
+        .complete("p1", "s1", "a1", None, &common::days_ago(20))
+        .complete("p2", "s1", "a2", None, "not a date");
+
+    let health = db.get_student_health(common::CLASS, None).unwrap();
+    let detail = db.get_student_detail(common::CLASS, "s1").unwrap().unwrap();
+    assert_eq!(detail.days_inactive, Some(20));
+    assert_eq!(detail.risk_score, health[0].risk_score);
+    assert_eq!(detail.risk_factors.len(), health[0].risk_factors.len());
+}
+
+#[test]
+fn test_logistic_model_separates_classes() {
+    use cohort_tracker::prediction::LogisticModel;
+
+    let samples: Vec<Vec<f64>> = (0..20).map(|i| vec![i as f64, 1.0]).collect();
+    let labels: Vec<f64> = (0..20).map(|i| if i < 10 { 1.0 } else { 0.0 }).collect();
+
+    let model = LogisticModel::train(&samples, &labels);
+    assert!(model.accuracy(&samples, &labels) >= 0.9);
+    assert!(model.predict(&[0.0, 1.0]) > 0.8);
+    assert!(model.predict(&[19.0, 1.0]) < 0.2);
+}
+
+/// Adds a class with four lessons running from `start` to `end`.
+fn add_class(db: &TestDb, id: &str, start: NaiveDate, end: NaiveDate) {
+    db.class(id, id);
+    db.set_calendar_settings(
+        id,
+        &CalendarSettings {
+            start_date: Some(start),
+            end_date: Some(end),
+            break_weeks: vec![],
+        },
+    )
+    .unwrap();
+    for a in 1..=4 {
+        db.insert_assignment(&format!("a{}", a), id, "Lesson", "lesson", None)
+            .unwrap();
+    }
+}
+
+fn complete(db: &TestDb, class_id: &str, student: &str, assignment: usize, on: NaiveDate) {
+    graded(db, class_id, student, assignment, on, Some(0.9));
+}
+
+fn graded(
+    db: &TestDb,
+    class_id: &str,
+    student: &str,
+    assignment: usize,
+    on: NaiveDate,
+    grade: Option<f64>,
+) {
+    let at = format!("{}T10:00:00", on);
+    db.insert_progression(
+        &format!("{}-{}-a{}", class_id, student, assignment),
+        class_id,
+        student,
+        &format!("a{}", assignment),
+        grade,
+        &at,
+        &at,
+        None,
+    )
+    .unwrap();
+}
+
+/// Two finished classes of twelve, half finishing steadily and half gone
+/// after one early, poorly graded completion.
+fn finished_cohorts() -> TestDb {
+    let db = TestDb::new();
+    let start = date("2024-01-01");
+    let day = |n| start + Duration::days(n);
+    for class_id in ["old1", "old2"] {
+        add_class(&db, class_id, start, date("2024-03-01"));
+        for i in 0..12 {
+            let student = format!("s{}", i);
+            db.insert_student(&student, class_id, "S", &student, "s@example.com")
+                .unwrap();
+            if i % 2 == 0 {
+                for a in 1..=4 {
+                    complete(&db, class_id, &student, a, day(a as i64 * 3 + i as i64 % 3));
+                }
+            } else {
+                graded(&db, class_id, &student, 1, day(1 + i as i64 % 3), Some(0.4));
+            }
+        }
+    }
+    db
+}
+
+/// A running class that started `days` ago with a steady student, who
+/// completes an assignment every three days, and a quiet one.
+fn running_class(db: &TestDb, days: i64) {
+    let today = chrono::Utc::now().date_naive();
+    let start = today - Duration::days(days);
+    add_class(db, "current", start, today + Duration::days(30));
+    for student in ["steady", "quiet"] {
+        db.insert_student(student, "current", "S", student, "s@example.com")
+            .unwrap();
+    }
+    for a in 1..=4 {
+        let on = start + Duration::days(a as i64 * 3);
+        if on <= today {
+            complete(db, "current", "steady", a, on);
+        }
+    }
+}
+
+fn dropout_probability(db: &TestDb, student_id: &str) -> Option<f64> {
+    db.get_student_health("current", None)
+        .unwrap()
+        .into_iter()
+        .find(|h| h.student_id == student_id)
+        .unwrap()
+        .dropout_probability
+}
+
+#[test]
+fn test_training_needs_finished_classes() {
+    let db = TestDb::new();
+    assert!(db.train_dropout_model(3).is_err());
+}
+
+#[test]
+fn test_train_dropout_model_reports_accuracy() {
+    let db = finished_cohorts();
+    let report = db.train_dropout_model(3).unwrap();
+    assert_eq!(report.classes, 2);
+    assert_eq!(report.train_size + report.holdout_size, 24);
+    assert_eq!(report.dropout_rate, 0.5);
+    assert!(report.holdout_accuracy >= 0.8);
+    assert_eq!(report.coefficients.len(), 4);
+}
+
+#[test]
+fn test_running_class_gets_predictions_after_the_early_weeks() {
+    let db = finished_cohorts();
+    db.train_dropout_model(3).unwrap();
+    running_class(&db, 21);
+
+    let quiet = dropout_probability(&db, "quiet").unwrap();
+    assert!(quiet > 0.5);
+    assert!(dropout_probability(&db, "steady").unwrap() < 0.5);
+
+    let detail = db.get_student_detail("current", "quiet").unwrap().unwrap();
+    assert_eq!(detail.dropout_probability, Some(quiet));
+}
+
+#[test]
+fn test_ungraded_work_is_not_read_as_failing() {
+    let db = finished_cohorts();
+    db.train_dropout_model(3).unwrap();
+    running_class(&db, 21);
+    db.insert_student("ungraded", "current", "S", "ungraded", "s@example.com")
+        .unwrap();
+    let start = chrono::Utc::now().date_naive() - Duration::days(21);
+    for a in 1..=4 {
+        graded(
+            &db,
+            "current",
+            "ungraded",
+            a,
+            start + Duration::days(a as i64 * 3),
+            None,
+        );
+    }
+
+    let steady = dropout_probability(&db, "steady").unwrap();
+    let ungraded = dropout_probability(&db, "ungraded").unwrap();
+    assert!(ungraded < 0.5);
+    assert!((ungraded - steady).abs() < 0.2);
+}
+
+#[test]
+fn test_no_predictions_during_the_early_weeks() {
+    let db = finished_cohorts();
+    db.train_dropout_model(3).unwrap();
+    running_class(&db, 14);
+
+    assert_eq!(dropout_probability(&db, "quiet"), None);
+    assert_eq!(dropout_probability(&db, "steady"), None);
+    let detail = db.get_student_detail("current", "steady").unwrap().unwrap();
+    assert_eq!(detail.dropout_probability, None);
+}
@@ -0,0 +1,88 @@
+mod common;
+
+use common::{TestDb, CLASS};
+
+/// John has completed the only lesson, Jane hasn't started.
+fn class_with_one_lesson() -> TestDb {
+    let test = TestDb::new();
+    test.student("s1", "John", "Doe")
+        .student("s2", "Jane", "Smith")
+        .assignment("a1", "Lesson 1", "lesson", None)
+        .complete("p1", "s1", "a1", Some(0.9), "2024-01-01T10:00:00");
+    test
+}
+
+#[test]
+fn test_snapshot_records_each_students_metrics() {
+    let test = class_with_one_lesson();
+    assert_eq!(test.record_student_snapshots(CLASS).unwrap(), 2);
+
+    let history = test.get_student_risk_history(CLASS, "s1").unwrap();
+    assert_eq!(history.len(), 1);
+    assert_eq!(history[0].completed, 1);
+    assert_eq!(history[0].risk, "low");
+    assert!(history[0].days_inactive.unwrap() > 0);
+}
+
+#[test]
+fn test_second_snapshot_on_the_same_day_replaces_the_first() {
+    let test = class_with_one_lesson();
+    test.record_student_snapshots(CLASS).unwrap();
+    test.record_student_snapshots(CLASS).unwrap();
+
+    assert_eq!(test.get_student_risk_history(CLASS, "s1").unwrap().len(), 1);
+}
+
+#[test]
+fn test_class_risk_history_counts_levels() {
+    let test = class_with_one_lesson();
+    test.record_student_snapshots(CLASS).unwrap();
+
+    let counts = test.get_class_risk_history(CLASS, None).unwrap();
+    assert_eq!(counts.len(), 1);
+    assert_eq!(counts[0].low, 1);
+    assert_eq!(counts[0].critical, 1);
+    assert_eq!(counts[0].total, 2);
+}
+
+#[test]
+fn test_snapshot_date_is_the_class_local_day() {
+    // 25 hours apart, so at least one of them is on a different day than UTC
+    for timezone in ["Pacific/Kiritimati", "Pacific/Pago_Pago"] {
+        let test = class_with_one_lesson();
+        test.set_class_timezone(CLASS, Some(timezone)).unwrap();
+        test.record_student_snapshots(CLASS).unwrap();
+
+        let tz: chrono_tz::Tz = timezone.parse().unwrap();
+        let local_today = chrono::Utc::now().with_timezone(&tz).date_naive();
+        let history = test.get_student_risk_history(CLASS, "s1").unwrap();
+        assert_eq!(
+            history[0].snapshot_date,
+            local_today.format("%Y-%m-%d").to_string(),
+            "{}",
+            timezone
+        );
+    }
+}
+
+#[test]
+fn test_failed_snapshot_rolls_back_and_releases_the_database() {
+    let test = class_with_one_lesson();
+    // Fail on the second student, after the first row is written
+    sqlite::open(test.path())
+        .unwrap()
+        .execute(
+            "CREATE TRIGGER fail_second BEFORE INSERT ON student_snapshots
+             WHEN (SELECT COUNT(*) FROM student_snapshots) > 0
+             BEGIN SELECT RAISE(ABORT, 'disk full'); END",
+        )
+        .unwrap();
+
+    assert!(test.record_student_snapshots(CLASS).is_err());
+    assert!(test.get_class_risk_history(CLASS, None).unwrap().is_empty());
+
+    // No transaction left open: another connection can write
+    let other = sqlite::open(test.path()).unwrap();
+    other.execute("BEGIN IMMEDIATE").unwrap();
+    other.execute("ROLLBACK").unwrap();
+}
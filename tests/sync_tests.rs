@@ -24,6 +24,13 @@ async fn test_authentication_success() {
         password: "password".to_string(),
         api_base: mock_server.uri(),
         check_for_updates: false,
+        database_path: None,
+        backup: Default::default(),
+        import: Default::default(),
+        risk: Default::default(),
+        pathways: Default::default(),
+        notifications: Default::default(),
+        reviews: Default::default(),
     };
 
     let mut client = OpenClassClient::new(config);
@@ -47,6 +54,13 @@ async fn test_authentication_failure() {
         password: "wrongpass".to_string(),
         api_base: mock_server.uri(),
         check_for_updates: false,
+        database_path: None,
+        backup: Default::default(),
+        import: Default::default(),
+        risk: Default::default(),
+        pathways: Default::default(),
+        notifications: Default::default(),
+        reviews: Default::default(),
     };
 
     let mut client = OpenClassClient::new(config);
@@ -114,6 +128,13 @@ async fn test_fetch_progressions() {
         password: "password".to_string(),
         api_base: mock_server.uri(),
         check_for_updates: false,
+        database_path: None,
+        backup: Default::default(),
+        import: Default::default(),
+        risk: Default::default(),
+        pathways: Default::default(),
+        notifications: Default::default(),
+        reviews: Default::default(),
     };
 
     let mut client = OpenClassClient::new(config);
@@ -127,3 +148,69 @@ async fn test_fetch_progressions() {
     assert_eq!(response.data[0].user.first_name, "John");
     assert!(!response.metadata.can_load_more);
 }
+
+#[tokio::test]
+async fn test_fetch_class_details_puts_repeated_assignments_in_their_last_unit() {
+    let mock_server = MockServer::start().await;
+
+    Mock::given(method("POST"))
+        .and(path("/v1/auth/login"))
+        .respond_with(ResponseTemplate::new(200).set_body_json(json!({
+            "result": {
+                "token": "test-token-123"
+            }
+        })))
+        .mount(&mock_server)
+        .await;
+
+    let class_data = json!({
+        "data": [{
+            "units": [
+                {"name": "Basics", "assignments": ["a1", "review"]},
+                {"name": "Functions", "assignments": ["a2", "review"]}
+            ]
+        }]
+    });
+
+    Mock::given(method("GET"))
+        .and(path("/v1/classes/class123"))
+        .and(header("bearer", "test-token-123"))
+        .respond_with(ResponseTemplate::new(200).set_body_json(json!({
+            "result": {
+                "objects": [serde_json::to_string(&class_data).unwrap()]
+            }
+        })))
+        .mount(&mock_server)
+        .await;
+
+    let config = Config {
+        email: "test@example.com".to_string(),
+        password: "password".to_string(),
+        api_base: mock_server.uri(),
+        check_for_updates: false,
+        database_path: None,
+        backup: Default::default(),
+        import: Default::default(),
+        risk: Default::default(),
+        pathways: Default::default(),
+        notifications: Default::default(),
+        reviews: Default::default(),
+    };
+
+    let mut client = OpenClassClient::new(config);
+    client.authenticate().await.unwrap();
+
+    let items = client.fetch_class_details("class123").await.unwrap();
+    let order: Vec<(&str, &str)> = items
+        .iter()
+        .map(|i| (i.assignment_id.as_str(), i.section.as_str()))
+        .collect();
+    assert_eq!(
+        order,
+        vec![
+            ("a1", "Basics"),
+            ("a2", "Functions"),
+            ("review", "Functions")
+        ]
+    );
+}
@@ -0,0 +1,94 @@
+use chrono::{Duration, NaiveDate};
+
+mod common;
+use common::{TestDb, CLASS};
+
+fn at(date: NaiveDate, minutes_after_nine: i64) -> String {
+    (date.and_hms_opt(9, 0, 0).unwrap() + Duration::minutes(minutes_after_nine))
+        .format("%Y-%m-%dT%H:%M:%S")
+        .to_string()
+}
+
+/// Six students. Quick takes 30 minutes and Slow two hours, except s5 who
+/// spends five hours on Quick. Untimed is started and finished at once.
+fn timed_class() -> TestDb {
+    let db = TestDb::new();
+    db.assignment("a1", "Quick", "lesson", Some("Unit 1"))
+        .assignment("a2", "Slow", "lesson", Some("Unit 2"))
+        .assignment("a3", "Untimed", "lesson", Some("Unit 2"));
+
+    let day = NaiveDate::from_ymd_opt(2024, 1, 1).unwrap();
+    for i in 0..6 {
+        let student = format!("s{}", i);
+        db.student(&student, "Student", &student);
+        let quick = if i == 5 { 300 } else { 30 };
+        for (assignment, minutes) in [("a1", quick), ("a2", 120)] {
+            db.progression(
+                &format!("{}-{}", student, assignment),
+                &student,
+                assignment,
+                Some(0.9),
+                &at(day, 0),
+                &at(day, minutes),
+                None,
+            );
+        }
+        let next_day = at(day.succ_opt().unwrap(), 0);
+        db.complete(
+            &format!("{}-a3", student),
+            &student,
+            "a3",
+            Some(0.9),
+            &next_day,
+        );
+    }
+    db
+}
+
+#[test]
+fn test_time_on_task_skips_untimed_assignments() {
+    let db = timed_class();
+    let times = db.get_time_on_task(CLASS, None).unwrap();
+    let ids: Vec<&str> = times.iter().map(|t| t.assignment_id.as_str()).collect();
+    assert_eq!(ids, vec!["a2", "a1"]);
+}
+
+#[test]
+fn test_time_on_task_median_and_p90() {
+    let db = timed_class();
+    let times = db.get_time_on_task(CLASS, None).unwrap();
+    assert_eq!(times[0].median_minutes, 120.0);
+    assert_eq!(times[1].median_minutes, 30.0);
+    assert_eq!(times[1].p90_minutes, 165.0);
+    assert_eq!(times[1].samples, 6);
+}
+
+#[test]
+fn test_section_time_on_task() {
+    let db = timed_class();
+    let sections = db.get_section_time_on_task(CLASS, None).unwrap();
+    assert_eq!(sections.len(), 2);
+    assert_eq!(sections[0].section, "Unit 1");
+    assert_eq!(sections[1].median_minutes, 120.0);
+}
+
+#[test]
+fn test_time_outliers() {
+    let db = timed_class();
+    let outliers = db.get_time_outliers(CLASS, None).unwrap();
+    assert_eq!(outliers.len(), 1);
+    assert_eq!(outliers[0].student_id, "s5");
+    assert_eq!(outliers[0].assignment_name, "Quick");
+    assert_eq!(outliers[0].ratio, 10.0);
+}
+
+#[test]
+fn test_time_on_task_breaks_difficulty_ties() {
+    // Same completion and grades, so time on task decides
+    let db = timed_class();
+    let difficulty = db.get_assignment_difficulty(CLASS, None).unwrap();
+    assert_eq!(difficulty[0].assignment_id, "a2");
+    assert_eq!(difficulty[0].median_minutes, Some(120.0));
+    assert_eq!(difficulty[1].median_minutes, None);
+    assert_eq!(difficulty[2].assignment_id, "a1");
+}
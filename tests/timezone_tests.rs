@@ -0,0 +1,142 @@
+use cohort_tracker::db::CalendarSettings;
+
+mod common;
+use common::{date, TestDb, CLASS};
+
+/// John and Jane both finish at Tuesday 1am UTC: Monday evening in New
+/// York, Tuesday morning in Tokyo.
+fn late_night_class() -> TestDb {
+    let db = TestDb::new();
+    db.student("s1", "John", "Doe")
+        .student("s2", "Jane", "Smith")
+        .assignment("a1", "Lesson 1", "lesson", None);
+    for student in ["s1", "s2"] {
+        db.complete(
+            &format!("p-{}", student),
+            student,
+            "a1",
+            Some(0.9),
+            "2024-01-02T01:00:00Z",
+        );
+    }
+    db
+}
+
+fn days(db: &TestDb) -> Vec<(String, i64)> {
+    db.get_completions_by_day_of_week(CLASS, None)
+        .unwrap()
+        .into_iter()
+        .map(|d| (d.day, d.count))
+        .collect()
+}
+
+fn periods(db: &TestDb) -> Vec<String> {
+    db.get_completions_by_time_of_day(CLASS, None)
+        .unwrap()
+        .into_iter()
+        .map(|p| p.day)
+        .collect()
+}
+
+/// New York for the class, Tokyo for Jane.
+fn with_timezones(db: &TestDb) {
+    db.set_class_timezone(CLASS, Some("America/New_York"))
+        .unwrap();
+    assert!(db
+        .set_student_timezone(CLASS, "s2", Some("Asia/Tokyo"))
+        .unwrap());
+}
+
+#[test]
+fn test_buckets_in_utc_by_default() {
+    let db = late_night_class();
+    assert_eq!(days(&db), vec![("Tuesday".to_string(), 2)]);
+    assert_eq!(periods(&db)[0], "Night (12am-6am)");
+}
+
+#[test]
+fn test_unknown_timezones_are_rejected() {
+    let db = late_night_class();
+    assert!(db.set_class_timezone(CLASS, Some("Mars/Olympus")).is_err());
+    assert!(!db
+        .set_student_timezone(CLASS, "missing", Some("Asia/Tokyo"))
+        .unwrap());
+}
+
+#[test]
+fn test_student_timezone_overrides_the_class() {
+    let db = late_night_class();
+    with_timezones(&db);
+    assert_eq!(
+        days(&db),
+        vec![("Monday".to_string(), 1), ("Tuesday".to_string(), 1)]
+    );
+    assert_eq!(
+        periods(&db),
+        vec!["Morning (6am-12pm)", "Evening (6pm-12am)"]
+    );
+}
+
+#[test]
+fn test_student_days_use_their_timezone() {
+    let db = late_night_class();
+    with_timezones(&db);
+    let john = db
+        .get_student_completions_by_day_of_week(CLASS, "s1")
+        .unwrap();
+    assert_eq!(john[0].day, "Monday");
+}
+
+#[test]
+fn test_timezone_info_lists_overrides() {
+    let db = late_night_class();
+    with_timezones(&db);
+    let info = db.get_class_timezone_info(CLASS).unwrap();
+    assert_eq!(info.timezone.as_deref(), Some("America/New_York"));
+    assert_eq!(info.student_overrides.len(), 1);
+}
+
+#[test]
+fn test_cleared_override_falls_back_to_the_class() {
+    let db = late_night_class();
+    with_timezones(&db);
+    db.set_student_timezone(CLASS, "s2", None).unwrap();
+    assert_eq!(days(&db), vec![("Monday".to_string(), 2)]);
+}
+
+#[test]
+fn test_weekly_series_use_local_dates() {
+    let db = late_night_class();
+    with_timezones(&db);
+    // Weeks run Tuesday to Monday, so John's Monday evening is still week 1
+    db.set_calendar_settings(
+        CLASS,
+        &CalendarSettings {
+            start_date: Some(date("2023-12-26")),
+            end_date: None,
+            break_weeks: vec![],
+        },
+    )
+    .unwrap();
+
+    let progress: Vec<(i64, i64)> = db
+        .get_progress_over_time(CLASS, None)
+        .unwrap()
+        .into_iter()
+        .map(|w| (w.week_number, w.completed))
+        .collect();
+    assert_eq!(progress, vec![(1, 1), (2, 1)]);
+
+    let velocity: Vec<i64> = db
+        .get_velocity_stats(CLASS, None)
+        .unwrap()
+        .into_iter()
+        .map(|w| w.week_number)
+        .collect();
+    assert_eq!(velocity, vec![1, 2]);
+
+    let john = db.get_student_progress_timeline(CLASS, "s1").unwrap();
+    assert_eq!(john[0].week_number, 1);
+    let jane = db.get_student_progress_timeline(CLASS, "s2").unwrap();
+    assert_eq!(jane[0].week_number, 2);
+}